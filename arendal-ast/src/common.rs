@@ -14,6 +14,8 @@ pub enum BinaryOp {
     Sub,
     Mul,
     Div,
+    Rem,
+    Pow,
     Eq,
     NEq,
     GT,
@@ -7,12 +7,6 @@ pub struct StringInput {
 }
 
 impl StringInput {
-    pub fn from_str(input: &str) -> StringInput {
-        StringInput {
-            input: input.into(),
-        }
-    }
-
     pub fn from_arcstr(input: ArcStr) -> StringInput {
         StringInput { input }
     }
@@ -22,6 +16,14 @@ impl StringInput {
     }
 }
 
+impl From<&str> for StringInput {
+    fn from(input: &str) -> Self {
+        StringInput {
+            input: input.into(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct StrLen {
     bytes: usize,
@@ -9,6 +9,7 @@ pub enum Keyword {
     Then,
     Else,
     Type,
+    Fn,
 }
 
 static KEYWORDS: phf::Map<&'static str, Keyword> = phf_map! {
@@ -18,6 +19,7 @@ static KEYWORDS: phf::Map<&'static str, Keyword> = phf_map! {
     "then" => Keyword::Then,
     "else" => Keyword::Else,
     "type" => Keyword::Type,
+    "fn" => Keyword::Fn,
 };
 
 impl Keyword {
@@ -33,6 +35,7 @@ impl Keyword {
             Self::Then => "then",
             Self::Else => "else",
             Self::Type => "type",
+            Self::Fn => "fn",
         }
     }
 }
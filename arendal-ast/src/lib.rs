@@ -7,7 +7,8 @@ pub mod symbol;
 
 use std::fmt::{self, Debug};
 
-use num::Integer;
+use arcstr::ArcStr;
+use num::{Decimal, Integer};
 use position::{EqNoPosition, Position};
 use symbol::{Symbol, TSymbol};
 
@@ -31,6 +32,7 @@ impl<T> Q<T> {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TypeExpr {
     Type(Q<TSymbol>),
+    Function(Vec<TypeExpr>, Box<TypeExpr>),
 }
 
 pub type ERef = Box<Expression>;
@@ -39,12 +41,82 @@ pub type Binary = common::Binary<ERef>;
 pub type Seq = common::Seq<ERef>;
 pub type Conditional = common::Conditional<ERef>;
 
+pub struct Param {
+    pub position: Position,
+    pub symbol: Symbol,
+    pub type_expr: TypeExpr,
+}
+
+impl EqNoPosition for Param {
+    fn eq_nopos(&self, other: &Self) -> bool {
+        self.symbol == other.symbol && self.type_expr == other.type_expr
+    }
+}
+
+impl fmt::Debug for Param {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {:?}{}", self.symbol, self.type_expr, self.position)
+    }
+}
+
+#[derive(Debug)]
+pub struct Lambda {
+    pub params: Vec<Param>,
+    pub result: Option<TypeExpr>,
+    pub body: ERef,
+}
+
+impl EqNoPosition for Lambda {
+    fn eq_nopos(&self, other: &Self) -> bool {
+        self.params.eq_nopos(&other.params)
+            && self.result == other.result
+            && self.body.eq_nopos(&other.body)
+    }
+}
+
+#[derive(Debug)]
+pub struct Call {
+    pub func: ERef,
+    pub args: Vec<Expression>,
+}
+
+impl EqNoPosition for Call {
+    fn eq_nopos(&self, other: &Self) -> bool {
+        self.func.eq_nopos(&other.func) && self.args.eq_nopos(&other.args)
+    }
+}
+
+// Part of an interpolated string
+#[derive(Debug)]
+pub enum StringPart {
+    Text(ArcStr),
+    Expr(ERef),
+}
+
+impl EqNoPosition for StringPart {
+    fn eq_nopos(&self, other: &Self) -> bool {
+        match (self, other) {
+            (StringPart::Text(t1), StringPart::Text(t2)) => t1 == t2,
+            (StringPart::Expr(e1), StringPart::Expr(e2)) => e1.eq_nopos(e2),
+            _ => false,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum Expr {
     LitInteger(Integer),
+    LitDecimal(Decimal),
+    LitString(ArcStr),
+    Interpolation(Vec<StringPart>),
+    Unary(Unary),
     Binary(Binary),
     Symbol(Q<Symbol>),
     Type(Q<TSymbol>),
+    Seq(Seq),
+    Conditional(Conditional),
+    Lambda(Lambda),
+    Call(Call),
 }
 
 impl Expr {
@@ -67,6 +139,34 @@ impl EqNoPosition for Expr {
                     false
                 }
             }
+            Expr::LitDecimal(d1) => {
+                if let Expr::LitDecimal(d2) = other {
+                    d1 == d2 && d1.scale() == d2.scale()
+                } else {
+                    false
+                }
+            }
+            Expr::LitString(s1) => {
+                if let Expr::LitString(s2) = other {
+                    s1 == s2
+                } else {
+                    false
+                }
+            }
+            Expr::Interpolation(p1) => {
+                if let Expr::Interpolation(p2) = other {
+                    p1.eq_nopos(p2)
+                } else {
+                    false
+                }
+            }
+            Expr::Unary(u1) => {
+                if let Expr::Unary(u2) = other {
+                    u1.eq_nopos(u2)
+                } else {
+                    false
+                }
+            }
             Expr::Binary(b1) => {
                 if let Expr::Binary(b2) = other {
                     b1.eq_nopos(b2)
@@ -74,7 +174,48 @@ impl EqNoPosition for Expr {
                     false
                 }
             }
-            _ => panic!("TODO!"),
+            Expr::Symbol(s1) => {
+                if let Expr::Symbol(s2) = other {
+                    s1 == s2
+                } else {
+                    false
+                }
+            }
+            Expr::Type(t1) => {
+                if let Expr::Type(t2) = other {
+                    t1 == t2
+                } else {
+                    false
+                }
+            }
+            Expr::Seq(s1) => {
+                if let Expr::Seq(s2) = other {
+                    s1.eq_nopos(s2)
+                } else {
+                    false
+                }
+            }
+            Expr::Conditional(c1) => {
+                if let Expr::Conditional(c2) = other {
+                    c1.eq_nopos(c2)
+                } else {
+                    false
+                }
+            }
+            Expr::Lambda(l1) => {
+                if let Expr::Lambda(l2) = other {
+                    l1.eq_nopos(l2)
+                } else {
+                    false
+                }
+            }
+            Expr::Call(c1) => {
+                if let Expr::Call(c2) = other {
+                    c1.eq_nopos(c2)
+                } else {
+                    false
+                }
+            }
         }
     }
 }
@@ -97,9 +238,28 @@ impl fmt::Debug for Expression {
     }
 }
 
+pub struct Assignment {
+    pub position: Position,
+    pub symbol: Symbol,
+    pub expr: Expression,
+}
+
+impl EqNoPosition for Assignment {
+    fn eq_nopos(&self, other: &Self) -> bool {
+        self.symbol == other.symbol && self.expr.eq_nopos(&other.expr)
+    }
+}
+
+impl fmt::Debug for Assignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "let {} = {:?}{}", self.symbol, self.expr, self.position)
+    }
+}
+
 #[derive(Debug)]
 pub enum Statement {
     Expression(Expression),
+    Assignment(Assignment),
 }
 
 impl EqNoPosition for Statement {
@@ -109,11 +269,16 @@ impl EqNoPosition for Statement {
                 Statement::Expression(e2) => e1.eq_nopos(e2),
                 _ => false,
             },
+            Statement::Assignment(a1) => match other {
+                Statement::Assignment(a2) => a1.eq_nopos(a2),
+                _ => false,
+            },
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct AST {
+    pub assignments: Vec<Assignment>,
     pub expression: Option<Expression>,
 }
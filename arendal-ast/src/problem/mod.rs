@@ -1,8 +1,9 @@
+use std::any::Any;
 use std::sync::Arc;
 
 use crate::position::Position;
 
-pub trait ErrorType: std::fmt::Debug + 'static {
+pub trait ErrorType: Any + std::fmt::Debug {
     fn at(self, position: Position) -> Error
     where
         Self: Sized,
@@ -25,6 +26,14 @@ impl Error {
         }
     }
 
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    pub fn error(&self) -> &dyn ErrorType {
+        self.error.as_ref()
+    }
+
     pub fn to_err<T>(self) -> Result<T> {
         Err(Problems {
             errors: vec![self],
@@ -56,6 +65,14 @@ impl Warning {
         }
     }
 
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    pub fn warning(&self) -> &dyn WarningType {
+        self.warning.as_ref()
+    }
+
     pub fn to_ok<T>(self, value: T) -> Result<T> {
         Ok(Warnings {
             warnings: vec![self],
@@ -140,6 +157,13 @@ pub struct Problems {
 }
 
 impl Problems {
+    // Returns whether any of the errors is the provided one
+    pub fn contains<E: ErrorType + PartialEq>(&self, error: &E) -> bool {
+        self.errors
+            .iter()
+            .any(|e| (e.error() as &dyn Any).downcast_ref::<E>() == Some(error))
+    }
+
     pub fn add_problems(&mut self, mut problems: Problems) {
         self.errors.append(&mut problems.errors);
         self.warnings.append(&mut problems.warnings);
@@ -159,14 +183,14 @@ impl Problems {
         }
     }
 
-    fn to_ok<T>(self, value: T) -> Result<T> {
+    fn into_ok<T>(self, value: T) -> Result<T> {
         Ok(Warnings {
-            warnings: Vec::default(),
+            warnings: self.warnings,
             value,
         })
     }
 
-    fn to_err<T>(self) -> Result<T> {
+    fn into_err<T>(self) -> Result<T> {
         Err(Problems {
             errors: self.errors,
             warnings: self.warnings,
@@ -175,9 +199,9 @@ impl Problems {
 
     pub fn to_result<T>(self, value: T) -> Result<T> {
         if self.errors.is_empty() {
-            self.to_ok(value)
+            self.into_ok(value)
         } else {
-            self.to_err()
+            self.into_err()
         }
     }
 
@@ -190,9 +214,9 @@ impl Problems {
         F: FnOnce() -> T,
     {
         if self.errors.is_empty() {
-            self.to_ok(supplier())
+            self.into_ok(supplier())
         } else {
-            self.to_err()
+            self.into_err()
         }
     }
 }
@@ -259,13 +283,19 @@ pub struct Output<T> {
     problems: Problems,
 }
 
-impl<T> Output<T> {
-    pub fn new() -> Output<T> {
+impl<T> Default for Output<T> {
+    fn default() -> Self {
         Output {
             value: None,
             problems: Problems::default(),
         }
     }
+}
+
+impl<T> Output<T> {
+    pub fn new() -> Output<T> {
+        Self::default()
+    }
 
     pub fn ok(value: T) -> Output<T> {
         Output {
@@ -304,13 +334,7 @@ impl<T> Output<T> {
 
     pub fn merge<U, V, F: FnOnce(T, U) -> V>(mut self, other: Output<U>, op: F) -> Output<V> {
         self.problems.add_problems(other.problems);
-        let value = match self.value {
-            Some(t) => match other.value {
-                Some(u) => Some(op(t, u)),
-                _ => None,
-            },
-            _ => None,
-        };
+        let value = self.value.zip(other.value).map(|(t, u)| op(t, u));
         Output {
             value,
             problems: self.problems,
@@ -327,16 +351,19 @@ impl<T> Output<T> {
     }
 
     pub fn to_result(self) -> Result<T> {
-        if self.value.is_some() && self.problems.errors.is_empty() {
-            Ok(Warnings {
+        match self.value {
+            Some(value) if self.problems.errors.is_empty() => Ok(Warnings {
                 warnings: self.problems.warnings,
-                value: self.value.unwrap(),
-            })
-        } else {
-            Err(self.problems)
+                value,
+            }),
+            _ => Err(self.problems),
         }
     }
 
+    pub fn has_errors(&self) -> bool {
+        !self.problems.errors.is_empty()
+    }
+
     pub fn add_error(&mut self, error: Error) {
         self.problems.errors.push(error);
     }
@@ -344,6 +371,10 @@ impl<T> Output<T> {
     pub fn add_warning(&mut self, warning: Warning) {
         self.problems.warnings.push(warning);
     }
+
+    pub fn add_problems(&mut self, problems: Problems) {
+        self.problems.add_problems(problems);
+    }
 }
 
 impl<T> Output<Vec<T>> {
@@ -360,7 +391,9 @@ impl<T> Output<Vec<T>> {
 
     pub fn add_output(&mut self, output: Output<T>) {
         self.problems.add_problems(output.problems);
-        output.value.map(|t| self.add_value(t));
+        if let Some(t) = output.value {
+            self.add_value(t);
+        }
     }
 }
 
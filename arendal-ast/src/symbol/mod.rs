@@ -53,10 +53,8 @@ impl Symbol {
                 if !c.is_ascii_alphabetic() || !c.is_ascii_lowercase() {
                     return Err(Error::InvalidInitial(c));
                 }
-            } else {
-                if !c.is_ascii_alphanumeric() {
-                    return Err(Error::InvalidChar(i, c));
-                }
+            } else if !c.is_ascii_alphanumeric() {
+                return Err(Error::InvalidChar(i, c));
             }
         }
         Ok(Self { name: name.into() })
@@ -90,10 +88,8 @@ impl TSymbol {
                 if !c.is_ascii_alphabetic() || !c.is_ascii_uppercase() {
                     return Err(Error::InvalidInitial(c));
                 }
-            } else {
-                if !c.is_ascii_alphanumeric() {
-                    return Err(Error::InvalidChar(i, c));
-                }
+            } else if !c.is_ascii_alphanumeric() {
+                return Err(Error::InvalidChar(i, c));
             }
         }
         Ok(Self { name: name.into() })
@@ -211,6 +207,10 @@ impl FQPath {
         Self::new(Lib::Std, ModulePath::empty())
     }
 
+    pub fn lib(&self) -> &Lib {
+        &self.data.lib
+    }
+
     pub fn is_empty(&self) -> bool {
         self.data.path.is_empty()
     }
@@ -246,8 +246,7 @@ pub struct TopLevel<T> {
 impl<T: fmt::Display> fmt::Display for TopLevel<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.path.fmt(f)?;
-        separator(f)?;
-        self.symbol.fmt(f)
+        add_segment(f, &self.symbol)
     }
 }
 
@@ -266,8 +265,7 @@ pub struct Nested<T> {
 impl<T: fmt::Display> fmt::Display for Nested<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.parent.fmt(f)?;
-        separator(f)?;
-        self.symbol.fmt(f)
+        add_segment(f, &self.symbol)
     }
 }
 
@@ -334,6 +332,14 @@ impl FQType {
         matches!(self, FQType::TopLevel(_))
     }
 
+    // Type defined inside this one, e.g., the variant of a union
+    pub fn nested(&self, symbol: TSymbol) -> FQType {
+        FQType::Nested(Nested {
+            parent: Arc::new(self.clone()),
+            symbol,
+        })
+    }
+
     pub fn path(&self) -> Option<FQPath> {
         match &self {
             FQType::Unit => None,
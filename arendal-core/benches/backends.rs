@@ -0,0 +1,67 @@
+// Compares the time each backend takes to run the same programs, including parsing and type
+// checking. Run with `cargo bench -p arendal-core`. The programs recurse deeper than the default
+// limits allow, so they run unbounded.
+
+use std::time::{Duration, Instant};
+
+use arendal_core::env0::{Backend, Env, Limits};
+
+const RUNS: u32 = 10;
+
+const FIB: &str = "fn fib(n: Integer): Integer = if n < 2 then n else fib(n - 1) + fib(n - 2)
+fib(22)";
+
+// Rules applied to a range of values
+const RULES: &str = "type Shape = Circle(Integer) | Rect { w: Integer, h: Integer } | Empty
+fn shape(i: Integer): Shape = match i % 3 {
+  0 -> Shape::Circle(i),
+  1 -> Shape::Rect { w: i, h: 2 },
+  _ -> Shape::Empty,
+}
+fn area(s: Shape): Integer = match s {
+  Shape::Circle(r) -> 3 * r * r,
+  Shape::Rect { w: w, h: h } -> w * h,
+  Shape::Empty -> 0,
+}
+fn total(from: Integer, to: Integer): Integer =
+  if from > to then 0 else area(shape(from)) + total(from + 1, to)
+total(1, 20000)";
+
+const COLLECTIONS: &str = "fn range(from: Integer, to: Integer): List[Integer] = [from, to]
+fn label(i: Integer): String = \"item {i}\"
+fn count(from: Integer, to: Integer): Integer = if from > to then 0 else {
+  let l = range(from, from + 1)
+  let m = {label(l[0]): l[1], \"other\": 0}
+  m.length + label(from).length + count(from + 1, to)
+}
+count(1, 10000)";
+
+fn time(backend: Backend, input: &str) -> Duration {
+    let mut best = Duration::MAX;
+    for _ in 0..RUNS {
+        let mut env = Env::default();
+        env.set_backend(backend);
+        env.set_limits(Limits::UNBOUNDED);
+        let start = Instant::now();
+        env.run(input).expect("The benchmarks run without errors");
+        best = best.min(start.elapsed());
+    }
+    best
+}
+
+fn main() {
+    println!(
+        "{:<12} {:>12} {:>12} {:>8}",
+        "program", "interpreter", "vm", "speedup"
+    );
+    for (name, input) in [("fib", FIB), ("rules", RULES), ("collections", COLLECTIONS)] {
+        let interpreter = time(Backend::Interpreter, input);
+        let vm = time(Backend::VM, input);
+        println!(
+            "{name:<12} {:>12.2?} {:>12.2?} {:>7.2}x",
+            interpreter,
+            vm,
+            interpreter.as_secs_f64() / vm.as_secs_f64()
+        );
+    }
+}
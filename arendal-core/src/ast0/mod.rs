@@ -4,7 +4,7 @@ use std::cmp::{Eq, PartialEq};
 use std::collections::HashSet;
 use std::sync::Arc;
 
-use super::Integer;
+use super::{ArcStr, Decimal, Integer};
 use crate::error::{Error, L, Loc, Result};
 use crate::symbol::{FQPath, Pkg, Symbol, TSymbol};
 use crate::visibility::{V, Visibility};
@@ -33,6 +33,8 @@ pub enum BinaryOp {
     Sub,
     Mul,
     Div,
+    Rem,
+    Pow,
     Eq,
     NEq,
     GT,
@@ -69,6 +71,126 @@ pub struct Conditional {
     pub otherwise: ExprRef,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeExpr {
+    Type(Q<TSymbol>),
+    Function(Vec<TypeExprRef>, TypeExprRef, Vec<TSymbol>), // params, result and effects
+    Tuple(Vec<TypeExprRef>),
+    Parameterized(Q<TSymbol>, Vec<TypeExprRef>), // e.g., List[Integer]
+}
+
+pub type TypeExprRef = Arc<L<TypeExpr>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    pub symbol: Symbol,
+    pub tipo: TypeExprRef,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lambda {
+    pub type_params: Vec<TSymbol>,
+    pub params: Vec<L<Param>>,
+    pub result: Option<TypeExprRef>,
+    pub effects: Vec<TSymbol>,
+    pub body: ExprRef,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Call {
+    pub func: ExprRef,
+    pub args: Vec<ExprRef>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TupleField {
+    pub expr: ExprRef,
+    pub index: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub symbol: Symbol,
+    pub expr: ExprRef,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub tipo: Q<TSymbol>,
+    pub fields: Vec<L<Field>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordField {
+    pub expr: ExprRef,
+    pub symbol: Symbol,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub key: ExprRef,
+    pub value: ExprRef,
+}
+
+// Element of a list or value of a map
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Index {
+    pub expr: ExprRef,
+    pub index: ExprRef,
+}
+
+// Expression with an explicit type, which must accept the type of the expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ascription {
+    pub expr: ExprRef,
+    pub tipo: TypeExprRef,
+}
+
+// Copy of a record with some of the fields replaced
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Update {
+    pub expr: ExprRef,
+    pub fields: Vec<L<Field>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldPattern {
+    pub symbol: Symbol,
+    pub pattern: PatternRef,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    Wildcard,
+    Symbol(Symbol), // Binding
+    LitInteger(Integer),
+    TSymbol(Q<TSymbol>),
+    Tuple(Vec<PatternRef>),
+    Constructor(Q<TSymbol>, Vec<PatternRef>),
+    Record(Q<TSymbol>, Vec<L<FieldPattern>>), // Missing fields match any value
+}
+
+pub type PatternRef = Arc<L<Pattern>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Arm {
+    pub pattern: PatternRef,
+    pub expr: ExprRef,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub expr: ExprRef,
+    pub arms: Vec<L<Arm>>,
+}
+
+// Part of an interpolated string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StringPart {
+    Text(ArcStr),
+    Expr(ExprRef),
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Assignment {
     pub symbol: Symbol,
@@ -87,6 +209,9 @@ pub struct Block {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Expr {
     LitInteger(Integer),
+    LitDecimal(Decimal),
+    LitString(ArcStr),
+    Interpolation(Vec<StringPart>),
     Symbol(Q<Symbol>),
     TSymbol(Q<TSymbol>),
     Unary(Unary),
@@ -94,6 +219,18 @@ pub enum Expr {
     Block(Block),
     Conditional(Conditional),
     Seq(Seq),
+    Lambda(Lambda),
+    Call(Call),
+    Tuple(Vec<ExprRef>),
+    TupleField(TupleField),
+    Record(Record),
+    RecordField(RecordField),
+    Update(Update),
+    Match(Match),
+    List(Vec<ExprRef>),
+    Map(Vec<L<Entry>>),
+    Index(Index),
+    Ascription(Ascription),
 }
 
 pub type ExprRef = Arc<L<Expr>>;
@@ -101,14 +238,39 @@ pub type ExprRef = Arc<L<Expr>>;
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NewType {
     pub symbol: TSymbol,
+    pub params: Vec<TSymbol>, // Shared by the variants of unions
     pub dfn: TypeDfn,
 }
 
 pub type NewTypeRef = Arc<L<V<NewType>>>;
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDfn {
+    pub symbol: Symbol,
+    pub tipo: TypeExprRef,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variant {
+    pub symbol: TSymbol,
+    pub dfn: TypeDfn, // Unions can't be nested
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TypeDfn {
     Singleton,
+    Tuple(Vec<TypeExprRef>),
+    Record(Vec<L<FieldDfn>>),
+    Union(Vec<L<Variant>>),
+}
+
+impl TypeDfn {
+    pub fn has_variant(&self, symbol: &TSymbol) -> bool {
+        match self {
+            TypeDfn::Union(variants) => variants.iter().any(|v| v.it.symbol == *symbol),
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -190,6 +352,18 @@ impl Builder {
         self.build(Expr::LitInteger(value))
     }
 
+    pub fn lit_decimal(&self, value: Decimal) -> ExprRef {
+        self.build(Expr::LitDecimal(value))
+    }
+
+    pub fn lit_string(&self, value: ArcStr) -> ExprRef {
+        self.build(Expr::LitString(value))
+    }
+
+    pub fn interpolation(&self, parts: Vec<StringPart>) -> ExprRef {
+        self.build(Expr::Interpolation(parts))
+    }
+
     pub fn symbol(&self, segments: Vec<Segment>, symbol: Symbol) -> ExprRef {
         self.build(Expr::Symbol(Q { segments, symbol }))
     }
@@ -231,6 +405,171 @@ impl Builder {
         self.build(Expr::Seq(Seq { expr, then }))
     }
 
+    pub fn lambda(
+        &self,
+        params: Vec<L<Param>>,
+        result: Option<TypeExprRef>,
+        body: ExprRef,
+    ) -> ExprRef {
+        self.generic_lambda(Vec::default(), params, result, body)
+    }
+
+    pub fn generic_lambda(
+        &self,
+        type_params: Vec<TSymbol>,
+        params: Vec<L<Param>>,
+        result: Option<TypeExprRef>,
+        body: ExprRef,
+    ) -> ExprRef {
+        self.effectful_lambda(type_params, params, result, Vec::default(), body)
+    }
+
+    pub fn effectful_lambda(
+        &self,
+        type_params: Vec<TSymbol>,
+        params: Vec<L<Param>>,
+        result: Option<TypeExprRef>,
+        effects: Vec<TSymbol>,
+        body: ExprRef,
+    ) -> ExprRef {
+        self.build(Expr::Lambda(Lambda {
+            type_params,
+            params,
+            result,
+            effects,
+            body,
+        }))
+    }
+
+    pub fn call(&self, func: ExprRef, args: Vec<ExprRef>) -> ExprRef {
+        self.build(Expr::Call(Call { func, args }))
+    }
+
+    pub fn tuple(&self, exprs: Vec<ExprRef>) -> ExprRef {
+        self.build(Expr::Tuple(exprs))
+    }
+
+    pub fn tuple_field(&self, expr: ExprRef, index: usize) -> ExprRef {
+        self.build(Expr::TupleField(TupleField { expr, index }))
+    }
+
+    pub fn record(&self, tipo: Q<TSymbol>, fields: Vec<L<Field>>) -> ExprRef {
+        self.build(Expr::Record(Record { tipo, fields }))
+    }
+
+    pub fn record_field(&self, expr: ExprRef, symbol: Symbol) -> ExprRef {
+        self.build(Expr::RecordField(RecordField { expr, symbol }))
+    }
+
+    pub fn update(&self, expr: ExprRef, fields: Vec<L<Field>>) -> ExprRef {
+        self.build(Expr::Update(Update { expr, fields }))
+    }
+
+    pub fn list(&self, exprs: Vec<ExprRef>) -> ExprRef {
+        self.build(Expr::List(exprs))
+    }
+
+    pub fn map(&self, entries: Vec<L<Entry>>) -> ExprRef {
+        self.build(Expr::Map(entries))
+    }
+
+    pub fn entry(&self, key: ExprRef, value: ExprRef) -> L<Entry> {
+        self.loc.wrap(Entry { key, value })
+    }
+
+    pub fn index(&self, expr: ExprRef, index: ExprRef) -> ExprRef {
+        self.build(Expr::Index(Index { expr, index }))
+    }
+
+    pub fn ascription(&self, expr: ExprRef, tipo: TypeExprRef) -> ExprRef {
+        self.build(Expr::Ascription(Ascription { expr, tipo }))
+    }
+
+    pub fn pattern_match(&self, expr: ExprRef, arms: Vec<L<Arm>>) -> ExprRef {
+        self.build(Expr::Match(Match { expr, arms }))
+    }
+
+    pub fn arm(&self, pattern: PatternRef, expr: ExprRef) -> L<Arm> {
+        self.loc.wrap(Arm { pattern, expr })
+    }
+
+    fn pattern(&self, pattern: Pattern) -> PatternRef {
+        Arc::new(self.loc.wrap(pattern))
+    }
+
+    pub fn p_wildcard(&self) -> PatternRef {
+        self.pattern(Pattern::Wildcard)
+    }
+
+    pub fn p_symbol(&self, symbol: Symbol) -> PatternRef {
+        self.pattern(Pattern::Symbol(symbol))
+    }
+
+    pub fn p_integer(&self, value: Integer) -> PatternRef {
+        self.pattern(Pattern::LitInteger(value))
+    }
+
+    pub fn p_tsymbol(&self, symbol: Q<TSymbol>) -> PatternRef {
+        self.pattern(Pattern::TSymbol(symbol))
+    }
+
+    pub fn p_tuple(&self, patterns: Vec<PatternRef>) -> PatternRef {
+        self.pattern(Pattern::Tuple(patterns))
+    }
+
+    pub fn p_constructor(&self, symbol: Q<TSymbol>, patterns: Vec<PatternRef>) -> PatternRef {
+        self.pattern(Pattern::Constructor(symbol, patterns))
+    }
+
+    pub fn p_record(&self, symbol: Q<TSymbol>, fields: Vec<L<FieldPattern>>) -> PatternRef {
+        self.pattern(Pattern::Record(symbol, fields))
+    }
+
+    pub fn field_pattern(&self, symbol: Symbol, pattern: PatternRef) -> L<FieldPattern> {
+        self.loc.wrap(FieldPattern { symbol, pattern })
+    }
+
+    pub fn field(&self, symbol: Symbol, expr: ExprRef) -> L<Field> {
+        self.loc.wrap(Field { symbol, expr })
+    }
+
+    pub fn field_dfn(&self, symbol: Symbol, tipo: TypeExprRef) -> L<FieldDfn> {
+        self.loc.wrap(FieldDfn { symbol, tipo })
+    }
+
+    pub fn variant(&self, symbol: TSymbol, dfn: TypeDfn) -> L<Variant> {
+        self.loc.wrap(Variant { symbol, dfn })
+    }
+
+    pub fn param(&self, symbol: Symbol, tipo: TypeExprRef) -> L<Param> {
+        self.loc.wrap(Param { symbol, tipo })
+    }
+
+    pub fn type_symbol(&self, symbol: Q<TSymbol>) -> TypeExprRef {
+        Arc::new(self.loc.wrap(TypeExpr::Type(symbol)))
+    }
+
+    pub fn type_function(&self, params: Vec<TypeExprRef>, result: TypeExprRef) -> TypeExprRef {
+        self.type_effectful_function(params, result, Vec::default())
+    }
+
+    pub fn type_effectful_function(
+        &self,
+        params: Vec<TypeExprRef>,
+        result: TypeExprRef,
+        effects: Vec<TSymbol>,
+    ) -> TypeExprRef {
+        Arc::new(self.loc.wrap(TypeExpr::Function(params, result, effects)))
+    }
+
+    pub fn type_tuple(&self, types: Vec<TypeExprRef>) -> TypeExprRef {
+        Arc::new(self.loc.wrap(TypeExpr::Tuple(types)))
+    }
+
+    pub fn type_parameterized(&self, symbol: Q<TSymbol>, types: Vec<TypeExprRef>) -> TypeExprRef {
+        Arc::new(self.loc.wrap(TypeExpr::Parameterized(symbol, types)))
+    }
+
     pub fn l_let(&self, symbol: Symbol, expr: ExprRef) -> LAssignmentRef {
         Arc::new(self.loc.wrap(Assignment { symbol, expr }))
     }
@@ -244,6 +583,7 @@ impl Builder {
             loc: self.loc.clone(),
             visibility,
             symbol,
+            params: Vec::default(),
         }
     }
 }
@@ -252,17 +592,36 @@ pub struct NewTypeBuilder {
     loc: Loc,
     visibility: Visibility,
     symbol: TSymbol,
+    params: Vec<TSymbol>,
 }
 
 impl NewTypeBuilder {
     fn build(self, dfn: TypeDfn) -> NewTypeRef {
         Arc::new(self.loc.to_wrap(self.visibility.wrap(NewType {
             symbol: self.symbol,
+            params: self.params,
             dfn,
         })))
     }
 
+    pub fn params(mut self, params: Vec<TSymbol>) -> Self {
+        self.params = params;
+        self
+    }
+
     pub fn singleton(self) -> NewTypeRef {
         self.build(TypeDfn::Singleton)
     }
+
+    pub fn tuple(self, types: Vec<TypeExprRef>) -> NewTypeRef {
+        self.build(TypeDfn::Tuple(types))
+    }
+
+    pub fn record(self, fields: Vec<L<FieldDfn>>) -> NewTypeRef {
+        self.build(TypeDfn::Record(fields))
+    }
+
+    pub fn union(self, variants: Vec<L<Variant>>) -> NewTypeRef {
+        self.build(TypeDfn::Union(variants))
+    }
 }
@@ -6,7 +6,7 @@ use super::Enclosure;
 use crate::error::{Error, Errors, L, Loc, Result};
 use crate::keyword::Keyword;
 use crate::symbol::{Symbol, TSymbol};
-use crate::{Integer, Substr};
+use crate::{ArcStr, Decimal, Integer, Substr};
 use tokenizer::{Token, Tokens, tokenize};
 
 pub(super) fn lex(input: &str) -> Result<Lexemes> {
@@ -48,7 +48,7 @@ impl Lexeme {
     fn new(separator: Separator, token: L<Token>, kind: LexemeKind) -> Self {
         Lexeme {
             separator,
-            token: token,
+            token,
             kind,
         }
     }
@@ -70,8 +70,11 @@ pub(super) type Lexemes = Vec<Lexeme>;
 pub(super) enum LexemeKind {
     Plus,
     Minus,
+    Arrow,
     Star,
+    DoubleStar,
     Slash,
+    Percent,
     Dot,
     Greater,
     GreaterOrEq,
@@ -83,14 +86,20 @@ pub(super) enum LexemeKind {
     NotEquals,
     LogicalAnd,
     LogicalOr,
+    Pipe,
+    Colon,
     PathSeparator,
+    Comma,
     Integer(Integer),
+    Decimal(Decimal),
     Open(Enclosure),
     Close(Enclosure),
     Underscore,
     Symbol(Symbol),
     TSymbol(TSymbol),
     Keyword(Keyword),
+    Quote,
+    Text(ArcStr), // Escape sequences are replaced by the chars they represent
 }
 
 struct Lexer {
@@ -147,8 +156,11 @@ impl Lexer {
                 }
                 Token::Plus => self.add_lexeme(LexemeKind::Plus, 1),
                 Token::Minus => self.add_lexeme(LexemeKind::Minus, 1),
+                Token::Arrow => self.add_lexeme(LexemeKind::Arrow, 1),
                 Token::Star => self.add_lexeme(LexemeKind::Star, 1),
+                Token::DoubleStar => self.add_lexeme(LexemeKind::DoubleStar, 1),
                 Token::Slash => self.add_lexeme(LexemeKind::Slash, 1),
+                Token::Percent => self.add_lexeme(LexemeKind::Percent, 1),
                 Token::Dot => self.add_lexeme(LexemeKind::Dot, 1),
                 Token::Greater => self.add_lexeme(LexemeKind::Greater, 1),
                 Token::GreaterOrEq => self.add_lexeme(LexemeKind::GreaterOrEq, 1),
@@ -159,16 +171,23 @@ impl Lexer {
                 Token::Equals => self.add_lexeme(LexemeKind::Equals, 1),
                 Token::LogicalAnd => self.add_lexeme(LexemeKind::LogicalAnd, 1),
                 Token::LogicalOr => self.add_lexeme(LexemeKind::LogicalOr, 1),
+                Token::Pipe => self.add_lexeme(LexemeKind::Pipe, 1),
                 Token::NotEquals => self.add_lexeme(LexemeKind::NotEquals, 1),
                 Token::Underscore => self.add_lexeme(LexemeKind::Underscore, 1),
+                Token::Colon => self.add_lexeme(LexemeKind::Colon, 1),
                 Token::DoubleColon => self.add_lexeme(LexemeKind::PathSeparator, 1),
+                Token::Comma => self.add_lexeme(LexemeKind::Comma, 1),
                 Token::Open(e) => {
                     self.enclosures.push(e);
                     self.add_lexeme(LexemeKind::Open(e), 1)
                 }
                 Token::Close(e) => self.add_close(loc, e),
                 Token::Digits(s) => self.add_digits(&s),
+                Token::Decimal(s) => self.add_decimal(&s),
                 Token::Word(s) => self.add_word(loc, &s),
+                Token::Quote => self.add_lexeme(LexemeKind::Quote, 1),
+                Token::Text(s) => self.add_lexeme(LexemeKind::Text(s.as_str().into()), 1),
+                Token::Escape(s) => self.add_escape(loc, &s),
                 _ => self.add_error(loc, Error::UnexpectedToken, 1),
             }
         }
@@ -228,6 +247,25 @@ impl Lexer {
         self.add_lexeme(LexemeKind::Integer(digits.parse().unwrap()), 1);
     }
 
+    fn add_decimal(&mut self, decimal: &Substr) {
+        self.add_lexeme(LexemeKind::Decimal(decimal.parse().unwrap()), 1);
+    }
+
+    fn add_escape(&mut self, loc: &Loc, escape: &Substr) {
+        let c = escape.chars().nth(1).unwrap_or('\\');
+        let text = match c {
+            'n' => "\n",
+            'r' => "\r",
+            't' => "\t",
+            '\\' => "\\",
+            '"' => "\"",
+            '{' => "{",
+            '}' => "}",
+            _ => return self.add_error(loc, Error::InvalidEscape(c), 1),
+        };
+        self.add_lexeme(LexemeKind::Text(text.into()), 1);
+    }
+
     fn add_word(&mut self, loc: &Loc, word: &Substr) {
         if let Some(k) = Keyword::parse(word) {
             self.add_lexeme(LexemeKind::Keyword(k), 1);
@@ -81,10 +81,7 @@ impl TestCase {
     }
 
     fn err(self) {
-        match self.lex() {
-            Ok(_) => panic!(),
-            Err(_) => (),
-        }
+        assert!(self.lex().is_err());
     }
 }
 
@@ -233,3 +230,24 @@ fn path() {
         .tsymbol(Separator::Nothing, "C")
         .ok_without_pos();
 }
+
+#[test]
+fn string() {
+    TestCase::new(r#"x == "a\tb{y}""#)
+        .symbol(Separator::NewLine, "x")
+        .token(Separator::Whitespace, LexemeKind::Equals)
+        .token(Separator::Whitespace, LexemeKind::Quote)
+        .token(Separator::Nothing, LexemeKind::Text("a".into()))
+        .token(Separator::Nothing, LexemeKind::Text("\t".into()))
+        .token(Separator::Nothing, LexemeKind::Text("b".into()))
+        .open(Separator::Nothing, Enclosure::Curly)
+        .symbol(Separator::Nothing, "y")
+        .close(Separator::Nothing, Enclosure::Curly)
+        .token(Separator::Nothing, LexemeKind::Quote)
+        .ok_without_pos();
+}
+
+#[test]
+fn invalid_escape() {
+    TestCase::new(r#""\q""#).err();
+}
@@ -11,14 +11,14 @@ pub(super) type Tokens = Vec<L<Token>>;
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(super) enum NewLine {
     LF,
-    CRLF,
+    CrLf,
 }
 
 impl NewLine {
     fn bytes(self) -> usize {
         match self {
             Self::LF => 1,
-            Self::CRLF => 2,
+            Self::CrLf => 2,
         }
     }
 
@@ -34,8 +34,11 @@ pub(super) enum Token {
     EndOfLine(NewLine),
     Plus,
     Minus,
+    Arrow,
     Star,
+    DoubleStar,
     Slash,
+    Percent,
     Dot,
     Greater,
     GreaterOrEq,
@@ -51,11 +54,24 @@ pub(super) enum Token {
     LogicalOr,
     Colon,
     DoubleColon,
+    Comma,
     Open(Enclosure),
     Close(Enclosure),
     Underscore,
     Digits(Substr),
+    Decimal(Substr),
     Word(Substr),
+    Quote,
+    Text(Substr),   // Text of a string literal, without escape sequences
+    Escape(Substr), // Backslash and the escaped char
+}
+
+// Nesting of the string literals being tokenized. Each level is either the text of a string or
+// an interpolated expression, with the number of curly braces open in it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Nesting {
+    Text,
+    Interpolation(usize),
 }
 
 impl Token {
@@ -69,8 +85,8 @@ impl Token {
     fn chars(&self) -> usize {
         match self {
             Token::EndOfLine(nl) => nl.chars(),
-            Token::Digits(s) => s.chars().count(),
-            Token::Word(s) => s.chars().count(),
+            Token::Digits(s) | Token::Decimal(s) => s.chars().count(),
+            Token::Word(s) | Token::Text(s) | Token::Escape(s) => s.chars().count(),
             _ => self.bytes(),
         }
     }
@@ -80,8 +96,11 @@ impl Token {
             Token::Spaces(n) => *n,
             Token::Tabs(n) => *n,
             Token::EndOfLine(nl) => nl.bytes(),
-            Token::Digits(s) => s.len(),
-            Token::Word(s) => s.len(),
+            Token::Digits(s) | Token::Decimal(s) => s.len(),
+            Token::Word(s) | Token::Text(s) | Token::Escape(s) => s.len(),
+            Token::Arrow => 2,
+            Token::DoubleStar => 2,
+            Token::Equals => 2,
             Token::NotEquals => 2,
             Token::LogicalAnd => 2,
             Token::LogicalOr => 2,
@@ -96,6 +115,7 @@ impl Token {
 struct Tokenizer {
     chars: Vec<char>,
     tokens: Tokens,
+    nesting: Vec<L<Nesting>>,
     input: ArcStr,
     byte_index: usize, // Current byte index from the beginning of the input
     char_index: usize, // Current char index from the beginning of the input
@@ -106,6 +126,7 @@ impl Tokenizer {
         Tokenizer {
             chars: input.chars().collect(),
             tokens: Default::default(),
+            nesting: Default::default(),
             input,
             byte_index: 0,
             char_index: 0,
@@ -188,24 +209,31 @@ impl Tokenizer {
     fn tokenize(mut self) -> Result<Tokens> {
         let mut errors: Errors = Default::default();
         while let Some(c) = self.peek() {
-            if !self.add_known_first_char(c) && !self.add_digits(c) && !self.add_word(c) {
+            if self.is_text() {
+                self.add_text(c);
+            } else if !self.add_known_first_char(c) && !self.add_digits(c) && !self.add_word(c) {
                 errors.add(self.loc().to_wrap(Error::UnexpectedChar(c)));
                 self.consume();
             }
         }
+        // The outermost string literal is the one left open
+        if let Some(open) = self.nesting.first() {
+            errors.add(open.loc.wrap(Error::UnterminatedString));
+        }
         errors.to_result(self.tokens)
     }
 
     fn add_known_first_char(&mut self, c: char) -> bool {
         match c {
             '\n' => self.add_token(Token::EndOfLine(NewLine::LF)),
-            '\r' => self.add_token_if_next('\n', Token::EndOfLine(NewLine::CRLF)),
+            '\r' => self.add_token_if_next('\n', Token::EndOfLine(NewLine::CrLf)),
             ' ' => self.add_token(Token::Spaces(self.count_while(' '))),
             '\t' => self.add_token(Token::Tabs(self.count_while('\t'))),
             '+' => self.add_token(Token::Plus),
-            '-' => self.add_token(Token::Minus),
-            '*' => self.add_token(Token::Star),
+            '-' => self.add_token_if_next_or_else('>', Token::Arrow, Token::Minus),
+            '*' => self.add_token_if_next_or_else('*', Token::DoubleStar, Token::Star),
             '/' => self.add_token(Token::Slash),
+            '%' => self.add_token(Token::Percent),
             '.' => self.add_token(Token::Dot),
             '>' => self.add_token_if_next_or_else('=', Token::GreaterOrEq, Token::Greater),
             '<' => self.add_token_if_next_or_else('=', Token::LessOrEq, Token::Less),
@@ -215,19 +243,87 @@ impl Tokenizer {
             '|' => self.add_token_if_next_or_else('|', Token::LogicalOr, Token::Pipe),
             '(' => self.add_token(Token::Open(Enclosure::Parens)),
             ')' => self.add_token(Token::Close(Enclosure::Parens)),
-            '{' => self.add_token(Token::Open(Enclosure::Curly)),
-            '}' => self.add_token(Token::Close(Enclosure::Curly)),
+            '{' => self.add_open_curly(),
+            '}' => self.add_close_curly(),
             '[' => self.add_token(Token::Open(Enclosure::Square)),
             ']' => self.add_token(Token::Close(Enclosure::Square)),
             '_' => self.add_token(Token::Underscore),
             ':' => self.add_token_if_next_or_else(':', Token::DoubleColon, Token::Colon),
+            ',' => self.add_token(Token::Comma),
+            '"' => {
+                self.nesting.push(self.loc().to_wrap(Nesting::Text));
+                self.add_token(Token::Quote)
+            }
             _ => false,
         }
     }
 
+    // Returns true if we are in the text of a string literal
+    fn is_text(&self) -> bool {
+        self.nesting.last().is_some_and(|n| n.it == Nesting::Text)
+    }
+
+    // A curly brace in the text of a string starts an interpolated expression
+    fn add_text(&mut self, c: char) {
+        match c {
+            '"' => {
+                self.nesting.pop();
+                self.add_token(Token::Quote)
+            }
+            '\\' => {
+                let to = self.byte_index + 1 + self.peek_ahead(1).map_or(0, char::len_utf8);
+                let escape = self.input.substr(self.byte_index..to);
+                self.add_token(Token::Escape(escape))
+            }
+            '{' => {
+                self.nesting
+                    .push(self.loc().to_wrap(Nesting::Interpolation(0)));
+                self.add_token(Token::Open(Enclosure::Curly))
+            }
+            _ => {
+                let text = self.substr_while(|n| !matches!(n, '"' | '\\' | '{'));
+                self.add_token(Token::Text(text))
+            }
+        };
+    }
+
+    fn add_open_curly(&mut self) -> bool {
+        if let Some(Nesting::Interpolation(n)) = self.nesting.last_mut().map(|n| &mut n.it) {
+            *n += 1;
+        }
+        self.add_token(Token::Open(Enclosure::Curly))
+    }
+
+    // The closing curly brace of an interpolated expression returns to the text of the string
+    fn add_close_curly(&mut self) -> bool {
+        match self.nesting.last_mut().map(|n| &mut n.it) {
+            Some(Nesting::Interpolation(0)) => {
+                self.nesting.pop();
+            }
+            Some(Nesting::Interpolation(n)) => *n -= 1,
+            _ => (),
+        }
+        self.add_token(Token::Close(Enclosure::Curly))
+    }
+
     fn add_digits(&mut self, c: char) -> bool {
         if c.is_ascii_digit() {
-            self.add_token(Token::Digits(self.substr_while(|n| n.is_ascii_digit())))
+            let digits = self.substr_while(|n| n.is_ascii_digit());
+            // A dot followed by a digit makes it a decimal literal
+            // Digits after a dot are a tuple index, so that t.0.1 is not read as t . 0.1
+            let n = digits.len();
+            let is_digit = |c: Option<char>| c.is_some_and(|c| c.is_ascii_digit());
+            let index = self.tokens.last().is_some_and(|t| t.it == Token::Dot);
+            if !index && self.peek_ahead(n) == Some('.') && is_digit(self.peek_ahead(n + 1)) {
+                let mut len = n + 2;
+                while is_digit(self.peek_ahead(len)) {
+                    len += 1;
+                }
+                let decimal = self.input.substr(self.byte_index..self.byte_index + len);
+                self.add_token(Token::Decimal(decimal))
+            } else {
+                self.add_token(Token::Digits(digits))
+            }
         } else {
             false
         }
@@ -252,10 +348,10 @@ impl Tokenizer {
     }
 
     fn add_token_if_next(&mut self, c: char, token: Token) -> bool {
-        if let Some(next) = self.peek_ahead(1) {
-            if next == c {
-                return self.add_token(token);
-            }
+        if let Some(next) = self.peek_ahead(1)
+            && next == c
+        {
+            return self.add_token(token);
         }
         false
     }
@@ -41,10 +41,22 @@ impl TestCase {
         self.token(Token::Digits(ArcStr::from(digits).substr(0..)))
     }
 
+    fn decimal(self, decimal: &str) -> Self {
+        self.token(Token::Decimal(ArcStr::from(decimal).substr(0..)))
+    }
+
     fn word(self, word: &str) -> Self {
         self.token(Token::Word(ArcStr::from(word).substr(0..)))
     }
 
+    fn text(self, text: &str) -> Self {
+        self.token(Token::Text(ArcStr::from(text).substr(0..)))
+    }
+
+    fn escape(self, escape: &str) -> Self {
+        self.token(Token::Escape(ArcStr::from(escape).substr(0..)))
+    }
+
     fn ok(&self) {
         match super::tokenize(self.input.as_str()) {
             Ok(tokens) => assert_eq!(tokens, self.tokens),
@@ -75,7 +87,7 @@ fn lf() {
 
 #[test]
 fn crlf() {
-    TestCase::new("\r\n").newline(CRLF).ok();
+    TestCase::new("\r\n").newline(CrLf).ok();
 }
 
 #[test]
@@ -125,6 +137,69 @@ fn digits2() {
         .ok();
 }
 
+#[test]
+fn decimal() {
+    TestCase::new("1.25 3.")
+        .decimal("1.25")
+        .spaces(1)
+        .digits("3")
+        .token(Token::Dot)
+        .ok();
+}
+
+#[test]
+fn string() {
+    TestCase::new(r#""a b\"c" """#)
+        .token(Token::Quote)
+        .text("a b")
+        .escape(r#"\""#)
+        .text("c")
+        .token(Token::Quote)
+        .spaces(1)
+        .token(Token::Quote)
+        .token(Token::Quote)
+        .ok();
+}
+
+#[test]
+fn interpolation() {
+    TestCase::new(r#""x={ {1} }""#)
+        .token(Token::Quote)
+        .text("x=")
+        .token(Token::Open(Enclosure::Curly))
+        .spaces(1)
+        .token(Token::Open(Enclosure::Curly))
+        .digits("1")
+        .token(Token::Close(Enclosure::Curly))
+        .spaces(1)
+        .token(Token::Close(Enclosure::Curly))
+        .token(Token::Quote)
+        .ok();
+    TestCase::new(r#""{"a"}""#)
+        .token(Token::Quote)
+        .token(Token::Open(Enclosure::Curly))
+        .token(Token::Quote)
+        .text("a")
+        .token(Token::Quote)
+        .token(Token::Close(Enclosure::Curly))
+        .token(Token::Quote)
+        .ok();
+}
+
+#[test]
+fn unterminated_string() {
+    assert!(super::tokenize(r#"1 "a{"b"}"#).is_err());
+}
+
+#[test]
+fn rem_and_pow() {
+    TestCase::new("%***")
+        .token(Token::Percent)
+        .token(Token::DoubleStar)
+        .token(Token::Star)
+        .ok();
+}
+
 #[test]
 fn word1() {
     TestCase::new("abc").word("abc").ok();
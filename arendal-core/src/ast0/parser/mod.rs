@@ -8,12 +8,13 @@ pub enum Enclosure {
 }
 
 use super::{
-    BinaryOp, Builder, ExprRef, GAssignmentRef, LAssignmentRef, Module, NewTypeRef, Package,
-    Segment,
+    Arm, BinaryOp, Builder, Entry, Expr, ExprRef, Field, FieldDfn, FieldPattern, GAssignmentRef,
+    LAssignmentRef, Module, NewTypeRef, Package, Param, PatternRef, Q, Segment, StringPart,
+    TypeDfn, TypeExprRef, UnaryOp, Variant,
 };
-use crate::error::{Error, Loc, Result};
+use crate::error::{Error, Errors, L, Loc, Result};
 use crate::keyword::Keyword;
-use crate::symbol::{self, FQPath, Pkg, Symbol};
+use crate::symbol::{self, FQPath, Path, Pkg, Symbol, TSymbol};
 use crate::visibility::Visibility;
 use std::rc::Rc;
 use std::sync::Arc;
@@ -27,6 +28,19 @@ pub fn parse(pkg: Pkg, input: &str) -> Result<Package> {
     Package::new(vec![Arc::new(module)])
 }
 
+// Parses each input as the module of the package at the provided path
+pub fn parse_modules(pkg: Pkg, inputs: &[(Path, &str)]) -> Result<Package> {
+    let mut errors = Errors::default();
+    let mut modules = Vec::with_capacity(inputs.len());
+    for (path, input) in inputs {
+        if let Some(module) = errors.add_result(parse_module(pkg.path(path.clone()), input)) {
+            modules.push(Arc::new(module));
+        }
+    }
+    errors.to_unit_result()?;
+    Package::new(modules)
+}
+
 fn parse_module(path: FQPath, input: &str) -> Result<Module> {
     let lexemes = lex(input)?;
     Parser::new(lexemes).parse(path)
@@ -35,6 +49,7 @@ fn parse_module(path: FQPath, input: &str) -> Result<Module> {
 type PResult<T> = Result<(T, Parser)>;
 type EResult = PResult<ExprRef>;
 type TResult = PResult<NewTypeRef>;
+type TEResult = PResult<TypeExprRef>;
 
 fn map<T, F, U>(result: PResult<T>, f: F) -> PResult<U>
 where
@@ -50,6 +65,7 @@ where
 struct Parser {
     input: Rc<Lexemes>,
     index: usize,
+    records: bool, // Whether a type followed by curly braces is a record construction
 }
 
 impl Parser {
@@ -57,6 +73,7 @@ impl Parser {
         Self {
             input: Rc::new(input),
             index: 0,
+            records: true,
         }
     }
 
@@ -71,6 +88,15 @@ impl Parser {
         Self {
             input: self.input.clone(),
             index: self.index + 1,
+            records: self.records,
+        }
+    }
+
+    // Returns the same parser, allowing record constructions or not
+    fn with_records(&self, records: bool) -> Self {
+        Self {
+            records,
+            ..self.clone()
         }
     }
 
@@ -136,6 +162,20 @@ impl Parser {
         self.err(Error::ExpressionExpected)
     }
 
+    fn kind_expected(&self, kind: LexemeKind, error: Error) -> Result<Parser> {
+        if self.kind_equals(kind) {
+            Ok(self.advance())
+        } else {
+            self.err(error)
+        }
+    }
+
+    // Returns whether the current lexeme starts a named function definition
+    fn is_fn_definition(&self) -> bool {
+        self.is_keyword(Keyword::Fn)
+            && matches!(self.advance().peek_kind(), Some(LexemeKind::Symbol(_)))
+    }
+
     fn keyword_expected(&self, keyword: Keyword) -> Result<Parser> {
         if self.is_keyword(keyword) {
             Ok(self.advance())
@@ -146,10 +186,11 @@ impl Parser {
 
     fn expect_eos<O>(&self, o: O) -> PResult<()>
     where
-        O: FnOnce() -> (),
+        O: FnOnce(),
     {
         if self.is_eos() {
-            self.ok(o())
+            o();
+            self.ok(())
         } else {
             self.err(Error::EndOfItemExpected)
         }
@@ -174,10 +215,13 @@ impl Parser {
             &next
         };
         if parser.is_keyword(Keyword::Type) {
-            let (newtype, parser) = self.advance().rule_typedef(visibility)?;
+            let (newtype, parser) = parser.advance().rule_typedef(visibility)?;
             parser.expect_eos(|| module.types.push(newtype))
         } else if parser.is_keyword(Keyword::Let) {
-            let (a, parser) = self.advance().rule_g_assignment(visibility)?;
+            let (a, parser) = parser.advance().rule_g_assignment(visibility)?;
+            parser.expect_eos(|| module.assignments.push(a))
+        } else if parser.is_fn_definition() {
+            let (a, parser) = parser.advance().rule_g_fn_definition(visibility)?;
             parser.expect_eos(|| module.assignments.push(a))
         } else {
             if visibility == Visibility::Module {
@@ -194,8 +238,49 @@ impl Parser {
             Some(LexemeKind::TSymbol(symbol)) => Ok(symbol.clone()),
             _ => self.err(Error::TSymbolAfterTypeExpected),
         }?;
-        self.advance()
-            .ok(self.builder().new_type(visibility, symbol).singleton())
+        let (params, parser) = self.advance().rule_type_params()?;
+        let builder = self.builder().new_type(visibility, symbol).params(params);
+        if parser.kind_equals(LexemeKind::Assignment) {
+            map(parser.advance().rule_variants(), |variants| {
+                builder.union(variants)
+            })
+        } else {
+            map(parser.rule_type_dfn(), |dfn| builder.build(dfn))
+        }
+    }
+
+    // Definition of a type or a variant, other than unions
+    fn rule_type_dfn(&self) -> PResult<TypeDfn> {
+        if self.is_unseparated(LexemeKind::Open(Enclosure::Parens)) {
+            map(self.rule_list(Self::rule_type), TypeDfn::Tuple)
+        } else if self.is_same_line(LexemeKind::Open(Enclosure::Curly)) {
+            map(self.rule_fields(Self::rule_field_dfn), TypeDfn::Record)
+        } else {
+            self.ok(TypeDfn::Singleton)
+        }
+    }
+
+    // Variants are separated by pipes, and there can be one before the first variant
+    fn rule_variants(&self) -> PResult<Vec<L<Variant>>> {
+        let mut parser = if self.kind_equals(LexemeKind::Pipe) {
+            self.advance()
+        } else {
+            self.clone()
+        };
+        let mut variants = Vec::default();
+        loop {
+            let symbol = match parser.peek_kind() {
+                Some(LexemeKind::TSymbol(symbol)) => symbol.clone(),
+                _ => return parser.err(Error::VariantExpected),
+            };
+            let (dfn, next) = parser.advance().rule_type_dfn()?;
+            variants.push(parser.builder().variant(symbol, dfn));
+            if next.kind_equals(LexemeKind::Pipe) {
+                parser = next.advance();
+            } else {
+                return next.ok(variants);
+            }
+        }
     }
 
     fn rule_bstatement(
@@ -207,6 +292,10 @@ impl Parser {
             map(self.advance().rule_l_assignment(), |a| {
                 assignments.push(a);
             })
+        } else if self.is_fn_definition() {
+            map(self.advance().rule_l_fn_definition(), |a| {
+                assignments.push(a);
+            })
         } else {
             map(self.rule_expression(), |e| {
                 exprs.push(e);
@@ -216,33 +305,67 @@ impl Parser {
 
     fn rule_g_assignment(&self, visibility: Visibility) -> PResult<GAssignmentRef> {
         let (lvalue, parser) = self.get_lvalue()?;
-        if parser.kind_equals(LexemeKind::Assignment) {
-            let (expr, next) = parser.advance().rule_expression()?;
-            next.ok(parser.builder().g_let(visibility, lvalue, expr))
-        } else {
-            parser.err(Error::AssignmentExpected)
-        }
+        let (expr, next) = parser.rule_assignment_rest()?;
+        next.ok(parser.builder().g_let(visibility, lvalue, expr))
     }
 
     fn rule_l_assignment(&self) -> PResult<LAssignmentRef> {
         let (lvalue, parser) = self.get_lvalue()?;
+        let (expr, next) = parser.rule_assignment_rest()?;
+        next.ok(parser.builder().l_let(lvalue, expr))
+    }
+
+    // The assigned expression is ascribed the type annotation of the symbol, if any
+    fn rule_assignment_rest(&self) -> EResult {
+        let (tipo, parser) = if self.kind_equals(LexemeKind::Colon) {
+            map(self.advance().rule_type(), Some)?
+        } else {
+            (None, self.clone())
+        };
         if parser.kind_equals(LexemeKind::Assignment) {
             let (expr, next) = parser.advance().rule_expression()?;
-            next.ok(parser.builder().l_let(lvalue, expr))
+            match tipo {
+                Some(tipo) => next.ok(parser.builder().ascription(expr, tipo)),
+                None => next.ok(expr),
+            }
         } else {
             parser.err(Error::AssignmentExpected)
         }
     }
 
+    // Named functions are assignments of lambdas, with a mandatory result type
+    fn rule_g_fn_definition(&self, visibility: Visibility) -> PResult<GAssignmentRef> {
+        let (lvalue, parser) = self.get_lvalue()?;
+        let (lambda, next) = parser.rule_lambda_rest(true)?;
+        next.ok(parser.builder().g_let(visibility, lvalue, lambda))
+    }
+
+    fn rule_l_fn_definition(&self) -> PResult<LAssignmentRef> {
+        let (lvalue, parser) = self.get_lvalue()?;
+        let (lambda, next) = parser.rule_lambda_rest(true)?;
+        next.ok(parser.builder().l_let(lvalue, lambda))
+    }
+
     fn get_lvalue(&self) -> PResult<Symbol> {
-        if let Some(lexeme) = self.peek() {
-            if let LexemeKind::Symbol(id) = &lexeme.kind {
-                return self.advance().ok(id.clone());
-            }
+        if let Some(lexeme) = self.peek()
+            && let LexemeKind::Symbol(id) = &lexeme.kind
+        {
+            return self.advance().ok(id.clone());
         }
         self.err(Error::LValueExpected)
     }
 
+    // Expressions in parentheses can be ascribed a type, e.g., (x + 1 : Integer)
+    fn rule_ascribed(&self) -> EResult {
+        let (expr, parser) = self.rule_expression()?;
+        if parser.kind_equals(LexemeKind::Colon) {
+            let (tipo, next) = parser.advance().rule_type()?;
+            next.ok(self.builder().ascription(expr, tipo))
+        } else {
+            parser.ok(expr)
+        }
+    }
+
     fn rule_expression(&self) -> EResult {
         let (mut left, mut parser) = self.rule_expr()?;
         while parser.is_keyword(Keyword::Then) {
@@ -255,6 +378,10 @@ impl Parser {
     fn rule_expr(&self) -> EResult {
         if self.is_keyword(Keyword::If) {
             self.rule_conditional()
+        } else if self.is_keyword(Keyword::Fn) {
+            self.advance().rule_lambda_rest(false)
+        } else if self.is_keyword(Keyword::Match) {
+            self.rule_match()
         } else {
             self.rule_subexpr()
         }
@@ -267,6 +394,301 @@ impl Parser {
         parser4.ok(self.builder().conditional(expr, then, otherwise))
     }
 
+    // The arms are enclosed in curly braces, so the matched expression can't be a record
+    // construction unless it is enclosed.
+    fn rule_match(&self) -> EResult {
+        let (expr, parser) = self.advance().with_records(false).rule_subexpr()?;
+        let (arms, next) = parser
+            .with_records(self.records)
+            .rule_fields(Self::rule_arm)?;
+        next.ok(self.builder().pattern_match(expr, arms))
+    }
+
+    fn rule_arm(&self) -> PResult<L<Arm>> {
+        let (pattern, parser) = self.rule_pattern()?;
+        let (expr, next) = parser
+            .kind_expected(LexemeKind::Arrow, Error::ArrowExpected)?
+            .rule_expression()?;
+        next.ok(self.builder().arm(pattern, expr))
+    }
+
+    fn rule_pattern(&self) -> PResult<PatternRef> {
+        match self.peek_kind() {
+            Some(LexemeKind::Underscore) => self.advance().ok(self.builder().p_wildcard()),
+            Some(LexemeKind::Integer(n)) => self.advance().ok(self.builder().p_integer(n.clone())),
+            Some(LexemeKind::Minus) => match self.advance().peek_kind() {
+                Some(LexemeKind::Integer(n)) => {
+                    let n = -n.clone();
+                    self.advance().advance().ok(self.builder().p_integer(n))
+                }
+                _ => self.err(Error::PatternExpected),
+            },
+            Some(LexemeKind::TSymbol(symbol)) => self.rule_q_pattern(Segment::Type(symbol.clone())),
+            Some(LexemeKind::Symbol(symbol)) => {
+                self.rule_q_pattern(Segment::Symbol(symbol.clone()))
+            }
+            // As with expressions, a single pattern without a trailing comma is not a tuple
+            Some(LexemeKind::Open(Enclosure::Parens)) => {
+                let ((mut patterns, trailing), next) = self.rule_items(Self::rule_pattern)?;
+                if patterns.is_empty() {
+                    let none = Q {
+                        segments: Vec::default(),
+                        symbol: symbol::NONE.clone(),
+                    };
+                    next.ok(self.builder().p_tsymbol(none))
+                } else if patterns.len() == 1 && !trailing {
+                    next.ok(patterns.pop().unwrap())
+                } else {
+                    next.ok(self.builder().p_tuple(patterns))
+                }
+            }
+            _ => self.err(Error::PatternExpected),
+        }
+    }
+
+    // Unqualified symbols are bindings. Types can be followed by the patterns of the tuple
+    // elements or of the record fields.
+    fn rule_q_pattern(&self, segment: Segment) -> PResult<PatternRef> {
+        let (expr, next) = self.rule_q(segment)?;
+        match &expr.it {
+            Expr::Symbol(q) if q.segments.is_empty() => {
+                next.ok(self.builder().p_symbol(q.symbol.clone()))
+            }
+            Expr::TSymbol(q) => {
+                if next.is_unseparated(LexemeKind::Open(Enclosure::Parens)) {
+                    map(next.rule_list(Self::rule_pattern), |patterns| {
+                        self.builder().p_constructor(q.clone(), patterns)
+                    })
+                } else if next.is_same_line(LexemeKind::Open(Enclosure::Curly)) {
+                    map(next.rule_fields(Self::rule_field_pattern), |fields| {
+                        self.builder().p_record(q.clone(), fields)
+                    })
+                } else {
+                    next.ok(self.builder().p_tsymbol(q.clone()))
+                }
+            }
+            _ => self.err(Error::PatternExpected),
+        }
+    }
+
+    fn rule_field_pattern(&self) -> PResult<L<FieldPattern>> {
+        match self.peek_kind() {
+            Some(LexemeKind::Symbol(symbol)) => {
+                let (pattern, next) = self
+                    .advance()
+                    .kind_expected(LexemeKind::Colon, Error::ColonExpected)?
+                    .rule_pattern()?;
+                next.ok(self.builder().field_pattern(symbol.clone(), pattern))
+            }
+            _ => self.err(Error::FieldExpected),
+        }
+    }
+
+    // Parses the parameters, result type and body of a function. The body extends as far as
+    // possible, as with conditionals.
+    fn rule_lambda_rest(&self, named: bool) -> EResult {
+        let (type_params, parser) = self.rule_type_params()?;
+        let (params, parser) = parser.rule_list(Self::rule_param)?;
+        let (result, parser) = if parser.kind_equals(LexemeKind::Colon) {
+            map(parser.advance().rule_type(), Some)?
+        } else if named {
+            return parser.err(Error::TypeAnnotationExpected);
+        } else {
+            (None, parser)
+        };
+        let (effects, parser) = parser.rule_effects()?;
+        let (body, next) = parser
+            .kind_expected(LexemeKind::Assignment, Error::AssignmentExpected)?
+            .rule_expr()?;
+        next.ok(self
+            .builder()
+            .effectful_lambda(type_params, params, result, effects, body))
+    }
+
+    // The effects of a function follow its result type, e.g., (String) -> None with IO
+    fn rule_effects(&self) -> PResult<Vec<TSymbol>> {
+        let mut effects = Vec::default();
+        if !self.is_keyword(Keyword::With) {
+            return self.ok(effects);
+        }
+        let mut parser = self.clone();
+        loop {
+            parser = parser.advance();
+            match parser.peek_kind() {
+                Some(LexemeKind::TSymbol(symbol)) => effects.push(symbol.clone()),
+                _ => return parser.err(Error::EffectExpected),
+            }
+            parser = parser.advance();
+            if !parser.kind_equals(LexemeKind::Comma) {
+                return parser.ok(effects);
+            }
+        }
+    }
+
+    // Type parameters follow the type or function with no separator, e.g., Pair[A, B]
+    fn rule_type_params(&self) -> PResult<Vec<TSymbol>> {
+        if self.is_unseparated(LexemeKind::Open(Enclosure::Square)) {
+            map(
+                self.rule_enclosed(Enclosure::Square, Self::rule_type_param),
+                |(params, _)| params,
+            )
+        } else {
+            self.ok(Vec::default())
+        }
+    }
+
+    fn rule_type_param(&self) -> PResult<TSymbol> {
+        match self.peek_kind() {
+            Some(LexemeKind::TSymbol(symbol)) => self.advance().ok(symbol.clone()),
+            _ => self.err(Error::TypeParameterExpected),
+        }
+    }
+
+    fn rule_param(&self) -> PResult<L<Param>> {
+        match self.peek_kind() {
+            Some(LexemeKind::Symbol(symbol)) => {
+                let (tipo, next) = self
+                    .advance()
+                    .kind_expected(LexemeKind::Colon, Error::TypeAnnotationExpected)?
+                    .rule_type()?;
+                next.ok(self.builder().param(symbol.clone(), tipo))
+            }
+            _ => self.err(Error::ParameterExpected),
+        }
+    }
+
+    fn rule_field_dfn(&self) -> PResult<L<FieldDfn>> {
+        match self.peek_kind() {
+            Some(LexemeKind::Symbol(symbol)) => {
+                let (tipo, next) = self
+                    .advance()
+                    .kind_expected(LexemeKind::Colon, Error::TypeAnnotationExpected)?
+                    .rule_type()?;
+                next.ok(self.builder().field_dfn(symbol.clone(), tipo))
+            }
+            _ => self.err(Error::FieldExpected),
+        }
+    }
+
+    fn rule_field(&self) -> PResult<L<Field>> {
+        match self.peek_kind() {
+            Some(LexemeKind::Symbol(symbol)) => {
+                let (expr, next) = self
+                    .advance()
+                    .kind_expected(LexemeKind::Colon, Error::ColonExpected)?
+                    .rule_expression()?;
+                next.ok(self.builder().field(symbol.clone(), expr))
+            }
+            _ => self.err(Error::FieldExpected),
+        }
+    }
+
+    // Function types are right-associative. Parenthesized types not followed by an arrow are
+    // tuple types, unless there is a single type without a trailing comma.
+    fn rule_type(&self) -> TEResult {
+        match self.peek_kind() {
+            Some(LexemeKind::TSymbol(symbol)) => self.rule_qtype(Segment::Type(symbol.clone())),
+            Some(LexemeKind::Symbol(symbol)) => self.rule_qtype(Segment::Symbol(symbol.clone())),
+            Some(LexemeKind::Open(Enclosure::Parens)) => {
+                let ((mut types, trailing), parser) = self.rule_items(Self::rule_type)?;
+                if parser.kind_equals(LexemeKind::Arrow) {
+                    let (result, parser) = parser.advance().rule_type()?;
+                    let (effects, next) = parser.rule_effects()?;
+                    next.ok(self
+                        .builder()
+                        .type_effectful_function(types, result, effects))
+                } else if types.len() == 1 && !trailing {
+                    parser.ok(types.pop().unwrap())
+                } else {
+                    parser.ok(self.builder().type_tuple(types))
+                }
+            }
+            _ => self.err(Error::TypeExpected),
+        }
+    }
+
+    // Type arguments follow the type with no separator, e.g., List[Integer]
+    fn rule_qtype(&self, segment: Segment) -> TEResult {
+        let (expr, next) = self.rule_q(segment)?;
+        match &expr.it {
+            Expr::TSymbol(q) if next.is_unseparated(LexemeKind::Open(Enclosure::Square)) => {
+                let ((types, _), next) = next.rule_enclosed(Enclosure::Square, Self::rule_type)?;
+                next.ok(self.builder().type_parameterized(q.clone(), types))
+            }
+            Expr::TSymbol(q) => next.ok(self.builder().type_symbol(q.clone())),
+            _ => self.err(Error::TypeExpected),
+        }
+    }
+
+    // Parses a comma-separated list of items enclosed in parentheses. A trailing comma is allowed.
+    fn rule_list<T, F>(&self, rule: F) -> PResult<Vec<T>>
+    where
+        F: Fn(&Parser) -> PResult<T>,
+    {
+        map(self.rule_items(rule), |(items, _)| items)
+    }
+
+    // Same as rule_list, with the items enclosed in curly braces
+    fn rule_fields<T, F>(&self, rule: F) -> PResult<Vec<T>>
+    where
+        F: Fn(&Parser) -> PResult<T>,
+    {
+        map(self.rule_enclosed(Enclosure::Curly, rule), |(items, _)| {
+            items
+        })
+    }
+
+    // Same as rule_list, also returning whether there is a trailing comma
+    fn rule_items<T, F>(&self, rule: F) -> PResult<(Vec<T>, bool)>
+    where
+        F: Fn(&Parser) -> PResult<T>,
+    {
+        self.rule_enclosed(Enclosure::Parens, rule)
+    }
+
+    fn rule_enclosed<T, F>(&self, enclosure: Enclosure, rule: F) -> PResult<(Vec<T>, bool)>
+    where
+        F: Fn(&Parser) -> PResult<T>,
+    {
+        let mut parser = self
+            .with_records(true)
+            .kind_expected(LexemeKind::Open(enclosure), Error::OpenExpected(enclosure))?;
+        let mut items = Vec::default();
+        loop {
+            if parser.kind_equals(LexemeKind::Close(enclosure)) {
+                let trailing = !items.is_empty();
+                return parser
+                    .advance()
+                    .with_records(self.records)
+                    .ok((items, trailing));
+            }
+            let (item, next) = rule(&parser)?;
+            items.push(item);
+            if next.kind_equals(LexemeKind::Comma) {
+                parser = next.advance();
+            } else {
+                return next
+                    .kind_expected(
+                        LexemeKind::Close(enclosure),
+                        Error::CloseExpected(enclosure),
+                    )
+                    .map(|p| ((items, false), p.with_records(self.records)));
+            }
+        }
+    }
+
+    // Returns whether the current lexeme is of the provided kind and in the same line
+    fn is_same_line(&self, kind: LexemeKind) -> bool {
+        self.peek_if_kind(kind)
+            .is_some_and(|l| l.separator != Separator::NewLine)
+    }
+
+    // Returns whether the current lexeme is of the provided kind with no separator before it
+    fn is_unseparated(&self, kind: LexemeKind) -> bool {
+        self.peek_if_kind(kind)
+            .is_some_and(|l| l.separator == Separator::Nothing)
+    }
+
     fn rule_subexpr(&self) -> EResult {
         self.rule_logterm()
     }
@@ -333,30 +755,139 @@ impl Parser {
         let op = |k: &LexemeKind| match k {
             LexemeKind::Star => Some(BinaryOp::Mul),
             LexemeKind::Slash => Some(BinaryOp::Div),
+            LexemeKind::Percent => Some(BinaryOp::Rem),
             _ => None,
         };
-        self.binary_rule(op, Self::rule_primary)
+        self.binary_rule(op, Self::rule_unary)
+    }
+
+    // Unary operators bind tighter than any binary operator but exponentiation.
+    fn rule_unary(&self) -> EResult {
+        let op = self.peek().and_then(|l| match l.kind {
+            LexemeKind::Minus => Some(UnaryOp::Minus),
+            LexemeKind::Bang => Some(UnaryOp::Not),
+            _ => None,
+        });
+        match op {
+            Some(op) => {
+                let (expr, parser) = self.advance().rule_unary()?;
+                parser.ok(self.builder().unary(op, expr))
+            }
+            None => self.rule_power(),
+        }
+    }
+
+    // Exponentiation is right-associative.
+    fn rule_power(&self) -> EResult {
+        let (base, parser) = self.rule_call()?;
+        if parser
+            .peek()
+            .is_some_and(|l| l.kind == LexemeKind::DoubleStar)
+        {
+            let (exp, p2) = parser.advance().rule_unary()?;
+            p2.ok(parser.builder().binary(BinaryOp::Pow, base, exp))
+        } else {
+            parser.ok(base)
+        }
+    }
+
+    // Calls, field accesses, indexing and record updates bind tighter than any operator. The
+    // arguments and indices must follow the expression with no separator, so that a parenthesized
+    // expression or a list in the next line is not an argument list or an index.
+    fn rule_call(&self) -> EResult {
+        let (mut expr, mut parser) = self.rule_primary()?;
+        loop {
+            if parser.is_unseparated(LexemeKind::Open(Enclosure::Parens)) {
+                let (args, next) = parser.rule_list(Self::rule_expression)?;
+                (expr, parser) = next.ok(self.builder().call(expr, args))?;
+            } else if parser.is_unseparated(LexemeKind::Open(Enclosure::Square)) {
+                let (index, next) = parser.advance().with_records(true).rule_expression()?;
+                let next = next.kind_expected(
+                    LexemeKind::Close(Enclosure::Square),
+                    Error::CloseExpected(Enclosure::Square),
+                )?;
+                (expr, parser) = next
+                    .with_records(self.records)
+                    .ok(self.builder().index(expr, index))?;
+            } else if parser.is_unseparated(LexemeKind::Dot) {
+                let next = parser.advance();
+                if let Some(Lexeme {
+                    kind: LexemeKind::Symbol(symbol),
+                    separator: Separator::Nothing,
+                    ..
+                }) = next.peek()
+                {
+                    (expr, parser) = next
+                        .advance()
+                        .ok(self.builder().record_field(expr, symbol.clone()))?;
+                    continue;
+                }
+                let index = match next.peek() {
+                    Some(Lexeme {
+                        kind: LexemeKind::Integer(n),
+                        separator: Separator::Nothing,
+                        ..
+                    }) => n.to_string().parse::<usize>().ok(),
+                    _ => None,
+                };
+                match index {
+                    Some(index) => {
+                        (expr, parser) =
+                            next.advance().ok(self.builder().tuple_field(expr, index))?
+                    }
+                    None => return next.err(Error::TupleIndexExpected),
+                }
+            } else if parser.is_keyword(Keyword::With) {
+                let (fields, next) = parser.advance().rule_fields(Self::rule_field)?;
+                (expr, parser) = next.ok(self.builder().update(expr, fields))?;
+            } else {
+                return parser.ok(expr);
+            }
+        }
     }
 
     fn rule_primary(&self) -> EResult {
         if let Some(lexeme) = self.peek() {
             match &lexeme.kind {
                 LexemeKind::Integer(n) => self.advance().ok(self.builder().lit_integer(n.clone())),
-                LexemeKind::TSymbol(symbol) => self.rule_q(Segment::Type(symbol.clone())),
-                LexemeKind::Symbol(symbol) => self.rule_q(Segment::Symbol(symbol.clone())),
+                LexemeKind::Decimal(n) => self.advance().ok(self.builder().lit_decimal(n.clone())),
+                LexemeKind::Quote => self.rule_string(),
+                LexemeKind::TSymbol(symbol) => self.rule_q_record(Segment::Type(symbol.clone())),
+                LexemeKind::Symbol(symbol) => self.rule_q_record(Segment::Symbol(symbol.clone())),
+                // A single expression without a trailing comma is just a parenthesized one
                 LexemeKind::Open(Enclosure::Parens) => {
-                    let (expr, next) = self.advance().rule_expression()?;
-                    if !next.kind_equals(LexemeKind::Close(Enclosure::Parens)) {
-                        next.err(Error::CloseExpected(Enclosure::Parens))
+                    let ((mut exprs, trailing), next) = self.rule_items(Self::rule_ascribed)?;
+                    if exprs.is_empty() {
+                        next.ok(self.builder().tsymbol(Vec::default(), symbol::NONE.clone()))
+                    } else if exprs.len() == 1 && !trailing {
+                        next.ok(exprs.pop().unwrap())
                     } else {
-                        next.advance().ok(expr)
+                        next.ok(self.builder().tuple(exprs))
                     }
                 }
+                LexemeKind::Open(Enclosure::Square) => {
+                    let ((exprs, _), next) =
+                        self.rule_enclosed(Enclosure::Square, Self::rule_expression)?;
+                    next.ok(self.builder().list(exprs))
+                }
+                // Curly braces enclose a map if the first item is an expression followed by a
+                // colon, and a block otherwise. The empty map is {:}, as {} is an empty block.
                 LexemeKind::Open(Enclosure::Curly) => {
-                    let mut parser = self.advance();
-                    if parser.kind_equals(LexemeKind::Close(Enclosure::Curly)) {
+                    let mut parser = self.advance().with_records(true);
+                    if parser.kind_equals(LexemeKind::Colon)
+                        && parser
+                            .advance()
+                            .kind_equals(LexemeKind::Close(Enclosure::Curly))
+                    {
+                        parser
+                            .advance()
+                            .advance()
+                            .with_records(self.records)
+                            .ok(self.builder().map(Vec::default()))
+                    } else if parser.kind_equals(LexemeKind::Close(Enclosure::Curly)) {
                         parser
                             .advance()
+                            .with_records(self.records)
                             .ok(self.builder().tsymbol(Vec::default(), symbol::NONE.clone()))
                     } else {
                         let mut assignments = Vec::default();
@@ -366,8 +897,14 @@ impl Parser {
                                 return parser.err(Error::CloseExpected(Enclosure::Curly));
                             }
                             (_, parser) = parser.rule_bstatement(&mut assignments, &mut exprs)?;
+                            if assignments.is_empty()
+                                && exprs.len() == 1
+                                && parser.kind_equals(LexemeKind::Colon)
+                            {
+                                return parser.rule_map_rest(self, exprs.pop().unwrap());
+                            }
                             if parser.kind_equals(LexemeKind::Close(Enclosure::Curly)) {
-                                parser = parser.advance();
+                                parser = parser.advance().with_records(self.records);
                                 break;
                             } else if !parser.is_eos() {
                                 return parser.err(Error::EndOfItemExpected);
@@ -383,6 +920,88 @@ impl Parser {
         }
     }
 
+    // Parses the rest of a map from the colon after the first key. A trailing comma is allowed.
+    fn rule_map_rest(&self, open: &Parser, key: ExprRef) -> EResult {
+        let (value, mut parser) = self.advance().rule_expression()?;
+        let mut entries = vec![open.advance().builder().entry(key, value)];
+        while !parser.kind_equals(LexemeKind::Close(Enclosure::Curly)) {
+            parser =
+                parser.kind_expected(LexemeKind::Comma, Error::CloseExpected(Enclosure::Curly))?;
+            if parser.kind_equals(LexemeKind::Close(Enclosure::Curly)) {
+                break;
+            }
+            let (entry, next) = parser.rule_entry()?;
+            entries.push(entry);
+            parser = next;
+        }
+        parser
+            .advance()
+            .with_records(open.records)
+            .ok(open.builder().map(entries))
+    }
+
+    fn rule_entry(&self) -> PResult<L<Entry>> {
+        let (key, next) = self.rule_expression()?;
+        let (value, next) = next
+            .kind_expected(LexemeKind::Colon, Error::ColonExpected)?
+            .rule_expression()?;
+        next.ok(self.builder().entry(key, value))
+    }
+
+    // A string without interpolated expressions is a literal
+    fn rule_string(&self) -> EResult {
+        let mut parser = self.advance();
+        let mut parts = Vec::default();
+        let mut text = String::default();
+        loop {
+            match parser.peek_kind() {
+                Some(LexemeKind::Quote) => break,
+                Some(LexemeKind::Text(t)) => {
+                    text.push_str(t);
+                    parser = parser.advance();
+                }
+                Some(LexemeKind::Open(Enclosure::Curly)) => {
+                    if !text.is_empty() {
+                        parts.push(StringPart::Text(std::mem::take(&mut text).into()));
+                    }
+                    let (expr, next) = parser.advance().with_records(true).rule_expression()?;
+                    parser = next
+                        .kind_expected(
+                            LexemeKind::Close(Enclosure::Curly),
+                            Error::CloseExpected(Enclosure::Curly),
+                        )?
+                        .with_records(parser.records);
+                    parts.push(StringPart::Expr(expr));
+                }
+                _ => return parser.err(Error::UnterminatedString),
+            }
+        }
+        let parser = parser.advance();
+        if parts.is_empty() {
+            parser.ok(self.builder().lit_string(text.into()))
+        } else {
+            if !text.is_empty() {
+                parts.push(StringPart::Text(text.into()));
+            }
+            parser.ok(self.builder().interpolation(parts))
+        }
+    }
+
+    // A type followed by curly braces in the same line is a record construction, unless they
+    // enclose the arms of a match
+    fn rule_q_record(&self, segment: Segment) -> EResult {
+        let (expr, next) = self.rule_q(segment)?;
+        match &expr.it {
+            Expr::TSymbol(q)
+                if self.records && next.is_same_line(LexemeKind::Open(Enclosure::Curly)) =>
+            {
+                let (fields, next) = next.rule_fields(Self::rule_field)?;
+                next.ok(self.builder().record(q.clone(), fields))
+            }
+            _ => next.ok(expr),
+        }
+    }
+
     fn rule_q(&self, segment: Segment) -> EResult {
         let segments = Vec::default();
         self.rule_q_add(self, segments, segment)
@@ -1,5 +1,5 @@
-use crate::ast0::{BinaryOp, ExprRef, LAssignmentRef, Module, NewTypeRef};
-use crate::ast0::{Builder, Segment};
+use crate::ast0::{BinaryOp, ExprRef, LAssignmentRef, Module, NewTypeRef, StringPart, UnaryOp};
+use crate::ast0::{Builder, Q, Segment, TypeDfn, TypeExprRef};
 use crate::error::Loc;
 use crate::symbol::{self, Pkg, Symbol, TSymbol};
 use crate::visibility::Visibility;
@@ -21,7 +21,7 @@ impl Test {
 
     fn check(self, input: &str) {
         let package = parse(Pkg::Local, input).unwrap();
-        let module = package.modules.iter().next().unwrap();
+        let module = package.modules.first().unwrap();
         assert_eq!(**module, self.expected, "Left=Actual; Right=Expected")
     }
 
@@ -63,7 +63,7 @@ fn expect_error(input: &str, expected: &Error) {
     match parse(Pkg::Local, input) {
         Ok(_) => panic!("Parsed correctly but expected {:?}", expected),
         Err(e) => assert!(
-            e.contains(&expected),
+            e.contains(expected),
             "Expected {:?} but error was {:?}",
             expected,
             e
@@ -107,6 +107,13 @@ fn e_y() -> ExprRef {
     B.symbol(Vec::default(), y())
 }
 
+fn t_int() -> TypeExprRef {
+    B.type_symbol(Q {
+        segments: Vec::default(),
+        symbol: symbol::INTEGER.clone(),
+    })
+}
+
 fn e_none() -> ExprRef {
     B.tsymbol(Vec::default(), symbol::NONE.clone())
 }
@@ -193,6 +200,28 @@ fn sub1() {
     check_expression("1 - 2 + 1", add(sub_i64(1, 2), e_i64(1)));
 }
 
+#[test]
+fn rem_and_pow() {
+    let rem = B.binary(BinaryOp::Rem, e_i64(2), e_i64(3));
+    check_expression("1 - 2 % 3", sub(e_i64(1), rem));
+    let pow = B.binary(BinaryOp::Pow, e_i64(3), e_i64(4));
+    let pow = B.binary(BinaryOp::Pow, e_i64(2), pow);
+    check_expression("1 + 2 ** 3 ** 4", add(e_i64(1), pow));
+}
+
+#[test]
+fn unary() {
+    let neg = |e| B.unary(UnaryOp::Minus, e);
+    check_expression("-1", neg(e_i64(1)));
+    check_expression("1 - -x", sub(e_i64(1), neg(e_x())));
+    check_expression("--1", neg(neg(e_i64(1))));
+    check_expression("!True && x", and(B.unary(UnaryOp::Not, e_true()), e_x()));
+    let pow = B.binary(BinaryOp::Pow, e_i64(2), e_i64(2));
+    check_expression("-2 ** 2", neg(pow));
+    let pow = B.binary(BinaryOp::Pow, e_i64(2), neg(e_i64(1)));
+    check_expression("2 ** -1", pow);
+}
+
 #[test]
 fn lit_type() {
     check_expression("  True ", e_true());
@@ -211,6 +240,27 @@ fn assignment() {
         .check("let x = y + 2");
 }
 
+#[test]
+fn annotations() {
+    Test::new()
+        .m_let(x(), B.ascription(add(e_y(), e_i64(2)), t_int()))
+        .check("let x: Integer = y + 2");
+    check_expression(
+        "(y + 2 : Integer) * 2",
+        B.binary(
+            BinaryOp::Mul,
+            B.ascription(add(e_y(), e_i64(2)), t_int()),
+            e_i64(2),
+        ),
+    );
+    check_expression(
+        "(x: Integer, 1)",
+        B.tuple(vec![B.ascription(e_x(), t_int()), e_i64(1)]),
+    );
+    expect_error("let x: = 1", &Error::TypeExpected);
+    expect_error("let x: Integer 1", &Error::AssignmentExpected);
+}
+
 #[test]
 fn parens1() {
     check_expression(
@@ -256,6 +306,106 @@ fn blocks() {
     expect_error("{ 1\n 2 ", &Error::CloseExpected(Enclosure::Curly))
 }
 
+#[test]
+fn functions() {
+    let int_to_int = || B.type_function(vec![t_int()], t_int());
+    check_expression(
+        "fn(x: Integer) = x",
+        B.lambda(vec![B.param(x(), t_int())], None, e_x()),
+    );
+    check_expression(
+        "fn(x: Integer, y: (Integer) -> Integer,): Integer = y(x)",
+        B.lambda(
+            vec![B.param(x(), t_int()), B.param(y(), int_to_int())],
+            Some(t_int()),
+            B.call(e_y(), vec![e_x()]),
+        ),
+    );
+    check_expression("fn() = 1", B.lambda(vec![], None, e_i64(1)));
+    check_expression(
+        "x(1, y)(2) + 3",
+        add(
+            B.call(B.call(e_x(), vec![e_i64(1), e_y()]), vec![e_i64(2)]),
+            e_i64(3),
+        ),
+    );
+    check_expression("x()", B.call(e_x(), vec![]));
+    expect_error("x ()", &Error::EndOfItemExpected);
+    Test::new()
+        .m_let(x(), B.lambda(vec![], Some(int_to_int()), e_y()))
+        .check("fn x(): (Integer) -> Integer = y");
+    expect_error("fn(x) = x", &Error::TypeAnnotationExpected);
+    expect_error("fn(x: 1) = x", &Error::TypeExpected);
+    expect_error("fn x() = 1", &Error::TypeAnnotationExpected);
+    expect_error("fn(x: Integer) x", &Error::AssignmentExpected);
+    expect_error("fn(1) = 1", &Error::ParameterExpected);
+}
+
+#[test]
+fn effects() {
+    let io = || vec![tsym("IO")];
+    check_expression(
+        "fn(x: Integer) with IO = x",
+        B.effectful_lambda(vec![], vec![B.param(x(), t_int())], None, io(), e_x()),
+    );
+    check_expression(
+        "fn(y: (Integer) -> Integer with IO): Integer with IO = y(1)",
+        B.effectful_lambda(
+            vec![],
+            vec![B.param(y(), B.type_effectful_function(vec![t_int()], t_int(), io()))],
+            Some(t_int()),
+            io(),
+            B.call(e_y(), vec![e_i64(1)]),
+        ),
+    );
+    Test::new()
+        .m_let(
+            x(),
+            B.effectful_lambda(vec![], vec![], Some(t_int()), io(), e_y()),
+        )
+        .check("fn x(): Integer with IO = y");
+    expect_error("fn() with = 1", &Error::EffectExpected);
+    expect_error("fn() with IO, = 1", &Error::EffectExpected);
+}
+
+#[test]
+fn tuples() {
+    check_expression("(1, x)", B.tuple(vec![e_i64(1), e_x()]));
+    check_expression("(1,)", B.tuple(vec![e_i64(1)]));
+    check_expression("(1)", e_i64(1));
+    check_expression("()", e_none());
+    check_expression(
+        "x.0.1 + y(1).2",
+        add(
+            B.tuple_field(B.tuple_field(e_x(), 0), 1),
+            B.tuple_field(B.call(e_y(), vec![e_i64(1)]), 2),
+        ),
+    );
+    check_expression(
+        "fn(p: (Integer, (Integer,)), q: (Integer)) = p",
+        B.lambda(
+            vec![
+                B.param(
+                    sym("p"),
+                    B.type_tuple(vec![t_int(), B.type_tuple(vec![t_int()])]),
+                ),
+                B.param(sym("q"), t_int()),
+            ],
+            None,
+            B.symbol(Vec::default(), sym("p")),
+        ),
+    );
+    Test::new()
+        .new_type(
+            B.new_type(Visibility::Module, tsym("Point"))
+                .tuple(vec![t_int(), t_int()]),
+        )
+        .check("type Point(Integer, Integer)");
+    expect_error("x.A", &Error::TupleIndexExpected);
+    expect_error("x. 0", &Error::TupleIndexExpected);
+    expect_error("(1, 2", &Error::CloseExpected(Enclosure::Parens));
+}
+
 #[test]
 fn qsymbols() {
     check_qsymbol("x", Vec::default(), x());
@@ -271,3 +421,288 @@ fn typedef_singleton() {
         .new_type(singleton(Visibility::Module, "Red"))
         .check("type Red")
 }
+
+#[test]
+fn records() {
+    let field = |name: &str, expr: ExprRef| B.field(sym(name), expr);
+    let point = || Q {
+        segments: Vec::default(),
+        symbol: tsym("Point"),
+    };
+    check_expression(
+        "Point { x: 1, y: y }",
+        B.record(point(), vec![field("x", e_i64(1)), field("y", e_y())]),
+    );
+    check_expression("Point {}", B.record(point(), vec![]));
+    check_expression(
+        "x.y.z + x.0",
+        add(
+            B.record_field(B.record_field(e_x(), y()), sym("z")),
+            B.tuple_field(e_x(), 0),
+        ),
+    );
+    check_expression(
+        "x with { y: 2 }.y",
+        B.record_field(B.update(e_x(), vec![field("y", e_i64(2))]), y()),
+    );
+    Test::new()
+        .new_type(
+            B.new_type(Visibility::Module, tsym("Point"))
+                .record(vec![B.field_dfn(x(), t_int()), B.field_dfn(y(), t_int())]),
+        )
+        .check("type Point {\n  x: Integer,\n  y: Integer,\n}");
+    // A block in the next line is not a record
+    Test::new()
+        .expr(B.tsymbol(Vec::default(), tsym("Point")))
+        .expr(B.block(vec![], vec![e_i64(1)]))
+        .check("Point\n{ 1 }");
+    expect_error("Point { x 1 }", &Error::ColonExpected);
+    expect_error("Point { 1 }", &Error::FieldExpected);
+    expect_error("type Point { x }", &Error::TypeAnnotationExpected);
+    expect_error("x with 1", &Error::OpenExpected(Enclosure::Curly));
+}
+
+#[test]
+fn unions() {
+    let shape = || {
+        B.new_type(Visibility::Module, tsym("Shape")).union(vec![
+            B.variant(tsym("Circle"), TypeDfn::Tuple(vec![t_int()])),
+            B.variant(tsym("Square"), TypeDfn::Tuple(vec![t_int()])),
+            B.variant(tsym("Empty"), TypeDfn::Singleton),
+        ])
+    };
+    Test::new()
+        .new_type(shape())
+        .check("type Shape = Circle(Integer) | Square(Integer) | Empty");
+    Test::new().new_type(shape()).check(
+        "type Shape =
+  | Circle(Integer)
+  | Square(Integer)
+  | Empty",
+    );
+    Test::new()
+        .new_type(
+            B.new_type(Visibility::Module, tsym("Point"))
+                .union(vec![B.variant(
+                    tsym("Cartesian"),
+                    TypeDfn::Record(vec![B.field_dfn(x(), t_int()), B.field_dfn(y(), t_int())]),
+                )]),
+        )
+        .check("type Point = Cartesian { x: Integer, y: Integer }");
+    expect_error("type Shape = Circle |", &Error::VariantExpected);
+    expect_error("type Shape = circle", &Error::VariantExpected);
+}
+
+#[test]
+fn matches() {
+    let q = |segments: Vec<Segment>, name: &str| Q {
+        segments,
+        symbol: tsym(name),
+    };
+    check_expression(
+        "match x { 0 -> 1, -1 -> 2, y -> y, _ -> 3 }",
+        B.pattern_match(
+            e_x(),
+            vec![
+                B.arm(B.p_integer(0.into()), e_i64(1)),
+                B.arm(B.p_integer((-1).into()), e_i64(2)),
+                B.arm(B.p_symbol(y()), e_y()),
+                B.arm(B.p_wildcard(), e_i64(3)),
+            ],
+        ),
+    );
+    check_expression(
+        "match x {\n  (True, _) -> 1,\n  Shape::Circle(y) -> y,\n  Shape::Empty -> 2,\n}",
+        B.pattern_match(
+            e_x(),
+            vec![
+                B.arm(
+                    B.p_tuple(vec![B.p_tsymbol(q(vec![], "True")), B.p_wildcard()]),
+                    e_i64(1),
+                ),
+                B.arm(
+                    B.p_constructor(q(vec![stsym("Shape")], "Circle"), vec![B.p_symbol(y())]),
+                    e_y(),
+                ),
+                B.arm(B.p_tsymbol(q(vec![stsym("Shape")], "Empty")), e_i64(2)),
+            ],
+        ),
+    );
+    check_expression(
+        "match x { Point { x: 0 } -> 1, (y) -> y }",
+        B.pattern_match(
+            e_x(),
+            vec![
+                B.arm(
+                    B.p_record(
+                        q(vec![], "Point"),
+                        vec![B.field_pattern(x(), B.p_integer(0.into()))],
+                    ),
+                    e_i64(1),
+                ),
+                B.arm(B.p_symbol(y()), e_y()),
+            ],
+        ),
+    );
+    // A type before the arms is not a record construction, unless enclosed
+    check_expression(
+        "match True { True -> 1, _ -> 2 }",
+        B.pattern_match(
+            e_true(),
+            vec![
+                B.arm(B.p_tsymbol(q(vec![], "True")), e_i64(1)),
+                B.arm(B.p_wildcard(), e_i64(2)),
+            ],
+        ),
+    );
+    check_expression(
+        "match Shape::Empty { _ -> 1 }",
+        B.pattern_match(
+            B.tsymbol(vec![stsym("Shape")], tsym("Empty")),
+            vec![B.arm(B.p_wildcard(), e_i64(1))],
+        ),
+    );
+    check_expression(
+        "match (Point { x: 0 }) { _ -> 1 }",
+        B.pattern_match(
+            B.record(q(vec![], "Point"), vec![B.field(x(), e_i64(0))]),
+            vec![B.arm(B.p_wildcard(), e_i64(1))],
+        ),
+    );
+    check_expression(
+        "match x(Point { x: 0 }) { _ -> 1 }",
+        B.pattern_match(
+            B.call(
+                e_x(),
+                vec![B.record(q(vec![], "Point"), vec![B.field(x(), e_i64(0))])],
+            ),
+            vec![B.arm(B.p_wildcard(), e_i64(1))],
+        ),
+    );
+    expect_error("match x { 1 }", &Error::ArrowExpected);
+    expect_error("match x { 1 + 2 -> 3 }", &Error::ArrowExpected);
+    expect_error("match x { a::b -> 3 }", &Error::PatternExpected);
+    expect_error("match x 1", &Error::OpenExpected(Enclosure::Curly));
+}
+
+#[test]
+fn typedef_visibility() {
+    Test::new()
+        .new_type(singleton(Visibility::Exported, "Red"))
+        .check("pub type Red");
+    Test::new()
+        .new_type(singleton(Visibility::Package, "Red"))
+        .check("pkg type Red");
+}
+
+#[test]
+fn strings() {
+    check_expression(r#""""#, B.lit_string("".into()));
+    check_expression(r#""a\"b\n""#, B.lit_string("a\"b\n".into()));
+    check_expression(
+        r#""a" + "b""#,
+        add(B.lit_string("a".into()), B.lit_string("b".into())),
+    );
+    check_expression(
+        r#""x = {x}, \{y} = {x + 1}""#,
+        B.interpolation(vec![
+            StringPart::Text("x = ".into()),
+            StringPart::Expr(e_x()),
+            StringPart::Text(", {y} = ".into()),
+            StringPart::Expr(add(e_x(), e_i64(1))),
+        ]),
+    );
+    check_expression(
+        r#""{"a{x}"}""#,
+        B.interpolation(vec![StringPart::Expr(B.interpolation(vec![
+            StringPart::Text("a".into()),
+            StringPart::Expr(e_x()),
+        ]))]),
+    );
+    expect_error(r#""{x y}""#, &Error::CloseExpected(Enclosure::Curly));
+}
+
+#[test]
+fn collections() {
+    let t_list = |t| {
+        let q = Q {
+            segments: Vec::default(),
+            symbol: symbol::LIST.clone(),
+        };
+        B.type_parameterized(q, vec![t])
+    };
+    let map = Q {
+        segments: Vec::default(),
+        symbol: symbol::MAP.clone(),
+    };
+    check_expression("[1, x]", B.list(vec![e_i64(1), e_x()]));
+    check_expression("[1,]", B.list(vec![e_i64(1)]));
+    check_expression("[]", B.list(vec![]));
+    check_expression(
+        "{1: x, y: 2,}",
+        B.map(vec![B.entry(e_i64(1), e_x()), B.entry(e_y(), e_i64(2))]),
+    );
+    check_expression("{ x: 1 }", B.map(vec![B.entry(e_x(), e_i64(1))]));
+    check_expression("{:}", B.map(vec![]));
+    check_expression("{ : }", B.map(vec![]));
+    check_expression("{:}.length", B.record_field(B.map(vec![]), sym("length")));
+    check_expression(
+        "x[0][1] + y(1)[x]",
+        add(
+            B.index(B.index(e_x(), e_i64(0)), e_i64(1)),
+            B.index(B.call(e_y(), vec![e_i64(1)]), e_x()),
+        ),
+    );
+    check_expression(
+        "fn(l: List[Integer], m: Map[Integer, List[Integer]]) = l",
+        B.lambda(
+            vec![
+                B.param(sym("l"), t_list(t_int())),
+                B.param(
+                    sym("m"),
+                    B.type_parameterized(map, vec![t_int(), t_list(t_int())]),
+                ),
+            ],
+            None,
+            B.symbol(Vec::default(), sym("l")),
+        ),
+    );
+    expect_error("x [0]", &Error::EndOfItemExpected);
+    expect_error("x[0", &Error::CloseExpected(Enclosure::Square));
+    expect_error("[1, 2", &Error::CloseExpected(Enclosure::Square));
+    expect_error("{x: 1 y: 2}", &Error::CloseExpected(Enclosure::Curly));
+    expect_error("{x: 1, y}", &Error::ColonExpected);
+    expect_error("{:", &Error::ExpressionExpected);
+}
+
+#[test]
+fn generics() {
+    let t = |name: &str| {
+        B.type_symbol(Q {
+            segments: Vec::default(),
+            symbol: tsym(name),
+        })
+    };
+    let pair = Q {
+        segments: Vec::default(),
+        symbol: tsym("Pair"),
+    };
+    Test::new()
+        .new_type(
+            B.new_type(Visibility::Module, tsym("Pair"))
+                .params(vec![tsym("A"), tsym("B")])
+                .tuple(vec![t("A"), t("B")]),
+        )
+        .check("type Pair[A, B](A, B)");
+    check_expression(
+        "fn[A, B](p: Pair[A, B]) = p",
+        B.generic_lambda(
+            vec![tsym("A"), tsym("B")],
+            vec![B.param(sym("p"), B.type_parameterized(pair, vec![t("A"), t("B")]))],
+            None,
+            B.symbol(Vec::default(), sym("p")),
+        ),
+    );
+    expect_error("type Pair[a](a)", &Error::TypeParameterExpected);
+    expect_error("fn[T(x: T) = x", &Error::CloseExpected(Enclosure::Square));
+}
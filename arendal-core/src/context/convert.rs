@@ -0,0 +1,440 @@
+// Conversions between Rust types and values, used by native functions and embedders. Rust
+// structs and enums can derive them, mapping to named tuples and unions.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use crate::error::{Error, Loc};
+use crate::symbol::{FQ, FQType, Path, Pkg, Symbol, TSymbol};
+use crate::{ArcStr, Decimal, Integer};
+
+use super::{NamedTuple, Tuple, Type, TypeData, TypeRef, Union, Val, Value};
+
+// Rust types with an Arendal counterpart
+pub trait Typed {
+    fn tipo() -> Type;
+}
+
+fn mismatch<T: Typed>(value: Value) -> Error {
+    Error::type_mismatch(T::tipo(), value.tipo)
+}
+
+impl Typed for () {
+    fn tipo() -> Type {
+        Type::type_none()
+    }
+}
+
+impl From<()> for Value {
+    fn from(_: ()) -> Self {
+        Value::v_none()
+    }
+}
+
+impl TryFrom<Value> for () {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        if value.tipo.is_none() {
+            Ok(())
+        } else {
+            Err(mismatch::<()>(value))
+        }
+    }
+}
+
+impl Typed for bool {
+    fn tipo() -> Type {
+        Type::type_boolean()
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::v_bool(value)
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        match value.tipo.data {
+            TypeData::True => Ok(true),
+            TypeData::False => Ok(false),
+            _ => Err(mismatch::<bool>(value)),
+        }
+    }
+}
+
+impl Typed for Integer {
+    fn tipo() -> Type {
+        Type::type_integer()
+    }
+}
+
+impl From<Integer> for Value {
+    fn from(value: Integer) -> Self {
+        Value {
+            tipo: Type::type_integer(),
+            val: Val::Integer(value),
+        }
+    }
+}
+
+impl TryFrom<Value> for Integer {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        match value.val {
+            Val::Integer(v) => Ok(v),
+            _ => Err(mismatch::<Integer>(value)),
+        }
+    }
+}
+
+// Integers are unbounded, so they may not fit in the Rust type
+macro_rules! integer {
+    ($($t:ty),*) => {
+        $(
+            impl Typed for $t {
+                fn tipo() -> Type {
+                    Type::type_integer()
+                }
+            }
+
+            impl From<$t> for Value {
+                fn from(value: $t) -> Self {
+                    Integer::from(i64::from(value)).into()
+                }
+            }
+
+            impl TryFrom<Value> for $t {
+                type Error = Error;
+
+                fn try_from(value: Value) -> Result<Self, Error> {
+                    let value = Integer::try_from(value)?;
+                    value
+                        .to_i64()
+                        .and_then(|v| <$t>::try_from(v).ok())
+                        .ok_or(Error::IntegerOverflow)
+                }
+            }
+        )*
+    };
+}
+
+integer!(i8, i16, i32, i64, u8, u16, u32);
+
+// Unsigned integers that may not fit in an i64
+macro_rules! wide_integer {
+    ($($t:ty),*) => {
+        $(
+            impl Typed for $t {
+                fn tipo() -> Type {
+                    Type::type_integer()
+                }
+            }
+
+            impl From<$t> for Value {
+                fn from(value: $t) -> Self {
+                    Integer::from_u64(value as u64).into()
+                }
+            }
+
+            impl TryFrom<Value> for $t {
+                type Error = Error;
+
+                fn try_from(value: Value) -> Result<Self, Error> {
+                    let value = Integer::try_from(value)?;
+                    value
+                        .to_u64()
+                        .and_then(|v| <$t>::try_from(v).ok())
+                        .ok_or(Error::IntegerOverflow)
+                }
+            }
+        )*
+    };
+}
+
+wide_integer!(u64, usize);
+
+impl Typed for Decimal {
+    fn tipo() -> Type {
+        Type::type_decimal()
+    }
+}
+
+impl From<Decimal> for Value {
+    fn from(value: Decimal) -> Self {
+        Value {
+            tipo: Type::type_decimal(),
+            val: Val::Decimal(value),
+        }
+    }
+}
+
+impl TryFrom<Value> for Decimal {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        match value.val {
+            Val::Decimal(v) => Ok(v),
+            _ => Err(mismatch::<Decimal>(value)),
+        }
+    }
+}
+
+impl Typed for ArcStr {
+    fn tipo() -> Type {
+        Type::type_string()
+    }
+}
+
+impl From<ArcStr> for Value {
+    fn from(value: ArcStr) -> Self {
+        Value {
+            tipo: Type::type_string(),
+            val: Val::String(value),
+        }
+    }
+}
+
+impl TryFrom<Value> for ArcStr {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        match value.val {
+            Val::String(v) => Ok(v),
+            _ => Err(mismatch::<ArcStr>(value)),
+        }
+    }
+}
+
+impl Typed for String {
+    fn tipo() -> Type {
+        Type::type_string()
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        ArcStr::from(value).into()
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        ArcStr::try_from(value).map(|v| v.to_string())
+    }
+}
+
+// Rust tuples are anonymous tuples
+macro_rules! tuple {
+    ($($t:ident),+) => {
+        impl<$($t: Typed),+> Typed for ($($t,)+) {
+            fn tipo() -> Type {
+                Type::type_tuple(vec![$($t::tipo()),+])
+            }
+        }
+
+        impl<$($t: Typed + Into<Value>),+> From<($($t,)+)> for Value {
+            #[allow(non_snake_case)]
+            fn from(($($t,)+): ($($t,)+)) -> Self {
+                Value {
+                    tipo: <($($t,)+)>::tipo(),
+                    val: Val::Tuple(Arc::new(vec![$($t.into()),+])),
+                }
+            }
+        }
+
+        impl<$($t: Typed + TryFrom<Value, Error = Error>),+> TryFrom<Value> for ($($t,)+) {
+            type Error = Error;
+
+            fn try_from(value: Value) -> Result<Self, Error> {
+                let expected = <($($t,)+)>::tipo();
+                match &value.val {
+                    Val::Tuple(values)
+                        if value.tipo.is_anonymous() && values.len() == expected.arity() =>
+                    {
+                        let mut values = values.iter().cloned();
+                        Ok(($($t::try_from(values.next().unwrap())?,)+))
+                    }
+                    _ => Err(Error::type_mismatch(expected, value.tipo)),
+                }
+            }
+        }
+    };
+}
+
+tuple!(A);
+tuple!(A, B);
+tuple!(A, B, C);
+tuple!(A, B, C, D);
+
+impl<T: Typed> Typed for Vec<T> {
+    fn tipo() -> Type {
+        Type::type_list(T::tipo())
+    }
+}
+
+impl<T: Typed + Into<Value>> From<Vec<T>> for Value {
+    fn from(values: Vec<T>) -> Self {
+        Value {
+            tipo: Vec::<T>::tipo(),
+            val: Val::List(values.into_iter().map(Into::into).collect()),
+        }
+    }
+}
+
+impl<T: Typed + TryFrom<Value, Error = Error>> TryFrom<Value> for Vec<T> {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        match value.val {
+            Val::List(values) => values.into_iter().map(T::try_from).collect(),
+            _ => Err(mismatch::<Vec<T>>(value)),
+        }
+    }
+}
+
+impl<K: Typed, V: Typed> Typed for HashMap<K, V> {
+    fn tipo() -> Type {
+        Type::type_map(K::tipo(), V::tipo())
+    }
+}
+
+impl<K: Typed + Into<Value>, V: Typed + Into<Value>> From<HashMap<K, V>> for Value {
+    fn from(entries: HashMap<K, V>) -> Self {
+        Value {
+            tipo: HashMap::<K, V>::tipo(),
+            val: Val::Map(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k.into(), v.into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl<K, V> TryFrom<Value> for HashMap<K, V>
+where
+    K: Typed + TryFrom<Value, Error = Error> + Eq + Hash,
+    V: Typed + TryFrom<Value, Error = Error>,
+{
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        match value.val {
+            Val::Map(entries) => entries
+                .into_iter()
+                .map(|(k, v)| Ok((K::try_from(k)?, V::try_from(v)?)))
+                .collect(),
+            _ => Err(mismatch::<HashMap<K, V>>(value)),
+        }
+    }
+}
+
+// Returns the symbol of a type of the local package, e.g., geo::Point. Panics if the names are
+// not valid, as they are provided by the derived conversions.
+pub fn local_type(path: &str, name: &str) -> FQType {
+    let loc = Loc::None;
+    let segments = path
+        .split("::")
+        .filter(|s| !s.is_empty())
+        .map(|s| Symbol::new(&loc, s.into()))
+        .collect::<crate::error::Result<Vec<_>>>()
+        .unwrap_or_else(|e| panic!("Invalid path {path}: {e}"));
+    let symbol =
+        TSymbol::new(&loc, name.into()).unwrap_or_else(|e| panic!("Invalid type {name}: {e}"));
+    Pkg::Local.path(Path::new(segments)).fq_type(symbol)
+}
+
+// Returns the symbol of a variant of a union, e.g., Shape::Circle
+pub fn variant_type(union: &FQType, name: &str) -> FQType {
+    let symbol = TSymbol::new(&Loc::None, name.into())
+        .unwrap_or_else(|e| panic!("Invalid variant {name}: {e}"));
+    FQ::enclosed(union, symbol)
+}
+
+// Types of the Rust structs and enums with derived conversions. Structs without fields are
+// singletons.
+impl Type {
+    pub fn type_singleton(symbol: FQType) -> Type {
+        Type {
+            data: TypeData::Singleton(symbol),
+        }
+    }
+
+    pub fn type_named_tuple(symbol: FQType, types: Vec<Type>) -> Type {
+        if types.is_empty() {
+            return Self::type_singleton(symbol);
+        }
+        Type {
+            data: TypeData::NamedTuple(Arc::new(NamedTuple {
+                symbol,
+                types: Tuple {
+                    types: types.into_iter().map(TypeRef::Type).collect(),
+                },
+                args: Vec::default(),
+            })),
+        }
+    }
+
+    // The variants are enclosed in the union, e.g., Shape::Circle
+    pub fn type_union(symbol: FQType, variants: Vec<Type>) -> Type {
+        Type {
+            data: TypeData::Union(Arc::new(Union {
+                symbol,
+                variants,
+                args: Vec::default(),
+            })),
+        }
+    }
+
+    // Number of elements of tuple types, zero for the rest
+    fn arity(&self) -> usize {
+        match &self.data {
+            TypeData::NamedTuple(t) => t.types.types.len(),
+            TypeData::AnonTuple(t) => t.types.len(),
+            _ => 0,
+        }
+    }
+}
+
+impl Value {
+    // Builds a value of a named tuple or singleton type from its elements, which must be of
+    // the types of the tuple
+    pub fn v_named(tipo: Type, values: Vec<Value>) -> Value {
+        let val = if values.is_empty() {
+            Val::Singleton
+        } else {
+            Val::Tuple(Arc::new(values))
+        };
+        Value { tipo, val }
+    }
+
+    // Returns the symbol and the elements of a value of the provided named tuple or singleton
+    // type. For unions, the value can be of any of the variants.
+    pub fn into_elements(self, tipo: &Type) -> Result<(TSymbol, Vec<Value>), Error> {
+        let expected = match &tipo.data {
+            TypeData::Union(u) => u.variants.iter().find(|v| v.fq() == self.tipo.fq()),
+            _ => Some(tipo).filter(|t| t.fq() == self.tipo.fq()),
+        };
+        let values = match &self.val {
+            Val::Singleton => Vec::default(),
+            Val::Tuple(values) => values.as_ref().clone(),
+            _ => return Err(Error::type_mismatch(tipo.clone(), self.tipo)),
+        };
+        match (expected, self.tipo.fq()) {
+            (Some(expected), Some(fq)) if expected.arity() == values.len() => {
+                Ok((fq.symbol().clone(), values))
+            }
+            _ => Err(Error::type_mismatch(tipo.clone(), self.tipo)),
+        }
+    }
+}
@@ -1,13 +1,23 @@
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 
 use im::{HashMap, HashSet};
-use num::Integer;
+use num::{Decimal, Integer, RoundingMode};
 
+use crate::ArcStr;
+use crate::effect::{Effect, Effects};
 use crate::error::{Error, Errors, L, Loc, Result};
-use crate::symbol::{self, FQType};
+use crate::store::Literal;
+use crate::symbol::{self, FQ, FQSym, FQType, Symbol, TSymbol};
+use crate::tst::Lambda;
 use crate::visibility::{V, Visibility};
 
+mod convert;
+
+pub use convert::{Typed, local_type, variant_type};
+pub use derive::Typed;
+
 #[derive(Clone, PartialEq, Eq)]
 pub struct Type {
     data: TypeData,
@@ -20,16 +30,27 @@ enum TypeData {
     False,
     Boolean,
     Integer,
+    Decimal,
+    String,
     Singleton(FQType),
     NamedTuple(Arc<NamedTuple>),
     AnonTuple(Arc<Tuple>),
+    Record(Arc<Record>),
+    Union(Arc<Union>),
+    Function(Arc<FunctionType>),
+    List(Arc<Type>),
+    Map(Arc<MapType>),
+    Var(TSymbol), // Type parameter, in the definitions of generic types and functions
+    Unbound,      // Type argument not constrained by any value, accepted by every type
+    Generic(Arc<Generic>),
 }
 
 impl TypeData {
-    fn named_tuple(symbol: &FQType, types: Vec<TypeRef>) -> Self {
+    fn named_tuple(symbol: &FQType, types: Vec<TypeRef>, args: Vec<Type>) -> Self {
         TypeData::NamedTuple(Arc::new(NamedTuple {
             symbol: symbol.clone(),
             types: Tuple { types },
+            args,
         }))
     }
 }
@@ -40,6 +61,32 @@ impl TypeData {
 enum TypeRef {
     Type(Type),
     Symbol(FQType),
+    Apply(FQType, Vec<TypeRef>), // Instance of a generic type
+    // Builtin types of references, e.g., a list of the type being defined
+    List(Box<TypeRef>),
+    Map(Box<TypeRef>, Box<TypeRef>),
+    Function(Vec<TypeRef>, Box<TypeRef>, Effects),
+}
+
+impl TypeRef {
+    fn substitute(&self, bindings: &HashMap<TSymbol, Type>) -> TypeRef {
+        let substitute = |r: &TypeRef| Box::new(r.substitute(bindings));
+        match self {
+            TypeRef::Type(t) => TypeRef::Type(t.substitute(bindings)),
+            TypeRef::Symbol(_) => self.clone(),
+            TypeRef::Apply(fq, args) => TypeRef::Apply(
+                fq.clone(),
+                args.iter().map(|a| a.substitute(bindings)).collect(),
+            ),
+            TypeRef::List(element) => TypeRef::List(substitute(element)),
+            TypeRef::Map(key, value) => TypeRef::Map(substitute(key), substitute(value)),
+            TypeRef::Function(params, result, effects) => TypeRef::Function(
+                params.iter().map(|p| p.substitute(bindings)).collect(),
+                substitute(result),
+                *effects,
+            ),
+        }
+    }
 }
 
 #[derive(Clone, PartialEq, Eq)]
@@ -47,10 +94,57 @@ struct Tuple {
     types: Vec<TypeRef>,
 }
 
+impl Tuple {
+    fn substitute(&self, bindings: &HashMap<TSymbol, Type>) -> Tuple {
+        Tuple {
+            types: self.types.iter().map(|t| t.substitute(bindings)).collect(),
+        }
+    }
+}
+
+// The type arguments of the instances of generic types are kept to tell them apart
 #[derive(Clone, PartialEq, Eq)]
 struct NamedTuple {
     symbol: FQType,
     types: Tuple,
+    args: Vec<Type>,
+}
+
+// Fields are kept in definition order
+#[derive(Clone, PartialEq, Eq)]
+struct Record {
+    symbol: FQType,
+    fields: Vec<(Symbol, TypeRef)>,
+    args: Vec<Type>,
+}
+
+// Variants are types enclosed in the union
+#[derive(Clone, PartialEq, Eq)]
+struct Union {
+    symbol: FQType,
+    variants: Vec<Type>,
+    args: Vec<Type>,
+}
+
+// Generic types are instantiated by replacing their parameters with the type arguments
+#[derive(Clone, PartialEq, Eq)]
+struct Generic {
+    params: Vec<TSymbol>,
+    tipo: Type,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+struct MapType {
+    key: Type,
+    value: Type,
+}
+
+// Calling a function performs its effects
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct FunctionType {
+    pub(crate) params: Vec<Type>,
+    pub(crate) result: Type,
+    pub(crate) effects: Effects,
 }
 
 impl Type {
@@ -84,8 +178,80 @@ impl Type {
         }
     }
 
+    pub fn type_decimal() -> Type {
+        Type {
+            data: TypeData::Decimal,
+        }
+    }
+
+    pub fn type_string() -> Type {
+        Type {
+            data: TypeData::String,
+        }
+    }
+
+    pub fn type_function(params: Vec<Type>, result: Type) -> Type {
+        Self::type_function_with_effects(params, result, Effects::NONE)
+    }
+
+    pub fn type_function_with_effects(params: Vec<Type>, result: Type, effects: Effects) -> Type {
+        Type {
+            data: TypeData::Function(Arc::new(FunctionType {
+                params,
+                result,
+                effects,
+            })),
+        }
+    }
+
+    pub fn type_list(element: Type) -> Type {
+        Type {
+            data: TypeData::List(Arc::new(element)),
+        }
+    }
+
+    pub fn type_map(key: Type, value: Type) -> Type {
+        Type {
+            data: TypeData::Map(Arc::new(MapType { key, value })),
+        }
+    }
+
+    pub(crate) fn type_var(symbol: TSymbol) -> Type {
+        Type {
+            data: TypeData::Var(symbol),
+        }
+    }
+
+    pub(crate) fn type_unbound() -> Type {
+        Type {
+            data: TypeData::Unbound,
+        }
+    }
+
+    // Types without parameters are not generic
+    pub(crate) fn type_generic(params: Vec<TSymbol>, tipo: Type) -> Type {
+        if params.is_empty() {
+            return tipo;
+        }
+        Type {
+            data: TypeData::Generic(Arc::new(Generic { params, tipo })),
+        }
+    }
+
+    // The empty tuple is None
+    pub fn type_tuple(types: Vec<Type>) -> Type {
+        if types.is_empty() {
+            return Self::type_none();
+        }
+        Type {
+            data: TypeData::AnonTuple(Arc::new(Tuple {
+                types: types.into_iter().map(TypeRef::Type).collect(),
+            })),
+        }
+    }
+
     pub fn is_anonymous(&self) -> bool {
-        false
+        self.fq().is_none()
     }
 
     pub fn fq(&self) -> Option<FQType> {
@@ -96,23 +262,35 @@ impl Type {
                 TypeData::False => &symbol::FQ_FALSE,
                 TypeData::Boolean => &symbol::FQ_BOOLEAN,
                 TypeData::Integer => &symbol::FQ_INTEGER,
+                TypeData::Decimal => &symbol::FQ_DECIMAL,
+                TypeData::String => &symbol::FQ_STRING,
                 TypeData::Singleton(fq) => fq,
                 TypeData::NamedTuple(t) => &t.symbol,
-                TypeData::AnonTuple(_) => return None,
+                TypeData::Record(r) => &r.symbol,
+                TypeData::Union(u) => &u.symbol,
+                TypeData::AnonTuple(_)
+                | TypeData::Function(_)
+                | TypeData::List(_)
+                | TypeData::Map(_)
+                | TypeData::Var(_)
+                | TypeData::Unbound
+                | TypeData::Generic(_) => return None,
             }
             .clone(),
         )
     }
 
     pub fn is_builtin(&self) -> bool {
-        match &self.data {
+        matches!(
+            &self.data,
             TypeData::None
-            | TypeData::True
-            | TypeData::False
-            | TypeData::Boolean
-            | TypeData::Integer => true,
-            _ => false,
-        }
+                | TypeData::True
+                | TypeData::False
+                | TypeData::Boolean
+                | TypeData::Integer
+                | TypeData::Decimal
+                | TypeData::String
+        )
     }
 
     pub fn is_none(&self) -> bool {
@@ -128,48 +306,333 @@ impl Type {
     }
 
     pub fn is_boolean(&self) -> bool {
-        match self.data {
-            TypeData::True | TypeData::False | TypeData::Boolean => true,
-            _ => false,
-        }
+        matches!(
+            self.data,
+            TypeData::True | TypeData::False | TypeData::Boolean
+        )
     }
 
     pub fn is_integer(&self) -> bool {
         self.data == TypeData::Integer
     }
 
+    pub fn is_decimal(&self) -> bool {
+        self.data == TypeData::Decimal
+    }
+
+    pub fn is_string(&self) -> bool {
+        self.data == TypeData::String
+    }
+
+    pub(crate) fn as_function(&self) -> Option<&FunctionType> {
+        match &self.data {
+            TypeData::Function(f) => Some(f),
+            _ => None,
+        }
+    }
+
+    pub fn is_tuple(&self) -> bool {
+        matches!(self.data, TypeData::NamedTuple(_) | TypeData::AnonTuple(_))
+    }
+
+    pub fn is_record(&self) -> bool {
+        matches!(self.data, TypeData::Record(_))
+    }
+
+    pub fn is_list(&self) -> bool {
+        matches!(self.data, TypeData::List(_))
+    }
+
+    pub fn is_map(&self) -> bool {
+        matches!(self.data, TypeData::Map(_))
+    }
+
+    pub(crate) fn list_element(&self) -> Option<Type> {
+        match &self.data {
+            TypeData::List(element) => Some(element.as_ref().clone()),
+            _ => None,
+        }
+    }
+
+    // Returns the key and value types of a map
+    pub(crate) fn map_types(&self) -> Option<(Type, Type)> {
+        match &self.data {
+            TypeData::Map(m) => Some((m.key.clone(), m.value.clone())),
+            _ => None,
+        }
+    }
+
+    pub fn is_generic(&self) -> bool {
+        matches!(self.data, TypeData::Generic(_))
+    }
+
+    // Returns the parameters of a generic type and the type they are used in
+    pub(crate) fn generic(&self) -> Option<(&[TSymbol], &Type)> {
+        match &self.data {
+            TypeData::Generic(g) => Some((&g.params, &g.tipo)),
+            _ => None,
+        }
+    }
+
+    // Returns the type arguments of an instance of a generic type
+    pub(crate) fn args(&self) -> &[Type] {
+        match &self.data {
+            TypeData::NamedTuple(t) => &t.args,
+            TypeData::Record(r) => &r.args,
+            TypeData::Union(u) => &u.args,
+            _ => &[],
+        }
+    }
+
+    // Returns the instance of a generic type for the provided type arguments
+    pub(crate) fn instantiate(&self, args: &[Type]) -> Option<Type> {
+        match &self.data {
+            TypeData::Generic(g) if g.params.len() == args.len() => {
+                let bindings = g.params.iter().cloned().zip(args.iter().cloned()).collect();
+                Some(g.tipo.substitute(&bindings))
+            }
+            _ => None,
+        }
+    }
+
+    fn substitute(&self, bindings: &HashMap<TSymbol, Type>) -> Type {
+        let substitute_all =
+            |types: &[Type]| types.iter().map(|t| t.substitute(bindings)).collect();
+        let data = match &self.data {
+            TypeData::Var(symbol) => return bindings.get(symbol).unwrap_or(self).clone(),
+            TypeData::NamedTuple(t) => TypeData::NamedTuple(Arc::new(NamedTuple {
+                symbol: t.symbol.clone(),
+                types: t.types.substitute(bindings),
+                args: substitute_all(&t.args),
+            })),
+            TypeData::AnonTuple(t) => TypeData::AnonTuple(Arc::new(t.substitute(bindings))),
+            TypeData::Record(r) => TypeData::Record(Arc::new(Record {
+                symbol: r.symbol.clone(),
+                fields: r
+                    .fields
+                    .iter()
+                    .map(|(s, t)| (s.clone(), t.substitute(bindings)))
+                    .collect(),
+                args: substitute_all(&r.args),
+            })),
+            TypeData::Union(u) => TypeData::Union(Arc::new(Union {
+                symbol: u.symbol.clone(),
+                variants: substitute_all(&u.variants),
+                args: substitute_all(&u.args),
+            })),
+            TypeData::Function(f) => TypeData::Function(Arc::new(FunctionType {
+                params: substitute_all(&f.params),
+                result: f.result.substitute(bindings),
+                effects: f.effects,
+            })),
+            TypeData::List(element) => TypeData::List(Arc::new(element.substitute(bindings))),
+            TypeData::Map(m) => TypeData::Map(Arc::new(MapType {
+                key: m.key.substitute(bindings),
+                value: m.value.substitute(bindings),
+            })),
+            // The parameters of nested generic types shadow the enclosing ones
+            TypeData::Generic(g) => {
+                let bindings = g.params.iter().fold(bindings.clone(), |b, p| b.without(p));
+                TypeData::Generic(Arc::new(Generic {
+                    params: g.params.clone(),
+                    tipo: g.tipo.substitute(&bindings),
+                }))
+            }
+            _ => return self.clone(),
+        };
+        Type { data }
+    }
+
+    // Returns the union this type is a variant of. True and False are the variants of Boolean.
+    pub fn union(&self) -> Option<FQType> {
+        match &self.data {
+            TypeData::True | TypeData::False => Some(symbol::FQ_BOOLEAN.clone()),
+            _ => self.fq().and_then(|fq| fq.parent().cloned()),
+        }
+    }
+
+    // Returns whether a value of the provided type can be used where this type is expected.
+    // Variants are subtypes of their union. Anonymous tuples are structural, so their elements
+    // are checked one by one. Collections and type arguments are covariant, as values are
+    // immutable. Pure functions can be used where effectful ones are expected.
+    pub fn accepts(&self, other: &Type) -> bool {
+        match (&self.data, &other.data) {
+            (_, TypeData::Unbound) => true,
+            (TypeData::Function(f1), TypeData::Function(f2)) => {
+                f1.params == f2.params && f1.result == f2.result && f1.effects.includes(f2.effects)
+            }
+            (TypeData::List(e1), TypeData::List(e2)) => e1.accepts(e2),
+            (TypeData::Map(m1), TypeData::Map(m2)) => {
+                m1.key.accepts(&m2.key) && m1.value.accepts(&m2.value)
+            }
+            (TypeData::AnonTuple(t1), TypeData::AnonTuple(t2)) => {
+                t1.types.len() == t2.types.len()
+                    && t1.types.iter().zip(&t2.types).all(|pair| match pair {
+                        (TypeRef::Type(e1), TypeRef::Type(e2)) => e1.accepts(e2),
+                        (r1, r2) => r1 == r2,
+                    })
+            }
+            _ => {
+                self == other
+                    || (self.fq().is_some()
+                        && (self.fq() == other.fq() || self.fq() == other.union())
+                        && accepts_args(self.args(), other.args()))
+            }
+        }
+    }
+
+    // Values of the same union can be compared even if they are of different variants
+    pub fn is_comparable(&self, other: &Type) -> bool {
+        self.accepts(other)
+            || other.accepts(self)
+            || (self.union().is_some() && self.union() == other.union())
+    }
+
     pub fn is_singleton(&self) -> bool {
-        match self.data {
-            TypeData::None | TypeData::True | TypeData::False | TypeData::Singleton(_) => true,
-            _ => false,
+        matches!(
+            self.data,
+            TypeData::None | TypeData::True | TypeData::False | TypeData::Singleton(_)
+        )
+    }
+}
+
+// Variants without type arguments belong to every instance of their union
+fn accepts_args(args: &[Type], other: &[Type]) -> bool {
+    other.is_empty()
+        || (args.len() == other.len() && args.iter().zip(other).all(|(a, o)| a.accepts(o)))
+}
+
+// Writes the type arguments or parameters, if any, enclosed in square brackets
+fn fmt_args<T: fmt::Display>(f: &mut fmt::Formatter<'_>, args: &[T]) -> fmt::Result {
+    if args.is_empty() {
+        return Ok(());
+    }
+    f.write_str("[")?;
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        arg.fmt(f)?;
+    }
+    f.write_str("]")
+}
+
+fn fmt_function<T: fmt::Display>(
+    f: &mut fmt::Formatter<'_>,
+    params: &[T],
+    result: &T,
+    effects: Effects,
+) -> fmt::Result {
+    f.write_str("(")?;
+    for (i, param) in params.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
         }
+        param.fmt(f)?;
+    }
+    write!(f, ") -> {result}")?;
+    if !effects.is_pure() {
+        write!(f, " with {effects}")?;
     }
+    Ok(())
 }
 
 impl fmt::Display for Type {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self.fq() {
-            Some(fq) => fq.fmt(f),
-            None => f.write_str("Anonymous - TODO!"),
+        match (self.fq(), &self.data) {
+            (Some(fq), _) => {
+                fq.fmt(f)?;
+                fmt_args(f, self.args())
+            }
+            (None, TypeData::Function(function)) => {
+                fmt_function(f, &function.params, &function.result, function.effects)
+            }
+            (None, TypeData::AnonTuple(tuple)) => {
+                f.write_str("(")?;
+                for (i, t) in tuple.types.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    t.fmt(f)?;
+                }
+                // Trailing comma to tell a single element tuple from a parenthesized type
+                if tuple.types.len() == 1 {
+                    f.write_str(",")?;
+                }
+                f.write_str(")")
+            }
+            (None, TypeData::List(element)) => write!(f, "List[{element}]"),
+            (None, TypeData::Map(m)) => write!(f, "Map[{}, {}]", m.key, m.value),
+            (None, TypeData::Var(symbol)) => symbol.fmt(f),
+            (None, TypeData::Unbound) => f.write_str("_"),
+            // Generic functions are preceded by their parameters
+            (None, TypeData::Generic(g)) => match g.tipo.fq() {
+                Some(fq) => {
+                    fq.fmt(f)?;
+                    fmt_args(f, &g.params)
+                }
+                None => {
+                    fmt_args(f, &g.params)?;
+                    g.tipo.fmt(f)
+                }
+            },
+            (None, _) => {
+                unreachable!("Types without a symbol are functions, tuples or collections")
+            }
+        }
+    }
+}
+
+impl fmt::Display for TypeRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeRef::Type(t) => t.fmt(f),
+            TypeRef::Symbol(fq) => fq.fmt(f),
+            TypeRef::Apply(fq, args) => {
+                fq.fmt(f)?;
+                fmt_args(f, args)
+            }
+            TypeRef::List(element) => write!(f, "List[{element}]"),
+            TypeRef::Map(key, value) => write!(f, "Map[{key}, {value}]"),
+            TypeRef::Function(params, result, effects) => fmt_function(f, params, result, *effects),
         }
     }
 }
 
 impl fmt::Debug for Type {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt::Display::fmt(&self, f)
+        fmt::Display::fmt(self, f)
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum TypeDfnRef {
     Symbol(FQType),
+    Var(TSymbol),                      // Parameter of the type being defined
+    Apply(FQType, Vec<L<TypeDfnRef>>), // Instance of a generic type
+    List(Box<L<TypeDfnRef>>),
+    Map(Box<L<TypeDfnRef>>, Box<L<TypeDfnRef>>),
+    Tuple(Vec<L<TypeDfnRef>>),
+    Function(Vec<L<TypeDfnRef>>, Box<L<TypeDfnRef>>, Effects),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum TypeDfn {
     Singleton,
     Tuple(Vec<L<TypeDfnRef>>),
+    Record(Vec<(Symbol, L<TypeDfnRef>)>),
+    Union(Vec<(TSymbol, TypeDfn)>),
+    Generic(Vec<TSymbol>, Box<TypeDfn>), // The parameters are shared by the variants of unions
+}
+
+impl TypeDfn {
+    // Returns the type parameters and the definition they are used in
+    fn generic(&self) -> (&[TSymbol], &TypeDfn) {
+        match self {
+            TypeDfn::Generic(params, dfn) => (params, dfn),
+            _ => (&[], self),
+        }
+    }
 }
 
 pub(crate) type LVTypeDfn = L<V<TypeDfn>>;
@@ -210,9 +673,9 @@ impl<'a> NewTypes<'a> {
         .validate()
     }
 
-    fn add(&mut self, fq: &FQType, lvdfn: &LVTypeDfn, data: TypeData) {
+    fn add(&mut self, fq: &FQType, lvdfn: &LVTypeDfn, tipo: Type) {
         self.added
-            .insert(fq.clone(), lvdfn.it.visibility.wrap(Type { data }));
+            .insert(fq.clone(), lvdfn.it.visibility.wrap(tipo));
     }
 
     fn validate(mut self) -> Result<TypeMap> {
@@ -225,7 +688,8 @@ impl<'a> NewTypes<'a> {
             } else {
                 match lvdfn.it.it {
                     TypeDfn::Singleton => {
-                        self.add(fq, lvdfn, TypeData::Singleton(fq.clone()));
+                        let data = TypeData::Singleton(fq.clone());
+                        self.add(fq, lvdfn, Type { data });
                     }
                     _ => {
                         pending.insert(fq.clone());
@@ -233,58 +697,232 @@ impl<'a> NewTypes<'a> {
                 }
             }
         }
-        // Second pass: tuples
+        // Second pass: tuples, records and unions, whose variants are added too. The variants
+        // of generic unions are generic too, except singletons, which belong to every instance.
         pending.retain(|fq| {
             let lvdfn = self.candidates.get(fq).unwrap();
-            match &lvdfn.it.it {
-                TypeDfn::Tuple(types) => {
-                    if types.is_empty() {
-                        self.add(fq, lvdfn, TypeData::None);
-                    } else {
-                        let mut refs = Vec::<TypeRef>::with_capacity(types.len());
-                        for dfnref in types {
-                            errors
-                                .add_result(self.validate_ref(dfnref))
-                                .map(|r| refs.push(r));
+            let (params, dfn) = lvdfn.it.it.generic();
+            match dfn {
+                TypeDfn::Singleton => true,
+                TypeDfn::Tuple(types) if types.is_empty() => {
+                    self.add(fq, lvdfn, Type::type_none());
+                    false
+                }
+                TypeDfn::Union(variants) => {
+                    let mut types = Vec::with_capacity(variants.len());
+                    for (symbol, dfn) in variants {
+                        let variant = FQ::enclosed(fq, symbol.clone());
+                        let tipo = Type {
+                            data: self.data(&variant, params, dfn, &mut errors),
+                        };
+                        if tipo.is_singleton() {
+                            self.add(&variant, lvdfn, tipo.clone());
+                        } else {
+                            let generic = Type::type_generic(params.to_vec(), tipo.clone());
+                            self.add(&variant, lvdfn, generic);
                         }
-                        self.add(fq, lvdfn, TypeData::named_tuple(fq, refs));
+                        types.push(tipo);
                     }
+                    let union = Union {
+                        symbol: fq.clone(),
+                        variants: types,
+                        args: vars(params),
+                    };
+                    let data = TypeData::Union(Arc::new(union));
+                    self.add(
+                        fq,
+                        lvdfn,
+                        Type::type_generic(params.to_vec(), Type { data }),
+                    );
+                    false
+                }
+                dfn => {
+                    let data = self.data(fq, params, dfn, &mut errors);
+                    self.add(
+                        fq,
+                        lvdfn,
+                        Type::type_generic(params.to_vec(), Type { data }),
+                    );
                     false
                 }
-                _ => true,
             }
         });
         errors.to_result(self.added)
     }
 
-    fn validate_ref(&self, dfnref: &L<TypeDfnRef>) -> Result<TypeRef> {
+    // Data of a type that is not a union. Variants without elements are singletons.
+    fn data(
+        &self,
+        fq: &FQType,
+        params: &[TSymbol],
+        dfn: &TypeDfn,
+        errors: &mut Errors,
+    ) -> TypeData {
+        match dfn {
+            TypeDfn::Tuple(types) if !types.is_empty() => {
+                let mut refs = Vec::<TypeRef>::with_capacity(types.len());
+                for dfnref in types {
+                    if let Some(r) = errors.add_result(self.validate_ref(fq, dfnref)) {
+                        refs.push(r)
+                    }
+                }
+                TypeData::named_tuple(fq, refs, vars(params))
+            }
+            TypeDfn::Record(fields) => {
+                let mut refs = Vec::with_capacity(fields.len());
+                for (symbol, dfnref) in fields {
+                    if let Some(r) = errors.add_result(self.validate_ref(fq, dfnref)) {
+                        refs.push((symbol.clone(), r))
+                    }
+                }
+                let record = Record {
+                    symbol: fq.clone(),
+                    fields: refs,
+                    args: vars(params),
+                };
+                TypeData::Record(Arc::new(record))
+            }
+            TypeDfn::Union(_) | TypeDfn::Generic(..) => unreachable!("Unions can't be nested"),
+            _ => TypeData::Singleton(fq.clone()),
+        }
+    }
+
+    // Returns whether the symbol is a variant of a union being defined
+    fn is_candidate_variant(&self, s: &FQType) -> bool {
+        self.candidate_variant(s).is_some()
+    }
+
+    // Returns the definition of a variant of a union being defined, and the union parameters
+    fn candidate_variant(&self, s: &FQType) -> Option<(&[TSymbol], &TypeDfn)> {
+        let (params, dfn) = self.candidates.get(s.parent()?)?.it.it.generic();
+        match dfn {
+            TypeDfn::Union(variants) => variants
+                .iter()
+                .find(|(v, _)| v == s.symbol())
+                .map(|(_, dfn)| (params, dfn)),
+            _ => None,
+        }
+    }
+
+    // Returns the number of type parameters of a type that exists or is being defined.
+    // Singleton variants have none.
+    fn param_count(&self, s: &FQType) -> usize {
+        let count = |t: &Type| t.generic().map_or(0, |(params, _)| params.len());
+        if let Some(t) = self.types.get(s).or_else(|| self.added.get(s)) {
+            count(&t.it)
+        } else if let Some(t) = self.candidates.get(s) {
+            t.it.it.generic().0.len()
+        } else {
+            match self.candidate_variant(s) {
+                Some((_, TypeDfn::Singleton)) | None => 0,
+                Some((_, TypeDfn::Tuple(types))) if types.is_empty() => 0,
+                Some((params, _)) => params.len(),
+            }
+        }
+    }
+
+    // References are resolved from the module of the type being defined. The number of type
+    // arguments must match the parameters of the referenced type.
+    fn validate_ref(&self, fq: &FQType, dfnref: &L<TypeDfnRef>) -> Result<TypeRef> {
+        let check_args = |s: &FQType, count: usize| {
+            let expected = self.param_count(s);
+            if expected == count {
+                Ok(())
+            } else {
+                dfnref.err(Error::TypeArgumentCountMismatch(expected, count))
+            }
+        };
         match &dfnref.it {
+            TypeDfnRef::Var(symbol) => Ok(TypeRef::Type(Type::type_var(symbol.clone()))),
             TypeDfnRef::Symbol(s) => {
-                if let Some(t) = self.types.get(s) {
-                    // TODO: check visibility
-                    if t.it.is_builtin() || t.it.is_singleton() {
+                let r = self.validate_symbol(fq, dfnref, s)?;
+                check_args(s, 0)?;
+                Ok(r)
+            }
+            TypeDfnRef::Apply(s, args) => {
+                self.validate_symbol(fq, dfnref, s)?;
+                check_args(s, args.len())?;
+                let mut errors = Errors::default();
+                let mut refs = Vec::with_capacity(args.len());
+                for a in args {
+                    if let Some(r) = errors.add_result(self.validate_ref(fq, a)) {
+                        refs.push(r);
+                    }
+                }
+                errors.to_result(TypeRef::Apply(s.clone(), refs))
+            }
+            TypeDfnRef::List(element) => Ok(list_ref(self.validate_ref(fq, element)?)),
+            TypeDfnRef::Map(key, value) => {
+                let mut errors = Errors::default();
+                let key = errors.add_result(self.validate_ref(fq, key));
+                let value = errors.add_result(self.validate_ref(fq, value));
+                errors.to_lazy_result(|| map_ref(key.unwrap(), value.unwrap()))
+            }
+            TypeDfnRef::Tuple(types) => {
+                let refs = self.validate_refs(fq, types)?;
+                Ok(TypeRef::Type(if refs.is_empty() {
+                    Type::type_none()
+                } else {
+                    Type {
+                        data: TypeData::AnonTuple(Arc::new(Tuple { types: refs })),
+                    }
+                }))
+            }
+            TypeDfnRef::Function(params, result, effects) => {
+                let mut errors = Errors::default();
+                let params = errors.add_result(self.validate_refs(fq, params));
+                let result = errors.add_result(self.validate_ref(fq, result));
+                errors.to_lazy_result(|| function_ref(params.unwrap(), result.unwrap(), *effects))
+            }
+        }
+    }
+
+    fn validate_refs(&self, fq: &FQType, dfnrefs: &[L<TypeDfnRef>]) -> Result<Vec<TypeRef>> {
+        let mut errors = Errors::default();
+        let mut refs = Vec::with_capacity(dfnrefs.len());
+        for r in dfnrefs {
+            if let Some(r) = errors.add_result(self.validate_ref(fq, r)) {
+                refs.push(r);
+            }
+        }
+        errors.to_result(refs)
+    }
+
+    fn validate_symbol(&self, fq: &FQType, dfnref: &L<TypeDfnRef>, s: &FQType) -> Result<TypeRef> {
+        let visible = |visibility: Visibility| {
+            if fq.can_see(visibility, s) {
+                Ok(())
+            } else {
+                dfnref.err(Error::TypeNotVisible(s.clone()))
+            }
+        };
+        if let Some(t) = self.types.get(s) {
+            visible(t.visibility)?;
+            if t.it.is_builtin() || t.it.is_singleton() {
+                Ok(TypeRef::Type(t.it.clone()))
+            } else {
+                Ok(TypeRef::Symbol(s.clone()))
+            }
+        } else {
+            match self.added.get(s) {
+                Some(t) => {
+                    visible(t.visibility)?;
+                    if t.it.is_singleton() {
                         Ok(TypeRef::Type(t.it.clone()))
                     } else {
                         Ok(TypeRef::Symbol(s.clone()))
                     }
-                } else {
-                    match self.added.get(s) {
-                        Some(t) => {
-                            // TODO: check visibility
-                            if t.it.is_singleton() {
-                                Ok(TypeRef::Type(t.it.clone()))
-                            } else {
-                                Ok(TypeRef::Symbol(s.clone()))
-                            }
-                        }
-                        _ => {
-                            if let Some(t) = self.candidates.get(s) {
-                                // TODO: check visibility
-                                Ok(TypeRef::Symbol(s.clone()))
-                            } else {
-                                dfnref.err(Error::InvalidType) // TODO
-                            }
-                        }
+                }
+                _ => {
+                    if let Some(t) = self.candidates.get(s) {
+                        visible(t.it.visibility)?;
+                        Ok(TypeRef::Symbol(s.clone()))
+                    } else if self.is_candidate_variant(s) {
+                        let parent = self.candidates.get(s.parent().unwrap()).unwrap();
+                        visible(parent.it.visibility)?;
+                        Ok(TypeRef::Symbol(s.clone()))
+                    } else {
+                        dfnref.err(Error::InvalidType) // TODO
                     }
                 }
             }
@@ -292,16 +930,200 @@ impl<'a> NewTypes<'a> {
     }
 }
 
-#[derive(Clone, PartialEq, Eq)]
+// Builtin types of references are resolved right away unless they refer to named types
+fn list_ref(element: TypeRef) -> TypeRef {
+    match element {
+        TypeRef::Type(t) => TypeRef::Type(Type::type_list(t)),
+        element => TypeRef::List(Box::new(element)),
+    }
+}
+
+fn map_ref(key: TypeRef, value: TypeRef) -> TypeRef {
+    match (key, value) {
+        (TypeRef::Type(k), TypeRef::Type(v)) => TypeRef::Type(Type::type_map(k, v)),
+        (key, value) => TypeRef::Map(Box::new(key), Box::new(value)),
+    }
+}
+
+fn function_ref(params: Vec<TypeRef>, result: TypeRef, effects: Effects) -> TypeRef {
+    let types: Option<Vec<Type>> = params
+        .iter()
+        .map(|p| match p {
+            TypeRef::Type(t) => Some(t.clone()),
+            _ => None,
+        })
+        .collect();
+    match (types, result) {
+        (Some(types), TypeRef::Type(r)) => {
+            TypeRef::Type(Type::type_function_with_effects(types, r, effects))
+        }
+        (_, result) => TypeRef::Function(params, Box::new(result), effects),
+    }
+}
+
+// The parameters of a generic type, used as the type arguments of its definition
+fn vars(params: &[TSymbol]) -> Vec<Type> {
+    params.iter().cloned().map(Type::type_var).collect()
+}
+
+#[derive(Clone)]
 pub struct Value {
     tipo: Type,
     val: Val,
 }
 
+// Values are compared structurally, so the types they were built with only matter for the
+// names of singletons, tuples and records
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.val, &other.val) {
+            (Val::Singleton, Val::Singleton)
+            | (Val::Tuple(_), Val::Tuple(_))
+            | (Val::Record(_), Val::Record(_))
+                if self.tipo.fq() != other.tipo.fq() =>
+            {
+                false
+            }
+            _ => self.val == other.val,
+        }
+    }
+}
+
+impl Eq for Value {}
+
 #[derive(Clone, PartialEq, Eq)]
 enum Val {
     Singleton,
     Integer(Integer),
+    Decimal(Decimal),
+    String(ArcStr),
+    Closure(Arc<Closure>),
+    Builtin(Builtin),
+    Native(Arc<Native>),
+    Tuple(Arc<Vec<Value>>),
+    Record(Arc<Vec<Value>>), // In definition order
+    List(im::Vector<Value>),
+    Map(HashMap<Value, Value>),
+}
+
+// Equal values must have equal hashes, so only the variant and the contents of integers and
+// strings are hashed, which are the common map keys
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(&self.val).hash(state);
+        match &self.val {
+            Val::Integer(v) => v.hash(state),
+            Val::String(v) => v.hash(state),
+            _ => (),
+        }
+    }
+}
+
+// Function value, with the local values in scope when it was created
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Closure {
+    pub(crate) lambda: Arc<Lambda>,
+    pub(crate) captured: HashMap<Symbol, Value>,
+}
+
+// Functions provided by the environment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Builtin {
+    Print, // Writes a string followed by a line break to the output
+    Div,   // Divides decimals with the provided scale and rounding mode
+}
+
+impl Builtin {
+    pub(crate) const ALL: [Builtin; 2] = [Builtin::Print, Builtin::Div];
+
+    pub(crate) fn symbol(self) -> Symbol {
+        match self {
+            Self::Print => symbol::PRINT.clone(),
+            Self::Div => symbol::DIV.clone(),
+        }
+    }
+
+    pub(crate) fn tipo(self) -> Type {
+        match self {
+            Self::Print => Type::type_function_with_effects(
+                vec![Type::type_string()],
+                Type::type_none(),
+                Effect::IO.into(),
+            ),
+            Self::Div => Type::type_function(
+                vec![
+                    Type::type_decimal(),
+                    Type::type_decimal(),
+                    Type::type_integer(),
+                    Type::type_rounding(),
+                ],
+                Type::type_decimal(),
+            ),
+        }
+    }
+}
+
+// Variants of the std Rounding union, which are singletons named after the modes
+pub(crate) const ROUNDING_MODES: [(&str, RoundingMode); 7] = [
+    ("Up", RoundingMode::Up),
+    ("Down", RoundingMode::Down),
+    ("Ceiling", RoundingMode::Ceiling),
+    ("Floor", RoundingMode::Floor),
+    ("HalfUp", RoundingMode::HalfUp),
+    ("HalfDown", RoundingMode::HalfDown),
+    ("HalfEven", RoundingMode::HalfEven),
+];
+
+impl Type {
+    pub(crate) fn type_rounding() -> Type {
+        let variants = ROUNDING_MODES
+            .iter()
+            .map(|(name, _)| Type::type_singleton(variant_type(&symbol::FQ_ROUNDING, name)))
+            .collect();
+        Type::type_union(symbol::FQ_ROUNDING.clone(), variants)
+    }
+}
+
+impl Value {
+    pub(crate) fn as_rounding(&self, loc: &Loc) -> Result<RoundingMode> {
+        let mode = self
+            .tipo
+            .fq()
+            .filter(|fq| fq.parent() == Some(&symbol::FQ_ROUNDING))
+            .and_then(|fq| {
+                let name = fq.symbol().to_string();
+                ROUNDING_MODES.iter().find(|(n, _)| *n == name)
+            });
+        match mode {
+            Some((_, mode)) => Ok(*mode),
+            None => self.type_mismatch(loc, Type::type_rounding()),
+        }
+    }
+}
+
+// Function implemented by the host, taking the arguments already checked by the typechecker.
+// Errors are reported at the location of the call.
+pub(crate) type NativeFn =
+    dyn Fn(Vec<Value>) -> std::result::Result<Value, Error> + Send + Sync + 'static;
+
+pub(crate) struct Native {
+    pub(crate) symbol: FQSym,
+    pub(crate) function: Box<NativeFn>,
+}
+
+// Natives are told apart by the symbol they are registered with
+impl PartialEq for Native {
+    fn eq(&self, other: &Self) -> bool {
+        self.symbol == other.symbol
+    }
+}
+
+impl Eq for Native {}
+
+impl fmt::Debug for Native {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Native({})", self.symbol)
+    }
 }
 
 impl Value {
@@ -316,6 +1138,28 @@ impl Value {
         }
     }
 
+    pub fn as_decimal(self, loc: &Loc) -> Result<Decimal> {
+        match self.val {
+            Val::Decimal(v) => Ok(v),
+            _ => self.type_mismatch(loc, Type::type_decimal()),
+        }
+    }
+
+    pub fn as_string(self, loc: &Loc) -> Result<ArcStr> {
+        match self.val {
+            Val::String(v) => Ok(v),
+            _ => self.type_mismatch(loc, Type::type_string()),
+        }
+    }
+
+    // Returns the text of the value in an interpolated string, where strings are not quoted
+    pub(crate) fn text(&self) -> String {
+        match &self.val {
+            Val::String(v) => v.to_string(),
+            _ => self.to_string(),
+        }
+    }
+
     pub fn as_boolean(self, loc: &Loc) -> Result<bool> {
         match self.tipo.data {
             TypeData::True => Ok(true),
@@ -324,6 +1168,125 @@ impl Value {
         }
     }
 
+    pub(crate) fn as_closure(&self, loc: &Loc) -> Result<Arc<Closure>> {
+        match &self.val {
+            Val::Closure(c) => Ok(c.clone()),
+            _ => loc.err(Error::FunctionExpected(self.tipo.clone())),
+        }
+    }
+
+    pub(crate) fn as_builtin(&self) -> Option<Builtin> {
+        match &self.val {
+            Val::Builtin(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_native(&self) -> Option<Arc<Native>> {
+        match &self.val {
+            Val::Native(n) => Some(n.clone()),
+            _ => None,
+        }
+    }
+
+    // Returns the value of a tuple element, already checked by the typechecker
+    pub(crate) fn tuple_field(&self, loc: &Loc, index: usize) -> Result<Value> {
+        match &self.val {
+            Val::Tuple(values) => match values.get(index) {
+                Some(value) => Ok(value.clone()),
+                None => loc.err(Error::TupleIndexOutOfBounds(self.tipo.clone(), index)),
+            },
+            _ => loc.err(Error::TupleExpected(self.tipo.clone())),
+        }
+    }
+
+    // Returns the value of a record field, by its position in the type definition
+    pub(crate) fn record_field(&self, loc: &Loc, index: usize) -> Result<Value> {
+        match &self.val {
+            Val::Record(values) => Ok(values[index].clone()),
+            _ => loc.err(Error::RecordExpected(self.tipo.clone())),
+        }
+    }
+
+    // Returns the element of a list at the provided index
+    pub(crate) fn list_get(&self, loc: &Loc, index: Integer) -> Result<Value> {
+        match &self.val {
+            Val::List(values) => match index.to_usize().and_then(|i| values.get(i)) {
+                Some(value) => Ok(value.clone()),
+                None => loc.err(Error::IndexOutOfBounds(index)),
+            },
+            _ => loc.err(Error::CollectionExpected(self.tipo.clone())),
+        }
+    }
+
+    // Returns the value of a map for the provided key
+    pub(crate) fn map_get(&self, loc: &Loc, key: &Value) -> Result<Value> {
+        match &self.val {
+            Val::Map(entries) => match entries.get(key) {
+                Some(value) => Ok(value.clone()),
+                None => loc.err(Error::KeyNotFound(key.clone())),
+            },
+            _ => loc.err(Error::CollectionExpected(self.tipo.clone())),
+        }
+    }
+
+    // Returns the number of elements of a list or entries of a map
+    pub(crate) fn length(&self, loc: &Loc) -> Result<usize> {
+        match &self.val {
+            Val::List(values) => Ok(values.len()),
+            Val::Map(entries) => Ok(entries.len()),
+            _ => loc.err(Error::CollectionExpected(self.tipo.clone())),
+        }
+    }
+
+    // Returns the elements of a tuple or the fields of a record
+    pub(crate) fn fields(&self) -> Option<&[Value]> {
+        match &self.val {
+            Val::Tuple(values) | Val::Record(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    // Returns a copy of a record with some of its fields replaced
+    pub(crate) fn update(&self, loc: &Loc, fields: Vec<(usize, Value)>) -> Result<Value> {
+        match &self.val {
+            Val::Record(values) => {
+                let mut values = values.as_ref().clone();
+                for (index, value) in fields {
+                    values[index] = value;
+                }
+                Self::v_record(loc, self.tipo.clone(), values)
+            }
+            _ => loc.err(Error::RecordExpected(self.tipo.clone())),
+        }
+    }
+
+    // Functions, tuples, records and collections can't be represented as literals
+    pub(crate) fn to_literal(&self) -> Option<Literal> {
+        match &self.val {
+            Val::Singleton => Some(Literal::Singleton),
+            Val::Integer(v) => Some(Literal::Integer(v.clone())),
+            Val::Decimal(v) => Some(Literal::Decimal(v.clone())),
+            Val::String(v) => Some(Literal::String(v.clone())),
+            Val::Closure(_)
+            | Val::Builtin(_)
+            | Val::Native(_)
+            | Val::Tuple(_)
+            | Val::Record(_)
+            | Val::List(_)
+            | Val::Map(_) => None,
+        }
+    }
+
+    pub(crate) fn from_literal(loc: &Loc, tipo: Type, literal: &Literal) -> Result<Value> {
+        match literal {
+            Literal::Singleton => Self::v_singleton(loc, tipo),
+            Literal::Integer(v) => Self::v_integer(loc, tipo, v.clone()),
+            Literal::Decimal(v) => Self::v_decimal(loc, tipo, v.clone()),
+            Literal::String(v) => Self::v_string(loc, tipo, v.clone()),
+        }
+    }
+
     fn type_mismatch<T>(&self, loc: &Loc, expected: Type) -> Result<T> {
         loc.err(Error::type_mismatch(expected, self.get_type()))
     }
@@ -366,6 +1329,97 @@ impl Value {
         }
     }
 
+    pub fn v_decimal(loc: &Loc, tipo: Type, value: Decimal) -> Result<Value> {
+        if tipo.is_decimal() {
+            Ok(Value {
+                tipo,
+                val: Val::Decimal(value),
+            })
+        } else {
+            loc.err(Error::SingletonExpected(tipo))
+        }
+    }
+
+    pub fn v_string(loc: &Loc, tipo: Type, value: ArcStr) -> Result<Value> {
+        if tipo.is_string() {
+            Ok(Value {
+                tipo,
+                val: Val::String(value),
+            })
+        } else {
+            loc.err(Error::SingletonExpected(tipo))
+        }
+    }
+
+    pub(crate) fn v_closure(lambda: Arc<Lambda>, captured: HashMap<Symbol, Value>) -> Value {
+        Value {
+            tipo: lambda.tipo.clone(),
+            val: Val::Closure(Arc::new(Closure { lambda, captured })),
+        }
+    }
+
+    pub(crate) fn v_builtin(builtin: Builtin) -> Value {
+        Value {
+            tipo: builtin.tipo(),
+            val: Val::Builtin(builtin),
+        }
+    }
+
+    pub(crate) fn v_native(tipo: Type, symbol: FQSym, function: Box<NativeFn>) -> Value {
+        Value {
+            tipo,
+            val: Val::Native(Arc::new(Native { symbol, function })),
+        }
+    }
+
+    // The values are already checked against the tuple type by the typechecker
+    pub(crate) fn v_tuple(loc: &Loc, tipo: Type, values: Vec<Value>) -> Result<Value> {
+        if tipo.is_tuple() {
+            Ok(Value {
+                tipo,
+                val: Val::Tuple(Arc::new(values)),
+            })
+        } else {
+            loc.err(Error::TupleExpected(tipo))
+        }
+    }
+
+    // The values are in definition order and already checked by the typechecker
+    pub(crate) fn v_record(loc: &Loc, tipo: Type, values: Vec<Value>) -> Result<Value> {
+        if tipo.is_record() {
+            Ok(Value {
+                tipo,
+                val: Val::Record(Arc::new(values)),
+            })
+        } else {
+            loc.err(Error::RecordExpected(tipo))
+        }
+    }
+
+    // The elements are already checked against the list type by the typechecker
+    pub(crate) fn v_list(loc: &Loc, tipo: Type, values: im::Vector<Value>) -> Result<Value> {
+        if tipo.is_list() {
+            Ok(Value {
+                tipo,
+                val: Val::List(values),
+            })
+        } else {
+            loc.err(Error::CollectionExpected(tipo))
+        }
+    }
+
+    // The entries are already checked against the map type by the typechecker
+    pub(crate) fn v_map(loc: &Loc, tipo: Type, entries: HashMap<Value, Value>) -> Result<Value> {
+        if tipo.is_map() {
+            Ok(Value {
+                tipo,
+                val: Val::Map(entries),
+            })
+        } else {
+            loc.err(Error::CollectionExpected(tipo))
+        }
+    }
+
     pub fn v_singleton(loc: &Loc, tipo: Type) -> Result<Value> {
         if tipo.is_singleton() {
             Ok(Value {
@@ -378,11 +1432,89 @@ impl Value {
     }
 }
 
+// Returns the string as a literal, escaping the chars that can't appear as they are
+pub(crate) fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' | '\\' | '{' | '}' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match &self.val {
             Val::Integer(value) => value.fmt(f),
+            Val::Decimal(value) => value.fmt(f),
+            Val::String(value) => f.write_str(&quote(value)),
             Val::Singleton => self.tipo.fmt(f),
+            Val::Closure(_) | Val::Builtin(_) | Val::Native(_) => write!(f, "fn{}", self.tipo),
+            Val::Tuple(values) => {
+                if let Some(fq) = self.tipo.fq() {
+                    fq.fmt(f)?;
+                }
+                f.write_str("(")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    value.fmt(f)?;
+                }
+                if values.len() == 1 && self.tipo.is_anonymous() {
+                    f.write_str(",")?;
+                }
+                f.write_str(")")
+            }
+            Val::Record(values) => {
+                write!(f, "{} {{", self.tipo)?;
+                if let TypeData::Record(r) = &self.tipo.data {
+                    for (i, ((symbol, _), value)) in r.fields.iter().zip(values.iter()).enumerate()
+                    {
+                        if i > 0 {
+                            f.write_str(",")?;
+                        }
+                        write!(f, " {symbol}: {value}")?;
+                    }
+                }
+                f.write_str(" }")
+            }
+            Val::List(values) => {
+                f.write_str("[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    value.fmt(f)?;
+                }
+                f.write_str("]")
+            }
+            // Entries are sorted by key, as maps are unordered. The empty map is written as
+            // its literal.
+            Val::Map(entries) if entries.is_empty() => f.write_str("{:}"),
+            Val::Map(entries) => {
+                let mut entries: Vec<(String, &Value)> =
+                    entries.iter().map(|(k, v)| (k.to_string(), v)).collect();
+                entries.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+                f.write_str("{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, " {key}: {value}")?;
+                }
+                f.write_str(" }")
+            }
         }
     }
 }
@@ -409,14 +1541,203 @@ impl Context {
         self.types.types.get(symbol)
     }
 
-    pub(crate) fn contains(&self, symbol: &FQType) -> bool {
-        self.types.types.contains_key(symbol)
+    // References to instances of generic types are resolved by instantiating them
+    fn resolve_ref(&self, r: &TypeRef) -> Option<Type> {
+        match r {
+            TypeRef::Type(t) => Some(t.clone()),
+            TypeRef::Symbol(fq) => self.get(fq).map(|t| t.it.clone()),
+            TypeRef::Apply(fq, args) => {
+                let args: Option<Vec<Type>> = args.iter().map(|a| self.resolve_ref(a)).collect();
+                self.get(fq)?.it.instantiate(&args?)
+            }
+            TypeRef::List(element) => Some(Type::type_list(self.resolve_ref(element)?)),
+            TypeRef::Map(key, value) => Some(Type::type_map(
+                self.resolve_ref(key)?,
+                self.resolve_ref(value)?,
+            )),
+            TypeRef::Function(params, result, effects) => {
+                let params: Option<Vec<Type>> =
+                    params.iter().map(|p| self.resolve_ref(p)).collect();
+                let result = self.resolve_ref(result)?;
+                Some(Type::type_function_with_effects(params?, result, *effects))
+            }
+        }
     }
 
-    fn add(&mut self, fq: &FQType, visibility: Visibility, data: TypeData) {
-        self.types
-            .types
-            .insert(fq.clone(), visibility.wrap(Type { data }));
+    // Returns the element types of a tuple type, resolving references to other named types
+    pub(crate) fn tuple_types(&self, tipo: &Type) -> Option<Vec<Type>> {
+        let types = match &tipo.data {
+            TypeData::NamedTuple(t) => &t.types.types,
+            TypeData::AnonTuple(t) => &t.types,
+            _ => return None,
+        };
+        types.iter().map(|r| self.resolve_ref(r)).collect()
+    }
+
+    // Returns the type that accepts both types, if any. Variants of the same union are joined
+    // into the union. Instances of the same generic type and collections are joined by their
+    // type arguments.
+    pub(crate) fn join(&self, t1: &Type, t2: &Type) -> Option<Type> {
+        if t1.accepts(t2) {
+            return Some(t1.clone());
+        } else if t2.accepts(t1) {
+            return Some(t2.clone());
+        }
+        match (&t1.data, &t2.data) {
+            (TypeData::List(e1), TypeData::List(e2)) => self.join(e1, e2).map(Type::type_list),
+            (TypeData::Map(m1), TypeData::Map(m2)) => Some(Type::type_map(
+                self.join(&m1.key, &m2.key)?,
+                self.join(&m1.value, &m2.value)?,
+            )),
+            _ => {
+                // Variants are joined into their union
+                let family = |t: &Type| t.union().or_else(|| t.fq());
+                let fq = if t1.fq().is_some() && t1.fq() == t2.fq() {
+                    t1.fq()?
+                } else if family(t1).is_some() && family(t1) == family(t2) {
+                    family(t1)?
+                } else {
+                    return None;
+                };
+                let tipo = &self.get(&fq)?.it;
+                match tipo.generic() {
+                    None => Some(tipo.clone()),
+                    Some((params, _)) => {
+                        let args = self.join_args(params.len(), t1.args(), t2.args())?;
+                        tipo.instantiate(&args)
+                    }
+                }
+            }
+        }
+    }
+
+    // Returns the union a variant is declared in, with the type arguments of the variant, or
+    // the type itself if it is not a variant. True and False are declared in Boolean.
+    pub(crate) fn declared(&self, tipo: &Type) -> Type {
+        let union = tipo.union().and_then(|fq| {
+            let union = &self.get(&fq)?.it;
+            match union.generic() {
+                None => Some(union.clone()),
+                Some((params, _)) => {
+                    union.instantiate(&self.join_args(params.len(), tipo.args(), &[])?)
+                }
+            }
+        });
+        union.unwrap_or_else(|| tipo.clone())
+    }
+
+    // Variants without type arguments don't constrain the joined ones
+    fn join_args(&self, count: usize, args1: &[Type], args2: &[Type]) -> Option<Vec<Type>> {
+        match (args1.is_empty(), args2.is_empty()) {
+            (true, true) => Some(vec![Type::type_unbound(); count]),
+            (true, false) => Some(args2.to_vec()),
+            (false, true) => Some(args1.to_vec()),
+            (false, false) => args1
+                .iter()
+                .zip(args2)
+                .map(|(a1, a2)| self.join(a1, a2))
+                .collect(),
+        }
+    }
+
+    // Instantiates a generic type with the type arguments inferred from values of the actual
+    // types used where the expected ones, written in terms of its parameters, are. Parameters
+    // not constrained by any value are unbound. Mismatches are left to be reported by the
+    // caller, once the expected types are instantiated.
+    pub(crate) fn infer(&self, tipo: &Type, expected: &[Type], actual: &[Type]) -> Type {
+        let Some((params, _)) = tipo.generic() else {
+            return tipo.clone();
+        };
+        let mut bindings = HashMap::new();
+        for (e, a) in expected.iter().zip(actual) {
+            self.unify(params, e, a, &mut bindings);
+        }
+        let args: Vec<Type> = params
+            .iter()
+            .map(|p| bindings.get(p).cloned().unwrap_or_else(Type::type_unbound))
+            .collect();
+        tipo.instantiate(&args).unwrap()
+    }
+
+    // Binds the parameters found in the expected type to the matching parts of the actual one.
+    // Parameters found more than once are bound to the join of the matching types.
+    fn unify(
+        &self,
+        params: &[TSymbol],
+        expected: &Type,
+        actual: &Type,
+        bindings: &mut HashMap<TSymbol, Type>,
+    ) {
+        match (&expected.data, &actual.data) {
+            (TypeData::Var(symbol), _) if params.contains(symbol) => {
+                let bound = match bindings.get(symbol) {
+                    Some(tipo) => self.join(tipo, actual),
+                    None => Some(actual.clone()),
+                };
+                if let Some(tipo) = bound {
+                    bindings.insert(symbol.clone(), tipo);
+                }
+            }
+            (TypeData::List(e), TypeData::List(a)) => self.unify(params, e, a, bindings),
+            (TypeData::Map(e), TypeData::Map(a)) => {
+                self.unify(params, &e.key, &a.key, bindings);
+                self.unify(params, &e.value, &a.value, bindings);
+            }
+            (TypeData::AnonTuple(e), TypeData::AnonTuple(a)) => {
+                for pair in e.types.iter().zip(&a.types) {
+                    if let (TypeRef::Type(e), TypeRef::Type(a)) = pair {
+                        self.unify(params, e, a, bindings);
+                    }
+                }
+            }
+            (TypeData::Function(e), TypeData::Function(a)) => {
+                for (e, a) in e.params.iter().zip(&a.params) {
+                    self.unify(params, e, a, bindings);
+                }
+                self.unify(params, &e.result, &a.result, bindings);
+            }
+            _ => {
+                let related = expected.fq().is_some()
+                    && (expected.fq() == actual.fq() || expected.fq() == actual.union());
+                if related {
+                    for (e, a) in expected.args().iter().zip(actual.args()) {
+                        self.unify(params, e, a, bindings);
+                    }
+                }
+            }
+        }
+    }
+
+    // Returns the instance of a generic type that matches the expected type, which can be an
+    // instance of the same type or of the union it is a variant of
+    pub(crate) fn instance_of(&self, generic: &Type, expected: &Type) -> Option<Type> {
+        let (_, tipo) = generic.generic()?;
+        if tipo.fq() == expected.fq() || tipo.union() == expected.fq() {
+            generic.instantiate(expected.args())
+        } else {
+            None
+        }
+    }
+
+    // Returns the variants of a union. True and False are the variants of Boolean.
+    pub(crate) fn variants(&self, tipo: &Type) -> Option<Vec<Type>> {
+        match &tipo.data {
+            TypeData::Boolean => Some(vec![Type::type_true(), Type::type_false()]),
+            TypeData::Union(u) => Some(u.variants.clone()),
+            _ => None,
+        }
+    }
+
+    // Returns the fields of a record type in definition order, resolving type references
+    pub(crate) fn record_fields(&self, tipo: &Type) -> Option<Vec<(Symbol, Type)>> {
+        match &tipo.data {
+            TypeData::Record(r) => r
+                .fields
+                .iter()
+                .map(|(symbol, r)| self.resolve_ref(r).map(|t| (symbol.clone(), t)))
+                .collect(),
+            _ => None,
+        }
     }
 
     pub(crate) fn add_types(&self, candidates: &TypeDfnMap) -> Result<Context> {
@@ -439,6 +1760,16 @@ impl Default for Context {
         types.export(Type::type_false());
         types.export(Type::type_boolean());
         types.export(Type::type_integer());
+        types.export(Type::type_decimal());
+        types.export(Type::type_string());
+        let rounding = Type::type_rounding();
+        for variant in types.variants(&rounding).unwrap() {
+            types.export(variant);
+        }
+        types.export(rounding);
         types
     }
 }
+
+#[cfg(test)]
+mod tests;
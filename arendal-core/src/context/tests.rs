@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use crate::Integer;
+use crate::effect::Effects;
+use crate::env0::Env;
+use crate::error::Error;
+use crate::symbol::Pkg;
+
+use super::{Type, Typed, Value, local_type, variant_type};
+
+fn round_trip<T>(value: T)
+where
+    T: Typed + Into<Value> + TryFrom<Value, Error = Error> + Clone + PartialEq + Debug,
+{
+    let converted: Value = value.clone().into();
+    assert!(T::tipo().accepts(&converted.get_type()), "{converted}");
+    assert_eq!(T::try_from(converted).unwrap(), value);
+}
+
+fn mismatch<T: TryFrom<Value, Error = Error> + Debug>(value: Value, expected: Type) {
+    let actual = value.get_type();
+    assert_eq!(
+        T::try_from(value).unwrap_err(),
+        Error::type_mismatch(expected, actual)
+    );
+}
+
+#[derive(Debug, Clone, PartialEq, Typed)]
+struct Point(i64, i64);
+
+#[derive(Debug, Clone, PartialEq, Typed)]
+#[arendal(path = "geo", name = "Size")]
+struct Dimensions {
+    width: u32,
+    height: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Typed)]
+struct Origin;
+
+#[derive(Debug, Clone, PartialEq, Typed)]
+enum Shape {
+    Circle(Point, i64),
+    Rectangle { corner: Point, size: Dimensions },
+    Empty,
+}
+
+#[derive(Debug, Clone, PartialEq, Typed)]
+struct Inventory {
+    items: Vec<i64>,
+    prices: HashMap<String, i64>,
+    range: (i64, i64),
+}
+
+#[test]
+fn primitives() {
+    round_trip(());
+    round_trip(true);
+    round_trip(-42i64);
+    round_trip(7u8);
+    round_trip("hello".to_string());
+    mismatch::<i64>(true.into(), Type::type_integer());
+    mismatch::<bool>(1i64.into(), Type::type_boolean());
+    assert_eq!(
+        u8::try_from(Value::from(256i64)),
+        Err(Error::IntegerOverflow)
+    );
+    assert_eq!(
+        u32::try_from(Value::from(-1i64)),
+        Err(Error::IntegerOverflow)
+    );
+}
+
+#[test]
+fn wide_integers() {
+    round_trip(0u64);
+    round_trip(u64::MAX);
+    round_trip(1u64 << 63);
+    round_trip(usize::MAX);
+    round_trip(42usize);
+    assert_eq!(Value::from(u64::MAX).to_string(), "18446744073709551615");
+    let too_big = Value::from(Integer::from_u64(u64::MAX) + Integer::from(1));
+    assert_eq!(u64::try_from(too_big.clone()), Err(Error::IntegerOverflow));
+    assert_eq!(usize::try_from(too_big), Err(Error::IntegerOverflow));
+    assert_eq!(
+        u64::try_from(Value::from(-1i64)),
+        Err(Error::IntegerOverflow)
+    );
+    assert_eq!(
+        usize::try_from(Value::from(-1i64)),
+        Err(Error::IntegerOverflow)
+    );
+    mismatch::<u64>(true.into(), Type::type_integer());
+}
+
+#[test]
+fn collections() {
+    round_trip((1i64, "a".to_string()));
+    round_trip((true,));
+    round_trip(vec![1i64, 2, 3]);
+    round_trip(vec![(1i64, false)]);
+    round_trip(HashMap::from([
+        ("a".to_string(), 1i64),
+        ("b".to_string(), 2),
+    ]));
+    mismatch::<(i64, i64)>(
+        (1i64, 2i64, 3i64).into(),
+        Type::type_tuple(vec![Type::type_integer(), Type::type_integer()]),
+    );
+    mismatch::<Vec<i64>>(1i64.into(), Type::type_list(Type::type_integer()));
+    assert_eq!(
+        Vec::<i64>::try_from(Value::from(vec![true])),
+        Err(Error::type_mismatch(
+            Type::type_integer(),
+            Type::type_true()
+        ))
+    );
+}
+
+#[test]
+fn derived() {
+    round_trip(Point(1, 2));
+    round_trip(Dimensions {
+        width: 3,
+        height: 4,
+    });
+    round_trip(Origin);
+    round_trip(Shape::Circle(Point(0, 0), 5));
+    round_trip(Shape::Rectangle {
+        corner: Point(1, 1),
+        size: Dimensions {
+            width: 2,
+            height: 2,
+        },
+    });
+    round_trip(Shape::Empty);
+    round_trip(Inventory {
+        items: vec![1, 2],
+        prices: HashMap::from([("a".to_string(), 3)]),
+        range: (0, 9),
+    });
+    let point = local_type("", "Point");
+    assert_eq!(Point::tipo().fq(), Some(point.clone()));
+    assert_eq!(Dimensions::tipo().fq(), Some(local_type("geo", "Size")));
+    assert!(Origin::tipo().is_singleton());
+    let circle = variant_type(&local_type("", "Shape"), "Circle");
+    assert_eq!(
+        Value::from(Shape::Circle(Point(0, 0), 1)).get_type().fq(),
+        Some(circle)
+    );
+    mismatch::<Point>(Origin.into(), Point::tipo());
+    mismatch::<Shape>(Point(1, 2).into(), Shape::tipo());
+    mismatch::<Point>((1i64, 2i64).into(), Point::tipo());
+}
+
+// Values of the derived types can be passed to and returned by native functions, given that the
+// program defines matching types
+#[test]
+fn derived_natives() {
+    let mut env = Env::default();
+    env.add_native(&Pkg::Std.empty(), "area", Effects::NONE, |shape: Shape| {
+        Ok::<_, String>(match shape {
+            Shape::Circle(_, r) => 3 * r * r,
+            Shape::Rectangle { size, .. } => (size.width * size.height).into(),
+            Shape::Empty => 0,
+        })
+    })
+    .unwrap();
+    env.add_native(&Pkg::Std.empty(), "mirror", Effects::NONE, |p: Point| {
+        Ok::<_, String>(Point(p.1, p.0))
+    })
+    .unwrap();
+    let program = "type Point(Integer, Integer)\n\
+        type Shape = Circle(Point, Integer) | Empty\n\
+        area(Shape::Circle(Point(0, 0), 2)) + area(Shape::Empty) + mirror(Point(1, 2)).0";
+    assert_eq!(env.run(program).unwrap(), Value::from(14i64));
+}
+
+// Fields of collection and tuple types map to the same types declared by programs
+#[test]
+fn derived_collections() {
+    let mut env = Env::default();
+    env.add_native(&Pkg::Std.empty(), "total", Effects::NONE, |i: Inventory| {
+        Ok::<_, String>(i.items.iter().sum::<i64>() + i.prices.values().sum::<i64>() + i.range.1)
+    })
+    .unwrap();
+    env.add_native(
+        &Pkg::Std.empty(),
+        "restock",
+        Effects::NONE,
+        |mut i: Inventory| {
+            i.items.push(10);
+            i.prices.insert("b".to_string(), 20);
+            i.range = (i.range.1, i.range.0);
+            Ok::<_, String>(i)
+        },
+    )
+    .unwrap();
+    let program = "type Inventory(List[Integer], Map[String, Integer], (Integer, Integer))\n\
+        let i = Inventory([1, 2], {\"a\": 3}, (0, 9))\n\
+        let r = restock(i)\n\
+        let range = r.2\n\
+        total(i) + r.0[2] + r.1[\"b\"] + range.0";
+    assert_eq!(env.run(program).unwrap(), Value::from(54i64));
+}
@@ -0,0 +1,83 @@
+use std::fmt;
+
+use crate::symbol::TSymbol;
+
+// Side effects functions can perform when called
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Effect {
+    IO,
+}
+
+impl Effect {
+    const ALL: [Effect; 1] = [Effect::IO];
+
+    pub fn parse(symbol: &TSymbol) -> Option<Effect> {
+        let name = symbol.to_string();
+        Self::ALL.into_iter().find(|e| e.to_string() == name)
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+impl fmt::Display for Effect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IO => f.write_str("IO"),
+        }
+    }
+}
+
+// Set of effects, e.g., the ones a function performs or the ones allowed in an expression
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Effects(u8);
+
+impl Effects {
+    pub const NONE: Effects = Effects(0);
+
+    pub fn all() -> Effects {
+        Effect::ALL.into_iter().collect()
+    }
+
+    pub fn is_pure(self) -> bool {
+        self == Self::NONE
+    }
+
+    pub fn contains(self, effect: Effect) -> bool {
+        self.0 & effect.bit() != 0
+    }
+
+    // Returns whether every effect of the other set is in this one
+    pub fn includes(self, other: Effects) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = Effect> {
+        Effect::ALL.into_iter().filter(move |e| self.contains(*e))
+    }
+}
+
+impl From<Effect> for Effects {
+    fn from(effect: Effect) -> Self {
+        Effects(effect.bit())
+    }
+}
+
+impl FromIterator<Effect> for Effects {
+    fn from_iter<I: IntoIterator<Item = Effect>>(iter: I) -> Self {
+        Effects(iter.into_iter().fold(0, |bits, e| bits | e.bit()))
+    }
+}
+
+impl fmt::Display for Effects {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, effect) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            effect.fmt(f)?;
+        }
+        Ok(())
+    }
+}
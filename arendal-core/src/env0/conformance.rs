@@ -0,0 +1,545 @@
+// Programs are run with every backend, which must produce the same results
+
+use crate::context::Type;
+use crate::error::{Error, Loc, Result};
+
+use super::{Backend, Env, Limits, Value};
+
+// Every backend runs with the same limits
+const LIMITS: Limits = Limits {
+    fuel: Some(10_000_000),
+    max_depth: Some(Limits::DEFAULT_MAX_DEPTH),
+    max_memory: Some(1 << 30),
+};
+
+fn run(backend: Backend, input: &str) -> Result<Value> {
+    let mut env = Env::default();
+    env.set_backend(backend);
+    env.set_limits(LIMITS);
+    env.run(input)
+}
+
+// The interpreter is the reference the other backends are compared against
+const REFERENCE: Backend = Backend::Interpreter;
+
+fn eval_module(input: &str) -> Result<Value> {
+    let expected = run(REFERENCE, input);
+    for &backend in Backend::ALL.iter().filter(|b| **b != REFERENCE) {
+        match (&expected, run(backend, input)) {
+            (Ok(v1), Ok(v2)) => {
+                assert_eq!(*v1, v2, "{backend:?} evaluating {input}");
+                assert_eq!(
+                    v1.to_string(),
+                    v2.to_string(),
+                    "{backend:?} evaluating {input}"
+                );
+            }
+            (Err(e1), Err(e2)) => {
+                assert_eq!(
+                    format!("{e1:?}"),
+                    format!("{e2:?}"),
+                    "{backend:?} evaluating {input}"
+                )
+            }
+            (r1, r2) => panic!("{backend:?} evaluating {input}: {r2:?} instead of {r1:?}"),
+        }
+    }
+    expected
+}
+
+fn eval_ok(input: &str, result: Value) {
+    match eval_module(input) {
+        Ok(value) => assert_eq!(value, result),
+        Err(e) => panic!("Error evaluating expression {:?}", e),
+    }
+}
+
+fn eval_err(input: &str, expected: Error) {
+    match eval_module(input) {
+        Ok(value) => panic!("Expected error evaluating {input} but got {value:?}"),
+        Err(e) => assert!(e.contains(&expected), "{e:?}"),
+    }
+}
+
+fn v_i64(value: i64) -> Value {
+    Value::v_integer(&Loc::None, Type::type_integer(), value.into()).unwrap()
+}
+
+fn eval_i64(input: &str, result: i64) {
+    eval_ok(input, v_i64(result))
+}
+
+#[test]
+fn empty1() {
+    eval_ok("", Value::v_none());
+}
+
+#[test]
+fn empty2() {
+    eval_ok(" \t\n", Value::v_none());
+}
+
+#[test]
+fn integer() {
+    eval_i64("1234", 1234);
+}
+
+#[test]
+fn add1() {
+    eval_i64("1+2", 3);
+}
+
+#[test]
+fn add2() {
+    eval_i64("1 + 2 + 3", 6);
+}
+
+#[test]
+fn sub1() {
+    eval_i64("1 - 2", -1);
+}
+
+#[test]
+fn mul1() {
+    eval_i64("1 + 2 * 2", 5);
+}
+
+#[test]
+fn parens1() {
+    eval_i64("(1 + 2) * 2", 6);
+}
+
+#[test]
+fn parens2() {
+    eval_i64("(((1 + 2) * 2) + 1) * 2", 14);
+}
+
+#[test]
+fn logical() {
+    eval_ok("(True || False) && True", Value::v_true());
+}
+
+#[test]
+fn conditional1() {
+    eval_i64("if True then 1 else 2", 1);
+}
+
+#[test]
+fn seq() {
+    eval_i64("1 then 2", 2);
+    eval_i64("1 then 2 then 3", 3);
+}
+
+#[test]
+fn assignments() {
+    eval_i64("let a = b + c\nlet b = c\nlet c = 3\na - 1", 5);
+}
+
+#[test]
+fn block() {
+    eval_i64("let x = 1\n{ let y = x + 1\n y + 2\n}", 4);
+}
+
+#[test]
+fn decimal() {
+    let value = "0.3".parse().unwrap();
+    eval_ok(
+        "0.1 + 0.2",
+        Value::v_decimal(&Loc::None, Type::type_decimal(), value).unwrap(),
+    );
+}
+
+fn v_dec(value: &str) -> Value {
+    Value::v_decimal(&Loc::None, Type::type_decimal(), value.parse().unwrap()).unwrap()
+}
+
+// Decimals are divided with an explicit scale and rounding mode
+#[test]
+fn decimal_division() {
+    eval_ok("div(1.0, 3.0, 4, Rounding::HalfUp)", v_dec("0.3333"));
+    eval_ok("div(2.0, 3.0, 2, Rounding::Down)", v_dec("0.66"));
+    eval_ok("div(-2.5, 1.0, 0, Rounding::HalfEven)", v_dec("-2"));
+    eval_ok(
+        "\"{div(10.0, 4.0, 3, Rounding::Floor)}\"",
+        v_string("2.500"),
+    );
+    eval_ok(
+        "fn third(d: Decimal, r: Rounding): Decimal = div(d, 3.0, 0, r)\nthird(1.0, Rounding::Up) + third(1.0, Rounding::Down)",
+        v_dec("1"),
+    );
+    eval_err("1.0 / 2.0", Error::ImplicitDecimalDivision);
+    eval_err("div(1.0, 0.0, 2, Rounding::Up)", Error::DivisionByZero);
+    eval_err(
+        "div(1.0, 3.0, -1, Rounding::Up)",
+        Error::InvalidScale((-1).into()),
+    );
+    eval_err(
+        "div(1.0, 3.0, 100000, Rounding::Up)",
+        Error::DecimalOverflow,
+    );
+    eval_err(
+        "div(1.0, 3.0, 10000000000000000000000, Rounding::Up)",
+        Error::DecimalOverflow,
+    );
+}
+
+// Like integers, decimals are bounded
+#[test]
+fn decimal_overflow() {
+    let square =
+        "fn square(d: Decimal, n: Integer): Decimal = if n > 0 then square(d * d, n - 1) else d\n";
+    eval_ok(&format!("{square}square(1.5, 2)"), v_dec("5.0625"));
+    eval_err(&format!("{square}square(0.1, 17)"), Error::DecimalOverflow);
+    eval_err(
+        &format!("{square}square(0.1, 16) * 10.0"),
+        Error::DecimalOverflow,
+    );
+}
+
+#[test]
+fn deep_recursion() {
+    let sum = "fn sum(n: Integer): Integer = if n > 0 then n + sum(n - 1) else 0\n";
+    eval_i64(&format!("{sum}sum(9000)"), 40504500);
+    eval_err(
+        &format!("{sum}sum(20000)"),
+        Error::MaxDepthExceeded(Limits::DEFAULT_MAX_DEPTH),
+    );
+    let even = "fn even(n: Integer): Boolean = if n == 0 then True else odd(n - 1)
+fn odd(n: Integer): Boolean = if n == 0 then False else even(n - 1)
+";
+    eval_ok(&format!("{even}even(9999)"), Value::v_false());
+}
+
+#[test]
+fn rem_and_pow() {
+    eval_i64("7 % 3", 1);
+    eval_i64("(0 - 7) % 3", -1);
+    eval_i64("2 ** 10 - 1", 1023);
+    eval_i64("2 ** 3 ** 2", 512);
+}
+
+#[test]
+fn unary() {
+    eval_i64("-7 % 3", -1);
+    eval_i64("--2", 2);
+    eval_i64("-2 ** 2", -4);
+    eval_i64("let x = 3\n1 - -x", 4);
+    eval_ok(
+        "-1.5",
+        Value::v_decimal(&Loc::None, Type::type_decimal(), "-1.5".parse().unwrap()).unwrap(),
+    );
+    eval_ok("!True", Value::v_false());
+    eval_ok("!(True && False)", Value::v_true());
+    eval_err(
+        "-True",
+        Error::type_mismatch(Type::type_integer(), Type::type_true()),
+    );
+    eval_err(
+        "!1",
+        Error::type_mismatch(Type::type_boolean(), Type::type_integer()),
+    );
+}
+
+#[test]
+fn comparisons() {
+    eval_ok("1 < 2", Value::v_true());
+    eval_ok("2 <= 1", Value::v_false());
+    eval_ok("2 > 1 && 2 >= 2", Value::v_true());
+    eval_ok("1 + 1 == 2", Value::v_true());
+    eval_ok("1 != 1", Value::v_false());
+    eval_ok("1.5 == 1.50", Value::v_true());
+    eval_ok("(1 < 2) == True", Value::v_true());
+    eval_ok("True != False", Value::v_true());
+    eval_ok("let x = 3\nif x > 2 then x else 0", v_i64(3));
+    eval_err(
+        "1 == True",
+        Error::type_mismatch(Type::type_integer(), Type::type_true()),
+    );
+}
+
+#[test]
+fn functions() {
+    eval_i64("fn inc(x: Integer): Integer = x + 1\ninc(41)", 42);
+    eval_i64(
+        "let y = twice(inc, 1)\nfn twice(f: (Integer) -> Integer, x: Integer): Integer = f(f(x))\nfn inc(x: Integer): Integer = x + 1\ny",
+        3,
+    );
+    eval_i64("(fn(x: Integer, y: Integer) = x * y)(6, 7)", 42);
+    eval_i64(
+        "{ let n = 10\nlet add = fn(x: Integer) = x + n\nadd(5) }",
+        15,
+    );
+    eval_i64(
+        "fn adder(n: Integer): (Integer) -> Integer = fn(x: Integer) = x + n\nadder(2)(3)",
+        5,
+    );
+    eval_i64("{ fn sq(x: Integer): Integer = x * x\nsq(sq(2)) }", 16);
+    eval_err("1(2)", Error::FunctionExpected(Type::type_integer()));
+    eval_err(
+        "fn inc(x: Integer): Integer = x + 1\ninc(1, 2)",
+        Error::ArgumentCountMismatch(1, 2),
+    );
+}
+
+#[test]
+fn tuples() {
+    eval_i64("(1, 2).0 + (1, 2).1", 3);
+    eval_i64("{ let t = (1, (2, 3))\nt.1.1 }", 3);
+    eval_i64(
+        "type Point(Integer, Integer)\nlet p = Point(4, 2)\np.0 * p.1",
+        8,
+    );
+    eval_i64(
+        "type Point(Integer, Integer)\nfn swap(p: Point): Point = Point(p.1, p.0)\nswap(Point(1, 2)).0",
+        2,
+    );
+    let display = |input: &str| eval_module(input).unwrap().to_string();
+    assert_eq!(display("(1, True)"), "(1, std::True)");
+    assert_eq!(display("(1,)"), "(1,)");
+    assert_eq!(
+        display("type Point(Integer, Integer)\nPoint(1, 2)"),
+        "pkg::Point(1, 2)"
+    );
+    eval_ok("(1, 2) == (1, 2)", Value::v_true());
+    eval_ok("(1, 2) == (2, 1)", Value::v_false());
+}
+
+#[test]
+fn records() {
+    let person = "type Person { age: Integer, adult: Boolean }\n";
+    let eval = |input: &str| eval_module(&format!("{person}{input}")).unwrap();
+    assert_eq!(eval("Person { adult: True, age: 20 }.age"), v_i64(20));
+    assert_eq!(
+        eval("let p = Person { age: 1, adult: False }\np with { age: 2 }").to_string(),
+        "pkg::Person { age: 2, adult: std::False }"
+    );
+    assert_eq!(
+        eval("let p = Person { age: 1, adult: False }\n(p with { age: 2 }).age + p.age"),
+        v_i64(3)
+    );
+    assert_eq!(
+        eval("Person { age: 1, adult: False } == Person { adult: False, age: 1 }"),
+        Value::v_true()
+    );
+    assert_eq!(
+        eval(
+            "Person { age: 1, adult: False } with { adult: True } != Person { age: 1, adult: False }"
+        ),
+        Value::v_true()
+    );
+}
+
+#[test]
+fn unions() {
+    let shape = "type Shape = Circle(Integer) | Square(Integer) | Empty\n";
+    let eval = |input: &str| eval_module(&format!("{shape}{input}")).unwrap();
+    assert_eq!(
+        eval("Shape::Circle(1)").to_string(),
+        "pkg::Shape::Circle(1)"
+    );
+    assert_eq!(
+        eval("if False then Shape::Circle(1) else Shape::Empty").to_string(),
+        "pkg::Shape::Empty"
+    );
+    assert_eq!(eval("Shape::Square(2).0 + 1"), v_i64(3));
+    assert_eq!(
+        eval("Shape::Circle(1) == Shape::Circle(1)"),
+        Value::v_true()
+    );
+    assert_eq!(
+        eval("Shape::Circle(1) == Shape::Square(1)"),
+        Value::v_false()
+    );
+    assert_eq!(eval("Shape::Empty != Shape::Circle(1)"), Value::v_true());
+}
+
+#[test]
+fn matches() {
+    let shape = "type Shape = Circle(Integer) | Rect { w: Integer, h: Integer } | Empty\n";
+    let area = "fn area(s: Shape): Integer = match s {\n  Shape::Circle(r) -> 3 * r * r,\n  Shape::Rect { w: w, h: h } -> w * h,\n  Shape::Empty -> 0,\n}\n";
+    let eval = |input: &str| eval_module(&format!("{shape}{area}{input}")).unwrap();
+    assert_eq!(eval("area(Shape::Circle(2))"), v_i64(12));
+    assert_eq!(eval("area(Shape::Rect { w: 2, h: 5 })"), v_i64(10));
+    assert_eq!(eval("area(Shape::Empty)"), v_i64(0));
+    eval_i64(
+        "fn sign(n: Integer): Integer = match n { 0 -> 0, -1 -> -1, n -> if n > 0 then 1 else -1 }\nsign(-1) + sign(0) * 10 + sign(42) * 100",
+        99,
+    );
+    eval_i64(
+        "fn f(t: (Integer, Boolean)): Integer = match t { (0, _) -> 0, (n, True) -> n, (n, False) -> -n }\nf((3, True)) + f((5, False)) + f((0, True))",
+        -2,
+    );
+    eval_i64("let n = 1\nlet m = match 2 { n -> n }\nm + n", 3);
+    eval_i64("match True { True -> 1 }", 1);
+    assert_eq!(eval("match Shape::Empty { Shape::Empty -> 1 }"), v_i64(1));
+    assert_eq!(
+        eval("match (Shape::Rect { w: 2, h: 3 }) { Shape::Rect { w: w, h: _ } -> w }"),
+        v_i64(2)
+    );
+}
+
+#[test]
+fn generics() {
+    let types =
+        "type Pair[A, B](A, B)\ntype Option[T] = Some(T) | None\ntype Box[T] { value: T }\n";
+    let eval = |input: &str| eval_module(&format!("{types}{input}")).unwrap();
+    assert_eq!(eval("Pair(1, \"a\")").to_string(), r#"pkg::Pair(1, "a")"#);
+    assert_eq!(
+        eval("fn first[A, B](p: Pair[A, B]): A = p.0\nfirst(Pair(3, True)) + 1"),
+        v_i64(4)
+    );
+    assert_eq!(eval("fn id[T](x: T): T = x\nid(2) * id(21)"), v_i64(42));
+    assert_eq!(eval("(fn[T](x: T) = [x, x])(True)[1]"), Value::v_true());
+    assert_eq!(eval("Box { value: [1, 2] }.value.length"), v_i64(2));
+    let get =
+        "fn get[T](o: Option[T], d: T): T = match o { Option::Some(x) -> x, Option::None -> d }\n";
+    assert_eq!(
+        eval(&format!(
+            "{get}get(Option::Some(5), 0) + get(Option::None, 10)"
+        )),
+        v_i64(15)
+    );
+    assert_eq!(
+        eval("fn some(n: Integer): Option[Integer] = Option::Some(n)\nsome(1) == Option::Some(1)"),
+        Value::v_true()
+    );
+}
+
+#[test]
+fn recursive_generics() {
+    eval_i64(
+        "type Stack[T] = Empty | Push(T, Stack[T])\nfn size[T](s: Stack[T]): Integer = match s { Stack::Empty -> 0, Stack::Push(_, rest) -> 1 + size(rest) }\nsize(Stack::Push(1, Stack::Push(2, Stack::Empty)))",
+        2,
+    );
+}
+
+#[test]
+fn recursion() {
+    eval_i64(
+        "fn fact(n: Integer): Integer = if n > 1 then n * fact(n - 1) else 1\nfact(10)",
+        3628800,
+    );
+    eval_ok(
+        "let x = odd(7)\nfn even(n: Integer): Boolean = if n == 0 then True else odd(n - 1)\nfn odd(n: Integer): Boolean = if n == 0 then False else even(n - 1)\nx",
+        Value::v_true(),
+    );
+}
+
+#[test]
+fn arithmetic_errors() {
+    eval_err("1 / 0", Error::DivisionByZero);
+    eval_err("1 % 0", Error::DivisionByZero);
+    eval_err("2 ** (0 - 1)", Error::NegativeExponent);
+    eval_err("10 ** 1000000000", Error::IntegerOverflow);
+}
+
+fn v_string(value: &str) -> Value {
+    Value::v_string(&Loc::None, Type::type_string(), value.into()).unwrap()
+}
+
+#[test]
+fn strings() {
+    eval_ok(r#""a\tb\\""#, v_string("a\tb\\"));
+    eval_ok(r#""ab" + "" + "c""#, v_string("abc"));
+    eval_i64(r#""añb".length"#, 3);
+    eval_ok(r#""abc" < "abd""#, Value::v_true());
+    eval_ok(r#""b" <= "abc""#, Value::v_false());
+    eval_ok(r#""a" + "b" == "ab""#, Value::v_true());
+    eval_ok(
+        "let x = 2\nlet s = \"s\"\n\"x = {x}, {x * 2 + 1} {0.5}, {s}, {(s, True)}, \\{x}\"",
+        v_string(r#"x = 2, 5 0.5, s, ("s", std::True), {x}"#),
+    );
+    eval_ok(r#""{"{1}" + "2"}3""#, v_string("123"));
+}
+
+#[test]
+fn collections() {
+    eval_i64("let l = [1, 2, 3]\nl[0] + l[2] * l.length", 10);
+    eval_i64(
+        "let m = {\"a\": 1, \"b\": 2, \"a\": 3}\nm[\"a\"] * 10 + m.length",
+        32,
+    );
+    eval_i64("[[1], [2, 3]][1][0]", 2);
+    eval_ok("[1, 2] == [1, 2]", Value::v_true());
+    eval_ok("[1, 2] != [2, 1]", Value::v_true());
+    eval_ok(
+        "{1: True, 2: False} == {2: False, 1: True}",
+        Value::v_true(),
+    );
+    eval_ok(
+        "let m = {\"b\": [1], \"a\": [2, 3]}\n\"{m}\"",
+        v_string(r#"{ "a": [2, 3], "b": [1] }"#),
+    );
+    eval_err("[1, 2][2]", Error::IndexOutOfBounds(2.into()));
+    eval_err("[1, 2][0 - 1]", Error::IndexOutOfBounds((-1).into()));
+    eval_err(r#"{"a": 1}["b"]"#, Error::KeyNotFound(v_string("b")));
+}
+
+#[test]
+fn empty_collections() {
+    eval_i64(
+        "let l: List[Integer] = []\nlet m: Map[String, Integer] = {:}\nl.length + m.length",
+        0,
+    );
+    eval_ok(
+        "([] : List[Integer]) == ([] : List[Integer])",
+        Value::v_true(),
+    );
+    eval_ok(
+        "let m: Map[Integer, Integer] = {:}\n\"{m}\"",
+        v_string("{:}"),
+    );
+    eval_ok("let l: List[Integer] = []\n\"{l}\"", v_string("[]"));
+    eval_err(
+        "let l: List[Integer] = []\nl[0]",
+        Error::IndexOutOfBounds(0.into()),
+    );
+}
+
+#[test]
+fn collections_in_types() {
+    eval_i64(
+        "type Bag { items: List[Integer], counts: Map[String, Integer] }\nlet b = Bag { items: [1, 2], counts: {\"a\": 3} }\nb.items[1] + b.counts[\"a\"]",
+        5,
+    );
+    eval_i64(
+        "type Tree = Leaf(Integer) | Node(List[Tree])
+fn sum(t: Tree): Integer = match t {
+  Tree::Leaf(n) -> n,
+  Tree::Node(l) -> if l.length == 1 then sum(l[0]) else sum(l[0]) + sum(l[1]),
+}
+sum(Tree::Node([Tree::Leaf(1), Tree::Node([Tree::Leaf(2), Tree::Leaf(3)])]))",
+        6,
+    );
+    eval_i64(
+        "type Op((Integer, Integer), (Integer, Integer) -> Integer)\nlet o = Op((3, 4), fn(a: Integer, b: Integer) = a * b)\no.1(o.0.0, o.0.1)",
+        12,
+    );
+}
+
+fn eval_output(input: &str, output: &str) {
+    for backend in Backend::ALL {
+        let mut env = Env::default();
+        env.set_backend(backend);
+        env.capture_output();
+        if let Err(e) = env.run(input) {
+            panic!("Error evaluating expression with {backend:?}: {e:?}")
+        }
+        assert_eq!(env.take_output(), output, "{backend:?}");
+    }
+}
+
+#[test]
+fn effects() {
+    eval_output("print(\"a\") then print(\"b\")", "a\nb\n");
+    eval_output(
+        "fn greet(name: String): None with IO = print(\"Hello, {name}!\")\ngreet(\"world\")",
+        "Hello, world!\n",
+    );
+    eval_output(
+        "fn twice(f: () -> None with IO): None with IO = { f() then f() }\ntwice(fn() with IO = print(\"x\"))",
+        "x\nx\n",
+    );
+    // The first expression is evaluated even if its value is discarded
+    eval_err("1 / 0 then 2", Error::DivisionByZero);
+}
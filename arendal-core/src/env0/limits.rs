@@ -0,0 +1,120 @@
+use std::cell::Cell;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::error::{Error, Loc, Result};
+
+// Bounds on the evaluation of a run, unbounded if None. The depth is bounded by default, so
+// that deep recursion is reported as an error instead of overflowing the stack of the host
+// when interpreted. The default is the same for every backend, so that they produce the same
+// results, and runs have a stack large enough for it.
+//
+// Only the calls are bounded: expressions nested many thousands of levels deep in the source,
+// such as a long chain of additions, can still overflow the stack while being checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    // Number of expressions evaluated, which is the same for every backend
+    pub fuel: Option<u64>,
+    // Number of nested function calls
+    pub max_depth: Option<usize>,
+    // Approximate number of bytes allocated by the values built, including discarded ones
+    pub max_memory: Option<usize>,
+}
+
+impl Limits {
+    pub const DEFAULT_MAX_DEPTH: usize = 10_000;
+
+    pub const UNBOUNDED: Limits = Limits {
+        fuel: None,
+        max_depth: None,
+        max_memory: None,
+    };
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_depth: Some(Self::DEFAULT_MAX_DEPTH),
+            ..Self::UNBOUNDED
+        }
+    }
+}
+
+// Cancels the run in progress of an environment, and of its clones, from any thread. Each run
+// clears the request when it starts, so cancelling with no run in progress has no effect.
+#[derive(Debug, Clone, Default)]
+pub struct CancelHandle(Arc<AtomicBool>);
+
+impl CancelHandle {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub(super) fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+// Resources consumed by a run, checked against the limits
+#[derive(Debug)]
+pub(super) struct Budget {
+    limits: Limits,
+    cancel: CancelHandle,
+    steps: Cell<u64>,
+    depth: Cell<usize>,
+    memory: Cell<usize>,
+}
+
+impl Budget {
+    pub(super) fn new(limits: Limits, cancel: CancelHandle) -> Self {
+        Budget {
+            limits,
+            cancel,
+            steps: Cell::new(0),
+            depth: Cell::new(0),
+            memory: Cell::new(0),
+        }
+    }
+
+    // Called before evaluating the provided number of expressions
+    pub(super) fn step(&self, loc: &Loc, expressions: u64) -> Result<()> {
+        if self.cancel.is_cancelled() {
+            return loc.err(Error::Cancelled);
+        }
+        let steps = self.steps.get() + expressions;
+        self.steps.set(steps);
+        match self.limits.fuel {
+            Some(fuel) if steps > fuel => loc.err(Error::FuelExhausted(fuel)),
+            _ => Ok(()),
+        }
+    }
+
+    // Called before evaluating the body of a function, which must be followed by a call to exit
+    pub(super) fn enter(&self, loc: &Loc) -> Result<()> {
+        let depth = self.depth.get() + 1;
+        match self.limits.max_depth {
+            Some(max) if depth > max => loc.err(Error::MaxDepthExceeded(max)),
+            _ => {
+                self.depth.set(depth);
+                Ok(())
+            }
+        }
+    }
+
+    pub(super) fn exit(&self) {
+        self.depth.set(self.depth.get() - 1);
+    }
+
+    pub(super) fn allocate(&self, loc: &Loc, bytes: usize) -> Result<()> {
+        let memory = self.memory.get().saturating_add(bytes);
+        self.memory.set(memory);
+        match self.limits.max_memory {
+            Some(max) if memory > max => loc.err(Error::MemoryLimitExceeded(max)),
+            _ => Ok(()),
+        }
+    }
+}
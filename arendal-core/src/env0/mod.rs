@@ -1,29 +1,41 @@
+mod limits;
+mod native;
 mod twi;
+mod vm;
+
+use std::sync::{Arc, Mutex};
 
 use im::HashMap;
 
 use crate::{
-    context::{Context, Type, Value},
-    error::{Error, Loc, Result},
-    symbol::FQSym,
+    ArcStr,
+    context::{Builtin, Context, Type, TypeDfn, TypeDfnMap, TypeDfnRef, Value},
+    effect::{Effect, Effects},
+    error::{Error, L, Loc, Result},
+    id::{Id, Ids, PrefixError},
+    store::{self, Name, SymbolEntry, TypeEntry, TypeRef},
+    symbol::{FQ, FQPath, FQSym, FQType, Path, Pkg, Symbol, TSymbol},
     tst,
     visibility::{V, Visibility},
 };
 
+pub use limits::{CancelHandle, Limits};
+pub use native::NativeFunction;
+
 #[derive(Debug, Default, Clone)]
 pub(crate) struct Symbols {
     values: HashMap<FQSym, V<Type>>,
 }
 
 impl Symbols {
-    pub(crate) fn contains(&self, symbol: &FQSym) -> bool {
-        self.values.contains_key(symbol)
-    }
-
     pub(crate) fn get(&self, symbol: &FQSym) -> Option<V<Type>> {
         self.values.get(symbol).cloned()
     }
 
+    fn iter(&self) -> impl Iterator<Item = (&FQSym, &V<Type>)> {
+        self.values.iter()
+    }
+
     pub(crate) fn set(
         &mut self,
         loc: &Loc,
@@ -46,10 +58,6 @@ pub(crate) struct Values {
 }
 
 impl Values {
-    pub(crate) fn contains(&self, symbol: &FQSym) -> bool {
-        self.values.contains_key(symbol)
-    }
-
     pub(crate) fn get(&self, symbol: &FQSym) -> Option<Value> {
         self.values.get(symbol).cloned()
     }
@@ -64,16 +72,474 @@ impl Values {
     }
 }
 
+// Destination of the text printed by programs. Captured text is shared by the clones of the
+// environment.
 #[derive(Debug, Clone, Default)]
+enum Output {
+    #[default]
+    Stdout,
+    Captured(Arc<Mutex<String>>),
+}
+
+impl Output {
+    fn print(&self, text: &str) {
+        match self {
+            Output::Stdout => println!("{text}"),
+            Output::Captured(buffer) => {
+                let mut buffer = buffer.lock().unwrap();
+                buffer.push_str(text);
+                buffer.push('\n');
+            }
+        }
+    }
+}
+
+// Evaluators of the typechecked programs, which produce the same results
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    #[default]
+    Interpreter, // Walks the typed syntax tree
+    VM, // Compiles the tree to bytecode for a stack machine
+}
+
+impl Backend {
+    pub const ALL: [Backend; 2] = [Backend::Interpreter, Backend::VM];
+}
+
+#[derive(Debug, Clone)]
 pub struct Env {
     pub(crate) types: Context,
+    type_dfns: TypeDfnMap, // Definitions of the local types, kept to export them
     pub(crate) symbols: Symbols,
     values: Values,
+    ids: Ids,
+    pub(crate) dependencies: HashMap<Symbol, Id>,
+    output: Output,
+    limits: Limits,
+    cancel: CancelHandle,
+    backend: Backend,
+}
+
+// The builtin functions are defined in the std package
+impl Default for Env {
+    fn default() -> Self {
+        let mut symbols = Symbols::default();
+        let mut values = Values::default();
+        for builtin in Builtin::ALL {
+            let fq = Pkg::Std.empty().fq_sym(builtin.symbol());
+            symbols
+                .values
+                .insert(fq.clone(), Visibility::Exported.wrap(builtin.tipo()));
+            values.values.insert(fq, Value::v_builtin(builtin));
+        }
+        Env {
+            types: Context::default(),
+            type_dfns: TypeDfnMap::default(),
+            symbols,
+            values,
+            ids: Ids::default(),
+            dependencies: HashMap::default(),
+            output: Output::default(),
+            limits: Limits::default(),
+            cancel: CancelHandle::default(),
+            backend: Backend::default(),
+        }
+    }
 }
 
 impl Env {
+    // Keeps the text printed by programs instead of writing it to the standard output
+    pub fn capture_output(&mut self) {
+        self.output = Output::Captured(Arc::default());
+    }
+
+    // Returns the text captured since the last call, empty if the output is not captured
+    pub fn take_output(&mut self) -> String {
+        match &self.output {
+            Output::Stdout => String::default(),
+            Output::Captured(buffer) => std::mem::take(&mut *buffer.lock().unwrap()),
+        }
+    }
+
+    // Bounds the evaluation of the following runs
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    // Returns the limits of the following runs
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.cancel.clone()
+    }
+
+    // Evaluates the following runs with the provided backend
+    pub fn set_backend(&mut self, backend: Backend) {
+        self.backend = backend;
+    }
+
     pub fn run(&mut self, input: &str) -> Result<Value> {
-        let package = tst::check(self, input)?;
-        twi::run(self, &package)
+        self.cancel.reset();
+        on_large_stack(|| {
+            let package = tst::check(self, input)?;
+            self.run_package(package)
+        })
+    }
+
+    // Runs a package made of several modules, each one given with its path
+    pub fn run_modules(&mut self, inputs: &[(Path, &str)]) -> Result<Value> {
+        self.cancel.reset();
+        on_large_stack(|| {
+            let package = tst::check_modules(self, inputs)?;
+            self.run_package(package)
+        })
+    }
+
+    fn run_package(&mut self, package: tst::Package) -> Result<Value> {
+        self.types = package.types.clone();
+        self.type_dfns.extend(package.new_types.clone());
+        self.symbols = package.symbols.clone();
+        let value = match self.backend {
+            Backend::Interpreter => twi::run(self, &package)?,
+            Backend::VM => vm::run(self, &package)?,
+        };
+        self.ids.extend(package.ids());
+        Ok(value)
+    }
+
+    // Makes a Rust function available to programs as path::name. The function can perform the
+    // provided effects, which are part of its type.
+    pub fn add_native<A, F: NativeFunction<A>>(
+        &mut self,
+        path: &FQPath,
+        name: &str,
+        effects: Effects,
+        function: F,
+    ) -> Result<()> {
+        let loc = Loc::None;
+        let symbol = path.fq_sym(Symbol::new(&loc, ArcStr::from(name))?);
+        let tipo = Type::type_function_with_effects(F::params(), F::result(), effects);
+        self.symbols
+            .set(&loc, symbol.clone(), Visibility::Exported, tipo.clone())?;
+        let value = Value::v_native(tipo, symbol.clone(), function.boxed());
+        self.values.set(&loc, symbol, value)
+    }
+
+    // The arguments are already checked against the builtin type by the typechecker
+    fn builtin(&self, loc: &Loc, builtin: Builtin, args: Vec<Value>) -> Result<Value> {
+        match builtin {
+            Builtin::Print => {
+                let text = args[0].clone().as_string(loc)?;
+                self.output.print(&text);
+                Ok(Value::v_none())
+            }
+            Builtin::Div => {
+                let mut args = args.into_iter();
+                let mut next = || args.next().unwrap();
+                let v1 = next().as_decimal(loc)?;
+                let v2 = next().as_decimal(loc)?;
+                let scale = next().as_integer(loc)?;
+                let rounding = next().as_rounding(loc)?;
+                if v2.is_zero() {
+                    return loc.err(Error::DivisionByZero);
+                }
+                if scale.is_negative() {
+                    return loc.err(Error::InvalidScale(scale));
+                }
+                // Scales that don't fit are too large for any result
+                let value = scale
+                    .to_usize()
+                    .and_then(|s| u32::try_from(s).ok())
+                    .and_then(|s| v1.div(&v2, s, rounding));
+                match value {
+                    Some(value) => Value::v_decimal(loc, Type::type_decimal(), value),
+                    None => loc.err(Error::DecimalOverflow),
+                }
+            }
+        }
+    }
+
+    // Looks up the id of a definition by an unambiguous prefix
+    pub fn find_id(&self, prefix: &str) -> std::result::Result<&Id, PrefixError> {
+        self.ids.find(prefix)
+    }
+
+    // Returns the types and symbols defined by the local package. The store encodes every type
+    // defined in terms of std and local types, but only symbols whose values are literals or
+    // singletons, so any other symbol, such as a function or a tuple, is NotExportable. Types
+    // using types of dependencies are NotExportable too.
+    pub fn export(&self) -> Result<store::Package> {
+        let mut package = store::Package::default();
+        for (fq, dfn) in self.type_dfns.iter() {
+            package.types.push(TypeEntry {
+                visibility: dfn.it.visibility,
+                name: name(fq),
+                dfn: export_dfn(fq, &dfn.it.it)?,
+            });
+        }
+        for (fq, tipo) in self.symbols.iter() {
+            // Native functions are provided by the host, not by the package
+            let native = self.values.get(fq).is_some_and(|v| v.as_native().is_some());
+            if fq.path().pkg != Pkg::Local || native {
+                continue;
+            }
+            let Some(type_ref) = tipo.it.fq().as_ref().and_then(type_ref) else {
+                return Loc::None.err(Error::NotExportable(fq.clone()));
+            };
+            let value = match self.values.get(fq).and_then(|v| v.to_literal()) {
+                Some(value) => value,
+                None => return Loc::None.err(Error::NotExportable(fq.clone())),
+            };
+            package.symbols.push(SymbolEntry {
+                visibility: tipo.visibility,
+                name: name(fq),
+                tipo: type_ref,
+                value,
+            });
+        }
+        Ok(package)
+    }
+
+    // Makes a package available as an external dependency, qualifying its members with the alias
+    pub fn add_dependency(&mut self, alias: &str, id: Id, package: &store::Package) -> Result<()> {
+        let loc = Loc::None;
+        let alias = Symbol::new(&loc, ArcStr::from(alias))?;
+        if self.dependencies.contains_key(&alias) {
+            return loc.err(Error::DuplicateDependency(alias));
+        }
+        let pkg = Pkg::External(id.clone());
+        let mut env = self.clone();
+        let mut types = TypeDfnMap::default();
+        for t in &package.types {
+            let fq = fq_type(&pkg, &t.name)?;
+            let dfn = import_dfn(&pkg, &t.dfn)?;
+            types.insert(fq, loc.wrap(t.visibility.wrap(dfn)));
+        }
+        env.types = env.types.add_types(&types)?;
+        for s in &package.symbols {
+            let fq = fq_path(&pkg, &s.name.path)?
+                .fq_sym(Symbol::new(&loc, s.name.name.as_str().into())?);
+            let type_fq = import_type(&pkg, &s.tipo)?;
+            let tipo = match env.types.get(&type_fq) {
+                Some(t) => t.cloned(),
+                None => return loc.err(Error::UnknownType(type_fq)),
+            };
+            let value = Value::from_literal(&loc, tipo.clone(), &s.value)?;
+            env.symbols.set(&loc, fq.clone(), s.visibility, tipo)?;
+            env.values.set(&loc, fq, value)?;
+        }
+        env.dependencies.insert(alias, id);
+        *self = env;
+        Ok(())
     }
 }
+
+// Programs are checked and run in a thread with a large stack, as the typechecker and the
+// interpreter recurse on it. The memory is only used as the stack grows.
+const STACK_SIZE: usize = 1 << 30;
+
+fn on_large_stack<T: Send, F: FnOnce() -> T + Send>(f: F) -> T {
+    std::thread::scope(|scope| {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn_scoped(scope, f)
+            .expect("The thread is spawned")
+            .join()
+            .unwrap_or_else(|e| std::panic::resume_unwind(e))
+    })
+}
+
+// Returns whether the value matches the pattern, binding the values of its symbols in the order
+// they appear in the pattern
+fn matches<F>(pattern: &tst::Pattern, value: &Value, bind: &mut F) -> bool
+where
+    F: FnMut(&Symbol, &Value),
+{
+    let elements = |patterns: &[tst::Pattern], bind: &mut F| {
+        value.fields().is_some_and(|fields| {
+            patterns
+                .iter()
+                .zip(fields)
+                .all(|(p, v)| matches(p, v, bind))
+        })
+    };
+    match pattern {
+        tst::Pattern::Any => true,
+        tst::Pattern::Binding(symbol) => {
+            bind(symbol, value);
+            true
+        }
+        tst::Pattern::Value(v) => v == value,
+        tst::Pattern::Tuple(patterns) => elements(patterns, bind),
+        // Values of generic types may have been built with other type arguments
+        tst::Pattern::Constructor(tipo, patterns) => {
+            value.get_type().fq() == tipo.fq() && elements(patterns, bind)
+        }
+    }
+}
+
+fn name<T: ToString>(fq: &FQ<T>) -> Name {
+    let path = fq
+        .path()
+        .path
+        .segments()
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    Name::new(path, fq.symbol().to_string())
+}
+
+// Stored types can only refer to std and local types
+fn type_ref(fq: &FQType) -> Option<TypeRef> {
+    match &fq.path().pkg {
+        Pkg::Std if fq.is_top_level() => Some(TypeRef::Std(fq.symbol().to_string())),
+        Pkg::Local if fq.is_top_level() => Some(TypeRef::Local(name(fq))),
+        _ => None,
+    }
+}
+
+fn export_dfn(symbol: &FQType, dfn: &TypeDfn) -> Result<store::TypeDfn> {
+    let expr = |dfnref: &L<TypeDfnRef>| export_ref(symbol, &dfnref.it);
+    Ok(match dfn {
+        TypeDfn::Singleton => store::TypeDfn::Singleton,
+        TypeDfn::Tuple(types) => {
+            store::TypeDfn::Tuple(types.iter().map(expr).collect::<Result<_>>()?)
+        }
+        TypeDfn::Record(fields) => store::TypeDfn::Record(
+            fields
+                .iter()
+                .map(|(s, t)| Ok((s.to_string(), expr(t)?)))
+                .collect::<Result<_>>()?,
+        ),
+        TypeDfn::Union(variants) => store::TypeDfn::Union(
+            variants
+                .iter()
+                .map(|(s, d)| Ok((s.to_string(), export_dfn(symbol, d)?)))
+                .collect::<Result<_>>()?,
+        ),
+        TypeDfn::Generic(params, dfn) => store::TypeDfn::Generic(
+            params.iter().map(|p| p.to_string()).collect(),
+            Box::new(export_dfn(symbol, dfn)?),
+        ),
+    })
+}
+
+fn export_ref(symbol: &FQType, dfnref: &TypeDfnRef) -> Result<store::TypeExpr> {
+    let type_ref = |fq: &FQType| match type_ref(fq) {
+        Some(t) => Ok(t),
+        None => Loc::None.err(Error::NotExportableType(symbol.clone())),
+    };
+    let expr = |dfnref: &L<TypeDfnRef>| export_ref(symbol, &dfnref.it).map(Box::new);
+    let exprs = |dfnrefs: &[L<TypeDfnRef>]| {
+        dfnrefs
+            .iter()
+            .map(|r| export_ref(symbol, &r.it))
+            .collect::<Result<_>>()
+    };
+    Ok(match dfnref {
+        TypeDfnRef::Symbol(fq) => store::TypeExpr::Type(type_ref(fq)?),
+        TypeDfnRef::Var(param) => store::TypeExpr::Var(param.to_string()),
+        TypeDfnRef::Apply(fq, args) => store::TypeExpr::Apply(type_ref(fq)?, exprs(args)?),
+        TypeDfnRef::List(element) => store::TypeExpr::List(expr(element)?),
+        TypeDfnRef::Map(key, value) => store::TypeExpr::Map(expr(key)?, expr(value)?),
+        TypeDfnRef::Tuple(types) => store::TypeExpr::Tuple(exprs(types)?),
+        TypeDfnRef::Function(params, result, effects) => store::TypeExpr::Function(
+            exprs(params)?,
+            expr(result)?,
+            effects.iter().map(|e| e.to_string()).collect(),
+        ),
+    })
+}
+
+fn import_type(pkg: &Pkg, tipo: &TypeRef) -> Result<FQType> {
+    match tipo {
+        TypeRef::Std(t) => Ok(Pkg::Std
+            .empty()
+            .fq_type(TSymbol::new(&Loc::None, t.as_str().into())?)),
+        TypeRef::Local(t) => fq_type(pkg, t),
+    }
+}
+
+// Local types of the stored definitions are types of the dependency
+fn import_dfn(pkg: &Pkg, dfn: &store::TypeDfn) -> Result<TypeDfn> {
+    let loc = Loc::None;
+    let tsymbol = |s: &String| TSymbol::new(&loc, s.as_str().into());
+    Ok(match dfn {
+        store::TypeDfn::Singleton => TypeDfn::Singleton,
+        store::TypeDfn::Tuple(types) => TypeDfn::Tuple(import_refs(pkg, types)?),
+        store::TypeDfn::Record(fields) => TypeDfn::Record(
+            fields
+                .iter()
+                .map(|(s, t)| {
+                    let symbol = Symbol::new(&loc, s.as_str().into())?;
+                    Ok((symbol, loc.wrap(import_ref(pkg, t)?)))
+                })
+                .collect::<Result<_>>()?,
+        ),
+        store::TypeDfn::Union(variants) => TypeDfn::Union(
+            variants
+                .iter()
+                .map(|(s, d)| Ok((tsymbol(s)?, import_dfn(pkg, d)?)))
+                .collect::<Result<_>>()?,
+        ),
+        store::TypeDfn::Generic(params, dfn) => TypeDfn::Generic(
+            params.iter().map(tsymbol).collect::<Result<_>>()?,
+            Box::new(import_dfn(pkg, dfn)?),
+        ),
+    })
+}
+
+fn import_ref(pkg: &Pkg, expr: &store::TypeExpr) -> Result<TypeDfnRef> {
+    let loc = Loc::None;
+    let boxed = |e: &store::TypeExpr| import_ref(pkg, e).map(|r| Box::new(loc.wrap(r)));
+    Ok(match expr {
+        store::TypeExpr::Type(t) => TypeDfnRef::Symbol(import_type(pkg, t)?),
+        store::TypeExpr::Var(param) => TypeDfnRef::Var(TSymbol::new(&loc, param.as_str().into())?),
+        store::TypeExpr::Apply(t, args) => {
+            TypeDfnRef::Apply(import_type(pkg, t)?, import_refs(pkg, args)?)
+        }
+        store::TypeExpr::List(element) => TypeDfnRef::List(boxed(element)?),
+        store::TypeExpr::Map(key, value) => TypeDfnRef::Map(boxed(key)?, boxed(value)?),
+        store::TypeExpr::Tuple(types) => TypeDfnRef::Tuple(import_refs(pkg, types)?),
+        store::TypeExpr::Function(params, result, effects) => TypeDfnRef::Function(
+            import_refs(pkg, params)?,
+            boxed(result)?,
+            effects
+                .iter()
+                .map(|e| {
+                    let symbol = TSymbol::new(&loc, e.as_str().into())?;
+                    match Effect::parse(&symbol) {
+                        Some(effect) => Ok(effect),
+                        None => loc.err(Error::UnknownEffect(symbol)),
+                    }
+                })
+                .collect::<Result<_>>()?,
+        ),
+    })
+}
+
+fn import_refs(pkg: &Pkg, exprs: &[store::TypeExpr]) -> Result<Vec<L<TypeDfnRef>>> {
+    exprs
+        .iter()
+        .map(|e| Ok(Loc::None.wrap(import_ref(pkg, e)?)))
+        .collect()
+}
+
+fn fq_path(pkg: &Pkg, path: &[String]) -> Result<FQPath> {
+    let segments = path
+        .iter()
+        .map(|s| Symbol::new(&Loc::None, s.as_str().into()))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(pkg.path(Path::new(segments)))
+}
+
+fn fq_type(pkg: &Pkg, name: &Name) -> Result<FQType> {
+    Ok(fq_path(pkg, &name.path)?.fq_type(TSymbol::new(&Loc::None, name.name.as_str().into())?))
+}
+
+#[cfg(test)]
+mod conformance;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,61 @@
+use std::fmt;
+
+use crate::context::{NativeFn, Type, Typed, Value};
+use crate::error::Error;
+
+// Rust functions that can be called by programs. Arguments and results are converted from and
+// to values, and the errors returned by the function are reported as host errors.
+pub trait NativeFunction<Args>: Send + Sync + 'static {
+    fn params() -> Vec<Type>;
+
+    fn result() -> Type;
+
+    fn call(&self, args: Vec<Value>) -> Result<Value, Error>;
+
+    fn boxed(self) -> Box<NativeFn>
+    where
+        Self: Sized,
+    {
+        Box::new(move |args| self.call(args))
+    }
+}
+
+macro_rules! native_function {
+    ($($arg:ident),*) => {
+        impl<F, R, E, $($arg),*> NativeFunction<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> Result<R, E> + Send + Sync + 'static,
+            R: Typed + Into<Value>,
+            E: fmt::Display,
+            $($arg: Typed + TryFrom<Value, Error = Error>),*
+        {
+            fn params() -> Vec<Type> {
+                vec![$($arg::tipo()),*]
+            }
+
+            fn result() -> Type {
+                R::tipo()
+            }
+
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn call(&self, args: Vec<Value>) -> Result<Value, Error> {
+                let expected = Self::params().len();
+                if args.len() != expected {
+                    return Err(Error::ArgumentCountMismatch(expected, args.len()));
+                }
+                let mut args = args.into_iter();
+                $(let $arg = $arg::try_from(args.next().unwrap())?;)*
+                match self($($arg),*) {
+                    Ok(result) => Ok(result.into()),
+                    Err(e) => Err(Error::HostError(e.to_string().into())),
+                }
+            }
+        }
+    };
+}
+
+native_function!();
+native_function!(A1);
+native_function!(A1, A2);
+native_function!(A1, A2, A3);
+native_function!(A1, A2, A3, A4);
@@ -0,0 +1,328 @@
+use crate::ArcStr;
+use crate::context::Typed;
+use crate::effect::{Effect, Effects};
+use crate::error::{Error, Loc};
+use crate::symbol::{FQPath, Path, Pkg, Symbol};
+
+use super::{Backend, Env, Limits, Value};
+
+fn host() -> FQPath {
+    let segment = Symbol::new(&Loc::None, "host".into()).unwrap();
+    Pkg::Local.path(Path::new(vec![segment]))
+}
+
+fn env() -> Env {
+    let mut env = Env::default();
+    env.add_native(&host(), "double", Effects::NONE, |x: i64| {
+        x.checked_mul(2).ok_or("overflow")
+    })
+    .unwrap();
+    env.add_native(&host(), "shout", Effects::NONE, |s: String, b: bool| {
+        Ok::<_, String>(if b { s.to_uppercase() } else { s })
+    })
+    .unwrap();
+    env.add_native(&Pkg::Std.empty(), "answer", Effects::NONE, || {
+        Ok::<_, String>(42)
+    })
+    .unwrap();
+    env.add_native(&host(), "save", Effect::IO.into(), |_: ArcStr| {
+        Ok::<_, String>(())
+    })
+    .unwrap();
+    env
+}
+
+fn eval_ok(input: &str, expected: Value) {
+    match env().run(input) {
+        Ok(value) => assert_eq!(value, expected),
+        Err(e) => panic!("Error evaluating {input}: {e:?}"),
+    }
+}
+
+fn eval_err(input: &str, expected: Error) {
+    match env().run(input) {
+        Ok(value) => panic!("Expected error evaluating {input} but got {value:?}"),
+        Err(e) => assert!(e.contains(&expected), "{e:?}"),
+    }
+}
+
+#[test]
+fn natives() {
+    eval_ok("host::double(21)", 42.into());
+    eval_ok("answer() + 1", 43.into());
+    eval_ok("host::shout(\"hi\", 1 > 0)", "HI".to_string().into());
+    eval_ok("host::shout(\"hi\", False)", "hi".to_string().into());
+    eval_ok("let f = host::double\nf(f(1))", 4.into());
+    eval_ok("host::save(\"x\")", ().into());
+}
+
+#[test]
+fn native_errors() {
+    eval_err(
+        "host::double(\"a\")",
+        Error::type_mismatch(i64::tipo(), String::tipo()),
+    );
+    eval_err("host::double(1, 2)", Error::ArgumentCountMismatch(1, 2));
+    eval_err(
+        "host::double(4611686018427387904)",
+        Error::HostError("overflow".into()),
+    );
+    eval_err("host::double(9223372036854775808)", Error::IntegerOverflow);
+    eval_err(
+        "let x = host::save(\"x\")",
+        Error::EffectNotAllowed(Effect::IO),
+    );
+    let mut env = env();
+    let duplicate = env.add_native(&host(), "double", Effects::NONE, |x: i64| {
+        Ok::<_, String>(x)
+    });
+    assert!(duplicate.is_err_and(|e| e.contains(&Error::DuplicateSymbol(
+        host().fq_sym(Symbol::new(&Loc::None, "double".into()).unwrap())
+    ))));
+    assert!(
+        env.add_native(&host(), "Double", Effects::NONE, || Ok::<_, String>(1))
+            .is_err()
+    );
+}
+
+#[test]
+fn natives_are_not_exported() {
+    let mut env = env();
+    env.run("let x = host::double(2)").unwrap();
+    let package = env.export().unwrap();
+    assert_eq!(package.symbols.len(), 1);
+}
+
+#[test]
+fn not_exportable() {
+    let symbol = |s: &str| {
+        Pkg::Local
+            .empty()
+            .fq_sym(Symbol::new(&Loc::None, s.into()).unwrap())
+    };
+    for (input, name) in [
+        ("fn f(x: Integer): Integer = x", "f"),
+        ("let t = (1, 2)", "t"),
+        ("let l = [1]", "l"),
+        ("let m = {\"a\": 1}", "m"),
+        ("type P(Integer)\nlet p = P(1)", "p"),
+        ("type R { x: Integer }\nlet r = R { x: 1 }", "r"),
+    ] {
+        let mut env = Env::default();
+        env.run(input).unwrap();
+        let e = env.export().unwrap_err();
+        assert!(
+            e.contains(&Error::NotExportable(symbol(name))),
+            "{input}: {e:?}"
+        );
+    }
+    // Every type is exported, even if no symbol has it
+    let mut env = Env::default();
+    env.run("type P(Integer)\ntype S\nlet s = S").unwrap();
+    let package = env.export().unwrap();
+    assert_eq!(package.types.len(), 2);
+    assert_eq!(package.symbols.len(), 1);
+}
+
+const SUM: &str = "fn sum(n: Integer): Integer = if n > 0 then n + sum(n - 1) else 0\n";
+
+fn limited(backend: Backend, limits: Limits) -> Env {
+    let mut env = Env::default();
+    env.set_backend(backend);
+    env.set_limits(limits);
+    env
+}
+
+// Limits apply to every backend
+fn limited_ok(limits: Limits, input: &str, expected: Value) {
+    for backend in Backend::ALL {
+        match limited(backend, limits).run(input) {
+            Ok(value) => assert_eq!(value, expected),
+            Err(e) => panic!("Error evaluating {input} with {backend:?}: {e:?}"),
+        }
+    }
+}
+
+fn limited_err(limits: Limits, input: &str, expected: Error) {
+    for backend in Backend::ALL {
+        match limited(backend, limits).run(input) {
+            Ok(value) => panic!("Expected error evaluating {input} with {backend:?}: {value:?}"),
+            Err(e) => assert!(e.contains(&expected), "{backend:?}: {e:?}"),
+        }
+    }
+}
+
+#[test]
+fn fuel() {
+    let limits = Limits {
+        fuel: Some(500),
+        ..Limits::default()
+    };
+    limited_ok(limits, &format!("{SUM}sum(10)"), 55.into());
+    limited_err(limits, &format!("{SUM}sum(50)"), Error::FuelExhausted(500));
+    // Each run gets the whole fuel
+    for backend in Backend::ALL {
+        let mut env = limited(backend, limits);
+        env.run(SUM).unwrap();
+        for _ in 0..10 {
+            assert_eq!(env.run("sum(10)").unwrap(), 55.into());
+        }
+    }
+}
+
+// Returns the least fuel the program runs with
+fn fuel_needed(backend: Backend, input: &str) -> u64 {
+    let run = |fuel| {
+        let limits = Limits {
+            fuel: Some(fuel),
+            ..Limits::default()
+        };
+        limited(backend, limits).run(input)
+    };
+    let (mut low, mut high) = (0, 100_000);
+    assert!(run(high).is_ok(), "{input}");
+    while low + 1 < high {
+        let middle = (low + high) / 2;
+        match run(middle) {
+            Ok(_) => high = middle,
+            Err(e) if e.contains(&Error::FuelExhausted(middle)) => low = middle,
+            Err(e) => panic!("{input}: {e:?}"),
+        }
+    }
+    high
+}
+
+// Fuel counts the expressions evaluated, whatever the backend
+#[test]
+fn fuel_is_the_same_for_every_backend() {
+    for input in [
+        "1",
+        "1 + 2 * 3",
+        &format!("{SUM}sum(10)"),
+        "let x = 2\nlet y = { let z = x * 2\n z + 1 }\ny - x",
+        "if 1 < 2 && 2 < 1 || True then \"a{1}b\" else \"c\"",
+        "fn adder(n: Integer): (Integer) -> Integer = fn(x: Integer) = x + n\nadder(1)(2)",
+        "type S = A(Integer) | B\nlet s: S = S::A(1)\nmatch s { S::B -> 0, S::A(x) -> x }",
+        "let l = [1, 2, 3]\nlet m = {\"a\": l[0]}\nm[\"a\"] + l.length",
+        "type P { x: Integer, y: Integer }\nlet p = P { x: 1, y: 2 }\n(p.x, (p: P).y).0",
+    ] {
+        let fuel = fuel_needed(Backend::Interpreter, input);
+        assert_eq!(fuel_needed(Backend::VM, input), fuel, "{input}");
+    }
+}
+
+#[test]
+fn max_depth() {
+    let limits = Limits {
+        max_depth: Some(10),
+        ..Limits::default()
+    };
+    limited_ok(limits, &format!("{SUM}sum(9)"), 45.into());
+    limited_err(
+        limits,
+        &format!("{SUM}sum(10)"),
+        Error::MaxDepthExceeded(10),
+    );
+    // Unbounded recursion is stopped by the default limit instead of overflowing the stack
+    limited_err(
+        Limits::default(),
+        "fn forever(n: Integer): Integer = forever(n + 1)\nforever(0)",
+        Error::MaxDepthExceeded(Limits::DEFAULT_MAX_DEPTH),
+    );
+    // Runs have a stack large enough for deeper recursion
+    limited_ok(
+        Limits::UNBOUNDED,
+        &format!("{SUM}sum(30000)"),
+        450015000i64.into(),
+    );
+}
+
+// The default limits allow recursion thousands of calls deep with every backend
+#[test]
+fn default_limits() {
+    let even = "fn even(n: Integer): Boolean = if n == 0 then True else odd(n - 1)
+fn odd(n: Integer): Boolean = if n == 0 then False else even(n - 1)
+";
+    for backend in Backend::ALL {
+        let mut env = Env::default();
+        env.set_backend(backend);
+        assert_eq!(env.limits(), Limits::default());
+        assert_eq!(
+            env.run(&format!("{even}even(5000)")).unwrap(),
+            Value::v_true()
+        );
+        assert_eq!(
+            env.run(&format!("{SUM}sum(9000)")).unwrap(),
+            40504500.into()
+        );
+        assert!(
+            env.run("sum(20000)")
+                .is_err_and(|e| e.contains(&Error::MaxDepthExceeded(Limits::DEFAULT_MAX_DEPTH)))
+        );
+    }
+}
+
+#[test]
+fn max_memory() {
+    let limits = Limits {
+        max_memory: Some(1000),
+        ..Limits::default()
+    };
+    let repeat =
+        "fn repeat(s: String, n: Integer): String = if n > 0 then s + repeat(s, n - 1) else \"\"\n";
+    limited_ok(
+        limits,
+        &format!("{repeat}repeat(\"ab\", 10).length"),
+        20.into(),
+    );
+    limited_err(
+        limits,
+        &format!("{repeat}repeat(\"ab\", 50).length"),
+        Error::MemoryLimitExceeded(1000),
+    );
+    // Discarded values are accounted too
+    limited_err(
+        limits,
+        "fn count(n: Integer): Integer = if n > 0 then [n, n][0] + count(n - 1) else 0\ncount(50)",
+        Error::MemoryLimitExceeded(1000),
+    );
+}
+
+#[test]
+fn cancellation() {
+    for backend in Backend::ALL {
+        cancel(backend);
+    }
+}
+
+fn cancel(backend: Backend) {
+    let mut env = limited(backend, Limits::default());
+    let handle = env.cancel_handle();
+    // Cancelling with no run in progress has no effect, e.g. after the run has finished
+    assert_eq!(env.run("1 + 1").unwrap(), 2.into());
+    handle.cancel();
+    assert_eq!(env.run("1 + 1").unwrap(), 2.into());
+    // Cancelled from another thread while running
+    let fib = "fn fib(n: Integer): Integer = if n < 2 then n else fib(n - 1) + fib(n - 2)\nfib(40)";
+    let canceller = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        handle.cancel();
+    });
+    assert!(env.run(fib).is_err_and(|e| e.contains(&Error::Cancelled)));
+    canceller.join().unwrap();
+}
+
+// Closures keep the lambdas they are created from, so they can be called by any backend
+#[test]
+fn switching_backends() {
+    let adder = "fn adder(n: Integer): (Integer) -> Integer = fn(x: Integer) = x + n\n";
+    for (first, second) in [
+        (Backend::Interpreter, Backend::VM),
+        (Backend::VM, Backend::Interpreter),
+    ] {
+        let mut env = limited(first, Limits::default());
+        env.run(&format!("{adder}let inc = adder(1)")).unwrap();
+        env.set_backend(second);
+        assert_eq!(env.run("inc(41) + adder(2)(0)").unwrap(), 44.into());
+    }
+}
@@ -1,15 +1,28 @@
+use std::sync::Arc;
+
 use im::HashMap;
 
-use crate::Integer;
+use crate::ast0::UnaryOp;
 use crate::context::Value;
 use crate::error::{Error, L, Loc, Result};
 use crate::symbol::Symbol;
-use crate::tst::{Block, Expr, Package, TwoInts};
+use crate::tst::{
+    Block, Call, Expr, Index, Lambda, List, Map, Match, Package, TwoDecs, TwoInts, TwoStrs,
+    TwoValues, Unary, Update,
+};
+use crate::{ArcStr, Decimal, Integer};
 
-use super::Env;
+use super::limits::Budget;
+use super::{Env, matches};
 
 pub(super) fn run(env: &mut Env, package: &Package) -> Result<Value> {
-    Interpreter { env, package }.run()
+    let budget = Budget::new(env.limits(), env.cancel.clone());
+    Interpreter {
+        env,
+        package,
+        budget,
+    }
+    .run()
 }
 
 #[derive(Debug, Default, Clone)]
@@ -18,10 +31,6 @@ struct Scope {
 }
 
 impl Scope {
-    fn contains(&self, symbol: &Symbol) -> bool {
-        self.values.contains_key(symbol)
-    }
-
     fn get(&self, symbol: &Symbol) -> Option<Value> {
         self.values.get(symbol).cloned()
     }
@@ -40,12 +49,11 @@ impl Scope {
 struct Interpreter<'a> {
     env: &'a mut Env,
     package: &'a Package,
+    budget: Budget,
 }
 
 impl<'a> Interpreter<'a> {
-    fn run(mut self) -> Result<Value> {
-        self.env.types = self.package.types.clone();
-        self.env.symbols = self.package.symbols.clone();
+    fn run(self) -> Result<Value> {
         let mut value = Value::v_none();
         let mut scope = Scope::default();
         for a in &self.package.assignments {
@@ -72,7 +80,10 @@ impl<'a> Interpreter<'a> {
         }
     }
 
+    // Arms delegate to other methods to keep the frame of this function, which recurses for
+    // every nested expression, small
     fn expression(&self, scope: &mut Scope, expr: &L<Expr>) -> Result<Value> {
+        self.budget.step(&expr.loc, 1)?;
         match &expr.it {
             Expr::Value(v) => Ok(v.clone()),
             Expr::Local(l) => match scope.get(&l.symbol) {
@@ -83,28 +94,238 @@ impl<'a> Interpreter<'a> {
                 Some(value) => Ok(value.clone()),
                 None => expr.err(Error::UnknownSymbol(g.symbol.clone())),
             },
-            Expr::Seq(s) => self.expression(scope, &s.then), // no effects for now
+            Expr::Seq(s) => self.seq(scope, &s.expr, &s.then),
             Expr::Conditional(c) => {
-                if self.expression(scope, &c.expr)?.as_boolean(&expr.loc)? {
-                    self.expression(scope, &c.then)
+                self.conditional(&expr.loc, scope, &c.expr, &c.then, &c.otherwise)
+            }
+            Expr::Unary(u) => self.unary(expr, scope, u),
+            Expr::IntAdd(t) => self.int_op(expr, scope, t, |v1, v2| overflow(v1.checked_add(v2))),
+            Expr::IntSub(t) => self.int_op(expr, scope, t, |v1, v2| overflow(v1.checked_sub(v2))),
+            Expr::IntMul(t) => self.int_op(expr, scope, t, |v1, v2| overflow(v1.checked_mul(v2))),
+            Expr::IntDiv(t) => self.int_op(expr, scope, t, |v1, v2| {
+                v1.checked_div(v2).ok_or(Error::DivisionByZero)
+            }),
+            Expr::IntRem(t) => self.int_op(expr, scope, t, |v1, v2| {
+                v1.checked_rem(v2).ok_or(Error::DivisionByZero)
+            }),
+            Expr::IntPow(t) => self.int_op(expr, scope, t, |v1, v2| {
+                if v2.is_negative() {
+                    Err(Error::NegativeExponent)
                 } else {
-                    self.expression(scope, &c.otherwise)
+                    overflow(v1.checked_pow(v2))
                 }
-            }
-            Expr::IntAdd(t) => self
-                .eval_two_ints(&expr.loc, scope, t)
-                .map(|(v1, v2)| Value::v_integer(&expr.loc, expr.get_type(), v1 + v2))?,
-            Expr::IntSub(t) => self
-                .eval_two_ints(&expr.loc, scope, t)
-                .map(|(v1, v2)| Value::v_integer(&expr.loc, expr.get_type(), v1 - v2))?,
-            Expr::IntMul(t) => self
-                .eval_two_ints(&expr.loc, scope, t)
-                .map(|(v1, v2)| Value::v_integer(&expr.loc, expr.get_type(), v1 * v2))?,
-            Expr::IntDiv(t) => self.div(&expr, scope, t),
+            }),
+            Expr::DecAdd(t) => self.dec_op(expr, scope, t, Decimal::checked_add),
+            Expr::DecSub(t) => self.dec_op(expr, scope, t, Decimal::checked_sub),
+            Expr::DecMul(t) => self.dec_op(expr, scope, t, Decimal::checked_mul),
+            Expr::StrConcat(t) => self.str_concat(expr, scope, t),
+            Expr::StrLength(e) => self.str_length(expr, scope, e),
+            Expr::Interpolation(i) => self.interpolation(expr, scope, &i.parts),
             Expr::LogicalAnd(t) => self.and(&expr.loc, scope, &t.expr1, &t.expr2),
             Expr::LogicalOr(t) => self.or(&expr.loc, scope, &t.expr1, &t.expr2),
+            Expr::Eq(t) => self.eq(scope, t).map(Value::v_bool),
+            Expr::NEq(t) => self.eq(scope, t).map(|eq| Value::v_bool(!eq)),
+            Expr::IntLT(t) => self.int_cmp(&expr.loc, scope, t, |v1, v2| v1 < v2),
+            Expr::IntLE(t) => self.int_cmp(&expr.loc, scope, t, |v1, v2| v1 <= v2),
+            Expr::IntGT(t) => self.int_cmp(&expr.loc, scope, t, |v1, v2| v1 > v2),
+            Expr::IntGE(t) => self.int_cmp(&expr.loc, scope, t, |v1, v2| v1 >= v2),
+            Expr::StrLT(t) => self.str_cmp(&expr.loc, scope, t, |v1, v2| v1 < v2),
+            Expr::StrLE(t) => self.str_cmp(&expr.loc, scope, t, |v1, v2| v1 <= v2),
+            Expr::StrGT(t) => self.str_cmp(&expr.loc, scope, t, |v1, v2| v1 > v2),
+            Expr::StrGE(t) => self.str_cmp(&expr.loc, scope, t, |v1, v2| v1 >= v2),
             Expr::Block(block) => self.block(&mut scope.clone(), block),
-            _ => expr.err(Error::NotImplemented),
+            Expr::Lambda(l) => self.lambda(&expr.loc, scope, l),
+            Expr::Call(c) => self.call(&expr.loc, scope, c),
+            Expr::Tuple(t) => {
+                let values = self.values(&expr.loc, scope, &t.exprs)?;
+                Value::v_tuple(&expr.loc, t.tipo.clone(), values)
+            }
+            Expr::TupleField(f) => self
+                .expression(scope, &f.expr)?
+                .tuple_field(&expr.loc, f.index),
+            Expr::Record(r) => {
+                let values = self.values(&expr.loc, scope, &r.exprs)?;
+                Value::v_record(&expr.loc, r.tipo.clone(), values)
+            }
+            Expr::RecordField(f) => self
+                .expression(scope, &f.expr)?
+                .record_field(&expr.loc, f.index),
+            Expr::Update(u) => self.update(&expr.loc, scope, u),
+            Expr::List(l) => self.list(&expr.loc, scope, l),
+            Expr::Map(m) => self.map(&expr.loc, scope, m),
+            Expr::ListIndex(i) => self.list_index(&expr.loc, scope, i),
+            Expr::MapGet(i) => self.map_get(&expr.loc, scope, i),
+            Expr::ListLength(e) | Expr::MapLength(e) => self.length(expr, scope, e),
+            Expr::Match(m) => self.pattern_match(&expr.loc, scope, m),
+            Expr::Ascription(a) => self.expression(scope, &a.expr),
+        }
+    }
+
+    // The first expression is only evaluated for its effects
+    fn seq(&self, scope: &mut Scope, expr: &L<Expr>, then: &L<Expr>) -> Result<Value> {
+        self.expression(scope, expr)?;
+        self.expression(scope, then)
+    }
+
+    fn conditional(
+        &self,
+        loc: &Loc,
+        scope: &mut Scope,
+        expr: &L<Expr>,
+        then: &L<Expr>,
+        otherwise: &L<Expr>,
+    ) -> Result<Value> {
+        if self.eval_bool(loc, scope, expr)? {
+            self.expression(scope, then)
+        } else {
+            self.expression(scope, otherwise)
+        }
+    }
+
+    fn str_concat(&self, expr: &L<Expr>, scope: &mut Scope, t: &TwoStrs) -> Result<Value> {
+        let (v1, v2) = self.eval_two_strs(&expr.loc, scope, t)?;
+        self.budget.allocate(&expr.loc, v1.len() + v2.len())?;
+        Value::v_string(&expr.loc, expr.get_type(), format!("{v1}{v2}").into())
+    }
+
+    // The length is the number of chars
+    fn str_length(&self, expr: &L<Expr>, scope: &mut Scope, e: &L<Expr>) -> Result<Value> {
+        let value = self.expression(scope, e)?.as_string(&expr.loc)?;
+        let length = value.chars().count() as i64;
+        Value::v_integer(&expr.loc, expr.get_type(), length.into())
+    }
+
+    fn interpolation(&self, expr: &L<Expr>, scope: &mut Scope, parts: &[L<Expr>]) -> Result<Value> {
+        let mut text = String::default();
+        for part in parts {
+            text.push_str(&self.expression(scope, part)?.text());
+        }
+        self.budget.allocate(&expr.loc, text.len())?;
+        Value::v_string(&expr.loc, expr.get_type(), text.into())
+    }
+
+    fn lambda(&self, loc: &Loc, scope: &mut Scope, lambda: &Arc<Lambda>) -> Result<Value> {
+        self.budget.allocate(loc, size_of::<Value>())?;
+        Ok(Value::v_closure(lambda.clone(), scope.values.clone()))
+    }
+
+    // Values are accounted by their size, not including what they point to
+    fn allocate_values(&self, loc: &Loc, count: usize) -> Result<()> {
+        self.budget.allocate(loc, count * size_of::<Value>())
+    }
+
+    fn values(&self, loc: &Loc, scope: &mut Scope, exprs: &[L<Expr>]) -> Result<Vec<Value>> {
+        self.allocate_values(loc, exprs.len())?;
+        let mut values = Vec::with_capacity(exprs.len());
+        for e in exprs {
+            values.push(self.expression(scope, e)?);
+        }
+        Ok(values)
+    }
+
+    fn update(&self, loc: &Loc, scope: &mut Scope, u: &Update) -> Result<Value> {
+        let record = self.expression(scope, &u.expr)?;
+        self.allocate_values(loc, record.fields().map_or(0, |f| f.len()))?;
+        let mut fields = Vec::with_capacity(u.fields.len());
+        for (index, e) in &u.fields {
+            fields.push((*index, self.expression(scope, e)?));
+        }
+        record.update(loc, fields)
+    }
+
+    fn list(&self, loc: &Loc, scope: &mut Scope, l: &List) -> Result<Value> {
+        self.allocate_values(loc, l.exprs.len())?;
+        let mut values = im::Vector::new();
+        for e in &l.exprs {
+            values.push_back(self.expression(scope, e)?);
+        }
+        Value::v_list(loc, l.tipo.clone(), values)
+    }
+
+    // Later entries replace earlier ones with the same key
+    fn map(&self, loc: &Loc, scope: &mut Scope, m: &Map) -> Result<Value> {
+        self.allocate_values(loc, 2 * m.entries.len())?;
+        let mut entries = HashMap::new();
+        for (key, value) in &m.entries {
+            let key = self.expression(scope, key)?;
+            entries.insert(key, self.expression(scope, value)?);
+        }
+        Value::v_map(loc, m.tipo.clone(), entries)
+    }
+
+    fn list_index(&self, loc: &Loc, scope: &mut Scope, i: &Index) -> Result<Value> {
+        let list = self.expression(scope, &i.expr)?;
+        let index = self.expression(scope, &i.index)?.as_integer(loc)?;
+        list.list_get(loc, index)
+    }
+
+    fn map_get(&self, loc: &Loc, scope: &mut Scope, i: &Index) -> Result<Value> {
+        let map = self.expression(scope, &i.expr)?;
+        let key = self.expression(scope, &i.index)?;
+        map.map_get(loc, &key)
+    }
+
+    fn length(&self, expr: &L<Expr>, scope: &mut Scope, e: &L<Expr>) -> Result<Value> {
+        let length = self.expression(scope, e)?.length(&expr.loc)?;
+        Value::v_integer(&expr.loc, expr.get_type(), (length as i64).into())
+    }
+
+    // The arm expression is evaluated with the values bound by its pattern, which shadow the
+    // ones in scope
+    fn pattern_match(&self, loc: &Loc, scope: &mut Scope, m: &Match) -> Result<Value> {
+        let value = self.expression(scope, &m.expr)?;
+        for arm in &m.arms {
+            let mut values = scope.values.clone();
+            let mut bind = |symbol: &Symbol, value: &Value| {
+                values.insert(symbol.clone(), value.clone());
+            };
+            if matches(&arm.pattern, &value, &mut bind) {
+                return self.expression(&mut Scope { values }, &arm.expr);
+            }
+        }
+        loc.err(Error::NonExhaustiveMatch)
+    }
+
+    // The body is evaluated with the captured values and the arguments, which shadow them
+    fn call(&self, loc: &Loc, scope: &mut Scope, call: &Call) -> Result<Value> {
+        let func = self.expression(scope, &call.func)?;
+        if let Some(builtin) = func.as_builtin() {
+            let args = self.args(scope, call)?;
+            return self.env.builtin(loc, builtin, args);
+        }
+        if let Some(native) = func.as_native() {
+            let args = self.args(scope, call)?;
+            return (native.function)(args).or_else(|e| loc.err(e));
+        }
+        let closure = func.as_closure(loc)?;
+        let mut values = closure.captured.clone();
+        for (param, arg) in closure.lambda.params.iter().zip(&call.args) {
+            values.insert(param.clone(), self.expression(scope, arg)?);
+        }
+        self.budget.enter(loc)?;
+        let result = self.expression(&mut Scope { values }, &closure.lambda.body);
+        self.budget.exit();
+        result
+    }
+
+    fn args(&self, scope: &mut Scope, call: &Call) -> Result<Vec<Value>> {
+        let mut args = Vec::with_capacity(call.args.len());
+        for arg in &call.args {
+            args.push(self.expression(scope, arg)?);
+        }
+        Ok(args)
+    }
+
+    fn unary(&self, expr: &L<Expr>, scope: &mut Scope, u: &Unary) -> Result<Value> {
+        let value = self.expression(scope, &u.expr)?;
+        match u.op {
+            UnaryOp::Not => Ok(Value::v_bool(!value.as_boolean(&expr.loc)?)),
+            UnaryOp::Minus if u.expr.get_type().is_decimal() => {
+                Value::v_decimal(&expr.loc, expr.get_type(), -value.as_decimal(&expr.loc)?)
+            }
+            UnaryOp::Minus => {
+                Value::v_integer(&expr.loc, expr.get_type(), -value.as_integer(&expr.loc)?)
+            }
         }
     }
 
@@ -120,20 +341,75 @@ impl<'a> Interpreter<'a> {
         )
     }
 
+    fn eval_two_decs(
+        &self,
+        loc: &Loc,
+        scope: &mut Scope,
+        t: &TwoDecs,
+    ) -> Result<(Decimal, Decimal)> {
+        Error::merge(
+            self.expression(scope, &t.expr1)?.as_decimal(loc),
+            self.expression(scope, &t.expr2)?.as_decimal(loc),
+        )
+    }
+
+    fn eval_two_strs(&self, loc: &Loc, scope: &mut Scope, t: &TwoStrs) -> Result<(ArcStr, ArcStr)> {
+        Error::merge(
+            self.expression(scope, &t.expr1)?.as_string(loc),
+            self.expression(scope, &t.expr2)?.as_string(loc),
+        )
+    }
+
     fn eval_bool(&self, loc: &Loc, scope: &mut Scope, expr: &L<Expr>) -> Result<bool> {
         self.expression(scope, expr)?.as_boolean(loc)
     }
 
-    fn div(&self, expr: &L<Expr>, scope: &mut Scope, t: &TwoInts) -> Result<Value> {
+    // Checked integer operations, errors are reported at the location of the expression
+    fn int_op<F>(&self, expr: &L<Expr>, scope: &mut Scope, t: &TwoInts, f: F) -> Result<Value>
+    where
+        F: FnOnce(Integer, Integer) -> std::result::Result<Integer, Error>,
+    {
         let (v1, v2) = self.eval_two_ints(&expr.loc, scope, t)?;
-        // We only have integers for now
-        if v2.is_zero() {
-            expr.err(Error::DivisionByZero)
-        } else {
-            Value::v_integer(&expr.loc, expr.get_type(), v1 / v2)
+        match f(v1, v2) {
+            Ok(value) => Value::v_integer(&expr.loc, expr.get_type(), value),
+            Err(error) => expr.err(error),
         }
     }
 
+    // Checked decimal operations, which return None on overflow
+    fn dec_op<F>(&self, expr: &L<Expr>, scope: &mut Scope, t: &TwoDecs, f: F) -> Result<Value>
+    where
+        F: FnOnce(Decimal, Decimal) -> Option<Decimal>,
+    {
+        let (v1, v2) = self.eval_two_decs(&expr.loc, scope, t)?;
+        match f(v1, v2) {
+            Some(value) => Value::v_decimal(&expr.loc, expr.get_type(), value),
+            None => expr.err(Error::DecimalOverflow),
+        }
+    }
+
+    fn int_cmp<F>(&self, loc: &Loc, scope: &mut Scope, t: &TwoInts, f: F) -> Result<Value>
+    where
+        F: FnOnce(&Integer, &Integer) -> bool,
+    {
+        let (v1, v2) = self.eval_two_ints(loc, scope, t)?;
+        Ok(Value::v_bool(f(&v1, &v2)))
+    }
+
+    // Strings are compared by their chars
+    fn str_cmp<F>(&self, loc: &Loc, scope: &mut Scope, t: &TwoStrs, f: F) -> Result<Value>
+    where
+        F: FnOnce(&str, &str) -> bool,
+    {
+        let (v1, v2) = self.eval_two_strs(loc, scope, t)?;
+        Ok(Value::v_bool(f(&v1, &v2)))
+    }
+
+    // Structural equality
+    fn eq(&self, scope: &mut Scope, t: &TwoValues) -> Result<bool> {
+        Ok(self.expression(scope, &t.expr1)? == self.expression(scope, &t.expr2)?)
+    }
+
     fn and(&self, loc: &Loc, scope: &mut Scope, expr1: &L<Expr>, expr2: &L<Expr>) -> Result<Value> {
         if self.eval_bool(loc, scope, expr1)? {
             Ok(Value::v_bool(self.eval_bool(loc, scope, expr2)?))
@@ -151,5 +427,6 @@ impl<'a> Interpreter<'a> {
     }
 }
 
-#[cfg(test)]
-mod tests;
+fn overflow(value: Option<Integer>) -> std::result::Result<Integer, Error> {
+    value.ok_or(Error::IntegerOverflow)
+}
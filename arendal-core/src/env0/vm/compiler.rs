@@ -0,0 +1,530 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::ast0::UnaryOp;
+use crate::context::{Type, Value};
+use crate::error::{Error, L, Loc};
+use crate::symbol::{FQSym, Symbol};
+use crate::tst::{Block, Expr, Lambda, Match, Package, Pattern, TwoInts, TwoStrs};
+use crate::{Decimal, Integer};
+
+// Instructions of the stack machine. Functions keep their arguments and local values in slots
+// at the bottom of their part of the stack, and the intermediate values above them. Operands
+// refer to slots, to jump targets or to the tables of the function.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Op {
+    Const(usize),     // Pushes a constant
+    Load(usize),      // Pushes the value of a slot
+    Store(usize),     // Pops a value into a slot
+    Global(usize),    // Pushes the value of a global symbol
+    SetGlobal(usize), // Pops the value of a global symbol
+    Pop,
+    Jump(usize),
+    JumpIfFalse(usize), // Pops a boolean
+    Closure(usize),     // Pops the captured values of a function
+    Call(usize),        // Pops the arguments and the function, pushing the result
+    Return,
+    Fail(usize), // Raises an error
+    Not,
+    Neg(usize), // Result type
+    NegDec(usize),
+    Int(IntOp, usize), // Pops two integers, pushing a value of the result type
+    IntCmp(Cmp),
+    Dec(DecOp, usize),
+    StrConcat(usize),
+    StrCmp(Cmp),
+    StrLength(usize),
+    Interpolation(usize, usize), // Number of parts and result type
+    Eq,
+    NEq,
+    Tuple(usize, usize), // Number of elements and type
+    Record(usize, usize),
+    TupleField(usize),
+    RecordField(usize),
+    Update(usize), // Pops the values of the updated fields and the record
+    List(usize, usize),
+    Map(usize, usize), // Number of entries, each one pushed as key and value, and type
+    ListIndex,
+    MapGet,
+    Length(usize),
+    Match(usize, usize), // Arm and target if the value on top doesn't match its pattern
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum IntOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+}
+
+impl IntOp {
+    pub(super) fn apply(self, v1: Integer, v2: Integer) -> Result<Integer, Error> {
+        let overflow = |value: Option<Integer>| value.ok_or(Error::IntegerOverflow);
+        match self {
+            Self::Add => overflow(v1.checked_add(v2)),
+            Self::Sub => overflow(v1.checked_sub(v2)),
+            Self::Mul => overflow(v1.checked_mul(v2)),
+            Self::Div => v1.checked_div(v2).ok_or(Error::DivisionByZero),
+            Self::Rem => v1.checked_rem(v2).ok_or(Error::DivisionByZero),
+            Self::Pow if v2.is_negative() => Err(Error::NegativeExponent),
+            Self::Pow => overflow(v1.checked_pow(v2)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum DecOp {
+    Add,
+    Sub,
+    Mul,
+}
+
+impl DecOp {
+    pub(super) fn apply(self, v1: Decimal, v2: Decimal) -> Result<Decimal, Error> {
+        match self {
+            Self::Add => v1.checked_add(v2),
+            Self::Sub => v1.checked_sub(v2),
+            Self::Mul => v1.checked_mul(v2),
+        }
+        .ok_or(Error::DecimalOverflow)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Cmp {
+    LT,
+    LE,
+    GT,
+    GE,
+}
+
+impl Cmp {
+    pub(super) fn apply<T: PartialOrd + ?Sized>(self, v1: &T, v2: &T) -> bool {
+        match self {
+            Self::LT => v1 < v2,
+            Self::LE => v1 <= v2,
+            Self::GT => v1 > v2,
+            Self::GE => v1 >= v2,
+        }
+    }
+}
+
+// Compiled body of a lambda, or of the assignments and expression of a package
+#[derive(Debug, Default)]
+pub(super) struct Function {
+    pub(super) ops: Vec<Op>,
+    pub(super) locs: Vec<Loc>, // Location of each instruction, for errors
+    // Number of expressions whose evaluation starts with each instruction, which is the fuel
+    // it consumes, so that it is the same as with the interpreter
+    pub(super) costs: Vec<u64>,
+    pub(super) slots: usize,                   // Including the arguments
+    pub(super) captures: Vec<(Symbol, usize)>, // Captured symbols and their slots
+    pub(super) constants: Vec<Value>,
+    pub(super) types: Vec<Type>,
+    pub(super) closures: Vec<(Arc<Lambda>, Rc<Function>)>,
+    pub(super) arms: Vec<(Pattern, usize)>, // Pattern and slot of its first binding
+    pub(super) updates: Vec<Vec<usize>>,    // Indices of the updated fields
+    pub(super) errors: Vec<Error>,
+}
+
+// Compiles the code of a run. Global symbols are referred to by their index, and lambdas are
+// compiled once, including the ones of closures created by previous runs.
+#[derive(Debug, Default)]
+pub(super) struct Compiler {
+    pub(super) globals: Vec<FQSym>,
+    indices: HashMap<FQSym, usize>,
+    // The lambdas are kept so that their addresses are not reused during the run
+    functions: HashMap<*const Lambda, (Arc<Lambda>, Rc<Function>)>,
+}
+
+impl Compiler {
+    pub(super) fn package(&mut self, package: &Package) -> Rc<Function> {
+        let mut builder = Builder::new(self, false);
+        let last = package.assignments.len();
+        for (i, a) in package.assignments.iter().enumerate() {
+            builder.expr(&a.it.expr);
+            let global = builder.compiler.global(&a.it.symbol);
+            builder.emit(&a.loc, Op::SetGlobal(global));
+            if i + 1 == last && package.expr.is_none() {
+                builder.emit(&a.loc, Op::Global(global));
+            }
+        }
+        match &package.expr {
+            Some(e) => builder.expr(e),
+            None if last == 0 => builder.constant(&Loc::None, Value::v_none()),
+            None => (),
+        }
+        builder.emit(&Loc::None, Op::Return);
+        Rc::new(builder.function)
+    }
+
+    // Returns the compiled body of a lambda, compiling it the first time
+    pub(super) fn function(&mut self, lambda: &Arc<Lambda>) -> Rc<Function> {
+        if let Some((_, function)) = self.functions.get(&Arc::as_ptr(lambda)) {
+            return function.clone();
+        }
+        let mut builder = Builder::new(self, true);
+        for param in &lambda.params {
+            builder.bind(param.clone());
+        }
+        builder.expr(&lambda.body);
+        builder.emit(&lambda.body.loc, Op::Return);
+        let function = Rc::new(builder.function);
+        self.functions
+            .insert(Arc::as_ptr(lambda), (lambda.clone(), function.clone()));
+        function
+    }
+
+    fn global(&mut self, symbol: &FQSym) -> usize {
+        if let Some(index) = self.indices.get(symbol) {
+            return *index;
+        }
+        let index = self.globals.len();
+        self.globals.push(symbol.clone());
+        self.indices.insert(symbol.clone(), index);
+        index
+    }
+}
+
+struct Builder<'a> {
+    compiler: &'a mut Compiler,
+    function: Function,
+    scopes: Vec<HashMap<Symbol, usize>>, // Innermost last
+    captures: bool,                      // Whether unknown local symbols are captured
+    started: u64,                        // Expressions started since the last instruction
+}
+
+impl<'a> Builder<'a> {
+    fn new(compiler: &'a mut Compiler, captures: bool) -> Self {
+        Builder {
+            compiler,
+            function: Function::default(),
+            scopes: vec![HashMap::default()],
+            captures,
+            started: 0,
+        }
+    }
+
+    fn emit(&mut self, loc: &Loc, op: Op) -> usize {
+        self.function.ops.push(op);
+        self.function.locs.push(loc.clone());
+        self.function.costs.push(std::mem::take(&mut self.started));
+        self.function.ops.len() - 1
+    }
+
+    // Makes a jump target the next instruction
+    fn patch(&mut self, at: usize) {
+        let next = self.function.ops.len();
+        match &mut self.function.ops[at] {
+            Op::Jump(target) | Op::JumpIfFalse(target) | Op::Match(_, target) => *target = next,
+            op => unreachable!("{op:?} is not a jump"),
+        }
+    }
+
+    fn slot(&mut self) -> usize {
+        self.function.slots += 1;
+        self.function.slots - 1
+    }
+
+    fn bind(&mut self, symbol: Symbol) -> usize {
+        let slot = self.slot();
+        self.scopes.last_mut().unwrap().insert(symbol, slot);
+        slot
+    }
+
+    // Captured symbols are visible in the whole function
+    fn resolve(&mut self, symbol: &Symbol) -> Option<usize> {
+        if let Some(slot) = self.scopes.iter().rev().find_map(|s| s.get(symbol)) {
+            return Some(*slot);
+        }
+        if !self.captures {
+            return None;
+        }
+        let slot = self.slot();
+        self.scopes[0].insert(symbol.clone(), slot);
+        self.function.captures.push((symbol.clone(), slot));
+        Some(slot)
+    }
+
+    fn constant(&mut self, loc: &Loc, value: Value) {
+        self.function.constants.push(value);
+        self.emit(loc, Op::Const(self.function.constants.len() - 1));
+    }
+
+    fn tipo(&mut self, tipo: Type) -> usize {
+        self.function.types.push(tipo);
+        self.function.types.len() - 1
+    }
+
+    fn fail(&mut self, loc: &Loc, error: Error) {
+        self.function.errors.push(error);
+        self.emit(loc, Op::Fail(self.function.errors.len() - 1));
+    }
+
+    fn exprs<'e>(&mut self, exprs: impl IntoIterator<Item = &'e L<Expr>>) {
+        for e in exprs {
+            self.expr(e);
+        }
+    }
+
+    // Every expression emits at least one instruction
+    fn expr(&mut self, expr: &L<Expr>) {
+        self.started += 1;
+        let loc = &expr.loc;
+        match &expr.it {
+            Expr::Value(v) => self.constant(loc, v.clone()),
+            Expr::Local(l) => match self.resolve(&l.symbol) {
+                Some(slot) => {
+                    self.emit(loc, Op::Load(slot));
+                }
+                None => self.fail(loc, Error::UnknownLocalSymbol(l.symbol.clone())),
+            },
+            Expr::Global(g) => {
+                let global = self.compiler.global(&g.symbol);
+                self.emit(loc, Op::Global(global));
+            }
+            Expr::Seq(s) => {
+                self.expr(&s.expr);
+                self.emit(loc, Op::Pop);
+                self.expr(&s.then);
+            }
+            Expr::Conditional(c) => {
+                self.expr(&c.expr);
+                let otherwise = self.emit(loc, Op::JumpIfFalse(0));
+                self.expr(&c.then);
+                let end = self.emit(loc, Op::Jump(0));
+                self.patch(otherwise);
+                self.expr(&c.otherwise);
+                self.patch(end);
+            }
+            Expr::Unary(u) => {
+                self.expr(&u.expr);
+                let op = match u.op {
+                    UnaryOp::Not => Op::Not,
+                    UnaryOp::Minus if u.expr.get_type().is_decimal() => {
+                        Op::NegDec(self.tipo(expr.get_type()))
+                    }
+                    UnaryOp::Minus => Op::Neg(self.tipo(expr.get_type())),
+                };
+                self.emit(loc, op);
+            }
+            Expr::IntAdd(t) => self.int_op(expr, t, IntOp::Add),
+            Expr::IntSub(t) => self.int_op(expr, t, IntOp::Sub),
+            Expr::IntMul(t) => self.int_op(expr, t, IntOp::Mul),
+            Expr::IntDiv(t) => self.int_op(expr, t, IntOp::Div),
+            Expr::IntRem(t) => self.int_op(expr, t, IntOp::Rem),
+            Expr::IntPow(t) => self.int_op(expr, t, IntOp::Pow),
+            Expr::DecAdd(t) | Expr::DecSub(t) | Expr::DecMul(t) => {
+                self.exprs([&t.expr1, &t.expr2]);
+                let op = match &expr.it {
+                    Expr::DecAdd(_) => DecOp::Add,
+                    Expr::DecSub(_) => DecOp::Sub,
+                    _ => DecOp::Mul,
+                };
+                let tipo = self.tipo(expr.get_type());
+                self.emit(loc, Op::Dec(op, tipo));
+            }
+            Expr::StrConcat(t) => {
+                self.exprs([&t.expr1, &t.expr2]);
+                let tipo = self.tipo(expr.get_type());
+                self.emit(loc, Op::StrConcat(tipo));
+            }
+            Expr::StrLength(e) => {
+                self.expr(e);
+                let tipo = self.tipo(expr.get_type());
+                self.emit(loc, Op::StrLength(tipo));
+            }
+            Expr::Interpolation(i) => {
+                self.exprs(&i.parts);
+                let tipo = self.tipo(expr.get_type());
+                self.emit(loc, Op::Interpolation(i.parts.len(), tipo));
+            }
+            // The second expression is only evaluated if needed
+            Expr::LogicalAnd(t) => {
+                self.expr(&t.expr1);
+                let short = self.emit(loc, Op::JumpIfFalse(0));
+                self.expr(&t.expr2);
+                let end = self.emit(loc, Op::Jump(0));
+                self.patch(short);
+                self.constant(loc, Value::v_false());
+                self.patch(end);
+            }
+            Expr::LogicalOr(t) => {
+                self.expr(&t.expr1);
+                let other = self.emit(loc, Op::JumpIfFalse(0));
+                self.constant(loc, Value::v_true());
+                let end = self.emit(loc, Op::Jump(0));
+                self.patch(other);
+                self.expr(&t.expr2);
+                self.patch(end);
+            }
+            Expr::Eq(t) | Expr::NEq(t) => {
+                self.exprs([&t.expr1, &t.expr2]);
+                let op = match &expr.it {
+                    Expr::Eq(_) => Op::Eq,
+                    _ => Op::NEq,
+                };
+                self.emit(loc, op);
+            }
+            Expr::IntLT(t) => self.int_cmp(loc, t, Cmp::LT),
+            Expr::IntLE(t) => self.int_cmp(loc, t, Cmp::LE),
+            Expr::IntGT(t) => self.int_cmp(loc, t, Cmp::GT),
+            Expr::IntGE(t) => self.int_cmp(loc, t, Cmp::GE),
+            Expr::StrLT(t) => self.str_cmp(loc, t, Cmp::LT),
+            Expr::StrLE(t) => self.str_cmp(loc, t, Cmp::LE),
+            Expr::StrGT(t) => self.str_cmp(loc, t, Cmp::GT),
+            Expr::StrGE(t) => self.str_cmp(loc, t, Cmp::GE),
+            Expr::Block(b) => self.block(loc, b),
+            Expr::Lambda(l) => self.lambda(loc, l),
+            Expr::Call(c) => {
+                self.expr(&c.func);
+                self.exprs(&c.args);
+                self.emit(loc, Op::Call(c.args.len()));
+            }
+            Expr::Tuple(t) => {
+                self.exprs(&t.exprs);
+                let tipo = self.tipo(t.tipo.clone());
+                self.emit(loc, Op::Tuple(t.exprs.len(), tipo));
+            }
+            Expr::TupleField(f) => {
+                self.expr(&f.expr);
+                self.emit(loc, Op::TupleField(f.index));
+            }
+            Expr::Record(r) => {
+                self.exprs(&r.exprs);
+                let tipo = self.tipo(r.tipo.clone());
+                self.emit(loc, Op::Record(r.exprs.len(), tipo));
+            }
+            Expr::RecordField(f) => {
+                self.expr(&f.expr);
+                self.emit(loc, Op::RecordField(f.index));
+            }
+            Expr::Update(u) => {
+                self.expr(&u.expr);
+                self.exprs(u.fields.iter().map(|(_, e)| e));
+                let indices = u.fields.iter().map(|(i, _)| *i).collect();
+                self.function.updates.push(indices);
+                self.emit(loc, Op::Update(self.function.updates.len() - 1));
+            }
+            Expr::List(l) => {
+                self.exprs(&l.exprs);
+                let tipo = self.tipo(l.tipo.clone());
+                self.emit(loc, Op::List(l.exprs.len(), tipo));
+            }
+            Expr::Map(m) => {
+                self.exprs(m.entries.iter().flat_map(|(k, v)| [k, v]));
+                let tipo = self.tipo(m.tipo.clone());
+                self.emit(loc, Op::Map(m.entries.len(), tipo));
+            }
+            Expr::ListIndex(i) => {
+                self.exprs([&i.expr, &i.index]);
+                self.emit(loc, Op::ListIndex);
+            }
+            Expr::MapGet(i) => {
+                self.exprs([&i.expr, &i.index]);
+                self.emit(loc, Op::MapGet);
+            }
+            Expr::ListLength(e) | Expr::MapLength(e) => {
+                self.expr(e);
+                let tipo = self.tipo(expr.get_type());
+                self.emit(loc, Op::Length(tipo));
+            }
+            Expr::Match(m) => self.pattern_match(loc, m),
+            Expr::Ascription(a) => self.expr(&a.expr),
+        }
+    }
+
+    fn int_op(&mut self, expr: &L<Expr>, t: &TwoInts, op: IntOp) {
+        self.exprs([&t.expr1, &t.expr2]);
+        let tipo = self.tipo(expr.get_type());
+        self.emit(&expr.loc, Op::Int(op, tipo));
+    }
+
+    fn int_cmp(&mut self, loc: &Loc, t: &TwoInts, cmp: Cmp) {
+        self.exprs([&t.expr1, &t.expr2]);
+        self.emit(loc, Op::IntCmp(cmp));
+    }
+
+    fn str_cmp(&mut self, loc: &Loc, t: &TwoStrs, cmp: Cmp) {
+        self.exprs([&t.expr1, &t.expr2]);
+        self.emit(loc, Op::StrCmp(cmp));
+    }
+
+    // The value is the one of the expression, if any, or the one of the last assignment
+    fn block(&mut self, loc: &Loc, block: &Block) {
+        self.scopes.push(HashMap::default());
+        let mut last = None;
+        for a in &block.assignments {
+            self.expr(&a.it.expr);
+            let slot = self.bind(a.it.symbol.clone());
+            self.emit(&a.loc, Op::Store(slot));
+            last = Some(slot);
+        }
+        match (&block.expr, last) {
+            (Some(e), _) => self.expr(e),
+            (None, Some(slot)) => {
+                self.emit(loc, Op::Load(slot));
+            }
+            (None, None) => self.constant(loc, Value::v_none()),
+        }
+        self.scopes.pop();
+    }
+
+    // The captured values are pushed in the order of the captures of the function
+    fn lambda(&mut self, loc: &Loc, lambda: &Arc<Lambda>) {
+        let function = self.compiler.function(lambda);
+        for (symbol, _) in &function.captures {
+            match self.resolve(symbol) {
+                Some(slot) => {
+                    self.emit(loc, Op::Load(slot));
+                }
+                None => self.fail(loc, Error::UnknownLocalSymbol(symbol.clone())),
+            }
+        }
+        self.function.closures.push((lambda.clone(), function));
+        self.emit(loc, Op::Closure(self.function.closures.len() - 1));
+    }
+
+    // The value is kept on the stack while the patterns are tried
+    fn pattern_match(&mut self, loc: &Loc, m: &Match) {
+        self.expr(&m.expr);
+        let mut ends = Vec::with_capacity(m.arms.len());
+        for arm in &m.arms {
+            let mut symbols = Vec::default();
+            bindings(&arm.pattern, &mut symbols);
+            self.scopes.push(HashMap::default());
+            let first = self.function.slots;
+            for symbol in symbols {
+                self.bind(symbol);
+            }
+            self.function.arms.push((arm.pattern.clone(), first));
+            let next = self.emit(loc, Op::Match(self.function.arms.len() - 1, 0));
+            self.emit(loc, Op::Pop);
+            self.expr(&arm.expr);
+            ends.push(self.emit(loc, Op::Jump(0)));
+            self.patch(next);
+            self.scopes.pop();
+        }
+        self.fail(loc, Error::NonExhaustiveMatch);
+        for end in ends {
+            self.patch(end);
+        }
+    }
+}
+
+// Symbols bound by a pattern, in the order they are bound when matching
+fn bindings(pattern: &Pattern, symbols: &mut Vec<Symbol>) {
+    match pattern {
+        Pattern::Any | Pattern::Value(_) => (),
+        Pattern::Binding(symbol) => symbols.push(symbol.clone()),
+        Pattern::Tuple(patterns) | Pattern::Constructor(_, patterns) => {
+            for p in patterns {
+                bindings(p, symbols);
+            }
+        }
+    }
+}
@@ -0,0 +1,301 @@
+mod compiler;
+
+use std::rc::Rc;
+
+use im::HashMap;
+
+use crate::context::Value;
+use crate::error::{Error, Loc, Result};
+use crate::tst::Package;
+
+use super::limits::Budget;
+use super::{Env, matches};
+use compiler::{Compiler, Function, Op};
+
+// Compiles the package and runs it. The compiled code only lives for the run, closures keep
+// the lambdas they are created from, as with the interpreter.
+pub(super) fn run(env: &mut Env, package: &Package) -> Result<Value> {
+    let mut compiler = Compiler::default();
+    let main = compiler.package(package);
+    let budget = Budget::new(env.limits(), env.cancel.clone());
+    Machine {
+        env,
+        compiler,
+        budget,
+        stack: Vec::default(),
+        globals: Vec::default(),
+    }
+    .execute(main)
+}
+
+// Function being executed, whose slots start at the base of the stack
+struct Frame {
+    function: Rc<Function>,
+    pc: usize,
+    base: usize,
+}
+
+struct Machine<'a> {
+    env: &'a mut Env,
+    compiler: Compiler,
+    budget: Budget,
+    stack: Vec<Value>,
+    globals: Vec<Option<Value>>, // Values of the global symbols, once looked up
+}
+
+impl Machine<'_> {
+    // Calls push a frame instead of recursing, so the depth is only bounded by the limits
+    fn execute(&mut self, main: Rc<Function>) -> Result<Value> {
+        let mut frames: Vec<Frame> = Vec::default();
+        let mut frame = Frame {
+            function: main,
+            pc: 0,
+            base: 0,
+        };
+        self.stack.resize(frame.function.slots, Value::v_none());
+        loop {
+            let function = &frame.function;
+            let loc = &function.locs[frame.pc];
+            self.budget.step(loc, function.costs[frame.pc])?;
+            let op = function.ops[frame.pc];
+            frame.pc += 1;
+            match op {
+                Op::Const(i) => self.stack.push(function.constants[i].clone()),
+                Op::Load(slot) => self.stack.push(self.stack[frame.base + slot].clone()),
+                Op::Store(slot) => self.stack[frame.base + slot] = self.pop(),
+                Op::Global(i) => {
+                    let value = self.global(loc, i)?;
+                    self.stack.push(value);
+                }
+                Op::SetGlobal(i) => {
+                    let value = self.pop();
+                    let symbol = self.compiler.globals[i].clone();
+                    self.env.values.set(loc, symbol, value)?;
+                }
+                Op::Pop => {
+                    self.pop();
+                }
+                Op::Jump(target) => frame.pc = target,
+                Op::JumpIfFalse(target) => {
+                    if !self.pop().as_boolean(loc)? {
+                        frame.pc = target;
+                    }
+                }
+                Op::Closure(i) => {
+                    let (lambda, closure) = &function.closures[i];
+                    let values = self.pop_n(closure.captures.len());
+                    let symbols = closure.captures.iter().map(|(s, _)| s.clone());
+                    self.budget.allocate(loc, size_of::<Value>())?;
+                    let captured = symbols.zip(values).collect();
+                    self.stack.push(Value::v_closure(lambda.clone(), captured));
+                }
+                Op::Call(args) => {
+                    if let Some(callee) = self.call(loc, args)? {
+                        frames.push(std::mem::replace(&mut frame, callee));
+                    }
+                }
+                Op::Return => {
+                    let value = self.pop();
+                    match frames.pop() {
+                        None => return Ok(value),
+                        Some(caller) => {
+                            // The function is below its arguments
+                            self.stack.truncate(frame.base - 1);
+                            self.stack.push(value);
+                            self.budget.exit();
+                            frame = caller;
+                        }
+                    }
+                }
+                Op::Fail(i) => return loc.err(function.errors[i].clone()),
+                Op::Match(arm, otherwise) => {
+                    let (pattern, first) = &function.arms[arm];
+                    let value = self.stack.last().expect("The value is pushed before");
+                    let mut bound = Vec::default();
+                    if matches(pattern, value, &mut |_, v| bound.push(v.clone())) {
+                        for (i, value) in bound.into_iter().enumerate() {
+                            self.stack[frame.base + first + i] = value;
+                        }
+                    } else {
+                        frame.pc = otherwise;
+                    }
+                }
+                op => {
+                    let value = self.operation(function, loc, op)?;
+                    self.stack.push(value);
+                }
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack
+            .pop()
+            .expect("Values are pushed before being popped")
+    }
+
+    fn pop_n(&mut self, n: usize) -> Vec<Value> {
+        self.stack.split_off(self.stack.len() - n)
+    }
+
+    fn global(&mut self, loc: &Loc, index: usize) -> Result<Value> {
+        if let Some(Some(value)) = self.globals.get(index) {
+            return Ok(value.clone());
+        }
+        let symbol = &self.compiler.globals[index];
+        let Some(value) = self.env.values.get(symbol) else {
+            return loc.err(Error::UnknownSymbol(symbol.clone()));
+        };
+        if self.globals.len() <= index {
+            self.globals.resize(index + 1, None);
+        }
+        self.globals[index] = Some(value.clone());
+        Ok(value)
+    }
+
+    // Builtins and natives are called right away, returning None. For closures, returns the
+    // frame of the call, whose slots start with the arguments and the captured values.
+    fn call(&mut self, loc: &Loc, args: usize) -> Result<Option<Frame>> {
+        let base = self.stack.len() - args;
+        let func = self.stack[base - 1].clone();
+        if let Some(builtin) = func.as_builtin() {
+            let args = self.pop_n(args);
+            self.pop();
+            let value = self.env.builtin(loc, builtin, args)?;
+            self.stack.push(value);
+            return Ok(None);
+        }
+        if let Some(native) = func.as_native() {
+            let args = self.pop_n(args);
+            self.pop();
+            let value = (native.function)(args).or_else(|e| loc.err(e))?;
+            self.stack.push(value);
+            return Ok(None);
+        }
+        let closure = func.as_closure(loc)?;
+        let function = self.compiler.function(&closure.lambda);
+        self.budget.enter(loc)?;
+        self.stack.resize(base + function.slots, Value::v_none());
+        for (symbol, slot) in &function.captures {
+            match closure.captured.get(symbol) {
+                Some(value) => self.stack[base + slot] = value.clone(),
+                None => return loc.err(Error::UnknownLocalSymbol(symbol.clone())),
+            }
+        }
+        Ok(Some(Frame {
+            function,
+            pc: 0,
+            base,
+        }))
+    }
+
+    // Operations on the values on top of the stack, which are replaced by the result
+    fn operation(&mut self, function: &Function, loc: &Loc, op: Op) -> Result<Value> {
+        let types = &function.types;
+        match op {
+            Op::Not => Ok(Value::v_bool(!self.pop().as_boolean(loc)?)),
+            Op::Neg(t) => Value::v_integer(loc, types[t].clone(), -self.pop().as_integer(loc)?),
+            Op::NegDec(t) => Value::v_decimal(loc, types[t].clone(), -self.pop().as_decimal(loc)?),
+            Op::Int(op, t) => {
+                let v2 = self.pop().as_integer(loc)?;
+                let v1 = self.pop().as_integer(loc)?;
+                match op.apply(v1, v2) {
+                    Ok(value) => Value::v_integer(loc, types[t].clone(), value),
+                    Err(error) => loc.err(error),
+                }
+            }
+            Op::IntCmp(cmp) => {
+                let v2 = self.pop().as_integer(loc)?;
+                let v1 = self.pop().as_integer(loc)?;
+                Ok(Value::v_bool(cmp.apply(&v1, &v2)))
+            }
+            Op::Dec(op, t) => {
+                let v2 = self.pop().as_decimal(loc)?;
+                let v1 = self.pop().as_decimal(loc)?;
+                match op.apply(v1, v2) {
+                    Ok(value) => Value::v_decimal(loc, types[t].clone(), value),
+                    Err(error) => loc.err(error),
+                }
+            }
+            Op::StrConcat(t) => {
+                let v2 = self.pop().as_string(loc)?;
+                let v1 = self.pop().as_string(loc)?;
+                self.budget.allocate(loc, v1.len() + v2.len())?;
+                Value::v_string(loc, types[t].clone(), format!("{v1}{v2}").into())
+            }
+            // Strings are compared by their chars
+            Op::StrCmp(cmp) => {
+                let v2 = self.pop().as_string(loc)?;
+                let v1 = self.pop().as_string(loc)?;
+                Ok(Value::v_bool(cmp.apply(v1.as_str(), v2.as_str())))
+            }
+            // The length is the number of chars
+            Op::StrLength(t) => {
+                let length = self.pop().as_string(loc)?.chars().count() as i64;
+                Value::v_integer(loc, types[t].clone(), length.into())
+            }
+            Op::Interpolation(parts, t) => {
+                let text: String = self.pop_n(parts).iter().map(Value::text).collect();
+                self.budget.allocate(loc, text.len())?;
+                Value::v_string(loc, types[t].clone(), text.into())
+            }
+            Op::Eq | Op::NEq => {
+                let v2 = self.pop();
+                let v1 = self.pop();
+                Ok(Value::v_bool((v1 == v2) == matches!(op, Op::Eq)))
+            }
+            Op::Tuple(n, t) => {
+                self.allocate_values(loc, n)?;
+                let values = self.pop_n(n);
+                Value::v_tuple(loc, types[t].clone(), values)
+            }
+            Op::Record(n, t) => {
+                self.allocate_values(loc, n)?;
+                let values = self.pop_n(n);
+                Value::v_record(loc, types[t].clone(), values)
+            }
+            Op::TupleField(index) => self.pop().tuple_field(loc, index),
+            Op::RecordField(index) => self.pop().record_field(loc, index),
+            Op::Update(i) => {
+                let indices = &function.updates[i];
+                let values = self.pop_n(indices.len());
+                let record = self.pop();
+                self.allocate_values(loc, record.fields().map_or(0, |f| f.len()))?;
+                record.update(loc, indices.iter().copied().zip(values).collect())
+            }
+            Op::List(n, t) => {
+                self.allocate_values(loc, n)?;
+                let values = self.pop_n(n).into_iter().collect();
+                Value::v_list(loc, types[t].clone(), values)
+            }
+            // Later entries replace earlier ones with the same key
+            Op::Map(n, t) => {
+                self.allocate_values(loc, 2 * n)?;
+                let mut entries = HashMap::new();
+                let mut values = self.pop_n(2 * n).into_iter();
+                while let (Some(key), Some(value)) = (values.next(), values.next()) {
+                    entries.insert(key, value);
+                }
+                Value::v_map(loc, types[t].clone(), entries)
+            }
+            Op::ListIndex => {
+                let index = self.pop().as_integer(loc)?;
+                self.pop().list_get(loc, index)
+            }
+            Op::MapGet => {
+                let key = self.pop();
+                self.pop().map_get(loc, &key)
+            }
+            Op::Length(t) => {
+                let length = self.pop().length(loc)?;
+                Value::v_integer(loc, types[t].clone(), (length as i64).into())
+            }
+            op => unreachable!("{op:?} is executed by the machine"),
+        }
+    }
+
+    // Values are accounted by their size, not including what they point to
+    fn allocate_values(&self, loc: &Loc, count: usize) -> Result<()> {
+        self.budget.allocate(loc, count * size_of::<Value>())
+    }
+}
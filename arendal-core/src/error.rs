@@ -1,6 +1,8 @@
 use crate::{
+    Integer,
     ast0::{Q, parser::Enclosure},
-    context::Type,
+    context::{Type, Value},
+    effect::Effect,
     keyword::Keyword,
     symbol::{FQPath, FQSym, FQType, Pkg, Symbol, TSymbol},
     visibility::{V, Visibility},
@@ -9,6 +11,7 @@ use crate::{
 use super::ArcStr;
 use std::{collections::HashSet, fmt, hash::Hash, sync::Arc};
 
+#[allow(dead_code)]
 #[derive(Debug)]
 pub struct Input {
     input: ArcStr,
@@ -128,19 +131,6 @@ impl ErrorVec {
         self.errors.iter().for_each(|e| f(&e.it))
     }
 
-    fn collect_vec<T, F>(&self, f: F) -> Vec<T>
-    where
-        F: Fn(&Error) -> Option<T>,
-    {
-        let mut result = Vec::default();
-        self.for_each_error(|e| {
-            if let Some(t) = f(e) {
-                result.push(t)
-            }
-        });
-        result
-    }
-
     fn collect_set<T: Eq + Hash, F>(&self, f: F) -> HashSet<T>
     where
         F: Fn(&Error) -> Option<T>,
@@ -154,16 +144,6 @@ impl ErrorVec {
         result
     }
 
-    pub(crate) fn missing_symbol_deps(&self) -> HashSet<FQSym> {
-        self.collect_set(|e| {
-            if let Error::MissingSymbolDependency(s) = e {
-                Some(s.clone())
-            } else {
-                None
-            }
-        })
-    }
-
     pub(crate) fn missing_local_symbol_deps(&self) -> HashSet<Symbol> {
         self.collect_set(|e| {
             if let Error::MissingLocalSymbolDependency(s) = e {
@@ -246,9 +226,11 @@ impl Errors {
 pub enum Error {
     // Tokenizer
     UnexpectedChar(char),
+    UnterminatedString,
     // Lexer
     InvalidClose(Enclosure),
     UnexpectedToken,
+    InvalidEscape(char),
     // Parser
     EmptyPackage,
     DuplicateModule(FQPath),
@@ -261,6 +243,18 @@ pub enum Error {
     KeywordExpected(Keyword),
     CloseExpected(Enclosure),
     TSymbolAfterTypeExpected,
+    OpenExpected(Enclosure),
+    ParameterExpected,
+    TypeParameterExpected,
+    TypeAnnotationExpected,
+    TypeExpected,
+    ArrowExpected,
+    TupleIndexExpected,
+    FieldExpected,
+    ColonExpected,
+    VariantExpected,
+    PatternExpected,
+    EffectExpected,
     ParsingError, // placeholder, temporary error
     // Symbols & type checking
     SymbolEmpty,
@@ -282,18 +276,51 @@ pub enum Error {
     DuplicateLocalSymbol(Symbol),
     UnableToResolveSymbol(Q<Symbol>),
     UnableToResolveType(Q<TSymbol>),
+    UnknownEffect(TSymbol),
     TLExpressionInNonRootModule,
     SymbolNotVisible(FQSym),
     TypeNotVisible(FQType),
     OnlyOneExpressionAllowed,
-    MissingSymbolDependency(FQSym),       // internal
+    DuplicateDependency(Symbol),
+    NotExportable(FQSym),
+    NotExportableType(FQType),            // it uses types of dependencies
+    CyclicDependency(Vec<FQSym>), // path of the cycle, starting and ending at the same symbol
+    ResultTypeExpected(FQSym),    // recursive functions must declare their result type
+    MissingSymbolDependency(FQSym), // internal
     MissingLocalSymbolDependency(Symbol), // internal
     // Type checking and runtime
     TypeMismatch(Arc<TypeMismatch>),
     IntegerExpected(Type),
+    FunctionExpected(Type),
+    EffectNotAllowed(Effect), // e.g., IO in the initializer of a top-level let
+    ArgumentCountMismatch(usize, usize), // expected, actual
+    TupleExpected(Type),
+    TupleIndexOutOfBounds(Type, usize),
+    RecordExpected(Type),
+    DuplicateField(Symbol),
+    UnknownField(Type, Symbol),
+    MissingField(Type, Symbol),
     SingletonExpected(Type),
+    EmptyCollection, // the element type can't be inferred
+    CollectionExpected(Type),
+    TypeArgumentCountMismatch(usize, usize), // expected, actual
+    IndexOutOfBounds(Integer),
+    KeyNotFound(Value),
+    NonExhaustiveMatch,
+    UnreachableArm,
     InvalidType, // placeholder, temporary error
     DivisionByZero,
+    NegativeExponent,
+    IntegerOverflow,
+    DecimalOverflow,
+    InvalidScale(Integer),   // of a decimal division
+    ImplicitDecimalDivision, // decimals are divided with div, given the scale and rounding
+    HostError(ArcStr),       // returned by a native function
+    // Limits
+    FuelExhausted(u64),
+    MaxDepthExceeded(usize),
+    MemoryLimitExceeded(usize),
+    Cancelled,
     NotImplemented,
 }
 
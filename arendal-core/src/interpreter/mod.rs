@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+
+use ast::{
+    position::Position,
+    problem::{self, ErrorType},
+    symbol::FQSym,
+};
+use num::{Decimal, Integer, RoundingMode};
+
+use crate::{
+    ArcStr, GlobalScope,
+    typechecked::{
+        Assignment, DecDiv, Expr, Expression, TwoBools, TwoDecs, TwoInts, TwoStrs, TwoValues,
+    },
+    types::Value,
+};
+
+type Result<T> = std::result::Result<T, problem::Error>;
+
+pub(crate) struct Interpreter<'a> {
+    global: &'a GlobalScope,
+    values: HashMap<FQSym, Value>, // Values of the symbols evaluated from the current input
+}
+
+impl<'a> Interpreter<'a> {
+    pub(crate) fn new(global: &'a GlobalScope) -> Self {
+        Interpreter {
+            global,
+            values: HashMap::default(),
+        }
+    }
+
+    // Assignments must be provided in dependency order
+    pub(crate) fn assignment(&mut self, assignment: &Assignment) -> Result<Value> {
+        let value = self.expression(&assignment.expr)?;
+        self.values.insert(assignment.symbol.clone(), value.clone());
+        Ok(value)
+    }
+
+    pub(crate) fn expression(&self, expression: &Expression) -> Result<Value> {
+        match &expression.expr {
+            Expr::Value(v) => Ok(v.clone()),
+            Expr::Global(symbol) => self.global(&expression.position, symbol),
+            Expr::Seq(s) => {
+                self.expression(&s.expr)?;
+                self.expression(&s.then)
+            }
+            Expr::Conditional(c) => {
+                if self.boolean(&c.expr)? {
+                    self.expression(&c.then)
+                } else {
+                    self.expression(&c.otherwise)
+                }
+            }
+            Expr::IntNeg(e) => Ok(Value::Integer(-self.integer(e)?)),
+            Expr::DecNeg(e) => Ok(Value::Decimal(-self.decimal(e)?)),
+            Expr::LogicalNot(e) => Ok(Value::boolean(!self.boolean(e)?)),
+            Expr::IntAdd(t) => self.int_op(expression, t, |i1, i2| overflow(i1.checked_add(i2))),
+            Expr::IntSub(t) => self.int_op(expression, t, |i1, i2| overflow(i1.checked_sub(i2))),
+            Expr::IntMul(t) => self.int_op(expression, t, |i1, i2| overflow(i1.checked_mul(i2))),
+            Expr::IntDiv(t) => self.int_op(expression, t, |i1, i2| {
+                i1.checked_div(i2).ok_or(Error::DivisionByZero)
+            }),
+            Expr::IntRem(t) => self.int_op(expression, t, |i1, i2| {
+                i1.checked_rem(i2).ok_or(Error::DivisionByZero)
+            }),
+            Expr::IntPow(t) => self.int_op(expression, t, |i1, i2| {
+                if i2.is_negative() {
+                    Err(Error::NegativeExponent)
+                } else {
+                    overflow(i1.checked_pow(i2))
+                }
+            }),
+            Expr::DecAdd(t) => self.dec_op(expression, t, Decimal::checked_add),
+            Expr::DecSub(t) => self.dec_op(expression, t, Decimal::checked_sub),
+            Expr::DecMul(t) => self.dec_op(expression, t, Decimal::checked_mul),
+            Expr::DecDiv(d) => self.dec_div(expression, d),
+            Expr::LogicalAnd(t) => self.and(t),
+            Expr::LogicalOr(t) => self.or(t),
+            Expr::Eq(t) => self.two_values(t).map(|(v1, v2)| Value::boolean(v1 == v2)),
+            Expr::NEq(t) => self.two_values(t).map(|(v1, v2)| Value::boolean(v1 != v2)),
+            Expr::IntLT(t) => self.two_ints(t).map(|(i1, i2)| Value::boolean(i1 < i2)),
+            Expr::IntLE(t) => self.two_ints(t).map(|(i1, i2)| Value::boolean(i1 <= i2)),
+            Expr::IntGT(t) => self.two_ints(t).map(|(i1, i2)| Value::boolean(i1 > i2)),
+            Expr::IntGE(t) => self.two_ints(t).map(|(i1, i2)| Value::boolean(i1 >= i2)),
+            Expr::StrConcat(t) => self
+                .two_strs(t)
+                .map(|(s1, s2)| Value::String(format!("{s1}{s2}").into())),
+            Expr::StrLT(t) => self.two_strs(t).map(|(s1, s2)| Value::boolean(s1 < s2)),
+            Expr::StrLE(t) => self.two_strs(t).map(|(s1, s2)| Value::boolean(s1 <= s2)),
+            Expr::StrGT(t) => self.two_strs(t).map(|(s1, s2)| Value::boolean(s1 > s2)),
+            Expr::StrGE(t) => self.two_strs(t).map(|(s1, s2)| Value::boolean(s1 >= s2)),
+            Expr::Interpolation(parts) => self.interpolation(parts),
+        }
+    }
+
+    fn global(&self, position: &Position, symbol: &FQSym) -> Result<Value> {
+        match self.values.get(symbol) {
+            Some(value) => Ok(value.clone()),
+            None => match self.global.symbols.get(symbol) {
+                Some(dfn) => Ok(dfn.value().clone()),
+                None => Err(Error::UnknownSymbol(symbol.clone()).at(position.clone())),
+            },
+        }
+    }
+
+    fn integer(&self, expression: &Expression) -> Result<Integer> {
+        match self.expression(expression)? {
+            Value::Integer(i) => Ok(i),
+            _ => Err(Error::IntegerExpected.at(expression.position.clone())),
+        }
+    }
+
+    fn decimal(&self, expression: &Expression) -> Result<Decimal> {
+        match self.expression(expression)? {
+            Value::Decimal(d) => Ok(d),
+            _ => Err(Error::DecimalExpected.at(expression.position.clone())),
+        }
+    }
+
+    fn string(&self, expression: &Expression) -> Result<ArcStr> {
+        match self.expression(expression)? {
+            Value::String(s) => Ok(s),
+            _ => Err(Error::StringExpected.at(expression.position.clone())),
+        }
+    }
+
+    fn boolean(&self, expression: &Expression) -> Result<bool> {
+        match self.expression(expression)? {
+            Value::True => Ok(true),
+            Value::False => Ok(false),
+            _ => Err(Error::BooleanExpected.at(expression.position.clone())),
+        }
+    }
+
+    fn rounding(&self, expression: &Expression) -> Result<RoundingMode> {
+        match self.expression(expression)? {
+            Value::Rounding(mode) => Ok(mode),
+            _ => Err(Error::RoundingExpected.at(expression.position.clone())),
+        }
+    }
+
+    fn two_ints(&self, t: &TwoInts) -> Result<(Integer, Integer)> {
+        Ok((self.integer(&t.expr1)?, self.integer(&t.expr2)?))
+    }
+
+    // Integer operations report their errors at the position of the expression
+    fn int_op<F>(&self, expression: &Expression, t: &TwoInts, f: F) -> Result<Value>
+    where
+        F: FnOnce(Integer, Integer) -> std::result::Result<Integer, Error>,
+    {
+        let (i1, i2) = self.two_ints(t)?;
+        f(i1, i2)
+            .map(Value::Integer)
+            .map_err(|e| e.at(expression.position.clone()))
+    }
+
+    fn dec_op<F>(&self, expression: &Expression, t: &TwoDecs, f: F) -> Result<Value>
+    where
+        F: FnOnce(Decimal, Decimal) -> Option<Decimal>,
+    {
+        let (d1, d2) = self.two_decs(t)?;
+        f(d1, d2)
+            .map(Value::Decimal)
+            .ok_or_else(|| Error::DecimalOverflow.at(expression.position.clone()))
+    }
+
+    fn dec_div(&self, expression: &Expression, d: &DecDiv) -> Result<Value> {
+        let (d1, d2) = (self.decimal(&d.expr1)?, self.decimal(&d.expr2)?);
+        let scale = self.integer(&d.scale)?;
+        let rounding = self.rounding(&d.rounding)?;
+        let error = |e: Error| Err(e.at(expression.position.clone()));
+        if d2.is_zero() {
+            return error(Error::DivisionByZero);
+        }
+        if scale.is_negative() {
+            return error(Error::InvalidScale(scale));
+        }
+        // Scales that don't fit are too large for any result
+        let value = scale
+            .to_usize()
+            .and_then(|s| u32::try_from(s).ok())
+            .and_then(|s| d1.div(&d2, s, rounding));
+        match value {
+            Some(value) => Ok(Value::Decimal(value)),
+            None => error(Error::DecimalOverflow),
+        }
+    }
+
+    fn two_decs(&self, t: &TwoDecs) -> Result<(Decimal, Decimal)> {
+        Ok((self.decimal(&t.expr1)?, self.decimal(&t.expr2)?))
+    }
+
+    fn two_strs(&self, t: &TwoStrs) -> Result<(ArcStr, ArcStr)> {
+        Ok((self.string(&t.expr1)?, self.string(&t.expr2)?))
+    }
+
+    // Strings are included verbatim, other values use their display form
+    fn interpolation(&self, parts: &[Expression]) -> Result<Value> {
+        let mut result = String::new();
+        for part in parts {
+            match self.expression(part)? {
+                Value::String(s) => result.push_str(&s),
+                value => result.push_str(&value.to_string()),
+            }
+        }
+        Ok(Value::String(result.into()))
+    }
+
+    fn two_values(&self, t: &TwoValues) -> Result<(Value, Value)> {
+        Ok((self.expression(&t.expr1)?, self.expression(&t.expr2)?))
+    }
+
+    fn and(&self, t: &TwoBools) -> Result<Value> {
+        Ok(Value::boolean(
+            self.boolean(&t.expr1)? && self.boolean(&t.expr2)?,
+        ))
+    }
+
+    fn or(&self, t: &TwoBools) -> Result<Value> {
+        Ok(Value::boolean(
+            self.boolean(&t.expr1)? || self.boolean(&t.expr2)?,
+        ))
+    }
+}
+
+fn overflow(value: Option<Integer>) -> std::result::Result<Integer, Error> {
+    value.ok_or(Error::IntegerOverflow)
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Error {
+    UnknownSymbol(FQSym),
+    IntegerExpected,
+    DecimalExpected,
+    StringExpected,
+    BooleanExpected,
+    RoundingExpected,
+    DivisionByZero,
+    InvalidScale(Integer),
+    NegativeExponent,
+    IntegerOverflow,
+    DecimalOverflow,
+}
+
+impl ErrorType for Error {}
@@ -10,6 +10,9 @@ pub enum Keyword {
     Then,
     Else,
     Type,
+    Fn,
+    With,
+    Match,
 }
 
 static KEYWORDS: phf::Map<&'static str, Keyword> = phf_map! {
@@ -20,6 +23,9 @@ static KEYWORDS: phf::Map<&'static str, Keyword> = phf_map! {
     "then" => Keyword::Then,
     "else" => Keyword::Else,
     "type" => Keyword::Type,
+    "fn" => Keyword::Fn,
+    "with" => Keyword::With,
+    "match" => Keyword::Match,
 };
 
 impl Keyword {
@@ -36,6 +42,9 @@ impl Keyword {
             Self::Then => "then",
             Self::Else => "else",
             Self::Type => "type",
+            Self::Fn => "fn",
+            Self::With => "with",
+            Self::Match => "match",
         }
     }
 }
@@ -1,29 +1,44 @@
+// The derived conversions refer to this crate by name
+extern crate self as arendal_core;
+
 pub mod ast0;
 pub mod context;
+pub mod effect;
 pub mod env0;
 pub mod error;
 pub mod id;
 pub mod keyword;
+pub mod loader;
+pub mod store;
 pub mod symbol;
 pub mod tst;
 pub mod visibility;
 
+use std::collections::HashMap;
+
 pub use arcstr::{ArcStr, Substr, literal};
 use ast::{
     AST,
-    problem::Output,
-    symbol::{FQPath, Lib, ModulePath},
+    position::Position,
+    problem::{ErrorType, Output},
+    symbol::{FQPath, FQSym, FQType, Lib, ModulePath, Symbol, TSymbol},
 };
-pub use num::Integer;
+pub use num::{Decimal, Integer, RoundingMode};
 
+pub use crate::types::{TypedValue, Value};
 use crate::{
-    symbols::Symbols,
+    id::Id,
+    interpreter::Interpreter,
+    store::{Literal, Name, SymbolEntry, TypeRef},
+    symbols::{SymbolDfn, Symbols},
     typechecked::TypeChecked,
-    types::{TypedValue, Types},
+    types::{Type, TypeExpr, Types},
+    visibility::Visibility,
 };
 
 use parser::parse;
 
+mod interpreter;
 mod resolved;
 mod resolver;
 mod symbols;
@@ -31,29 +46,147 @@ mod typechecked;
 mod typechecker;
 mod types;
 
+#[cfg(test)]
+mod tests;
+
 pub(crate) struct GlobalScope {
     symbols: Symbols,
     types: Types,
+    dependencies: HashMap<Symbol, Id>,
 }
 
 impl GlobalScope {
-    fn empty() -> Self {
+    fn prelude() -> Self {
         Self {
             symbols: Symbols::default(),
-            types: Types::default(),
+            types: Types::prelude(),
+            dependencies: HashMap::default(),
         }
     }
+
+    // Only the exported types and symbols of the package are added, as nothing else can be
+    // resolved. Singletons are the only user types of this pipeline, so other types are skipped.
+    fn add_dependency(&mut self, alias: &str, id: Id, package: &store::Package) -> Output<()> {
+        let Ok(alias) = Symbol::new(alias) else {
+            return DependencyError::InvalidAlias(alias.to_string())
+                .at(Position::NoPosition)
+                .into();
+        };
+        if self.dependencies.contains_key(&alias) || self.dependencies.values().any(|i| *i == id) {
+            return DependencyError::DuplicateDependency(alias)
+                .at(Position::NoPosition)
+                .into();
+        }
+        let lib = Lib::External(id.clone());
+        let mut output = Output::ok(());
+        let mut types = HashMap::default();
+        for t in &package.types {
+            if t.dfn == store::TypeDfn::Singleton {
+                match external_type(&lib, &t.name) {
+                    Ok(symbol) => {
+                        types.insert(t.name.clone(), (t.visibility, symbol));
+                    }
+                    Err(e) => output.add_error(e.at(Position::NoPosition)),
+                }
+            }
+        }
+        let mut dfns = Vec::with_capacity(package.symbols.len());
+        for s in &package.symbols {
+            if s.visibility == Visibility::Exported {
+                match self.external_symbol(&lib, &types, s) {
+                    Ok(dfn) => dfns.push(dfn),
+                    Err(e) => output.add_error(e.at(Position::NoPosition)),
+                }
+            }
+        }
+        if output.has_errors() {
+            return output;
+        }
+        for (visibility, symbol) in types.into_values() {
+            if visibility == Visibility::Exported {
+                self.types.add(symbol.clone(), Type::Singleton(symbol));
+            }
+        }
+        for (symbol, dfn) in dfns {
+            if let Err(problems) = self.symbols.set(symbol, dfn) {
+                output.add_problems(problems);
+            }
+        }
+        self.dependencies.insert(alias, id);
+        output
+    }
+
+    // Symbols can have the singleton types of the package, even if they are not exported
+    fn external_symbol(
+        &self,
+        lib: &Lib,
+        types: &HashMap<Name, (Visibility, FQType)>,
+        s: &SymbolEntry,
+    ) -> Result<(FQSym, SymbolDfn), DependencyError> {
+        let path = external_path(lib, &s.name)?;
+        let symbol =
+            Symbol::new(&s.name.name).map_err(|_| DependencyError::InvalidName(s.name.clone()))?;
+        let unsupported = || DependencyError::UnsupportedType(s.name.clone());
+        let type_expr = match &s.tipo {
+            TypeRef::Std(t) => TSymbol::new(t)
+                .ok()
+                .and_then(|t| self.types.get(&FQPath::std_empty().fq_type(t)))
+                .map(|dfn| dfn.type_expr().clone())
+                .ok_or_else(unsupported)?,
+            TypeRef::Local(name) => types
+                .get(name)
+                .map(|(_, t)| TypeExpr::Type(Type::Singleton(t.clone())))
+                .ok_or_else(unsupported)?,
+        };
+        let value = match (&s.value, type_expr.get_type()) {
+            (Literal::Integer(v), Type::Integer) => Value::Integer(v.clone()),
+            (Literal::Decimal(v), Type::Decimal) => Value::Decimal(v.clone()),
+            (Literal::String(v), Type::String) => Value::String(v.clone()),
+            (Literal::Singleton, t) => t.singleton().ok_or_else(unsupported)?,
+            _ => return Err(unsupported()),
+        };
+        Ok((
+            path.fq_sym(symbol),
+            SymbolDfn::new(Position::NoPosition, type_expr, value),
+        ))
+    }
+}
+
+fn external_path(lib: &Lib, name: &Name) -> Result<FQPath, DependencyError> {
+    let path = name
+        .path
+        .iter()
+        .map(|p| Symbol::new(p))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| DependencyError::InvalidName(name.clone()))?;
+    Ok(lib.path(ModulePath::new(path)))
+}
+
+fn external_type(lib: &Lib, name: &Name) -> Result<FQType, DependencyError> {
+    let symbol =
+        TSymbol::new(&name.name).map_err(|_| DependencyError::InvalidName(name.clone()))?;
+    Ok(external_path(lib, name)?.fq_type(symbol))
 }
 
+#[derive(Debug, PartialEq)]
+enum DependencyError {
+    InvalidAlias(String),
+    DuplicateDependency(Symbol),
+    InvalidName(Name),
+    UnsupportedType(Name),
+}
+
+impl ErrorType for DependencyError {}
+
 pub(crate) struct Env {
     global: GlobalScope,
 }
 
 impl Env {
     fn new() -> Self {
-        let global = GlobalScope::empty();
-        // TODO: add prelude
-        Env { global }
+        Env {
+            global: GlobalScope::prelude(),
+        }
     }
 
     fn resolve(&self, path: FQPath, ast: &AST) -> Output<resolved::Resolved> {
@@ -61,7 +194,7 @@ impl Env {
     }
 
     fn typecheck(&self, resolved: resolved::Resolved) -> Output<typechecked::TypeChecked> {
-        typechecker::typecheck(resolved)
+        typechecker::typecheck(&self.global, resolved)
     }
 
     fn add_input(&mut self, path: FQPath, input: &str) -> Output<Option<typechecked::Expression>> {
@@ -76,17 +209,44 @@ impl Env {
         &mut self,
         resolved: resolved::Resolved,
     ) -> Output<Option<typechecked::Expression>> {
-        self.typecheck(resolved)
-            .and_then(|c| self.add_typechecked(c));
-        panic!("TODO")
+        let typechecked = self.typecheck(resolved);
+        if typechecked.has_errors() {
+            return typechecked.map(|_| None);
+        }
+        typechecked.and_then(|c| self.add_typechecked(c))
     }
 
+    // Evaluates the assignments, adding them to the global scope only if all of them succeed
     fn add_typechecked(
         &mut self,
         typechecked: TypeChecked,
     ) -> Output<Option<typechecked::Expression>> {
-        // TODO
-        Output::ok(typechecked.expression)
+        let mut values = Vec::with_capacity(typechecked.assignments.len());
+        let mut interpreter = Interpreter::new(&self.global);
+        for a in &typechecked.assignments {
+            match interpreter.assignment(a) {
+                Ok(value) => values.push(value),
+                Err(error) => return error.into(),
+            }
+        }
+        let mut output = Output::ok(typechecked.expression);
+        for (a, value) in typechecked.assignments.into_iter().zip(values) {
+            let dfn = SymbolDfn::new(a.position, a.expr.type_expr, value);
+            if let Err(problems) = self.global.symbols.set(a.symbol, dfn) {
+                output.add_problems(problems);
+            }
+        }
+        output
+    }
+
+    fn eval(&self, expression: &typechecked::Expression) -> Output<TypedValue> {
+        match Interpreter::new(&self.global).expression(expression) {
+            Ok(value) => Output::ok(TypedValue::new(
+                value,
+                expression.type_expr.get_type().clone(),
+            )),
+            Err(error) => error.into(),
+        }
     }
 }
 
@@ -95,6 +255,12 @@ pub struct Interactive {
     env: Env,
 }
 
+impl Default for Interactive {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Interactive {
     pub fn new() -> Self {
         let path = FQPath::new(Lib::Local, ModulePath::empty());
@@ -104,8 +270,19 @@ impl Interactive {
         }
     }
 
+    // Makes a package available as an external dependency, qualifying its symbols with the alias
+    pub fn add_dependency(&mut self, alias: &str, id: Id, package: &store::Package) -> Output<()> {
+        self.env.global.add_dependency(alias, id, package)
+    }
+
     pub fn eval(&mut self, input: &str) -> Output<Option<TypedValue>> {
-        self.env.add_input(self.path.clone(), input);
-        panic!("TODO")
+        let output = self.env.add_input(self.path.clone(), input);
+        if output.has_errors() {
+            return output.map(|_| None);
+        }
+        output.and_then(|expression| match expression {
+            Some(e) => self.env.eval(&e).map(Some),
+            None => Output::ok(None),
+        })
     }
 }
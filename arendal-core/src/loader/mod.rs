@@ -0,0 +1,130 @@
+// Loads a package from a directory tree. Every `.ar` file is a module whose path is given
+// by its location relative to the root: `a/b.ar` is the module `pkg::a::b`. A `mod.ar`
+// file is the module of its directory, so the root `mod.ar` is the package root module.
+
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::ArcStr;
+use crate::context::Value;
+use crate::env0::Env;
+use crate::error::{ErrorVec, Loc};
+use crate::symbol::{self, Symbol};
+
+const EXTENSION: &str = "ar";
+const DIRECTORY_MODULE: &str = "mod";
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    InvalidModuleName(PathBuf),
+    Errors(ErrorVec),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => e.fmt(f),
+            Self::InvalidModuleName(file) => write!(f, "invalid module name: {}", file.display()),
+            Self::Errors(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(value: io::Error) -> Self {
+        LoadError::Io(value)
+    }
+}
+
+impl From<ErrorVec> for LoadError {
+    fn from(value: ErrorVec) -> Self {
+        LoadError::Errors(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Source {
+    pub path: symbol::Path,
+    pub input: String,
+}
+
+// Reads the modules of the package rooted at the provided directory, sorted by file name
+pub fn read<P: AsRef<Path>>(root: P) -> Result<Vec<Source>, LoadError> {
+    let mut sources = Vec::default();
+    read_dir(root.as_ref(), &mut Vec::default(), &mut sources)?;
+    Ok(sources)
+}
+
+fn read_dir(
+    dir: &Path,
+    segments: &mut Vec<Symbol>,
+    sources: &mut Vec<Source>,
+) -> Result<(), LoadError> {
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let file = entry.path();
+        if entry.file_type()?.is_dir() {
+            // Directories without modules don't need valid names
+            if let Some(symbol) = module_symbol(file.file_name()) {
+                segments.push(symbol);
+                read_dir(&file, segments, sources)?;
+                segments.pop();
+            } else if contains_modules(&file)? {
+                return Err(LoadError::InvalidModuleName(file));
+            }
+        } else if file.extension() == Some(OsStr::new(EXTENSION)) {
+            let mut path = segments.clone();
+            if file.file_stem() != Some(OsStr::new(DIRECTORY_MODULE)) {
+                match module_symbol(file.file_stem()) {
+                    Some(symbol) => path.push(symbol),
+                    None => return Err(LoadError::InvalidModuleName(file)),
+                }
+            }
+            sources.push(Source {
+                path: symbol::Path::new(path),
+                input: fs::read_to_string(&file)?,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn module_symbol(name: Option<&OsStr>) -> Option<Symbol> {
+    let name = name?.to_str()?;
+    Symbol::new(&Loc::None, ArcStr::from(name)).ok()
+}
+
+fn contains_modules(dir: &Path) -> io::Result<bool> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file = entry.path();
+        if entry.file_type()?.is_dir() {
+            if contains_modules(&file)? {
+                return Ok(true);
+            }
+        } else if file.extension() == Some(OsStr::new(EXTENSION)) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+// Loads the package rooted at the provided directory, typechecking all the modules together
+pub fn load<P: AsRef<Path>>(env: &mut Env, root: P) -> Result<Value, LoadError> {
+    let sources = read(root)?;
+    let inputs: Vec<(symbol::Path, &str)> = sources
+        .iter()
+        .map(|s| (s.path.clone(), s.input.as_str()))
+        .collect();
+    Ok(env.run_modules(&inputs)?)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,99 @@
+use std::fs;
+use std::path::PathBuf;
+
+use super::{LoadError, load, read};
+use crate::env0::Env;
+use crate::error::{Error, Loc};
+use crate::symbol::{Path, Pkg, Symbol};
+
+fn dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("arendal-loader-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    for (file, input) in files {
+        let file = root.join(file);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, input).unwrap();
+    }
+    root
+}
+
+fn path(segments: &[&str]) -> Path {
+    Path::new(segments.iter().map(|s| symbol(s)).collect())
+}
+
+fn symbol(name: &str) -> Symbol {
+    Symbol::new(&Loc::None, (*name).into()).unwrap()
+}
+
+fn load_err(root: PathBuf, expected: Error) {
+    match load(&mut Env::default(), &root) {
+        Err(LoadError::Errors(e)) => assert!(e.contains(&expected), "{e:?}"),
+        other => panic!("Expected {expected:?} but got {other:?}"),
+    }
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn module_paths() {
+    let root = dir(
+        "module_paths",
+        &[
+            ("mod.ar", "1"),
+            ("c.ar", ""),
+            ("a/b.ar", ""),
+            ("a/mod.ar", ""),
+            ("notes.txt", ""),
+            ("Docs/README", ""),
+        ],
+    );
+    let paths: Vec<Path> = read(&root).unwrap().into_iter().map(|s| s.path).collect();
+    assert_eq!(
+        paths,
+        vec![path(&["a", "b"]), path(&["a"]), path(&["c"]), path(&[])]
+    );
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn cross_module_references() {
+    let root = dir(
+        "cross_module_references",
+        &[
+            ("a/b.ar", "pkg let x = 40"),
+            ("c.ar", "pub let y = a::b::x + 1"),
+            ("mod.ar", "c::y + 1"),
+        ],
+    );
+    let mut env = Env::default();
+    assert_eq!(load(&mut env, &root).unwrap().to_string(), "42");
+    assert_eq!(env.run("c::y").unwrap().to_string(), "41");
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn errors() {
+    let hidden = Pkg::Local.path(path(&["a"])).fq_sym(symbol("hidden"));
+    load_err(
+        dir(
+            "not_visible",
+            &[("a.ar", "let hidden = 1"), ("mod.ar", "a::hidden")],
+        ),
+        Error::SymbolNotVisible(hidden),
+    );
+    load_err(
+        dir("non_root_expression", &[("a.ar", "1 + 2")]),
+        Error::TLExpressionInNonRootModule,
+    );
+    load_err(
+        dir("duplicate", &[("a.ar", ""), ("a/mod.ar", "")]),
+        Error::DuplicateModule(Pkg::Local.path(path(&["a"]))),
+    );
+    load_err(dir("empty", &[]), Error::EmptyPackage);
+    let root = dir("invalid_name", &[("Bad.ar", "")]);
+    assert!(matches!(
+        load(&mut Env::default(), &root),
+        Err(LoadError::InvalidModuleName(_))
+    ));
+    fs::remove_dir_all(root).unwrap();
+}
@@ -1,8 +1,11 @@
+use std::collections::HashSet;
 use std::fmt::{self, Debug};
 
 use ast::position::{EqNoPosition, Position};
-use ast::symbol::{FQPath, FQSym, FQType, Symbol};
-use num::Integer;
+use ast::symbol::{FQSym, FQType, Symbol};
+use num::{Decimal, Integer};
+
+use crate::ArcStr;
 
 pub(crate) type ERef = Box<Expression>;
 
@@ -11,13 +14,34 @@ pub(crate) type Binary = ast::common::Binary<ERef>;
 pub(crate) type Seq = ast::common::Seq<ERef>;
 pub(crate) type Conditional = ast::common::Conditional<ERef>;
 
+// Functions of the std package, which can only be called directly for now
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Builtin {
+    Div, // Decimal division with explicit scale and rounding mode
+}
+
+impl Builtin {
+    pub(crate) fn new(symbol: &Symbol) -> Option<Self> {
+        match symbol.to_string().as_str() {
+            "div" => Some(Builtin::Div),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) enum Expr {
     LitInteger(Integer),
+    LitDecimal(Decimal),
+    LitString(ArcStr),
+    Interpolation(Vec<Expression>), // Text parts are string literals
+    Unary(Unary),
     Binary(Binary),
-    LocalSymbol(Symbol),
     Symbol(FQSym),
     Type(FQType),
+    Seq(Seq),
+    Conditional(Conditional),
+    Builtin(Builtin, Vec<Expression>),
 }
 
 impl Expr {
@@ -25,6 +49,7 @@ impl Expr {
         Expression {
             position,
             expr: self,
+            annotation: None,
         }
     }
 
@@ -43,6 +68,34 @@ impl EqNoPosition for Expr {
                     false
                 }
             }
+            Expr::LitDecimal(d1) => {
+                if let Expr::LitDecimal(d2) = other {
+                    d1 == d2
+                } else {
+                    false
+                }
+            }
+            Expr::LitString(s1) => {
+                if let Expr::LitString(s2) = other {
+                    s1 == s2
+                } else {
+                    false
+                }
+            }
+            Expr::Interpolation(p1) => {
+                if let Expr::Interpolation(p2) = other {
+                    p1.eq_nopos(p2)
+                } else {
+                    false
+                }
+            }
+            Expr::Unary(u1) => {
+                if let Expr::Unary(u2) = other {
+                    u1.eq_nopos(u2)
+                } else {
+                    false
+                }
+            }
             Expr::Binary(b1) => {
                 if let Expr::Binary(b2) = other {
                     b1.eq_nopos(b2)
@@ -50,7 +103,41 @@ impl EqNoPosition for Expr {
                     false
                 }
             }
-            _ => panic!("TODO!"),
+            Expr::Symbol(s1) => {
+                if let Expr::Symbol(s2) = other {
+                    s1 == s2
+                } else {
+                    false
+                }
+            }
+            Expr::Type(t1) => {
+                if let Expr::Type(t2) = other {
+                    t1 == t2
+                } else {
+                    false
+                }
+            }
+            Expr::Seq(s1) => {
+                if let Expr::Seq(s2) = other {
+                    s1.eq_nopos(s2)
+                } else {
+                    false
+                }
+            }
+            Expr::Conditional(c1) => {
+                if let Expr::Conditional(c2) = other {
+                    c1.eq_nopos(c2)
+                } else {
+                    false
+                }
+            }
+            Expr::Builtin(b1, a1) => {
+                if let Expr::Builtin(b2, a2) = other {
+                    b1 == b2 && a1.eq_nopos(a2)
+                } else {
+                    false
+                }
+            }
         }
     }
 }
@@ -58,11 +145,43 @@ impl EqNoPosition for Expr {
 pub(crate) struct Expression {
     pub(crate) position: Position,
     pub(crate) expr: Expr,
+    pub(crate) annotation: Option<FQType>, // Type annotation, if any
+}
+
+impl Expression {
+    // Adds the global symbols referenced by the expression to the provided set
+    pub(crate) fn add_symbols(&self, symbols: &mut HashSet<FQSym>) {
+        match &self.expr {
+            Expr::LitInteger(_) | Expr::LitDecimal(_) | Expr::LitString(_) | Expr::Type(_) => (),
+            Expr::Interpolation(exprs) | Expr::Builtin(_, exprs) => {
+                for e in exprs {
+                    e.add_symbols(symbols);
+                }
+            }
+            Expr::Symbol(s) => {
+                symbols.insert(s.clone());
+            }
+            Expr::Unary(u) => u.expr.add_symbols(symbols),
+            Expr::Binary(b) => {
+                b.expr1.add_symbols(symbols);
+                b.expr2.add_symbols(symbols);
+            }
+            Expr::Seq(s) => {
+                s.expr.add_symbols(symbols);
+                s.then.add_symbols(symbols);
+            }
+            Expr::Conditional(c) => {
+                c.expr.add_symbols(symbols);
+                c.then.add_symbols(symbols);
+                c.otherwise.add_symbols(symbols);
+            }
+        }
+    }
 }
 
 impl EqNoPosition for Expression {
     fn eq_nopos(&self, other: &Self) -> bool {
-        self.expr.eq_nopos(&other.expr)
+        self.expr.eq_nopos(&other.expr) && self.annotation == other.annotation
     }
 }
 
@@ -72,8 +191,15 @@ impl fmt::Debug for Expression {
     }
 }
 
+#[derive(Debug)]
+pub(crate) struct Assignment {
+    pub(crate) position: Position,
+    pub(crate) symbol: FQSym,
+    pub(crate) expr: Expression,
+}
+
 #[derive(Debug)]
 pub(crate) struct Resolved {
-    pub(crate) path: FQPath,
+    pub(crate) assignments: Vec<Assignment>,
     pub(crate) expression: Option<Expression>,
 }
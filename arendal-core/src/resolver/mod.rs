@@ -1,36 +1,223 @@
-use ast::{self, AST, problem::Output, symbol::FQPath};
+use std::collections::HashSet;
+
+use ast::{
+    self, AST, Q,
+    problem::{ErrorType, Output},
+    symbol::{FQPath, FQSym, FQType, Lib, ModulePath, Symbol, TSymbol},
+};
 
 use crate::{
     GlobalScope,
-    resolved::{Binary, Expr, Expression, Resolved},
+    resolved::{Assignment, Binary, Builtin, Conditional, Expr, Expression, Resolved, Seq, Unary},
 };
 
 pub(super) fn resolve(path: FQPath, global: &GlobalScope, ast: &AST) -> Output<Resolved> {
-    match &ast.expression {
-        None => Output::ok(None),
-        Some(e) => resolve_expression(&e).map(|e| Some(e)),
-    }
-    .map(|e| Resolved {
+    let mut resolver = Resolver {
         path,
-        expression: e,
-    })
+        global,
+        symbols: HashSet::default(),
+    };
+    resolver
+        .add_symbols(ast)
+        .and_then(|_| resolver.resolve_assignments(ast))
+        .merge_to_tuple(match &ast.expression {
+            None => Output::ok(None),
+            Some(e) => resolver.resolve_expression(e).map(Some),
+        })
+        .map(|(assignments, expression)| Resolved {
+            assignments,
+            expression,
+        })
+}
+
+struct Resolver<'a> {
+    path: FQPath,
+    global: &'a GlobalScope,
+    symbols: HashSet<FQSym>, // Symbols defined by the input being resolved
 }
 
-fn resolve_expression(expression: &ast::Expression) -> Output<Expression> {
-    match &expression.expr {
-        ast::Expr::LitInteger(num) => {
-            Output::ok(Expr::LitInteger(num.clone()).wrap_from(expression))
-        }
-        ast::Expr::Binary(b) => resolve_expression(&b.expr1)
-            .merge_to_tuple(resolve_expression(&b.expr2))
-            .map(|(e1, e2)| {
-                Expr::Binary(Binary {
-                    op: b.op,
-                    expr1: e1.into(),
-                    expr2: e2.into(),
+impl Resolver<'_> {
+    fn add_symbols(&mut self, ast: &AST) -> Output<()> {
+        let mut output = Output::ok(());
+        for a in &ast.assignments {
+            let symbol = self.path.fq_sym(a.symbol.clone());
+            if self.global.symbols.contains(&symbol) || !self.symbols.insert(symbol.clone()) {
+                output.add_error(Error::DuplicateSymbol(symbol).at(a.position.clone()));
+            }
+        }
+        output
+    }
+
+    fn resolve_assignments(&self, ast: &AST) -> Output<Vec<Assignment>> {
+        let mut output = Output::empty();
+        for a in &ast.assignments {
+            output.add_output(self.resolve_expression(&a.expr).map(|expr| Assignment {
+                position: a.position.clone(),
+                symbol: self.path.fq_sym(a.symbol.clone()),
+                expr,
+            }));
+        }
+        output
+    }
+
+    fn resolve_expression(&self, expression: &ast::Expression) -> Output<Expression> {
+        match &expression.expr {
+            ast::Expr::LitInteger(num) => Output::ok(Expr::LitInteger(num.clone())),
+            ast::Expr::LitDecimal(num) => Output::ok(Expr::LitDecimal(num.clone())),
+            ast::Expr::LitString(s) => Output::ok(Expr::LitString(s.clone())),
+            ast::Expr::Interpolation(parts) => {
+                let mut output = Output::empty();
+                for part in parts {
+                    output.add_output(match part {
+                        ast::StringPart::Text(s) => {
+                            Output::ok(Expr::LitString(s.clone()).wrap_from(expression))
+                        }
+                        ast::StringPart::Expr(e) => self.resolve_expression(e),
+                    });
+                }
+                output.map(Expr::Interpolation)
+            }
+            ast::Expr::Symbol(q) => self.resolve_symbol(expression, q).map(Expr::Symbol),
+            ast::Expr::Type(q) => self.resolve_type(expression, q).map(Expr::Type),
+            ast::Expr::Unary(u) => self.resolve_expression(&u.expr).map(|expr| {
+                Expr::Unary(Unary {
+                    op: u.op,
+                    expr: expr.into(),
                 })
-                .wrap_from(expression)
             }),
-        _ => panic!("TODO"),
+            ast::Expr::Binary(b) => self
+                .resolve_expression(&b.expr1)
+                .merge_to_tuple(self.resolve_expression(&b.expr2))
+                .map(|(e1, e2)| {
+                    Expr::Binary(Binary {
+                        op: b.op,
+                        expr1: e1.into(),
+                        expr2: e2.into(),
+                    })
+                }),
+            ast::Expr::Seq(s) => self
+                .resolve_expression(&s.expr)
+                .merge_to_tuple(self.resolve_expression(&s.then))
+                .map(|(expr, then)| {
+                    Expr::Seq(Seq {
+                        expr: expr.into(),
+                        then: then.into(),
+                    })
+                }),
+            ast::Expr::Conditional(c) => self
+                .resolve_expression(&c.expr)
+                .merge_to_tuple(self.resolve_expression(&c.then))
+                .merge_to_tuple(self.resolve_expression(&c.otherwise))
+                .map(|((expr, then), otherwise)| {
+                    Expr::Conditional(Conditional {
+                        expr: expr.into(),
+                        then: then.into(),
+                        otherwise: otherwise.into(),
+                    })
+                }),
+            ast::Expr::Call(c) => self.resolve_call(expression, c),
+            // Functions are only supported by the legacy pipeline for now
+            ast::Expr::Lambda(_) => Error::UnsupportedExpression
+                .at(expression.position.clone())
+                .into(),
+        }
+        .merge_to_tuple(self.resolve_annotation(expression))
+        .map(|(e, annotation)| {
+            let mut resolved = e.wrap_from(expression);
+            resolved.annotation = annotation;
+            resolved
+        })
+    }
+
+    // Only the builtin functions can be called for now, and only by their unqualified name
+    // if no symbol of the current path shadows them.
+    fn resolve_call(&self, expression: &ast::Expression, call: &ast::Call) -> Output<Expr> {
+        let builtin = match &call.func.expr {
+            ast::Expr::Symbol(q) if q.path.is_empty() && q.types.is_empty() => {
+                Builtin::new(&q.symbol)
+                    .filter(|_| !self.is_defined(&self.path.fq_sym(q.symbol.clone())))
+            }
+            _ => None,
+        };
+        let Some(builtin) = builtin else {
+            return Error::UnsupportedExpression
+                .at(expression.position.clone())
+                .into();
+        };
+        let mut output = Output::empty();
+        for arg in &call.args {
+            output.add_output(self.resolve_expression(arg));
+        }
+        output.map(|args| Expr::Builtin(builtin, args))
+    }
+
+    // Only named types can be used in annotations for now
+    fn resolve_annotation(&self, expression: &ast::Expression) -> Output<Option<FQType>> {
+        match &expression.type_expr {
+            None => Output::ok(None),
+            Some(ast::TypeExpr::Type(q)) => self.resolve_type(expression, q).map(Some),
+            Some(ast::TypeExpr::Function(..)) => Error::UnsupportedType
+                .at(expression.position.clone())
+                .into(),
+        }
+    }
+
+    fn is_defined(&self, symbol: &FQSym) -> bool {
+        self.symbols.contains(symbol) || self.global.symbols.contains(symbol)
+    }
+
+    // Returns the paths in which to look for a possibly qualified symbol, in order
+    fn candidate_paths<T>(&self, q: &Q<T>) -> Vec<FQPath> {
+        if q.path.is_empty() {
+            vec![self.path.clone(), Lib::Std.empty()]
+        } else if let Some(id) = self.global.dependencies.get(&q.path[0]) {
+            let path = ModulePath::new(q.path[1..].to_vec());
+            vec![Lib::External(id.clone()).path(path)]
+        } else {
+            vec![self.path.lib().path(ModulePath::new(q.path.clone()))]
+        }
+    }
+
+    // Symbols nested in types are not supported yet
+    fn resolve_symbol(&self, expression: &ast::Expression, q: &Q<Symbol>) -> Output<FQSym> {
+        for path in self.candidate_paths(q) {
+            let symbol = path.fq_sym(q.symbol.clone());
+            if q.types.is_empty() && self.is_defined(&symbol) {
+                return Output::ok(symbol);
+            }
+        }
+        Error::UnknownSymbol(q.clone())
+            .at(expression.position.clone())
+            .into()
+    }
+
+    // Nested types are looked up in the paths of their outermost type, e.g., Rounding::Up
+    fn resolve_type(&self, expression: &ast::Expression, q: &Q<TSymbol>) -> Output<FQType> {
+        for path in self.candidate_paths(q) {
+            let symbol = match q.types.split_first() {
+                None => path.fq_type(q.symbol.clone()),
+                Some((first, rest)) => rest
+                    .iter()
+                    .chain([&q.symbol])
+                    .fold(path.fq_type(first.clone()), |t, s| t.nested(s.clone())),
+            };
+            if self.global.types.contains(&symbol) {
+                return Output::ok(symbol);
+            }
+        }
+        Error::UnknownType(q.clone())
+            .at(expression.position.clone())
+            .into()
     }
 }
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Error {
+    DuplicateSymbol(FQSym),
+    UnknownSymbol(Q<Symbol>),
+    UnknownType(Q<TSymbol>),
+    UnsupportedExpression,
+    UnsupportedType,
+}
+
+impl ErrorType for Error {}
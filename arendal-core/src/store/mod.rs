@@ -0,0 +1,460 @@
+// Local content-addressed package store. Each package is a file in the store directory
+// named after the hex id of its contents, holding the package types and symbols together
+// with their visibility and values. The encoding is a deterministic line-based text
+// format, so the same package always gets the same id.
+//
+// Every type definition can be stored, but only literal and singleton values. Function
+// definitions are not stored: the content encoding of definitions is only used for their ids
+// and can't be decoded, so packages defining functions can't be exported yet.
+
+use std::fmt::{self, Write};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use num::{Decimal, Integer};
+
+use crate::ArcStr;
+use crate::context::quote;
+use crate::id::{Id, Ids};
+use crate::visibility::Visibility;
+
+const HEADER: &str = "arendal-package 1";
+
+#[derive(Debug)]
+pub enum StoreError {
+    Io(io::Error),
+    NotFound(Id),
+    Corrupted(Id), // Contents don't match the id
+    Invalid(Id),   // Contents match the id but can't be decoded
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => e.fmt(f),
+            Self::NotFound(id) => write!(f, "package {} not found", id),
+            Self::Corrupted(id) => write!(f, "package {} is corrupted", id),
+            Self::Invalid(id) => write!(f, "package {} is not a valid package", id),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<io::Error> for StoreError {
+    fn from(value: io::Error) -> Self {
+        StoreError::Io(value)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Store {
+    root: PathBuf,
+}
+
+impl Store {
+    // Opens the store at the provided directory, creating it if needed
+    pub fn open<P: AsRef<Path>>(root: P) -> io::Result<Store> {
+        fs::create_dir_all(root.as_ref())?;
+        Ok(Store {
+            root: root.as_ref().to_path_buf(),
+        })
+    }
+
+    fn file(&self, id: &Id) -> PathBuf {
+        self.root.join(id.as_string())
+    }
+
+    // Adds a package to the store, returning its id
+    pub fn add(&self, package: &Package) -> io::Result<Id> {
+        let bytes = package.encode();
+        let id = Id::digest(bytes.as_bytes());
+        let file = self.file(&id);
+        if !file.exists() {
+            // Write and rename, so that a package file is either complete or missing
+            let tmp = self.root.join(format!("{}.tmp", id));
+            fs::write(&tmp, bytes)?;
+            fs::rename(tmp, file)?;
+        }
+        Ok(id)
+    }
+
+    pub fn get(&self, id: &Id) -> Result<Package, StoreError> {
+        let bytes = match fs::read(self.file(id)) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(StoreError::NotFound(id.clone()));
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if Id::digest(&bytes) != *id {
+            return Err(StoreError::Corrupted(id.clone()));
+        }
+        String::from_utf8(bytes)
+            .ok()
+            .and_then(|s| Package::decode(&s))
+            .ok_or_else(|| StoreError::Invalid(id.clone()))
+    }
+
+    // Returns the ids of the stored packages, ignoring any other file
+    pub fn ids(&self) -> io::Result<Ids> {
+        let mut ids = Ids::default();
+        for entry in fs::read_dir(&self.root)? {
+            if let Some(id) = entry?.file_name().to_str().and_then(|n| n.parse().ok()) {
+                ids.insert(id);
+            }
+        }
+        Ok(ids)
+    }
+}
+
+// Fully qualified name of a package member, relative to the package
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Name {
+    pub path: Vec<String>,
+    pub name: String,
+}
+
+impl Name {
+    pub fn new(path: Vec<String>, name: String) -> Self {
+        Name { path, name }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        let mut path: Vec<String> = s.split("::").map(str::to_owned).collect();
+        let name = path.pop()?;
+        if name.is_empty() || path.iter().any(String::is_empty) {
+            None
+        } else {
+            Some(Name { path, name })
+        }
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in &self.path {
+            f.write_str(segment)?;
+            f.write_str("::")?;
+        }
+        f.write_str(&self.name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeRef {
+    Std(String),
+    Local(Name), // A type defined in the same package
+}
+
+impl TypeRef {
+    fn parse(s: &str) -> Option<Self> {
+        if let Some(name) = s.strip_prefix("std::") {
+            Name::parse(name)
+                .filter(|n| n.path.is_empty())
+                .map(|n| TypeRef::Std(n.name))
+        } else {
+            s.strip_prefix("pkg::")
+                .and_then(Name::parse)
+                .map(TypeRef::Local)
+        }
+    }
+}
+
+impl fmt::Display for TypeRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Std(name) => write!(f, "std::{}", name),
+            Self::Local(name) => write!(f, "pkg::{}", name),
+        }
+    }
+}
+
+// Values that can be stored. Definitions such as functions are not supported yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Literal {
+    Singleton, // The only value of the symbol type
+    Integer(Integer),
+    Decimal(Decimal),
+    String(ArcStr),
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Singleton => f.write_str("singleton"),
+            Self::Integer(value) => write!(f, "integer {}", value),
+            Self::Decimal(value) => write!(f, "decimal {}", value),
+            Self::String(value) => write!(f, "string {}", quote(value)),
+        }
+    }
+}
+
+// Definition of a package type, encoded as words in prefix order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeDfn {
+    Singleton,
+    Tuple(Vec<TypeExpr>),
+    Record(Vec<(String, TypeExpr)>),
+    Union(Vec<(String, TypeDfn)>),
+    Generic(Vec<String>, Box<TypeDfn>), // The parameters are shared by the variants of unions
+}
+
+impl TypeDfn {
+    fn decode(words: &mut Words) -> Option<Self> {
+        Some(match words.next()? {
+            "singleton" => TypeDfn::Singleton,
+            "tuple" => TypeDfn::Tuple(words.many(TypeExpr::decode)?),
+            "record" => {
+                TypeDfn::Record(words.many(|w| Some((w.next()?.to_owned(), TypeExpr::decode(w)?)))?)
+            }
+            "union" => {
+                TypeDfn::Union(words.many(|w| Some((w.next()?.to_owned(), TypeDfn::decode(w)?)))?)
+            }
+            "generic" => TypeDfn::Generic(
+                words.many(|w| w.next().map(str::to_owned))?,
+                Box::new(TypeDfn::decode(words)?),
+            ),
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for TypeDfn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Singleton => f.write_str("singleton"),
+            Self::Tuple(types) => write!(f, "tuple {}", Many(types)),
+            Self::Record(fields) => {
+                write!(f, "record {}", fields.len())?;
+                fields.iter().try_for_each(|(s, t)| write!(f, " {s} {t}"))
+            }
+            Self::Union(variants) => {
+                write!(f, "union {}", variants.len())?;
+                variants.iter().try_for_each(|(s, d)| write!(f, " {s} {d}"))
+            }
+            Self::Generic(params, dfn) => write!(f, "generic {} {dfn}", Many(params)),
+        }
+    }
+}
+
+// Type used in a type definition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeExpr {
+    Type(TypeRef),
+    Var(String),                   // Parameter of the type being defined
+    Apply(TypeRef, Vec<TypeExpr>), // Instance of a generic type
+    List(Box<TypeExpr>),
+    Map(Box<TypeExpr>, Box<TypeExpr>),
+    Tuple(Vec<TypeExpr>),
+    Function(Vec<TypeExpr>, Box<TypeExpr>, Vec<String>), // Parameters, result and effects
+}
+
+impl TypeExpr {
+    fn decode(words: &mut Words) -> Option<Self> {
+        Some(match words.next()? {
+            "var" => TypeExpr::Var(words.next()?.to_owned()),
+            "apply" => TypeExpr::Apply(
+                TypeRef::parse(words.next()?)?,
+                words.many(TypeExpr::decode)?,
+            ),
+            "list" => TypeExpr::List(Self::boxed(words)?),
+            "map" => TypeExpr::Map(Self::boxed(words)?, Self::boxed(words)?),
+            "tuple" => TypeExpr::Tuple(words.many(TypeExpr::decode)?),
+            "fn" => TypeExpr::Function(
+                words.many(TypeExpr::decode)?,
+                Self::boxed(words)?,
+                words.many(|w| w.next().map(str::to_owned))?,
+            ),
+            word => TypeExpr::Type(TypeRef::parse(word)?),
+        })
+    }
+
+    fn boxed(words: &mut Words) -> Option<Box<Self>> {
+        Self::decode(words).map(Box::new)
+    }
+}
+
+impl fmt::Display for TypeExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Type(t) => t.fmt(f),
+            Self::Var(symbol) => write!(f, "var {symbol}"),
+            Self::Apply(t, args) => write!(f, "apply {t} {}", Many(args)),
+            Self::List(element) => write!(f, "list {element}"),
+            Self::Map(key, value) => write!(f, "map {key} {value}"),
+            Self::Tuple(types) => write!(f, "tuple {}", Many(types)),
+            Self::Function(params, result, effects) => {
+                write!(f, "fn {} {result} {}", Many(params), Many(effects))
+            }
+        }
+    }
+}
+
+// Items written as their number followed by the items
+struct Many<'a, T>(&'a [T]);
+
+impl<T: fmt::Display> fmt::Display for Many<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.len())?;
+        self.0.iter().try_for_each(|item| write!(f, " {item}"))
+    }
+}
+
+// Words of a line being decoded
+struct Words<'a>(std::slice::Iter<'a, &'a str>);
+
+impl<'a> Words<'a> {
+    fn next(&mut self) -> Option<&'a str> {
+        self.0.next().copied()
+    }
+
+    // Decodes the items written by Many. Capacity is not reserved, as the number is not trusted.
+    fn many<T, F: FnMut(&mut Self) -> Option<T>>(&mut self, mut item: F) -> Option<Vec<T>> {
+        let n: usize = self.next()?.parse().ok()?;
+        let mut items = Vec::default();
+        for _ in 0..n {
+            items.push(item(self)?);
+        }
+        Some(items)
+    }
+}
+
+// Type defined by a package. Singletons are written with no definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeEntry {
+    pub visibility: Visibility,
+    pub name: Name,
+    pub dfn: TypeDfn,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolEntry {
+    pub visibility: Visibility,
+    pub name: Name,
+    pub tipo: TypeRef,
+    pub value: Literal,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Package {
+    pub types: Vec<TypeEntry>,
+    pub symbols: Vec<SymbolEntry>,
+}
+
+fn visibility_str(visibility: Visibility) -> &'static str {
+    match visibility {
+        Visibility::Module => "module",
+        Visibility::Package => "package",
+        Visibility::Exported => "exported",
+    }
+}
+
+fn parse_visibility(s: &str) -> Option<Visibility> {
+    match s {
+        "module" => Some(Visibility::Module),
+        "package" => Some(Visibility::Package),
+        "exported" => Some(Visibility::Exported),
+        _ => None,
+    }
+}
+
+impl Package {
+    // Entries are sorted by name, so the encoding doesn't depend on their order
+    fn encode(&self) -> String {
+        let mut types: Vec<&TypeEntry> = self.types.iter().collect();
+        types.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut symbols: Vec<&SymbolEntry> = self.symbols.iter().collect();
+        symbols.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut encoded = String::from(HEADER);
+        encoded.push('\n');
+        for t in types {
+            write!(encoded, "type {} {}", visibility_str(t.visibility), t.name).unwrap();
+            if t.dfn != TypeDfn::Singleton {
+                write!(encoded, " = {}", t.dfn).unwrap();
+            }
+            encoded.push('\n');
+        }
+        for s in symbols {
+            writeln!(
+                encoded,
+                "let {} {} : {} = {}",
+                visibility_str(s.visibility),
+                s.name,
+                s.tipo,
+                s.value
+            )
+            .unwrap();
+        }
+        encoded
+    }
+
+    fn decode(encoded: &str) -> Option<Package> {
+        let mut lines = encoded.lines();
+        if lines.next()? != HEADER {
+            return None;
+        }
+        let mut package = Package::default();
+        for line in lines {
+            let words: Vec<&str> = line.split(' ').collect();
+            match words.as_slice() {
+                ["type", visibility, name] => package.types.push(TypeEntry {
+                    visibility: parse_visibility(visibility)?,
+                    name: Name::parse(name)?,
+                    dfn: TypeDfn::Singleton,
+                }),
+                ["type", visibility, name, "=", dfn @ ..] => {
+                    let mut words = Words(dfn.iter());
+                    package.types.push(TypeEntry {
+                        visibility: parse_visibility(visibility)?,
+                        name: Name::parse(name)?,
+                        dfn: TypeDfn::decode(&mut words)?,
+                    });
+                    if words.next().is_some() {
+                        return None;
+                    }
+                }
+                ["let", visibility, name, ":", tipo, "=", literal @ ..] => {
+                    package.symbols.push(SymbolEntry {
+                        visibility: parse_visibility(visibility)?,
+                        name: Name::parse(name)?,
+                        tipo: TypeRef::parse(tipo)?,
+                        value: match literal {
+                            ["singleton"] => Literal::Singleton,
+                            ["integer", value] => Literal::Integer(value.parse().ok()?),
+                            ["decimal", value] => Literal::Decimal(value.parse().ok()?),
+                            ["string", value @ ..] => Literal::String(unquote(&value.join(" "))?),
+                            _ => return None,
+                        },
+                    })
+                }
+                _ => return None,
+            }
+        }
+        Some(package)
+    }
+}
+
+// Reverses quote, returning None if the string is not a valid literal
+fn unquote(quoted: &str) -> Option<ArcStr> {
+    let mut value = String::with_capacity(quoted.len());
+    let mut chars = quoted.strip_prefix('"')?.strip_suffix('"')?.chars();
+    while let Some(c) = chars.next() {
+        value.push(match c {
+            '\\' => match chars.next()? {
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                c @ ('"' | '\\' | '{' | '}') => c,
+                _ => return None,
+            },
+            '"' => return None,
+            _ => c,
+        });
+    }
+    Some(value.into())
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,312 @@
+use std::fs;
+use std::path::PathBuf;
+
+use ast::problem::Problems;
+use ast::symbol::{Lib, ModulePath};
+
+use super::{
+    Literal, Name, Package, Store, StoreError, SymbolEntry, TypeDfn, TypeEntry, TypeExpr, TypeRef,
+};
+use crate::env0::Env;
+use crate::error::Error;
+use crate::id::Id;
+use crate::symbol::{Pkg, Symbol, TSymbol};
+use crate::types::{TypedValue, Value};
+use crate::visibility::Visibility;
+use crate::{ArcStr, Interactive, error::Loc};
+
+fn store(name: &str) -> (Store, PathBuf) {
+    let root = std::env::temp_dir().join(format!("arendal-store-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    (Store::open(&root).unwrap(), root)
+}
+
+fn name(path: &[&str], name: &str) -> Name {
+    Name::new(
+        path.iter().map(|s| s.to_string()).collect(),
+        name.to_string(),
+    )
+}
+
+fn symbol(visibility: Visibility, n: &str, tipo: &str, value: Literal) -> SymbolEntry {
+    SymbolEntry {
+        visibility,
+        name: name(&[], n),
+        tipo: TypeRef::Std(tipo.to_string()),
+        value,
+    }
+}
+
+fn package() -> Package {
+    let std = |t: &str| TypeExpr::Type(TypeRef::Std(t.to_string()));
+    let local = |t: &str| TypeRef::Local(name(&[], t));
+    let var = |t: &str| TypeExpr::Var(t.to_string());
+    Package {
+        types: vec![
+            TypeEntry {
+                visibility: Visibility::Package,
+                name: name(&[], "Green"),
+                dfn: TypeDfn::Singleton,
+            },
+            TypeEntry {
+                visibility: Visibility::Module,
+                name: name(&[], "Handlers"),
+                dfn: TypeDfn::Tuple(vec![
+                    TypeExpr::Map(
+                        Box::new(std("String")),
+                        Box::new(TypeExpr::Function(
+                            vec![std("String"), TypeExpr::Tuple(vec![])],
+                            Box::new(std("None")),
+                            vec!["IO".to_string()],
+                        )),
+                    ),
+                    TypeExpr::Type(local("Point")),
+                ]),
+            },
+            TypeEntry {
+                visibility: Visibility::Exported,
+                name: name(&[], "Point"),
+                dfn: TypeDfn::Record(vec![
+                    ("x".to_string(), std("Integer")),
+                    ("y".to_string(), std("Integer")),
+                ]),
+            },
+            TypeEntry {
+                visibility: Visibility::Package,
+                name: name(&[], "Tree"),
+                dfn: TypeDfn::Generic(
+                    vec!["T".to_string()],
+                    Box::new(TypeDfn::Union(vec![
+                        ("Leaf".to_string(), TypeDfn::Tuple(vec![var("T")])),
+                        (
+                            "Node".to_string(),
+                            TypeDfn::Tuple(vec![TypeExpr::List(Box::new(TypeExpr::Apply(
+                                local("Tree"),
+                                vec![var("T")],
+                            )))]),
+                        ),
+                        ("Empty".to_string(), TypeDfn::Singleton),
+                    ])),
+                ),
+            },
+            TypeEntry {
+                visibility: Visibility::Exported,
+                name: name(&["colors"], "Red"),
+                dfn: TypeDfn::Singleton,
+            },
+        ],
+        symbols: vec![
+            symbol(
+                Visibility::Exported,
+                "d",
+                "Decimal",
+                Literal::Decimal("1.50".parse().unwrap()),
+            ),
+            SymbolEntry {
+                visibility: Visibility::Exported,
+                name: name(&[], "g"),
+                tipo: TypeRef::Local(name(&[], "Green")),
+                value: Literal::Singleton,
+            },
+            symbol(
+                Visibility::Package,
+                "hidden",
+                "Integer",
+                Literal::Integer(7.into()),
+            ),
+            SymbolEntry {
+                visibility: Visibility::Exported,
+                name: name(&[], "r"),
+                tipo: TypeRef::Local(name(&["colors"], "Red")),
+                value: Literal::Singleton,
+            },
+            symbol(
+                Visibility::Exported,
+                "s",
+                "String",
+                Literal::String("say \"hi\"\n{x}".into()),
+            ),
+            symbol(Visibility::Exported, "t", "True", Literal::Singleton),
+            symbol(
+                Visibility::Exported,
+                "x",
+                "Integer",
+                Literal::Integer(42.into()),
+            ),
+        ],
+    }
+}
+
+#[test]
+fn round_trip() {
+    let (store, root) = store("round_trip");
+    let package = package();
+    let id = store.add(&package).unwrap();
+    // Entries are sorted by name
+    assert_eq!(store.get(&id).unwrap(), package);
+    // Entry order doesn't change the id
+    let mut reversed = package.clone();
+    reversed.symbols.reverse();
+    assert_eq!(store.add(&reversed).unwrap(), id);
+    assert_eq!(store.ids().unwrap().find(&id.as_string()[..6]), Ok(&id));
+    assert_ne!(store.add(&Package::default()).unwrap(), id);
+    assert_eq!(store.ids().unwrap().len(), 2);
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn errors() {
+    let (store, root) = store("errors");
+    let id = store.add(&package()).unwrap();
+    fs::write(root.join(id.as_string()), "arendal-package 1\n").unwrap();
+    assert!(matches!(store.get(&id), Err(StoreError::Corrupted(i)) if i == id));
+    let missing = Id::digest(b"missing");
+    assert!(matches!(store.get(&missing), Err(StoreError::NotFound(i)) if i == missing));
+    for invalid in [
+        "arendal-package 1\nlet nope\n",
+        "arendal-package 1\ntype exported P = tuple 2 std::Integer\n",
+        "arendal-package 1\ntype exported P = tuple 1 std::Integer std::Integer\n",
+        "arendal-package 1\ntype exported P = record 1 x Integer\n",
+        "arendal-package 1\ntype exported P = tuple 99999999999 list\n",
+        "arendal-package 1\ntype exported P =\n",
+    ] {
+        let invalid_id = Id::digest(invalid.as_bytes());
+        fs::write(root.join(invalid_id.as_string()), invalid).unwrap();
+        assert!(
+            matches!(store.get(&invalid_id), Err(StoreError::Invalid(_))),
+            "{invalid}"
+        );
+    }
+    fs::remove_dir_all(root).unwrap();
+}
+
+// Types are stored in terms of std and local types
+#[test]
+fn legacy_types() {
+    let (store, root) = store("legacy_types");
+    let mut lib = Env::default();
+    lib.run(
+        "pub type Point { x: Integer, y: Integer }
+pub type Shape = Circle(Integer) | Rect(Point) | Empty
+pub type Pair[A, B](A, B)
+pub type Tree[T] = Leaf(T) | Node(List[Tree[T]])
+pub type Ops { all: Map[String, (Integer) -> Integer] }
+pub type Log((String) -> None with IO)
+pkg type Hidden(Integer)
+pub let origin = 0",
+    )
+    .unwrap();
+    let id = store.add(&lib.export().unwrap()).unwrap();
+    let package = store.get(&id).unwrap();
+    assert_eq!(package.types.len(), 7);
+
+    let mut env = Env::default();
+    env.add_dependency("lib", id.clone(), &package).unwrap();
+    for (input, expected) in [
+        ("let p = lib::Point { x: 1, y: 2 }\np.x + p.y", "3"),
+        (
+            "fn area(s: lib::Shape): Integer = match s { lib::Shape::Circle(r) -> 3 * r * r, lib::Shape::Rect(p) -> p.x * p.y, lib::Shape::Empty -> 0 }\narea(lib::Shape::Rect(lib::Point { x: 2, y: 5 })) + area(lib::Shape::Circle(1))",
+            "13",
+        ),
+        ("lib::Pair(1, \"a\").1", "\"a\""),
+        (
+            "let t: lib::Tree[Integer] = lib::Tree::Node([lib::Tree::Leaf(4)])\nmatch t { lib::Tree::Node(l) -> l.length, lib::Tree::Leaf(n) -> n }",
+            "1",
+        ),
+        (
+            "let o = lib::Ops { all: {\"inc\": fn(x: Integer) = x + 1} }\no.all[\"inc\"](lib::origin)",
+            "1",
+        ),
+    ] {
+        let value = env.run(input).unwrap_or_else(|e| panic!("{input}: {e:?}"));
+        assert_eq!(value.to_string(), expected, "{input}");
+    }
+    let e = env.run("lib::Hidden(1)").unwrap_err();
+    let hidden = Pkg::External(id.clone())
+        .empty()
+        .fq_type(TSymbol::new(&Loc::None, ArcStr::from("Hidden")).unwrap());
+    assert!(e.contains(&Error::TypeNotVisible(hidden)), "{e:?}");
+
+    // Types of dependencies can't be stored
+    let mut user = Env::default();
+    user.add_dependency("lib", id.clone(), &package).unwrap();
+    user.run("pub type Line(lib::Point, lib::Point)").unwrap();
+    let e = user.export().unwrap_err();
+    let line = Pkg::Local
+        .empty()
+        .fq_type(TSymbol::new(&Loc::None, ArcStr::from("Line")).unwrap());
+    assert!(e.contains(&Error::NotExportableType(line)), "{e:?}");
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn legacy_dependency() {
+    let (store, root) = store("legacy_dependency");
+    let mut lib = Env::default();
+    lib.run("pub type Red\npub let x = 42\npub let r = Red\npkg let y = 1\nlet z = 2")
+        .unwrap();
+    let id = store.add(&lib.export().unwrap()).unwrap();
+    let package = store.get(&id).unwrap();
+
+    let mut env = Env::default();
+    env.add_dependency("lib", id.clone(), &package).unwrap();
+    let x = env.run("lib::x + 1").unwrap();
+    assert_eq!(x.to_string(), "43");
+    let r = env.run("lib::r").unwrap();
+    assert_eq!(r.get_type().to_string(), format!("pkg({id})::Red"));
+    let pkg = Pkg::External(id.clone()).empty();
+    let fq = |s: &str| pkg.fq_sym(Symbol::new(&Loc::None, ArcStr::from(s)).unwrap());
+    for (input, hidden) in [("lib::y", "y"), ("lib::z", "z")] {
+        let e = env.run(input).unwrap_err();
+        assert!(e.contains(&Error::SymbolNotVisible(fq(hidden))), "{e:?}");
+    }
+    let e = env.add_dependency("lib", id, &package).unwrap_err();
+    assert!(e.contains(&Error::DuplicateDependency(
+        Symbol::new(&Loc::None, ArcStr::from("lib")).unwrap()
+    )));
+    fs::remove_dir_all(root).unwrap();
+}
+
+fn eval(interactive: &mut Interactive, input: &str) -> Result<Option<TypedValue>, Problems> {
+    interactive.eval(input).to_result().map(|w| w.value)
+}
+
+#[test]
+fn dependency() {
+    let (store, root) = store("dependency");
+    let id = store.add(&package()).unwrap();
+    let mut interactive = Interactive::new();
+    assert!(
+        interactive
+            .add_dependency("lib", id.clone(), &store.get(&id).unwrap())
+            .to_result()
+            .is_ok()
+    );
+    let value = eval(&mut interactive, "lib::x * 2").unwrap().unwrap();
+    assert_eq!(value.value(), &Value::Integer(84.into()));
+    let value = eval(&mut interactive, "lib::d").unwrap().unwrap();
+    assert_eq!(value.value(), &Value::Decimal("1.50".parse().unwrap()));
+    let value = eval(&mut interactive, "lib::s + \"!\"").unwrap().unwrap();
+    assert_eq!(value.value(), &Value::String("say \"hi\"\n{x}!".into()));
+    let value = eval(&mut interactive, "lib::t").unwrap().unwrap();
+    assert_eq!(value.value(), &Value::True);
+    // Singleton types of the package can be used, and the types of exported symbols
+    let red = Lib::External(id.clone())
+        .path(ModulePath::new(vec![
+            ast::symbol::Symbol::new("colors").unwrap(),
+        ]))
+        .fq_type(ast::symbol::TSymbol::new("Red").unwrap());
+    let value = eval(&mut interactive, "lib::colors::Red").unwrap().unwrap();
+    assert_eq!(value.value(), &Value::Singleton(red));
+    let value = eval(&mut interactive, "lib::r == lib::colors::Red")
+        .unwrap()
+        .unwrap();
+    assert_eq!(value.value(), &Value::True);
+    let value = eval(&mut interactive, "lib::g").unwrap().unwrap();
+    assert!(matches!(value.value(), Value::Singleton(_)));
+    assert!(eval(&mut interactive, "lib::Green").is_err());
+    assert!(eval(&mut interactive, "lib::Point").is_err());
+    assert!(eval(&mut interactive, "lib::hidden").is_err());
+    assert!(eval(&mut interactive, "other::x").is_err());
+    fs::remove_dir_all(root).unwrap();
+}
@@ -15,11 +15,6 @@ fn separator(f: &mut fmt::Formatter<'_>) -> fmt::Result {
     f.write_str("::")
 }
 
-fn add_segment(f: &mut fmt::Formatter<'_>, it: &dyn Display) -> fmt::Result {
-    separator(f)?;
-    it.fmt(f)
-}
-
 fn debug(f: &mut fmt::Formatter<'_>, name: &str, it: &dyn fmt::Display) -> fmt::Result {
     f.write_str(name)?;
     f.write_char('(')?;
@@ -34,7 +29,6 @@ pub struct Other {
 
 #[derive(Clone, PartialEq, Eq, Hash)]
 enum Sym<T> {
-    One(char),
     Known(T),
     Other(ArcStr),
 }
@@ -42,9 +36,8 @@ enum Sym<T> {
 impl<T: fmt::Display> fmt::Display for Sym<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::One(c) => f.write_char(*c),
             Self::Known(s) => s.fmt(f),
-            Self::Other(s) => f.write_str(&s),
+            Self::Other(s) => f.write_str(s),
         }
     }
 }
@@ -73,7 +66,7 @@ impl Symbol {
                 }
             }
         }
-        Ok(Self { name: name.into() })
+        Ok(Self { name })
     }
 }
 
@@ -96,6 +89,11 @@ enum KnownTSymbol {
     False,
     Boolean,
     Integer,
+    Decimal,
+    String,
+    List,
+    Map,
+    Rounding,
 }
 
 impl fmt::Display for KnownTSymbol {
@@ -106,6 +104,11 @@ impl fmt::Display for KnownTSymbol {
             Self::False => f.write_str("False"),
             Self::Boolean => f.write_str("Boolean"),
             Self::Integer => f.write_str("Integer"),
+            Self::Decimal => f.write_str("Decimal"),
+            Self::String => f.write_str("String"),
+            Self::List => f.write_str("List"),
+            Self::Map => f.write_str("Map"),
+            Self::Rounding => f.write_str("Rounding"),
         }
     }
 }
@@ -121,6 +124,11 @@ static T_SYMBOLS: phf::Map<&'static str, TSymbol> = phf_map! {
     "False" => TSymbol::known(KnownTSymbol::False),
     "Boolean" => TSymbol::known(KnownTSymbol::Boolean),
     "Integer" => TSymbol::known(KnownTSymbol::Integer),
+    "Decimal" => TSymbol::known(KnownTSymbol::Decimal),
+    "String" => TSymbol::known(KnownTSymbol::String),
+    "List" => TSymbol::known(KnownTSymbol::List),
+    "Map" => TSymbol::known(KnownTSymbol::Map),
+    "Rounding" => TSymbol::known(KnownTSymbol::Rounding),
 };
 
 impl TSymbol {
@@ -154,10 +162,6 @@ impl TSymbol {
             }
         }
     }
-
-    pub(crate) fn is_known(&self) -> bool {
-        !matches!(self.symbol, Sym::Known(_))
-    }
 }
 
 impl fmt::Display for TSymbol {
@@ -246,6 +250,14 @@ impl Path {
     pub fn is_empty(&self) -> bool {
         self.data == PathData::Empty
     }
+
+    pub fn segments(&self) -> Vec<Symbol> {
+        match &self.data {
+            PathData::Empty => Vec::default(),
+            PathData::Single(s) => vec![s.clone()],
+            PathData::Multi(v) => v.as_ref().clone(),
+        }
+    }
 }
 
 impl fmt::Display for Path {
@@ -340,6 +352,19 @@ impl<T> FQ<T> {
         }
     }
 
+    pub fn enclosed(parent: &FQType, symbol: T) -> FQ<T> {
+        FQ {
+            data: FQData::Enclosed(Arc::new(parent.clone()), symbol),
+        }
+    }
+
+    pub fn parent(&self) -> Option<&FQType> {
+        match &self.data {
+            FQData::TopLevel(_, _) => None,
+            FQData::Enclosed(parent, _) => Some(parent),
+        }
+    }
+
     pub fn is_top_level(&self) -> bool {
         match self.data {
             FQData::TopLevel(_, _) => true,
@@ -354,6 +379,13 @@ impl<T> FQ<T> {
         }
     }
 
+    pub fn symbol(&self) -> &T {
+        match &self.data {
+            FQData::TopLevel(_, symbol) => symbol,
+            FQData::Enclosed(_, symbol) => symbol,
+        }
+    }
+
     pub fn can_see<O>(&self, visibility: Visibility, symbol: &FQ<O>) -> bool {
         self.path().can_see(visibility, &symbol.path())
     }
@@ -390,6 +422,24 @@ pub static TRUE: TSymbol = TSymbol::known(KnownTSymbol::True);
 pub static FALSE: TSymbol = TSymbol::known(KnownTSymbol::False);
 pub static BOOLEAN: TSymbol = TSymbol::known(KnownTSymbol::Boolean);
 pub static INTEGER: TSymbol = TSymbol::known(KnownTSymbol::Integer);
+pub static DECIMAL: TSymbol = TSymbol::known(KnownTSymbol::Decimal);
+pub static STRING: TSymbol = TSymbol::known(KnownTSymbol::String);
+// Collection types, which are only valid with type arguments
+pub static LIST: TSymbol = TSymbol::known(KnownTSymbol::List);
+pub static MAP: TSymbol = TSymbol::known(KnownTSymbol::Map);
+
+// Field of the builtin types that have a length
+pub static LENGTH: Symbol = Symbol {
+    name: literal!("length"),
+};
+
+// Builtin functions
+pub static PRINT: Symbol = Symbol {
+    name: literal!("print"),
+};
+pub static DIV: Symbol = Symbol {
+    name: literal!("div"),
+};
 
 const fn std_type(symbol: KnownTSymbol) -> FQType {
     FQ::top_level(FQPath::std_empty(), TSymbol::known(symbol))
@@ -400,3 +450,6 @@ pub static FQ_TRUE: FQType = std_type(KnownTSymbol::True);
 pub static FQ_FALSE: FQType = std_type(KnownTSymbol::False);
 pub static FQ_BOOLEAN: FQType = std_type(KnownTSymbol::Boolean);
 pub static FQ_INTEGER: FQType = std_type(KnownTSymbol::Integer);
+pub static FQ_DECIMAL: FQType = std_type(KnownTSymbol::Decimal);
+pub static FQ_STRING: FQType = std_type(KnownTSymbol::String);
+pub static FQ_ROUNDING: FQType = std_type(KnownTSymbol::Rounding);
@@ -1,17 +1,36 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, hash_map::Entry};
 
 use ast::{
     position::Position,
-    problem::{self, ErrorType, Problems, Result},
+    problem::{self, ErrorType, Result},
+    symbol::FQSym,
 };
-use num::Integer;
 
-use crate::{symbol::FQSym, typechecked::Expression, types::Type};
+use crate::types::{TypeExpr, Value};
 
 #[derive(Debug)]
 pub struct SymbolDfn {
     position: Position,
-    expr: Expression,
+    type_expr: TypeExpr,
+    value: Value,
+}
+
+impl SymbolDfn {
+    pub(crate) fn new(position: Position, type_expr: TypeExpr, value: Value) -> Self {
+        SymbolDfn {
+            position,
+            type_expr,
+            value,
+        }
+    }
+
+    pub(crate) fn type_expr(&self) -> &TypeExpr {
+        &self.type_expr
+    }
+
+    pub(crate) fn value(&self) -> &Value {
+        &self.value
+    }
 }
 
 #[derive(Debug, Default)]
@@ -29,16 +48,19 @@ impl Symbols {
     }
 
     pub(crate) fn set(&mut self, symbol: FQSym, dfn: SymbolDfn) -> Result<()> {
-        if self.values.contains_key(&symbol) {
-            Error::DuplicateSymbol(symbol).at(dfn.position).to_err()
-        } else {
-            self.values.insert(symbol, dfn);
-            problem::ok(())
+        match self.values.entry(symbol) {
+            Entry::Occupied(e) => Error::DuplicateSymbol(e.key().clone())
+                .at(dfn.position)
+                .to_err(),
+            Entry::Vacant(e) => {
+                e.insert(dfn);
+                problem::ok(())
+            }
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 enum Error {
     DuplicateSymbol(FQSym),
 }
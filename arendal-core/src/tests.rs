@@ -0,0 +1,428 @@
+use std::any::Any;
+
+use ast::{
+    position::Position,
+    problem::{ErrorType, Problems},
+    symbol::{FQPath, FQSym, Lib, ModulePath, Symbol, TSymbol},
+};
+
+use crate::{
+    Interactive, interpreter, resolver, typechecker,
+    types::{Type, TypedValue, Value},
+};
+
+struct Checker {
+    interactive: Interactive,
+}
+
+impl Checker {
+    fn new() -> Self {
+        Checker {
+            interactive: Interactive::new(),
+        }
+    }
+
+    fn eval(&mut self, input: &str) -> Option<TypedValue> {
+        match self.interactive.eval(input).to_result() {
+            Ok(w) => w.value,
+            Err(problems) => panic!("Error evaluating {input}: {problems:?}"),
+        }
+    }
+
+    fn ok(&mut self, input: &str, value: Value, value_type: Type) -> &mut Self {
+        assert_eq!(
+            self.eval(input),
+            Some(TypedValue::new(value, value_type)),
+            "{input}"
+        );
+        self
+    }
+
+    fn int(&mut self, input: &str, value: i64) -> &mut Self {
+        self.ok(input, Value::Integer(value.into()), Type::Integer)
+    }
+
+    fn t(&mut self, input: &str) -> &mut Self {
+        self.ok(input, Value::True, Type::True)
+    }
+
+    fn f(&mut self, input: &str) -> &mut Self {
+        self.ok(input, Value::False, Type::False)
+    }
+
+    fn none(&mut self, input: &str) -> &mut Self {
+        assert_eq!(self.eval(input), None, "{input}");
+        self
+    }
+
+    fn problems(&mut self, input: &str) -> Problems {
+        match self.interactive.eval(input).to_result() {
+            Ok(w) => panic!("Expected error evaluating {input}, got {:?}", w.value),
+            Err(problems) => problems,
+        }
+    }
+
+    // Checks that the error is reported at the provided fragment of the input
+    fn err_at<E: ErrorType + PartialEq>(&mut self, input: &str, error: E, at: &str) -> &mut Self {
+        let problems = self.problems(input);
+        let found = problems.errors.iter().any(|e| {
+            (e.error() as &dyn Any).downcast_ref::<E>() == Some(&error)
+                && matches!(e.position(), Position::String(r) if r.substr().as_str() == at)
+        });
+        assert!(found, "{input}: {problems:?}");
+        self
+    }
+
+    fn err<E: ErrorType + PartialEq>(&mut self, input: &str, error: E) -> &mut Self {
+        let problems = self.problems(input);
+        assert!(problems.contains(&error), "{input}: {problems:?}");
+        self
+    }
+}
+
+fn local(name: &str) -> FQSym {
+    FQPath::new(Lib::Local, ModulePath::empty()).fq_sym(Symbol::new(name).unwrap())
+}
+
+fn local_q(name: &str) -> ast::Q<Symbol> {
+    ast::Q {
+        path: Vec::default(),
+        types: Vec::default(),
+        symbol: Symbol::new(name).unwrap(),
+    }
+}
+
+fn rounding_q(name: &str) -> ast::Q<TSymbol> {
+    ast::Q {
+        path: Vec::default(),
+        types: vec![TSymbol::new("Rounding").unwrap()],
+        symbol: TSymbol::new(name).unwrap(),
+    }
+}
+
+#[test]
+fn empty() {
+    Checker::new().none("").none(" \t\n");
+}
+
+#[test]
+fn integers() {
+    Checker::new()
+        .int("1234", 1234)
+        .int("1+2", 3)
+        .int("1 + 2 * 3", 7)
+        .int("(1 + 2) * 3", 9)
+        .int("1 - 2", -1)
+        .int("7 / 2", 3);
+}
+
+#[test]
+fn big_integers() {
+    let product: crate::Integer = "9999999999800000000001".parse().unwrap();
+    Checker::new()
+        .ok(
+            "99999999999 * 99999999999",
+            Value::Integer(product),
+            Type::Integer,
+        )
+        .int("9223372036854775807 + 1 - 1", i64::MAX)
+        .int("99999999999999999999999 / 9999999999999", 10000000000);
+}
+
+#[test]
+fn rem_and_pow() {
+    let big: crate::Integer = "1267650600228229401496703205376".parse().unwrap();
+    Checker::new()
+        .int("7 % 3", 1)
+        .int("2 ** 10 % 1000", 24)
+        .int("2 ** 3 ** 2", 512)
+        .ok("2 ** 100", Value::Integer(big), Type::Integer)
+        .err("1 % 0", interpreter::Error::DivisionByZero)
+        .err("2 ** (1 - 2)", interpreter::Error::NegativeExponent)
+        .err("7 ** 100000000", interpreter::Error::IntegerOverflow)
+        .err(
+            "1.5 % 2",
+            typechecker::Error::TypeMismatch {
+                expected: Type::Integer,
+                actual: Type::Decimal,
+            },
+        );
+}
+
+#[test]
+fn decimals() {
+    let dec = |s: &str| Value::Decimal(s.parse().unwrap());
+    let tiny = format!("let d = 0.{}1\nd * d", "0".repeat(40_000));
+    Checker::new()
+        .ok("1.25", dec("1.25"), Type::Decimal)
+        .ok("1.25 + 0.75", dec("2.00"), Type::Decimal)
+        .ok("0.1 + 0.2 - 0.3", dec("0"), Type::Decimal)
+        .ok("1.5 * 1.5", dec("2.25"), Type::Decimal)
+        .ok("let price = 9.99\nprice * 3.0", dec("29.97"), Type::Decimal)
+        .err("1.0 / 2.0", typechecker::Error::ImplicitDecimalDivision)
+        .err(&tiny, interpreter::Error::DecimalOverflow)
+        .err(
+            "1 + 1.5",
+            typechecker::Error::TypeMismatch {
+                expected: Type::Decimal,
+                actual: Type::Integer,
+            },
+        );
+}
+
+#[test]
+fn decimal_division() {
+    use num::RoundingMode::*;
+    let dec = |s: &str| Value::Decimal(s.parse().unwrap());
+    let mismatch = |expected, actual| typechecker::Error::TypeMismatch { expected, actual };
+    Checker::new()
+        .ok(
+            "div(1.0, 3.0, 4, Rounding::HalfUp)",
+            dec("0.3333"),
+            Type::Decimal,
+        )
+        .ok(
+            "div(2.0, 3.0, 2, Rounding::Down)",
+            dec("0.66"),
+            Type::Decimal,
+        )
+        .ok(
+            "div(-2.5, 1.0, 0, Rounding::HalfEven)",
+            dec("-2"),
+            Type::Decimal,
+        )
+        .ok(
+            "let r = if 1 > 0 then Rounding::Up else Rounding::Down\ndiv(1.0, 8.0, 1, r)",
+            dec("0.2"),
+            Type::Decimal,
+        )
+        .ok(
+            "Rounding::Floor",
+            Value::Rounding(Floor),
+            Type::RoundingMode(Floor),
+        )
+        .ok(
+            "(Rounding::Floor: Rounding)",
+            Value::Rounding(Floor),
+            Type::Rounding,
+        )
+        .ok("Rounding::Up == Rounding::Down", Value::False, Type::False)
+        .err(
+            "div(1.0, 0.0, 2, Rounding::Up)",
+            interpreter::Error::DivisionByZero,
+        )
+        .err(
+            "div(1.0, 3.0, -1, Rounding::Up)",
+            interpreter::Error::InvalidScale((-1).into()),
+        )
+        .err(
+            "div(1.0, 3.0, 100000, Rounding::Up)",
+            interpreter::Error::DecimalOverflow,
+        )
+        .err(
+            "div(1.0, 3.0, 2)",
+            typechecker::Error::ArgumentCount {
+                expected: 4,
+                actual: 3,
+            },
+        )
+        .err_at(
+            "div(1, 3.0, 2, Rounding::Up)",
+            mismatch(Type::Decimal, Type::Integer),
+            "1",
+        )
+        .err_at(
+            "div(1.0, 3.0, 2, True)",
+            mismatch(Type::Rounding, Type::True),
+            "True",
+        )
+        .err(
+            "div(1.0, 3.0, 2, Rounding::Sideways)",
+            resolver::Error::UnknownType(rounding_q("Sideways")),
+        )
+        .err(
+            "let d = 1\nd(1.0, 3.0, 2, Rounding::Up)",
+            resolver::Error::UnsupportedExpression,
+        );
+    // Local symbols shadow the builtin functions
+    Checker::new().int("let div = 2\ndiv * 3", 6).err(
+        "div(1.0, 3.0, 2, Rounding::Up)",
+        resolver::Error::UnsupportedExpression,
+    );
+}
+
+#[test]
+fn strings() {
+    let string = |s: &str| Value::String(s.into());
+    Checker::new()
+        .ok(r#""a\tb""#, string("a\tb"), Type::String)
+        .ok(r#""ab" + "c\{\}""#, string("abc{}"), Type::String)
+        .ok(r#""abc" < "abd""#, Value::True, Type::Boolean)
+        .ok(r#""b" >= "abc""#, Value::True, Type::Boolean)
+        .ok(r#""a" == "a" + """#, Value::True, Type::Boolean)
+        .ok(
+            "let x = 2\n\"x = {x * 3}, {\"s\"}, {1.5}, {x > 1}\"",
+            string("x = 6, s, 1.5, True"),
+            Type::String,
+        )
+        .err(
+            r#""a" + 1"#,
+            typechecker::Error::TypeMismatch {
+                expected: Type::String,
+                actual: Type::Integer,
+            },
+        )
+        .err(r#""{y}""#, resolver::Error::UnknownSymbol(local_q("y")));
+    assert_eq!(string("a\"{\n").to_string(), r#""a\"\{\n""#);
+}
+
+#[test]
+fn booleans() {
+    Checker::new()
+        .t("True")
+        .f("False")
+        .ok("True && False", Value::False, Type::Boolean)
+        .ok("True || False", Value::True, Type::Boolean)
+        .ok("False || False", Value::False, Type::Boolean);
+}
+
+#[test]
+fn unary() {
+    Checker::new()
+        .int("-7 % 3", -1)
+        .int("--2", 2)
+        .int("-2 ** 2", -4)
+        .int("let x = 3\n1 - -x", 4)
+        .ok(
+            "-1.5",
+            Value::Decimal("-1.5".parse().unwrap()),
+            Type::Decimal,
+        )
+        .f("!True")
+        .t("!False")
+        .ok("!(True && False)", Value::True, Type::Boolean)
+        .err(
+            "-True",
+            typechecker::Error::TypeMismatch {
+                expected: Type::Integer,
+                actual: Type::True,
+            },
+        )
+        .err(
+            "!1",
+            typechecker::Error::TypeMismatch {
+                expected: Type::Boolean,
+                actual: Type::Integer,
+            },
+        );
+}
+
+#[test]
+fn comparisons() {
+    let mismatch = |expected, actual| typechecker::Error::TypeMismatch { expected, actual };
+    Checker::new()
+        .ok("1 < 2", Value::True, Type::Boolean)
+        .ok("2 <= 1", Value::False, Type::Boolean)
+        .ok("2 > 1 && 2 >= 2", Value::True, Type::Boolean)
+        .ok("1 + 1 == 2", Value::True, Type::Boolean)
+        .ok("1 != 1", Value::False, Type::Boolean)
+        .ok("1.5 == 1.50", Value::True, Type::Boolean)
+        .ok("(1 < 2) == True", Value::True, Type::Boolean)
+        .t("True == True")
+        .f("True == False")
+        .t("True != False")
+        .int("if 3 > 2 then 1 else 0", 1)
+        .err_at("1 == True", mismatch(Type::Integer, Type::True), "True")
+        .err_at("1.5 != 2", mismatch(Type::Decimal, Type::Integer), "2")
+        .err_at("1 < True", mismatch(Type::Integer, Type::True), "True")
+        .err_at("1.5 > 1", mismatch(Type::Integer, Type::Decimal), "1.5");
+}
+
+#[test]
+fn seq_and_conditional() {
+    Checker::new()
+        .int("1 then 2", 2)
+        .int("if True then 1 else 2", 1)
+        .int("if False || False then 1 else 2", 2)
+        .ok("if True then True else False", Value::True, Type::Boolean);
+}
+
+#[test]
+fn assignments() {
+    Checker::new()
+        .int("let x = 1\nx + 1", 2)
+        .int("x * 3", 3)
+        .none("let y = z + 1\nlet z = x + 1")
+        .int("y", 3);
+}
+
+#[test]
+fn annotations() {
+    let mismatch = |expected, actual| typechecker::Error::TypeMismatch { expected, actual };
+    Checker::new()
+        .int("1 : Integer", 1)
+        .int("(1 + 2 : Integer) * 2", 6)
+        .ok("True : Boolean", Value::True, Type::Boolean)
+        .ok("let b: Boolean = 1 < 2\nb", Value::True, Type::Boolean)
+        .ok("let t: Boolean = True\nt", Value::True, Type::Boolean)
+        .err_at("1 : Boolean", mismatch(Type::Boolean, Type::Integer), "1")
+        .err_at(
+            "let x: True = 1 > 2",
+            mismatch(Type::True, Type::Boolean),
+            ">",
+        )
+        .err(
+            "let x: Integer = 1.5",
+            mismatch(Type::Integer, Type::Decimal),
+        )
+        .err(
+            "1 : Whole",
+            resolver::Error::UnknownType(ast::Q::of(TSymbol::new("Whole").unwrap())),
+        )
+        .err("1 : (Integer) -> Integer", resolver::Error::UnsupportedType);
+}
+
+#[test]
+fn failed_assignments_are_not_added() {
+    Checker::new()
+        .err("let x = 1 / 0", interpreter::Error::DivisionByZero)
+        .err("x", resolver::Error::UnknownSymbol(local_q("x")))
+        .int("let x = 2\nx", 2);
+}
+
+#[test]
+fn errors() {
+    Checker::new()
+        .err("1 / 0", interpreter::Error::DivisionByZero)
+        .err("x + 1", resolver::Error::UnknownSymbol(local_q("x")))
+        .err(
+            "(fn(x: Integer) = x)(1)",
+            resolver::Error::UnsupportedExpression,
+        )
+        .err(
+            "let x = 1\nlet x = 2",
+            resolver::Error::DuplicateSymbol(local("x")),
+        )
+        .err(
+            "let x = y\nlet y = x",
+            typechecker::Error::CyclicDependency(local("x")),
+        )
+        .err(
+            "1 + True",
+            typechecker::Error::TypeMismatch {
+                expected: Type::Integer,
+                actual: Type::True,
+            },
+        )
+        .err(
+            "if 1 then 2 else 3",
+            typechecker::Error::TypeMismatch {
+                expected: Type::Boolean,
+                actual: Type::Integer,
+            },
+        )
+        .err(
+            "Boolean",
+            typechecker::Error::SingletonExpected(Type::Boolean),
+        );
+}
@@ -0,0 +1,449 @@
+// Canonical serialization of the typechecked top-level definitions, used to compute
+// content-addressed ids. Locations are not included, so ids don't depend on formatting.
+// Every node starts with a tag and every variable-length item is length-prefixed, so
+// different definitions can't produce the same bytes.
+
+use std::fmt;
+
+use crate::ast0::UnaryOp;
+use crate::context::{Type, TypeDfn, TypeDfnRef, Value};
+use crate::error::L;
+use crate::id::Id;
+use crate::symbol::FQType;
+use crate::visibility::{V, Visibility};
+
+use super::{Assignment, Expr, Pattern, TLAssignment};
+
+// Bumped whenever the encoding of existing content changes, as their ids change with it
+const VERSION: u8 = 2;
+
+impl TLAssignment {
+    pub(crate) fn id(&self) -> Id {
+        let mut writer = Writer::new(Tag::TLAssignment);
+        writer.display(&self.symbol);
+        writer.visibility(self.visibility);
+        writer.expr(&self.expr);
+        writer.id()
+    }
+}
+
+pub(crate) fn type_id(symbol: &FQType, dfn: &V<TypeDfn>) -> Id {
+    let mut writer = Writer::new(Tag::NewType);
+    writer.display(symbol);
+    writer.visibility(dfn.visibility);
+    writer.type_dfn(&dfn.it);
+    writer.id()
+}
+
+// Tags are written as their values, which must not change. New tags are added at the end.
+#[derive(Clone, Copy)]
+enum Tag {
+    TLAssignment = 0,
+    NewType = 1,
+    Singleton = 2,
+    Tuple = 3,
+    Value = 4,
+    Local = 5,
+    Global = 6,
+    Seq = 7,
+    Conditional = 8,
+    Unary = 9,
+    IntAdd = 10,
+    IntSub = 11,
+    IntMul = 12,
+    IntDiv = 13,
+    IntRem = 14,
+    IntPow = 15,
+    DecAdd = 16,
+    DecSub = 17,
+    DecMul = 18,
+    LogicalAnd = 19,
+    LogicalOr = 20,
+    Block = 21,
+    None = 22,
+    Some = 23,
+    Eq = 24,
+    NEq = 25,
+    IntLT = 26,
+    IntLE = 27,
+    IntGT = 28,
+    IntGE = 29,
+    Lambda = 30,
+    Call = 31,
+    NewTuple = 32,
+    TupleField = 33,
+    NewRecord = 34,
+    RecordField = 35,
+    Update = 36,
+    Record = 37,
+    Union = 38,
+    Match = 39,
+    Wildcard = 40,
+    Binding = 41,
+    Constructor = 42,
+    StrConcat = 43,
+    StrLength = 44,
+    Interpolation = 45,
+    StrLT = 46,
+    StrLE = 47,
+    StrGT = 48,
+    StrGE = 49,
+    List = 50,
+    Map = 51,
+    ListIndex = 52,
+    MapGet = 53,
+    ListLength = 54,
+    MapLength = 55,
+    Generic = 56,
+    Var = 57,
+    Apply = 58,
+    Ascription = 59,
+    ListType = 60,
+    MapType = 61,
+    TupleType = 62,
+    FunctionType = 63,
+    TypeSymbol = 64,
+}
+
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn new(tag: Tag) -> Self {
+        let mut writer = Writer {
+            bytes: vec![VERSION],
+        };
+        writer.tag(tag);
+        writer
+    }
+
+    fn id(self) -> Id {
+        Id::digest(&self.bytes)
+    }
+
+    fn tag(&mut self, tag: Tag) {
+        self.bytes.push(tag as u8);
+    }
+
+    fn len(&mut self, len: usize) {
+        self.bytes.extend_from_slice(&(len as u64).to_le_bytes());
+    }
+
+    fn str(&mut self, value: &str) {
+        self.len(value.len());
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    fn display<T: fmt::Display>(&mut self, value: &T) {
+        self.str(&value.to_string())
+    }
+
+    fn visibility(&mut self, visibility: Visibility) {
+        self.bytes.push(match visibility {
+            Visibility::Module => 0,
+            Visibility::Package => 1,
+            Visibility::Exported => 2,
+        });
+    }
+
+    fn type_dfn(&mut self, dfn: &TypeDfn) {
+        match dfn {
+            TypeDfn::Singleton => self.tag(Tag::Singleton),
+            TypeDfn::Tuple(types) => {
+                self.tag(Tag::Tuple);
+                self.len(types.len());
+                for t in types {
+                    self.type_dfn_ref(&t.it);
+                }
+            }
+            TypeDfn::Record(fields) => {
+                self.tag(Tag::Record);
+                self.len(fields.len());
+                for (symbol, t) in fields {
+                    self.display(symbol);
+                    self.type_dfn_ref(&t.it);
+                }
+            }
+            TypeDfn::Union(variants) => {
+                self.tag(Tag::Union);
+                self.len(variants.len());
+                for (symbol, dfn) in variants {
+                    self.display(symbol);
+                    self.type_dfn(dfn);
+                }
+            }
+            TypeDfn::Generic(params, dfn) => {
+                self.tag(Tag::Generic);
+                self.len(params.len());
+                for param in params {
+                    self.display(param);
+                }
+                self.type_dfn(dfn);
+            }
+        }
+    }
+
+    fn type_dfn_ref(&mut self, dfnref: &TypeDfnRef) {
+        match dfnref {
+            TypeDfnRef::Symbol(symbol) => {
+                self.tag(Tag::TypeSymbol);
+                self.display(symbol);
+            }
+            TypeDfnRef::Var(symbol) => {
+                self.tag(Tag::Var);
+                self.display(symbol);
+            }
+            TypeDfnRef::Apply(symbol, args) => {
+                self.tag(Tag::Apply);
+                self.display(symbol);
+                self.type_dfn_refs(args);
+            }
+            TypeDfnRef::List(element) => {
+                self.tag(Tag::ListType);
+                self.type_dfn_ref(&element.it);
+            }
+            TypeDfnRef::Map(key, value) => {
+                self.tag(Tag::MapType);
+                self.type_dfn_ref(&key.it);
+                self.type_dfn_ref(&value.it);
+            }
+            TypeDfnRef::Tuple(types) => {
+                self.tag(Tag::TupleType);
+                self.type_dfn_refs(types);
+            }
+            TypeDfnRef::Function(params, result, effects) => {
+                self.tag(Tag::FunctionType);
+                self.type_dfn_refs(params);
+                self.type_dfn_ref(&result.it);
+                self.display(effects);
+            }
+        }
+    }
+
+    fn type_dfn_refs(&mut self, dfnrefs: &[L<TypeDfnRef>]) {
+        self.len(dfnrefs.len());
+        for dfnref in dfnrefs {
+            self.type_dfn_ref(&dfnref.it);
+        }
+    }
+
+    fn tipo(&mut self, tipo: &Type) {
+        self.display(tipo);
+    }
+
+    fn value(&mut self, value: &Value) {
+        self.tipo(&value.get_type());
+        self.display(value);
+    }
+
+    fn two(&mut self, tag: Tag, expr1: &L<Expr>, expr2: &L<Expr>) {
+        self.tag(tag);
+        self.expr(expr1);
+        self.expr(expr2);
+    }
+
+    fn expr(&mut self, expr: &L<Expr>) {
+        match &expr.it {
+            Expr::Value(v) => {
+                self.tag(Tag::Value);
+                self.value(v);
+            }
+            Expr::Local(l) => {
+                self.tag(Tag::Local);
+                self.display(&l.symbol);
+                self.tipo(&l.tipo);
+            }
+            Expr::Global(g) => {
+                self.tag(Tag::Global);
+                self.display(&g.symbol);
+                self.tipo(&g.tipo);
+            }
+            Expr::Seq(s) => self.two(Tag::Seq, &s.expr, &s.then),
+            Expr::Conditional(c) => {
+                self.tag(Tag::Conditional);
+                self.expr(&c.expr);
+                self.expr(&c.then);
+                self.expr(&c.otherwise);
+            }
+            Expr::Unary(u) => {
+                self.tag(Tag::Unary);
+                self.bytes.push(match u.op {
+                    UnaryOp::Minus => 0,
+                    UnaryOp::Not => 1,
+                });
+                self.expr(&u.expr);
+            }
+            Expr::IntAdd(t) => self.two(Tag::IntAdd, &t.expr1, &t.expr2),
+            Expr::IntSub(t) => self.two(Tag::IntSub, &t.expr1, &t.expr2),
+            Expr::IntMul(t) => self.two(Tag::IntMul, &t.expr1, &t.expr2),
+            Expr::IntDiv(t) => self.two(Tag::IntDiv, &t.expr1, &t.expr2),
+            Expr::IntRem(t) => self.two(Tag::IntRem, &t.expr1, &t.expr2),
+            Expr::IntPow(t) => self.two(Tag::IntPow, &t.expr1, &t.expr2),
+            Expr::DecAdd(t) => self.two(Tag::DecAdd, &t.expr1, &t.expr2),
+            Expr::DecSub(t) => self.two(Tag::DecSub, &t.expr1, &t.expr2),
+            Expr::DecMul(t) => self.two(Tag::DecMul, &t.expr1, &t.expr2),
+            Expr::StrConcat(t) => self.two(Tag::StrConcat, &t.expr1, &t.expr2),
+            Expr::StrLength(e) => {
+                self.tag(Tag::StrLength);
+                self.expr(e);
+            }
+            Expr::Interpolation(i) => {
+                self.tag(Tag::Interpolation);
+                self.exprs(&i.parts);
+            }
+            Expr::LogicalAnd(t) => self.two(Tag::LogicalAnd, &t.expr1, &t.expr2),
+            Expr::LogicalOr(t) => self.two(Tag::LogicalOr, &t.expr1, &t.expr2),
+            Expr::Eq(t) => self.two(Tag::Eq, &t.expr1, &t.expr2),
+            Expr::NEq(t) => self.two(Tag::NEq, &t.expr1, &t.expr2),
+            Expr::IntLT(t) => self.two(Tag::IntLT, &t.expr1, &t.expr2),
+            Expr::IntLE(t) => self.two(Tag::IntLE, &t.expr1, &t.expr2),
+            Expr::IntGT(t) => self.two(Tag::IntGT, &t.expr1, &t.expr2),
+            Expr::IntGE(t) => self.two(Tag::IntGE, &t.expr1, &t.expr2),
+            Expr::StrLT(t) => self.two(Tag::StrLT, &t.expr1, &t.expr2),
+            Expr::StrLE(t) => self.two(Tag::StrLE, &t.expr1, &t.expr2),
+            Expr::StrGT(t) => self.two(Tag::StrGT, &t.expr1, &t.expr2),
+            Expr::StrGE(t) => self.two(Tag::StrGE, &t.expr1, &t.expr2),
+            Expr::Block(b) => {
+                self.tag(Tag::Block);
+                self.len(b.assignments.len());
+                for a in &b.assignments {
+                    self.assignment(&a.it);
+                }
+                match &b.expr {
+                    Some(e) => {
+                        self.tag(Tag::Some);
+                        self.expr(e);
+                    }
+                    None => self.tag(Tag::None),
+                }
+            }
+            Expr::Lambda(l) => {
+                self.tag(Tag::Lambda);
+                self.tipo(&l.tipo);
+                self.len(l.params.len());
+                for p in &l.params {
+                    self.display(p);
+                }
+                self.expr(&l.body);
+            }
+            Expr::Call(c) => {
+                self.tag(Tag::Call);
+                self.expr(&c.func);
+                self.exprs(&c.args);
+            }
+            Expr::Tuple(t) => {
+                self.tag(Tag::NewTuple);
+                self.tipo(&t.tipo);
+                self.exprs(&t.exprs);
+            }
+            Expr::TupleField(f) => {
+                self.tag(Tag::TupleField);
+                self.expr(&f.expr);
+                self.len(f.index);
+            }
+            Expr::Record(r) => {
+                self.tag(Tag::NewRecord);
+                self.tipo(&r.tipo);
+                self.exprs(&r.exprs);
+            }
+            Expr::RecordField(f) => {
+                self.tag(Tag::RecordField);
+                self.expr(&f.expr);
+                self.len(f.index);
+            }
+            Expr::Update(u) => {
+                self.tag(Tag::Update);
+                self.expr(&u.expr);
+                self.len(u.fields.len());
+                for (index, e) in &u.fields {
+                    self.len(*index);
+                    self.expr(e);
+                }
+            }
+            Expr::List(l) => {
+                self.tag(Tag::List);
+                self.tipo(&l.tipo);
+                self.exprs(&l.exprs);
+            }
+            Expr::Map(m) => {
+                self.tag(Tag::Map);
+                self.tipo(&m.tipo);
+                self.len(m.entries.len());
+                for (key, value) in &m.entries {
+                    self.expr(key);
+                    self.expr(value);
+                }
+            }
+            Expr::ListIndex(i) => self.two(Tag::ListIndex, &i.expr, &i.index),
+            Expr::MapGet(i) => self.two(Tag::MapGet, &i.expr, &i.index),
+            Expr::ListLength(e) => {
+                self.tag(Tag::ListLength);
+                self.expr(e);
+            }
+            Expr::MapLength(e) => {
+                self.tag(Tag::MapLength);
+                self.expr(e);
+            }
+            Expr::Ascription(a) => {
+                self.tag(Tag::Ascription);
+                self.tipo(&a.tipo);
+                self.expr(&a.expr);
+            }
+            Expr::Match(m) => {
+                self.tag(Tag::Match);
+                self.expr(&m.expr);
+                self.len(m.arms.len());
+                for arm in &m.arms {
+                    self.pattern(&arm.pattern);
+                    self.expr(&arm.expr);
+                }
+            }
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Any => self.tag(Tag::Wildcard),
+            Pattern::Binding(symbol) => {
+                self.tag(Tag::Binding);
+                self.display(symbol);
+            }
+            Pattern::Value(v) => {
+                self.tag(Tag::Value);
+                self.value(v);
+            }
+            Pattern::Tuple(patterns) => {
+                self.tag(Tag::Tuple);
+                self.patterns(patterns);
+            }
+            Pattern::Constructor(tipo, patterns) => {
+                self.tag(Tag::Constructor);
+                self.tipo(tipo);
+                self.patterns(patterns);
+            }
+        }
+    }
+
+    fn patterns(&mut self, patterns: &[Pattern]) {
+        self.len(patterns.len());
+        for p in patterns {
+            self.pattern(p);
+        }
+    }
+
+    fn exprs(&mut self, exprs: &[L<Expr>]) {
+        self.len(exprs.len());
+        for e in exprs {
+            self.expr(e);
+        }
+    }
+
+    fn assignment(&mut self, assignment: &Assignment) {
+        self.display(&assignment.symbol);
+        self.expr(&assignment.expr);
+    }
+}
+
+#[cfg(test)]
+mod tests;
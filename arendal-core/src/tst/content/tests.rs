@@ -0,0 +1,92 @@
+use crate::env0::Env;
+use crate::id::Id;
+use crate::tst::{self, Package};
+
+fn check(input: &str) -> Package {
+    tst::check(&Env::default(), input).unwrap()
+}
+
+fn assignment_id(input: &str) -> Id {
+    let package = check(input);
+    assert_eq!(package.assignments.len(), 1);
+    package.assignments[0].it.id()
+}
+
+fn type_id(input: &str) -> Id {
+    let package = check(input);
+    assert_eq!(package.new_types.len(), 1);
+    package.ids().next().unwrap()
+}
+
+#[test]
+fn assignments() {
+    let id = assignment_id("let x = 1 + 2 * 3");
+    assert_eq!(id, assignment_id("let x=1+2*3"));
+    assert_eq!(id, assignment_id("\n\nlet x =\t1 + 2 *  3\n"));
+    assert_eq!(id, assignment_id("let x = 1 + (2 * 3)"));
+    assert_ne!(id, assignment_id("let x = (1 + 2) * 3"));
+    assert_ne!(id, assignment_id("let y = 1 + 2 * 3"));
+    assert_ne!(id, assignment_id("pub let x = 1 + 2 * 3"));
+    assert_ne!(
+        assignment_id("pub let x = 1"),
+        assignment_id("pkg let x = 1")
+    );
+    assert_ne!(id, assignment_id("let x = 1 + 2 * 4"));
+    assert_ne!(
+        assignment_id("let x = 1 + 2"),
+        assignment_id("let x = 1 - 2")
+    );
+    assert_ne!(assignment_id("let x = 1"), assignment_id("let x = 1.0"));
+    assert_ne!(assignment_id("let x = 12"), assignment_id("let x = 1 + 2"));
+    // Changes to the serialization format must be deliberate
+    assert_eq!(
+        id.as_string(),
+        "711d9cb5d64a2622c6572a50bd5c6b57359aa3c343362bac007a520498e07a3b"
+    );
+}
+
+#[test]
+fn new_types() {
+    let id = type_id(
+        "  type   Red
+",
+    );
+    assert_eq!(id, type_id("  type   Red\n"));
+    assert_ne!(id, type_id("type Green"));
+    assert_ne!(id, type_id("pub type Red"));
+    // Changes to the serialization format must be deliberate
+    assert_eq!(
+        id.as_string(),
+        "88e47485c5a77ecce888fcf44bc96ef10022f0dcaafd671188b0c7f791f5e74e"
+    );
+    assert_eq!(
+        type_id("type Shape = Circle(Integer) | Rect { w: Integer, h: Integer }").as_string(),
+        "06f5ec2a1b0513f8ed457ca70ada3dce0d297bef53822f20794f2ad20e2dcbb5"
+    );
+}
+
+// Blocks are written with an optional expression
+#[test]
+fn blocks() {
+    let id = assignment_id("let x = {\n  let y = 1\n  y + 1\n}");
+    assert_ne!(id, assignment_id("let x = {\n  let y = 1\n  y + 2\n}"));
+    assert_eq!(
+        id.as_string(),
+        "b936dd8bf9bf659584c08deb2aaacc71b3c564f6d6fbc47e5ae51474bdbd9e87"
+    );
+    assert_eq!(
+        assignment_id("let x = {\n  let y = 1\n}").as_string(),
+        "0593ef9b13f24eed818049f447795c7482e4eec067daef555988edc10b7543ec"
+    );
+}
+
+#[test]
+fn env_prefix_lookup() {
+    let mut env = Env::default();
+    env.run("let x = 1\nlet y = 2").unwrap();
+    let x = assignment_id("let x = 1");
+    let y = assignment_id("let y = 2");
+    assert_eq!(env.find_id(&x.as_string()[..8]), Ok(&x));
+    assert_eq!(env.find_id(&y.as_string()[..8]), Ok(&y));
+    assert!(env.find_id("").is_err());
+}
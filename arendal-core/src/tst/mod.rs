@@ -1,23 +1,57 @@
+mod content;
 mod typecheck;
 
-use crate::Integer;
 use crate::ast0::UnaryOp;
-use crate::context::{Context, Type, Value};
+use crate::context::{Context, Type, TypeDfnMap, Value};
+use crate::effect::Effects;
 use crate::env0::{Env, Symbols};
-use crate::error::{Error, L, Loc, Result};
-use crate::symbol::{FQSym, Pkg, Symbol};
+use crate::error::{Error, Errors, L, Loc, Result};
+use crate::id::Id;
+use crate::symbol::{FQSym, Path, Pkg, Symbol, TSymbol};
+use crate::visibility::Visibility;
+use crate::{ArcStr, Decimal, Integer};
 use std::fmt;
 use std::sync::Arc;
 
 pub(crate) fn check(env: &Env, input: &str) -> Result<Package> {
     let parsed = crate::ast0::parser::parse(Pkg::Local, input)?;
-    typecheck::check(&env, &parsed)
+    typecheck::check(env, &parsed)
+}
+
+pub(crate) fn check_modules(env: &Env, inputs: &[(Path, &str)]) -> Result<Package> {
+    let parsed = crate::ast0::parser::parse_modules(Pkg::Local, inputs)?;
+    typecheck::check(env, &parsed)
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct Unary {
-    op: UnaryOp,
-    expr: L<Expr>,
+    pub(crate) op: UnaryOp,
+    pub(crate) expr: L<Expr>,
+}
+
+impl Unary {
+    fn new(op: UnaryOp, expr: L<Expr>) -> Result<Arc<Unary>> {
+        match op {
+            UnaryOp::Minus => {
+                let tipo = expr.get_type();
+                if !tipo.is_integer() && !tipo.is_decimal() {
+                    return expr.type_mismatch(Type::type_integer());
+                }
+            }
+            UnaryOp::Not => expr.check_boolean()?,
+        }
+        Ok(Arc::new(Unary { op, expr }))
+    }
+
+    fn get_type(&self) -> Type {
+        let tipo = self.expr.get_type();
+        match self.op {
+            UnaryOp::Minus => tipo,
+            UnaryOp::Not if tipo.is_true() => Type::type_false(),
+            UnaryOp::Not if tipo.is_false() => Type::type_true(),
+            UnaryOp::Not => Type::type_boolean(),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -33,6 +67,32 @@ impl TwoInts {
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub struct TwoDecs {
+    pub(crate) expr1: L<Expr>,
+    pub(crate) expr2: L<Expr>,
+}
+
+impl TwoDecs {
+    fn new(expr1: L<Expr>, expr2: L<Expr>) -> Result<Arc<TwoDecs>> {
+        Error::merge(expr1.check_decimal(), expr2.check_decimal())?;
+        Ok(Arc::new(TwoDecs { expr1, expr2 }))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct TwoStrs {
+    pub(crate) expr1: L<Expr>,
+    pub(crate) expr2: L<Expr>,
+}
+
+impl TwoStrs {
+    fn new(expr1: L<Expr>, expr2: L<Expr>) -> Result<Arc<TwoStrs>> {
+        Error::merge(expr1.check_string(), expr2.check_string())?;
+        Ok(Arc::new(TwoStrs { expr1, expr2 }))
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct TwoBools {
     pub(crate) expr1: L<Expr>,
@@ -46,6 +106,36 @@ impl TwoBools {
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub struct TwoValues {
+    pub(crate) expr1: L<Expr>,
+    pub(crate) expr2: L<Expr>,
+}
+
+impl TwoValues {
+    fn new(expr1: L<Expr>, expr2: L<Expr>) -> Result<Arc<TwoValues>> {
+        let (type1, type2) = (expr1.get_type(), expr2.get_type());
+        if !type1.is_comparable(&type2) {
+            return expr2.type_mismatch(type1);
+        }
+        Ok(Arc::new(TwoValues { expr1, expr2 }))
+    }
+
+    // The result is known if both types are singletons
+    fn get_type(&self, equal: bool) -> Type {
+        let (type1, type2) = (self.expr1.get_type(), self.expr2.get_type());
+        if type1.is_singleton() && type2.is_singleton() {
+            if (type1 == type2) == equal {
+                Type::type_true()
+            } else {
+                Type::type_false()
+            }
+        } else {
+            Type::type_boolean()
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Seq {
     pub(crate) expr: L<Expr>,
@@ -57,31 +147,319 @@ pub struct Conditional {
     pub(crate) expr: L<Expr>,
     pub(crate) then: L<Expr>,
     pub(crate) otherwise: L<Expr>,
+    pub(crate) tipo: Type,
 }
 
 impl Conditional {
-    fn new(expr: L<Expr>, then: L<Expr>, otherwise: L<Expr>) -> Result<Arc<Self>> {
-        Error::merge(expr.check_boolean(), Self::same_types(&then, &otherwise))?;
+    // The type is the join of the types of the branches, if any
+    fn new(
+        expr: L<Expr>,
+        then: L<Expr>,
+        otherwise: L<Expr>,
+        tipo: Option<Type>,
+    ) -> Result<Arc<Self>> {
+        let tipo = Error::merge(
+            expr.check_boolean(),
+            tipo.map_or_else(|| otherwise.type_mismatch(then.get_type()), Ok),
+        )?
+        .1;
         Ok(Arc::new(Self {
             expr,
             then,
             otherwise,
+            tipo,
         }))
     }
+}
 
-    fn same_types(then: &L<Expr>, otherwise: &L<Expr>) -> Result<()> {
-        if then.get_type() == otherwise.get_type() {
-            Ok(())
-        } else {
-            otherwise.type_mismatch(then.get_type())
+#[derive(Debug, PartialEq, Eq)]
+pub struct Lambda {
+    pub(crate) params: Vec<Symbol>,
+    pub(crate) tipo: Type, // Function type
+    pub(crate) body: L<Expr>,
+}
+
+impl Lambda {
+    fn new(
+        type_params: Vec<TSymbol>,
+        params: Vec<(Symbol, Type)>,
+        result: Option<Type>,
+        effects: Effects,
+        body: L<Expr>,
+    ) -> Result<Arc<Self>> {
+        let result = match result {
+            Some(tipo) if !tipo.accepts(&body.get_type()) => return body.type_mismatch(tipo),
+            Some(tipo) => tipo,
+            None => body.get_type(),
+        };
+        let (params, types) = params.into_iter().unzip();
+        Ok(Arc::new(Lambda {
+            params,
+            tipo: Type::type_generic(
+                type_params,
+                Type::type_function_with_effects(types, result, effects),
+            ),
+            body,
+        }))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Call {
+    pub(crate) func: L<Expr>,
+    pub(crate) args: Vec<L<Expr>>,
+    pub(crate) tipo: Type, // Function type, instantiated for generic functions
+}
+
+impl Call {
+    fn new(loc: &Loc, func: L<Expr>, tipo: Type, args: Vec<L<Expr>>) -> Result<Arc<Self>> {
+        let function = match tipo.as_function() {
+            Some(function) => function,
+            None => return func.err(Error::FunctionExpected(tipo.clone())),
+        };
+        if function.params.len() != args.len() {
+            return loc.err(Error::ArgumentCountMismatch(
+                function.params.len(),
+                args.len(),
+            ));
+        }
+        let mut errors = Errors::default();
+        for (param, arg) in function.params.iter().zip(&args) {
+            if !param.accepts(&arg.get_type()) {
+                errors.add_result(arg.type_mismatch::<()>(param.clone()));
+            }
         }
+        errors.to_unit_result()?;
+        Ok(Arc::new(Call { func, args, tipo }))
     }
 
     fn get_type(&self) -> Type {
-        self.then.get_type()
+        self.tipo.as_function().unwrap().result.clone()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Tuple {
+    pub(crate) tipo: Type,
+    pub(crate) exprs: Vec<L<Expr>>,
+}
+
+impl Tuple {
+    // Checks the expressions against the element types of the tuple type
+    fn new(loc: &Loc, tipo: Type, types: Vec<Type>, exprs: Vec<L<Expr>>) -> Result<Arc<Self>> {
+        if types.len() != exprs.len() {
+            return loc.err(Error::ArgumentCountMismatch(types.len(), exprs.len()));
+        }
+        let mut errors = Errors::default();
+        for (t, expr) in types.iter().zip(&exprs) {
+            if !t.accepts(&expr.get_type()) {
+                errors.add_result(expr.type_mismatch::<()>(t.clone()));
+            }
+        }
+        errors.to_unit_result()?;
+        Ok(Arc::new(Tuple { tipo, exprs }))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct TupleField {
+    pub(crate) expr: L<Expr>,
+    pub(crate) index: usize,
+    pub(crate) tipo: Type, // Type of the field
+}
+
+impl TupleField {
+    fn new(loc: &Loc, expr: L<Expr>, types: Option<Vec<Type>>, index: usize) -> Result<Arc<Self>> {
+        let tipo = expr.get_type();
+        match types {
+            None => loc.err(Error::TupleExpected(tipo)),
+            Some(types) => match types.get(index) {
+                None => loc.err(Error::TupleIndexOutOfBounds(tipo, index)),
+                Some(t) => Ok(Arc::new(TupleField {
+                    expr,
+                    index,
+                    tipo: t.clone(),
+                })),
+            },
+        }
+    }
+}
+
+// Field values by position in the record type, checked against the field types
+fn record_values(
+    tipo: &Type,
+    fields: &[(Symbol, Type)],
+    values: Vec<(Symbol, L<Expr>)>,
+) -> Result<Vec<(usize, L<Expr>)>> {
+    let mut errors = Errors::default();
+    let mut checked: Vec<(usize, L<Expr>)> = Vec::with_capacity(values.len());
+    for (symbol, expr) in values {
+        match fields.iter().position(|(s, _)| *s == symbol) {
+            None => errors.add(expr.error(Error::UnknownField(tipo.clone(), symbol))),
+            Some(index) if checked.iter().any(|(i, _)| *i == index) => {
+                errors.add(expr.error(Error::DuplicateField(symbol)))
+            }
+            Some(index) => {
+                let field_type = &fields[index].1;
+                if field_type.accepts(&expr.get_type()) {
+                    checked.push((index, expr));
+                } else {
+                    errors.add_result(expr.type_mismatch::<()>(field_type.clone()));
+                }
+            }
+        }
+    }
+    errors.to_result(checked)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Record {
+    pub(crate) tipo: Type,
+    pub(crate) exprs: Vec<L<Expr>>, // In definition order
+}
+
+impl Record {
+    fn new(
+        loc: &Loc,
+        tipo: Type,
+        fields: Option<Vec<(Symbol, Type)>>,
+        values: Vec<(Symbol, L<Expr>)>,
+    ) -> Result<Arc<Self>> {
+        let Some(fields) = fields else {
+            return loc.err(Error::RecordExpected(tipo));
+        };
+        let mut checked = record_values(&tipo, &fields, values)?;
+        let mut errors = Errors::default();
+        for (index, (symbol, _)) in fields.iter().enumerate() {
+            if !checked.iter().any(|(i, _)| *i == index) {
+                errors.add_result(loc.err::<()>(Error::MissingField(tipo.clone(), symbol.clone())));
+            }
+        }
+        errors.to_unit_result()?;
+        checked.sort_by_key(|(i, _)| *i);
+        let exprs = checked.into_iter().map(|(_, e)| e).collect();
+        Ok(Arc::new(Record { tipo, exprs }))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct RecordField {
+    pub(crate) expr: L<Expr>,
+    pub(crate) index: usize,
+    pub(crate) tipo: Type, // Type of the field
+}
+
+impl RecordField {
+    fn new(
+        loc: &Loc,
+        expr: L<Expr>,
+        fields: Option<Vec<(Symbol, Type)>>,
+        symbol: Symbol,
+    ) -> Result<Arc<Self>> {
+        let tipo = expr.get_type();
+        let Some(fields) = fields else {
+            return loc.err(Error::RecordExpected(tipo));
+        };
+        match fields
+            .into_iter()
+            .enumerate()
+            .find(|(_, (s, _))| *s == symbol)
+        {
+            Some((index, (_, tipo))) => Ok(Arc::new(RecordField { expr, index, tipo })),
+            None => loc.err(Error::UnknownField(tipo, symbol)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Update {
+    pub(crate) expr: L<Expr>,
+    pub(crate) fields: Vec<(usize, L<Expr>)>,
+}
+
+impl Update {
+    fn new(
+        loc: &Loc,
+        expr: L<Expr>,
+        fields: Option<Vec<(Symbol, Type)>>,
+        values: Vec<(Symbol, L<Expr>)>,
+    ) -> Result<Arc<Self>> {
+        let tipo = expr.get_type();
+        let Some(fields) = fields else {
+            return loc.err(Error::RecordExpected(tipo));
+        };
+        let fields = record_values(&tipo, &fields, values)?;
+        Ok(Arc::new(Update { expr, fields }))
+    }
+}
+
+// The type is the list type of the join of the element types
+#[derive(Debug, PartialEq, Eq)]
+pub struct List {
+    pub(crate) tipo: Type,
+    pub(crate) exprs: Vec<L<Expr>>,
+}
+
+// The type is the map type of the joins of the key and value types
+#[derive(Debug, PartialEq, Eq)]
+pub struct Map {
+    pub(crate) tipo: Type,
+    pub(crate) entries: Vec<(L<Expr>, L<Expr>)>,
+}
+
+// Element of a list by position, or value of a map by key
+#[derive(Debug, PartialEq, Eq)]
+pub struct Index {
+    pub(crate) expr: L<Expr>,
+    pub(crate) index: L<Expr>,
+    pub(crate) tipo: Type, // Type of the element or value
+}
+
+// The type is the ascribed one, which accepts the type of the expression
+#[derive(Debug, PartialEq, Eq)]
+pub struct Ascription {
+    pub(crate) expr: L<Expr>,
+    pub(crate) tipo: Type,
+}
+
+impl Ascription {
+    fn new(expr: L<Expr>, tipo: Type) -> Result<Arc<Self>> {
+        if tipo.accepts(&expr.get_type()) {
+            Ok(Arc::new(Ascription { expr, tipo }))
+        } else {
+            expr.type_mismatch(tipo)
+        }
     }
 }
 
+// The text of the interpolated values is concatenated
+#[derive(Debug, PartialEq, Eq)]
+pub struct Interpolation {
+    pub(crate) parts: Vec<L<Expr>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    Any,
+    Binding(Symbol),
+    Value(Value),                    // Integer literals and singletons
+    Tuple(Vec<Pattern>),             // Anonymous tuples
+    Constructor(Type, Vec<Pattern>), // Named tuples and records, with the fields in definition order
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Arm {
+    pub(crate) pattern: Pattern,
+    pub(crate) expr: L<Expr>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Match {
+    pub(crate) expr: L<Expr>,
+    pub(crate) arms: Vec<Arm>,
+    pub(crate) tipo: Type, // Join of the types of the arms
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct Assignment {
     pub(crate) symbol: Symbol,
@@ -130,9 +508,42 @@ pub enum Expr {
     IntSub(Arc<TwoInts>),
     IntMul(Arc<TwoInts>),
     IntDiv(Arc<TwoInts>),
+    IntRem(Arc<TwoInts>),
+    IntPow(Arc<TwoInts>),
+    DecAdd(Arc<TwoDecs>),
+    DecSub(Arc<TwoDecs>),
+    DecMul(Arc<TwoDecs>),
+    StrConcat(Arc<TwoStrs>),
+    StrLength(Arc<L<Expr>>),
+    Interpolation(Arc<Interpolation>),
     LogicalAnd(Arc<TwoBools>),
     LogicalOr(Arc<TwoBools>),
+    Eq(Arc<TwoValues>),
+    NEq(Arc<TwoValues>),
+    IntLT(Arc<TwoInts>),
+    IntLE(Arc<TwoInts>),
+    IntGT(Arc<TwoInts>),
+    IntGE(Arc<TwoInts>),
+    StrLT(Arc<TwoStrs>),
+    StrLE(Arc<TwoStrs>),
+    StrGT(Arc<TwoStrs>),
+    StrGE(Arc<TwoStrs>),
     Block(Arc<Block>),
+    Lambda(Arc<Lambda>),
+    Call(Arc<Call>),
+    Tuple(Arc<Tuple>),
+    TupleField(Arc<TupleField>),
+    Record(Arc<Record>),
+    RecordField(Arc<RecordField>),
+    Update(Arc<Update>),
+    List(Arc<List>),
+    Map(Arc<Map>),
+    ListIndex(Arc<Index>),
+    MapGet(Arc<Index>),
+    ListLength(Arc<L<Expr>>),
+    MapLength(Arc<L<Expr>>),
+    Match(Arc<Match>),
+    Ascription(Arc<Ascription>),
 }
 
 impl Expr {
@@ -142,14 +553,39 @@ impl Expr {
             Self::Local(l) => l.tipo.clone(),
             Self::Global(g) => g.tipo.clone(),
             Self::Seq(s) => s.then.get_type(),
-            Self::Conditional(c) => c.get_type(),
-            Self::Unary(u) => u.expr.get_type(),
+            Self::Conditional(c) => c.tipo.clone(),
+            Self::Unary(u) => u.get_type(),
             Self::IntAdd(t) => t.expr1.get_type(),
             Self::IntSub(t) => t.expr1.get_type(),
             Self::IntMul(t) => t.expr1.get_type(),
             Self::IntDiv(t) => t.expr1.get_type(),
+            Self::IntRem(t) => t.expr1.get_type(),
+            Self::IntPow(t) => t.expr1.get_type(),
+            Self::DecAdd(_) | Self::DecSub(_) | Self::DecMul(_) => Type::type_decimal(),
+            Self::StrConcat(_) | Self::Interpolation(_) => Type::type_string(),
+            Self::StrLength(_) | Self::ListLength(_) | Self::MapLength(_) => Type::type_integer(),
             Self::LogicalAnd(_) | Self::LogicalOr(_) => Type::type_boolean(),
+            Self::Eq(t) => t.get_type(true),
+            Self::NEq(t) => t.get_type(false),
+            Self::IntLT(_) | Self::IntLE(_) | Self::IntGT(_) | Self::IntGE(_) => {
+                Type::type_boolean()
+            }
+            Self::StrLT(_) | Self::StrLE(_) | Self::StrGT(_) | Self::StrGE(_) => {
+                Type::type_boolean()
+            }
             Self::Block(b) => b.get_type(),
+            Self::Lambda(l) => l.tipo.clone(),
+            Self::Call(c) => c.get_type(),
+            Self::Tuple(t) => t.tipo.clone(),
+            Self::TupleField(f) => f.tipo.clone(),
+            Self::Record(r) => r.tipo.clone(),
+            Self::RecordField(f) => f.tipo.clone(),
+            Self::Update(u) => u.expr.get_type(),
+            Self::List(l) => l.tipo.clone(),
+            Self::Map(m) => m.tipo.clone(),
+            Self::ListIndex(i) | Self::MapGet(i) => i.tipo.clone(),
+            Self::Match(m) => m.tipo.clone(),
+            Self::Ascription(a) => a.tipo.clone(),
         }
     }
 }
@@ -167,6 +603,22 @@ impl L<Expr> {
         }
     }
 
+    fn check_decimal(&self) -> Result<()> {
+        if self.get_type().is_decimal() {
+            Ok(())
+        } else {
+            self.type_mismatch(Type::type_decimal())
+        }
+    }
+
+    fn check_string(&self) -> Result<()> {
+        if self.get_type().is_string() {
+            Ok(())
+        } else {
+            self.type_mismatch(Type::type_string())
+        }
+    }
+
     fn check_boolean(&self) -> Result<()> {
         if self.get_type().is_boolean() {
             Ok(())
@@ -182,12 +634,61 @@ impl L<Expr> {
 
 impl fmt::Debug for Expr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?} : {:?}", self, self.get_type())
+        let (name, it): (&str, &dyn fmt::Debug) = match self {
+            Self::Value(v) => ("Value", v),
+            Self::Local(l) => ("Local", l),
+            Self::Global(g) => ("Global", g),
+            Self::Seq(s) => ("Seq", s),
+            Self::Conditional(c) => ("Conditional", c),
+            Self::Unary(u) => ("Unary", u),
+            Self::IntAdd(t) => ("IntAdd", t),
+            Self::IntSub(t) => ("IntSub", t),
+            Self::IntMul(t) => ("IntMul", t),
+            Self::IntDiv(t) => ("IntDiv", t),
+            Self::IntRem(t) => ("IntRem", t),
+            Self::IntPow(t) => ("IntPow", t),
+            Self::DecAdd(t) => ("DecAdd", t),
+            Self::DecSub(t) => ("DecSub", t),
+            Self::DecMul(t) => ("DecMul", t),
+            Self::StrConcat(t) => ("StrConcat", t),
+            Self::StrLength(e) => ("StrLength", e),
+            Self::Interpolation(i) => ("Interpolation", i),
+            Self::LogicalAnd(t) => ("LogicalAnd", t),
+            Self::LogicalOr(t) => ("LogicalOr", t),
+            Self::Eq(t) => ("Eq", t),
+            Self::NEq(t) => ("NEq", t),
+            Self::IntLT(t) => ("IntLT", t),
+            Self::IntLE(t) => ("IntLE", t),
+            Self::IntGT(t) => ("IntGT", t),
+            Self::IntGE(t) => ("IntGE", t),
+            Self::StrLT(t) => ("StrLT", t),
+            Self::StrLE(t) => ("StrLE", t),
+            Self::StrGT(t) => ("StrGT", t),
+            Self::StrGE(t) => ("StrGE", t),
+            Self::Block(b) => ("Block", b),
+            Self::Lambda(l) => ("Lambda", l),
+            Self::Call(c) => ("Call", c),
+            Self::Tuple(t) => ("Tuple", t),
+            Self::TupleField(f) => ("TupleField", f),
+            Self::Record(r) => ("Record", r),
+            Self::RecordField(f) => ("RecordField", f),
+            Self::Update(u) => ("Update", u),
+            Self::List(l) => ("List", l),
+            Self::Map(m) => ("Map", m),
+            Self::ListIndex(i) => ("ListIndex", i),
+            Self::MapGet(i) => ("MapGet", i),
+            Self::ListLength(e) => ("ListLength", e),
+            Self::MapLength(e) => ("MapLength", e),
+            Self::Match(m) => ("Match", m),
+            Self::Ascription(a) => ("Ascription", a),
+        };
+        write!(f, "{}({:?}) : {:?}", name, it, self.get_type())
     }
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct TLAssignment {
+    pub(crate) visibility: Visibility,
     pub(crate) symbol: FQSym,
     pub(crate) expr: L<Expr>,
 }
@@ -221,10 +722,19 @@ impl Builder {
         self.value(Value::v_integer(&self.loc, Type::type_integer(), value).unwrap())
     }
 
+    fn val_decimal(&self, value: Decimal) -> L<Expr> {
+        self.value(Value::v_decimal(&self.loc, Type::type_decimal(), value).unwrap())
+    }
+
+    fn val_string(&self, value: ArcStr) -> L<Expr> {
+        self.value(Value::v_string(&self.loc, Type::type_string(), value).unwrap())
+    }
+
     fn local0(&self, local: Local) -> L<Expr> {
         self.build(Expr::Local(Arc::new(local)))
     }
 
+    #[allow(dead_code)]
     fn local(&self, symbol: Symbol, tipo: Type) -> L<Expr> {
         self.local0(Local { symbol, tipo })
     }
@@ -233,6 +743,7 @@ impl Builder {
         self.build(Expr::Global(Arc::new(global)))
     }
 
+    #[allow(dead_code)]
     fn global(&self, symbol: FQSym, tipo: Type) -> L<Expr> {
         self.global0(Global { symbol, tipo })
     }
@@ -241,20 +752,38 @@ impl Builder {
         self.build(Expr::Seq(Arc::new(Seq { expr, then })))
     }
 
-    fn conditional(&self, expr: L<Expr>, then: L<Expr>, otherwise: L<Expr>) -> Result<L<Expr>> {
-        self.ok(Expr::Conditional(Conditional::new(expr, then, otherwise)?))
+    fn conditional(
+        &self,
+        expr: L<Expr>,
+        then: L<Expr>,
+        otherwise: L<Expr>,
+        tipo: Option<Type>,
+    ) -> Result<L<Expr>> {
+        self.ok(Expr::Conditional(Conditional::new(
+            expr, then, otherwise, tipo,
+        )?))
     }
 
     fn assignment(&self, symbol: Symbol, expr: L<Expr>) -> L<Assignment> {
         self.loc.wrap(Assignment { symbol, expr })
     }
 
-    fn tl_assignment(&self, symbol: FQSym, expr: L<Expr>) -> L<TLAssignment> {
-        self.loc.wrap(TLAssignment { symbol, expr })
+    #[allow(dead_code)]
+    fn tl_assignment(
+        &self,
+        visibility: Visibility,
+        symbol: FQSym,
+        expr: L<Expr>,
+    ) -> L<TLAssignment> {
+        self.loc.wrap(TLAssignment {
+            visibility,
+            symbol,
+            expr,
+        })
     }
 
-    fn unary(&self, op: UnaryOp, expr: L<Expr>) -> L<Expr> {
-        self.build(Expr::Unary(Arc::new(Unary { op, expr })))
+    fn unary(&self, op: UnaryOp, expr: L<Expr>) -> Result<L<Expr>> {
+        self.ok(Expr::Unary(Unary::new(op, expr)?))
     }
 
     fn int_add(&self, expr1: L<Expr>, expr2: L<Expr>) -> Result<L<Expr>> {
@@ -273,6 +802,39 @@ impl Builder {
         self.ok(Expr::IntDiv(TwoInts::new(expr1, expr2)?))
     }
 
+    fn int_rem(&self, expr1: L<Expr>, expr2: L<Expr>) -> Result<L<Expr>> {
+        self.ok(Expr::IntRem(TwoInts::new(expr1, expr2)?))
+    }
+
+    fn int_pow(&self, expr1: L<Expr>, expr2: L<Expr>) -> Result<L<Expr>> {
+        self.ok(Expr::IntPow(TwoInts::new(expr1, expr2)?))
+    }
+
+    fn dec_add(&self, expr1: L<Expr>, expr2: L<Expr>) -> Result<L<Expr>> {
+        self.ok(Expr::DecAdd(TwoDecs::new(expr1, expr2)?))
+    }
+
+    fn dec_sub(&self, expr1: L<Expr>, expr2: L<Expr>) -> Result<L<Expr>> {
+        self.ok(Expr::DecSub(TwoDecs::new(expr1, expr2)?))
+    }
+
+    fn dec_mul(&self, expr1: L<Expr>, expr2: L<Expr>) -> Result<L<Expr>> {
+        self.ok(Expr::DecMul(TwoDecs::new(expr1, expr2)?))
+    }
+
+    fn str_concat(&self, expr1: L<Expr>, expr2: L<Expr>) -> Result<L<Expr>> {
+        self.ok(Expr::StrConcat(TwoStrs::new(expr1, expr2)?))
+    }
+
+    fn str_length(&self, expr: L<Expr>) -> Result<L<Expr>> {
+        expr.check_string()?;
+        self.ok(Expr::StrLength(Arc::new(expr)))
+    }
+
+    fn interpolation(&self, parts: Vec<L<Expr>>) -> L<Expr> {
+        self.build(Expr::Interpolation(Arc::new(Interpolation { parts })))
+    }
+
     fn log_and(&self, expr1: L<Expr>, expr2: L<Expr>) -> Result<L<Expr>> {
         self.ok(Expr::LogicalAnd(TwoBools::new(expr1, expr2)?))
     }
@@ -281,6 +843,163 @@ impl Builder {
         self.ok(Expr::LogicalOr(TwoBools::new(expr1, expr2)?))
     }
 
+    fn eq(&self, expr1: L<Expr>, expr2: L<Expr>) -> Result<L<Expr>> {
+        self.ok(Expr::Eq(TwoValues::new(expr1, expr2)?))
+    }
+
+    fn neq(&self, expr1: L<Expr>, expr2: L<Expr>) -> Result<L<Expr>> {
+        self.ok(Expr::NEq(TwoValues::new(expr1, expr2)?))
+    }
+
+    fn int_lt(&self, expr1: L<Expr>, expr2: L<Expr>) -> Result<L<Expr>> {
+        self.ok(Expr::IntLT(TwoInts::new(expr1, expr2)?))
+    }
+
+    fn int_le(&self, expr1: L<Expr>, expr2: L<Expr>) -> Result<L<Expr>> {
+        self.ok(Expr::IntLE(TwoInts::new(expr1, expr2)?))
+    }
+
+    fn int_gt(&self, expr1: L<Expr>, expr2: L<Expr>) -> Result<L<Expr>> {
+        self.ok(Expr::IntGT(TwoInts::new(expr1, expr2)?))
+    }
+
+    fn int_ge(&self, expr1: L<Expr>, expr2: L<Expr>) -> Result<L<Expr>> {
+        self.ok(Expr::IntGE(TwoInts::new(expr1, expr2)?))
+    }
+
+    fn str_lt(&self, expr1: L<Expr>, expr2: L<Expr>) -> Result<L<Expr>> {
+        self.ok(Expr::StrLT(TwoStrs::new(expr1, expr2)?))
+    }
+
+    fn str_le(&self, expr1: L<Expr>, expr2: L<Expr>) -> Result<L<Expr>> {
+        self.ok(Expr::StrLE(TwoStrs::new(expr1, expr2)?))
+    }
+
+    fn str_gt(&self, expr1: L<Expr>, expr2: L<Expr>) -> Result<L<Expr>> {
+        self.ok(Expr::StrGT(TwoStrs::new(expr1, expr2)?))
+    }
+
+    fn str_ge(&self, expr1: L<Expr>, expr2: L<Expr>) -> Result<L<Expr>> {
+        self.ok(Expr::StrGE(TwoStrs::new(expr1, expr2)?))
+    }
+
+    fn lambda(
+        &self,
+        type_params: Vec<TSymbol>,
+        params: Vec<(Symbol, Type)>,
+        result: Option<Type>,
+        effects: Effects,
+        body: L<Expr>,
+    ) -> Result<L<Expr>> {
+        self.ok(Expr::Lambda(Lambda::new(
+            type_params,
+            params,
+            result,
+            effects,
+            body,
+        )?))
+    }
+
+    fn call(&self, func: L<Expr>, tipo: Type, args: Vec<L<Expr>>) -> Result<L<Expr>> {
+        self.ok(Expr::Call(Call::new(&self.loc, func, tipo, args)?))
+    }
+
+    fn tuple(&self, tipo: Type, types: Vec<Type>, exprs: Vec<L<Expr>>) -> Result<L<Expr>> {
+        self.ok(Expr::Tuple(Tuple::new(&self.loc, tipo, types, exprs)?))
+    }
+
+    fn tuple_field(
+        &self,
+        expr: L<Expr>,
+        types: Option<Vec<Type>>,
+        index: usize,
+    ) -> Result<L<Expr>> {
+        self.ok(Expr::TupleField(TupleField::new(
+            &self.loc, expr, types, index,
+        )?))
+    }
+
+    fn record(
+        &self,
+        tipo: Type,
+        fields: Option<Vec<(Symbol, Type)>>,
+        values: Vec<(Symbol, L<Expr>)>,
+    ) -> Result<L<Expr>> {
+        self.ok(Expr::Record(Record::new(&self.loc, tipo, fields, values)?))
+    }
+
+    fn record_field(
+        &self,
+        expr: L<Expr>,
+        fields: Option<Vec<(Symbol, Type)>>,
+        symbol: Symbol,
+    ) -> Result<L<Expr>> {
+        self.ok(Expr::RecordField(RecordField::new(
+            &self.loc, expr, fields, symbol,
+        )?))
+    }
+
+    fn update(
+        &self,
+        expr: L<Expr>,
+        fields: Option<Vec<(Symbol, Type)>>,
+        values: Vec<(Symbol, L<Expr>)>,
+    ) -> Result<L<Expr>> {
+        self.ok(Expr::Update(Update::new(&self.loc, expr, fields, values)?))
+    }
+
+    fn list(&self, element: Type, exprs: Vec<L<Expr>>) -> L<Expr> {
+        let tipo = Type::type_list(element);
+        self.build(Expr::List(Arc::new(List { tipo, exprs })))
+    }
+
+    fn map(&self, key: Type, value: Type, entries: Vec<(L<Expr>, L<Expr>)>) -> L<Expr> {
+        let tipo = Type::type_map(key, value);
+        self.build(Expr::Map(Arc::new(Map { tipo, entries })))
+    }
+
+    // Lists are indexed by position and maps by key
+    fn index(&self, expr: L<Expr>, index: L<Expr>) -> Result<L<Expr>> {
+        let tipo = expr.get_type();
+        if let Some(element) = tipo.list_element() {
+            index.check_integer()?;
+            let index = Index {
+                expr,
+                index,
+                tipo: element,
+            };
+            self.ok(Expr::ListIndex(Arc::new(index)))
+        } else if let Some((key, value)) = tipo.map_types() {
+            if !key.accepts(&index.get_type()) {
+                return index.type_mismatch(key);
+            }
+            let index = Index {
+                expr,
+                index,
+                tipo: value,
+            };
+            self.ok(Expr::MapGet(Arc::new(index)))
+        } else {
+            self.loc.err(Error::CollectionExpected(tipo))
+        }
+    }
+
+    fn list_length(&self, expr: L<Expr>) -> L<Expr> {
+        self.build(Expr::ListLength(Arc::new(expr)))
+    }
+
+    fn map_length(&self, expr: L<Expr>) -> L<Expr> {
+        self.build(Expr::MapLength(Arc::new(expr)))
+    }
+
+    fn ascription(&self, expr: L<Expr>, tipo: Type) -> Result<L<Expr>> {
+        self.ok(Expr::Ascription(Ascription::new(expr, tipo)?))
+    }
+
+    fn pattern_match(&self, expr: L<Expr>, arms: Vec<Arm>, tipo: Type) -> L<Expr> {
+        self.build(Expr::Match(Arc::new(Match { expr, arms, tipo })))
+    }
+
     fn block(&self, assignments: Vec<L<Assignment>>, expr: Option<L<Expr>>) -> Result<L<Expr>> {
         if assignments.is_empty() {
             Ok(expr.or_else(|| Some(self.v_none())).unwrap())
@@ -292,9 +1011,22 @@ impl Builder {
 
 #[derive(Debug)]
 pub(super) struct Package {
+    #[allow(dead_code)]
     pub(crate) pkg: Pkg,
     pub(crate) types: Context,
+    pub(crate) new_types: TypeDfnMap,
     pub(crate) symbols: Symbols,
     pub(crate) assignments: Vec<L<TLAssignment>>,
     pub(crate) expr: Option<L<Expr>>,
 }
+
+impl Package {
+    // Content-addressed ids of the new definitions
+    pub(crate) fn ids(&self) -> impl Iterator<Item = Id> + '_ {
+        let types = self
+            .new_types
+            .iter()
+            .map(|(symbol, dfn)| content::type_id(symbol, &dfn.it));
+        types.chain(self.assignments.iter().map(|a| a.it.id()))
+    }
+}
@@ -0,0 +1,320 @@
+// Dependency graph between top-level assignments. Assignments are checked one strongly
+// connected component at a time, with the dependencies of a component checked before it.
+
+use std::collections::{HashMap, VecDeque};
+
+use im::HashSet;
+
+use crate::ast0::{self, ExprRef};
+use crate::error::{L, Loc};
+use crate::symbol::{FQSym, Symbol};
+
+use super::{FQResolvers, Input};
+
+#[derive(Debug)]
+pub(super) struct Graph {
+    nodes: Vec<FQSym>,
+    indices: HashMap<FQSym, usize>,
+    edges: Vec<Vec<usize>>,
+}
+
+impl Graph {
+    pub(super) fn new(input: &Input, fqresolvers: &FQResolvers) -> Self {
+        // Sorted, so that the checking order and the reported cycles are deterministic
+        let mut nodes: Vec<FQSym> = input.assignments.keys().cloned().collect();
+        nodes.sort_by_cached_key(|fq| fq.to_string());
+        let indices: HashMap<FQSym, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, fq)| (fq.clone(), i))
+            .collect();
+        let edges = nodes
+            .iter()
+            .map(|fq| {
+                let mut collector = Collector {
+                    fqresolvers,
+                    fq,
+                    indices: &indices,
+                    edges: Vec::default(),
+                };
+                let a = input.assignments.get(fq).unwrap();
+                collector.expr(&HashSet::default(), &a.it.it.expr);
+                collector.edges
+            })
+            .collect();
+        Graph {
+            nodes,
+            indices,
+            edges,
+        }
+    }
+
+    // Returns the strongly connected components, each one after all its dependencies
+    pub(super) fn components(&self) -> Vec<Vec<FQSym>> {
+        let mut tarjan = Tarjan {
+            graph: self,
+            index: 0,
+            indices: vec![None; self.nodes.len()],
+            lowlinks: vec![0; self.nodes.len()],
+            stack: Vec::default(),
+            on_stack: vec![false; self.nodes.len()],
+            components: Vec::default(),
+        };
+        for node in 0..self.nodes.len() {
+            if tarjan.indices[node].is_none() {
+                tarjan.visit(node);
+            }
+        }
+        tarjan
+            .components
+            .into_iter()
+            .map(|c| c.into_iter().map(|n| self.nodes[n].clone()).collect())
+            .collect()
+    }
+
+    fn index(&self, fq: &FQSym) -> usize {
+        self.indices[fq]
+    }
+
+    // Returns whether the component depends on itself
+    pub(super) fn is_cyclic(&self, component: &[FQSym]) -> bool {
+        component.len() > 1 || {
+            let node = self.index(&component[0]);
+            self.edges[node].contains(&node)
+        }
+    }
+
+    // Returns whether the component depends on any of the provided symbols
+    pub(super) fn depends_on(&self, component: &[FQSym], symbols: &[FQSym]) -> bool {
+        component.iter().any(|fq| {
+            self.edges[self.index(fq)]
+                .iter()
+                .any(|to| symbols.contains(&self.nodes[*to]))
+        })
+    }
+
+    // Returns the shortest cycle within the component starting and ending at the provided symbol
+    pub(super) fn cycle(&self, component: &[FQSym], from: &FQSym) -> Vec<FQSym> {
+        let members: std::collections::HashSet<usize> =
+            component.iter().map(|fq| self.index(fq)).collect();
+        let start = self.index(from);
+        let mut previous: HashMap<usize, usize> = HashMap::default();
+        let mut queue = VecDeque::from([start]);
+        while let Some(node) = queue.pop_front() {
+            for to in &self.edges[node] {
+                if members.contains(to) && !previous.contains_key(to) {
+                    previous.insert(*to, node);
+                    queue.push_back(*to);
+                }
+            }
+            if previous.contains_key(&start) {
+                break;
+            }
+        }
+        let mut cycle = vec![from.clone()];
+        let mut node = previous[&start];
+        while node != start {
+            cycle.push(self.nodes[node].clone());
+            node = previous[&node];
+        }
+        cycle.push(from.clone());
+        cycle.reverse();
+        cycle
+    }
+}
+
+struct Tarjan<'a> {
+    graph: &'a Graph,
+    index: usize,
+    indices: Vec<Option<usize>>,
+    lowlinks: Vec<usize>,
+    stack: Vec<usize>,
+    on_stack: Vec<bool>,
+    components: Vec<Vec<usize>>,
+}
+
+// The traversal uses an explicit stack, as the graph of a large input can be deep enough
+// to overflow the native one.
+impl Tarjan<'_> {
+    fn visit(&mut self, root: usize) {
+        // Nodes being visited, with the position of the next edge to follow
+        let mut visiting = vec![(root, 0)];
+        self.enter(root);
+        while let Some((node, edge)) = visiting.last_mut() {
+            let node = *node;
+            if let Some(&to) = self.graph.edges[node].get(*edge) {
+                *edge += 1;
+                match self.indices[to] {
+                    None => {
+                        self.enter(to);
+                        visiting.push((to, 0));
+                    }
+                    Some(index) if self.on_stack[to] => {
+                        self.lowlinks[node] = self.lowlinks[node].min(index);
+                    }
+                    _ => (),
+                }
+                continue;
+            }
+            visiting.pop();
+            if let Some((parent, _)) = visiting.last() {
+                self.lowlinks[*parent] = self.lowlinks[*parent].min(self.lowlinks[node]);
+            }
+            if Some(self.lowlinks[node]) == self.indices[node] {
+                let mut component = Vec::default();
+                while let Some(member) = self.stack.pop() {
+                    self.on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                component.sort();
+                self.components.push(component);
+            }
+        }
+    }
+
+    fn enter(&mut self, node: usize) {
+        self.indices[node] = Some(self.index);
+        self.lowlinks[node] = self.index;
+        self.index += 1;
+        self.stack.push(node);
+        self.on_stack[node] = true;
+    }
+}
+
+// Collects the references to other top-level assignments, ignoring the ones shadowed by locals.
+// References that can't be resolved are ignored, they are reported when checking.
+struct Collector<'a> {
+    fqresolvers: &'a FQResolvers,
+    fq: &'a FQSym,
+    indices: &'a HashMap<FQSym, usize>,
+    edges: Vec<usize>,
+}
+
+impl Collector<'_> {
+    fn expr(&mut self, locals: &HashSet<Symbol>, expr: &ExprRef) {
+        match &expr.it {
+            ast0::Expr::LitInteger(_)
+            | ast0::Expr::LitDecimal(_)
+            | ast0::Expr::LitString(_)
+            | ast0::Expr::TSymbol(_) => (),
+            ast0::Expr::Interpolation(parts) => {
+                for part in parts {
+                    if let ast0::StringPart::Expr(e) = part {
+                        self.expr(locals, e);
+                    }
+                }
+            }
+            ast0::Expr::Symbol(q) => {
+                if q.segments.is_empty() && locals.contains(&q.symbol) {
+                    return;
+                }
+                let resolved = self
+                    .fqresolvers
+                    .for_path(&self.fq.path())
+                    .resolve_fq_symbol(&Loc::None, q);
+                if let Some(to) = resolved.ok().and_then(|fq| self.indices.get(&fq)) {
+                    self.edges.push(*to);
+                }
+            }
+            ast0::Expr::Unary(u) => self.expr(locals, &u.expr),
+            ast0::Expr::Binary(b) => {
+                self.expr(locals, &b.expr1);
+                self.expr(locals, &b.expr2);
+            }
+            ast0::Expr::Block(b) => {
+                // Block assignments can be used before they are defined
+                let mut locals = locals.clone();
+                for a in &b.assignments {
+                    locals.insert(a.it.symbol.clone());
+                }
+                for a in &b.assignments {
+                    self.expr(&locals, &a.it.expr);
+                }
+                for e in &b.exprs {
+                    self.expr(&locals, e);
+                }
+            }
+            ast0::Expr::Conditional(c) => {
+                self.expr(locals, &c.expr);
+                self.expr(locals, &c.then);
+                self.expr(locals, &c.otherwise);
+            }
+            ast0::Expr::Seq(s) => {
+                self.expr(locals, &s.expr);
+                self.expr(locals, &s.then);
+            }
+            ast0::Expr::Lambda(l) => {
+                let mut locals = locals.clone();
+                for p in &l.params {
+                    locals.insert(p.it.symbol.clone());
+                }
+                self.expr(&locals, &l.body);
+            }
+            ast0::Expr::Call(c) => {
+                self.expr(locals, &c.func);
+                for arg in &c.args {
+                    self.expr(locals, arg);
+                }
+            }
+            ast0::Expr::Tuple(exprs) | ast0::Expr::List(exprs) => {
+                for e in exprs {
+                    self.expr(locals, e);
+                }
+            }
+            ast0::Expr::TupleField(f) => self.expr(locals, &f.expr),
+            ast0::Expr::Record(r) => self.fields(locals, &r.fields),
+            ast0::Expr::RecordField(f) => self.expr(locals, &f.expr),
+            ast0::Expr::Update(u) => {
+                self.expr(locals, &u.expr);
+                self.fields(locals, &u.fields);
+            }
+            ast0::Expr::Match(m) => {
+                self.expr(locals, &m.expr);
+                for arm in &m.arms {
+                    let mut locals = locals.clone();
+                    bindings(&mut locals, &arm.it.pattern);
+                    self.expr(&locals, &arm.it.expr);
+                }
+            }
+            ast0::Expr::Map(entries) => {
+                for e in entries {
+                    self.expr(locals, &e.it.key);
+                    self.expr(locals, &e.it.value);
+                }
+            }
+            ast0::Expr::Index(i) => {
+                self.expr(locals, &i.expr);
+                self.expr(locals, &i.index);
+            }
+            ast0::Expr::Ascription(a) => self.expr(locals, &a.expr),
+        }
+    }
+
+    fn fields(&mut self, locals: &HashSet<Symbol>, fields: &[L<ast0::Field>]) {
+        for f in fields {
+            self.expr(locals, &f.it.expr);
+        }
+    }
+}
+
+fn bindings(locals: &mut HashSet<Symbol>, pattern: &ast0::PatternRef) {
+    match &pattern.it {
+        ast0::Pattern::Symbol(symbol) => {
+            locals.insert(symbol.clone());
+        }
+        ast0::Pattern::Tuple(patterns) | ast0::Pattern::Constructor(_, patterns) => {
+            for p in patterns {
+                bindings(locals, p);
+            }
+        }
+        ast0::Pattern::Record(_, fields) => {
+            for f in fields {
+                bindings(locals, &f.it.pattern);
+            }
+        }
+        ast0::Pattern::Wildcard | ast0::Pattern::LitInteger(_) | ast0::Pattern::TSymbol(_) => (),
+    }
+}
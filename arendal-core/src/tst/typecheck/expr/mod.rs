@@ -1,22 +1,28 @@
 use crate::ast0::{self, BinaryOp, Q};
 use crate::context::Type;
-use crate::error::{Error, Errors, L, Result};
-use crate::symbol::{Symbol, TSymbol};
-use crate::tst::Assignment;
+use crate::effect::{Effect, Effects};
+use crate::error::{Error, Errors, L, Loc, Result};
+use crate::symbol::{self, Symbol, TSymbol};
+use crate::tst::{Arm, Assignment};
 
 use super::{Builder, Expr, Resolved, Scope, Value};
 
+mod pattern;
+
 pub(super) fn check(scope: &Scope, input: &ast0::ExprRef) -> Result<L<Expr>> {
     match &input.it {
         ast0::Expr::LitInteger(value) => Ok(builder(input).val_integer(value.clone())),
-        ast0::Expr::Symbol(q) => resolve_symbol(scope, input, &q),
+        ast0::Expr::LitDecimal(value) => Ok(builder(input).val_decimal(value.clone())),
+        ast0::Expr::LitString(value) => Ok(builder(input).val_string(value.clone())),
+        ast0::Expr::Interpolation(parts) => check_interpolation(scope, input, parts),
+        ast0::Expr::Symbol(q) => resolve_symbol(scope, input, q),
         ast0::Expr::TSymbol(q) => {
-            let tipo = resolve_type(scope, input, &q)?;
+            let tipo = resolve_type(scope, input, q)?;
             let value = Value::v_singleton(&input.loc, tipo)?;
-            Ok(builder(&input).value(value))
+            Ok(builder(input).value(value))
         }
         ast0::Expr::Seq(s) => {
-            merge2(scope, &s.expr, &s.then).and_then(|(e1, e2)| Ok(builder(input).seq(e1, e2)))
+            merge2(scope, &s.expr, &s.then).map(|(e1, e2)| builder(input).seq(e1, e2))
         }
         ast0::Expr::Conditional(c) => {
             let (expr, then, otherwise) = Error::merge3(
@@ -24,18 +30,445 @@ pub(super) fn check(scope: &Scope, input: &ast0::ExprRef) -> Result<L<Expr>> {
                 check(scope, &c.then),
                 check(scope, &c.otherwise),
             )?;
-            builder(&input).conditional(expr, then, otherwise)
+            let tipo = scope.join(&then.get_type(), &otherwise.get_type());
+            builder(input).conditional(expr, then, otherwise, tipo)
         }
+        ast0::Expr::Unary(u) => check(scope, &u.expr).and_then(|e| builder(input).unary(u.op, e)),
         ast0::Expr::Binary(b) => merge2(scope, &b.expr1, &b.expr2)
             .and_then(|(t1, t2)| check_binary(scope, input, b.op, t1, t2)),
-        ast0::Expr::Block(b) => {
-            let result = check_block(scope, input, &b);
-            result
+        ast0::Expr::Block(b) => check_block(scope, input, b),
+        ast0::Expr::Lambda(l) => check_lambda(scope, input, l),
+        ast0::Expr::Call(c) => match &c.func.it {
+            ast0::Expr::TSymbol(q) => check_constructor(scope, input, q, &c.args),
+            _ => {
+                let func = check(scope, &c.func);
+                let params = func.as_ref().ok().and_then(|f| param_types(&f.get_type()));
+                Error::merge(func, check_args(scope, &c.args, params))
+                    .and_then(|(func, args)| check_call(scope, input, func, args))
+            }
+        },
+        ast0::Expr::Tuple(exprs) => check_all(scope, exprs).and_then(|exprs| {
+            let types: Vec<Type> = exprs.iter().map(|e| e.get_type()).collect();
+            builder(input).tuple(Type::type_tuple(types.clone()), types, exprs)
+        }),
+        ast0::Expr::TupleField(f) => check(scope, &f.expr).and_then(|expr| {
+            let types = scope.tuple_types(&expr.get_type());
+            builder(input).tuple_field(expr, types, f.index)
+        }),
+        ast0::Expr::Record(r) => {
+            let (tipo, values) = Error::merge(
+                resolve_type(scope, input, &r.tipo),
+                check_fields(scope, &r.fields),
+            )?;
+            let tipo = infer_record(scope, tipo, &values);
+            let fields = scope.record_fields(&tipo);
+            builder(input).record(tipo, fields, values)
+        }
+        ast0::Expr::RecordField(f) => check(scope, &f.expr).and_then(|expr| {
+            // Strings and collections have no fields, but they have a length
+            if f.symbol == symbol::LENGTH {
+                let tipo = expr.get_type();
+                if tipo.is_string() {
+                    return builder(input).str_length(expr);
+                } else if tipo.is_list() {
+                    return Ok(builder(input).list_length(expr));
+                } else if tipo.is_map() {
+                    return Ok(builder(input).map_length(expr));
+                }
+            }
+            let fields = scope.record_fields(&expr.get_type());
+            builder(input).record_field(expr, fields, f.symbol.clone())
+        }),
+        ast0::Expr::Update(u) => {
+            let (expr, values) =
+                Error::merge(check(scope, &u.expr), check_fields(scope, &u.fields))?;
+            let fields = scope.record_fields(&expr.get_type());
+            builder(input).update(expr, fields, values)
+        }
+        ast0::Expr::List(exprs) => check_list(scope, input, exprs),
+        ast0::Expr::Map(entries) => check_map(scope, input, entries),
+        ast0::Expr::Index(i) => merge2(scope, &i.expr, &i.index)
+            .and_then(|(expr, index)| builder(input).index(expr, index)),
+        ast0::Expr::Match(m) => check_match(scope, input, m),
+        ast0::Expr::Ascription(a) => {
+            let tipo = resolve_type_expr(scope, &a.tipo);
+            let expr = match &tipo {
+                Ok(tipo) => check_expected(scope, &a.expr, tipo),
+                Err(_) => check(scope, &a.expr),
+            };
+            Error::merge(expr, tipo).and_then(|(expr, tipo)| builder(input).ascription(expr, tipo))
+        }
+    }
+}
+
+// Checks an expression whose type is known from the context, e.g., an ascription or the
+// result type of a function. The expected type is passed down to the collection literals,
+// so empty ones get their element types from it. The caller checks that the types match.
+fn check_expected(scope: &Scope, input: &ast0::ExprRef, expected: &Type) -> Result<L<Expr>> {
+    match &input.it {
+        ast0::Expr::List(exprs) => match expected.list_element() {
+            Some(element) if exprs.is_empty() => Ok(builder(input).list(element, Vec::default())),
+            Some(element) => {
+                let expected = vec![element; exprs.len()];
+                let exprs = check_args(scope, exprs, Some(expected))?;
+                let element = join_all(scope, input, exprs.iter())?;
+                Ok(builder(input).list(element, exprs))
+            }
+            None => check(scope, input),
+        },
+        ast0::Expr::Map(entries) if entries.is_empty() => match expected.map_types() {
+            Some((key, value)) => Ok(builder(input).map(key, value, Vec::default())),
+            None => check(scope, input),
+        },
+        ast0::Expr::Conditional(c) => {
+            let (expr, then, otherwise) = Error::merge3(
+                check(scope, &c.expr),
+                check_expected(scope, &c.then, expected),
+                check_expected(scope, &c.otherwise, expected),
+            )?;
+            let tipo = scope.join(&then.get_type(), &otherwise.get_type());
+            builder(input).conditional(expr, then, otherwise, tipo)
         }
-        _ => error(input, Error::InvalidType),
+        ast0::Expr::Block(b) => check_block_expected(scope, input, b, Some(expected)),
+        _ => check(scope, input),
     }
 }
 
+// The parameter types of non-generic functions are the expected types of the arguments
+fn param_types(tipo: &Type) -> Option<Vec<Type>> {
+    tipo.as_function().map(|f| f.params.clone())
+}
+
+fn check_args(
+    scope: &Scope,
+    input: &[ast0::ExprRef],
+    expected: Option<Vec<Type>>,
+) -> Result<Vec<L<Expr>>> {
+    let Some(expected) = expected.filter(|e| e.len() == input.len()) else {
+        return check_all(scope, input);
+    };
+    let mut errors = Errors::default();
+    let mut exprs = Vec::with_capacity(input.len());
+    for (e, tipo) in input.iter().zip(&expected) {
+        if let Some(expr) = errors.add_result(check_expected(scope, e, tipo)) {
+            exprs.push(expr);
+        }
+    }
+    errors.to_result(exprs)
+}
+
+// The element type is the join of the types of the elements
+fn check_list(scope: &Scope, input: &ast0::ExprRef, exprs: &[ast0::ExprRef]) -> Result<L<Expr>> {
+    let exprs = check_all(scope, exprs)?;
+    let element = join_all(scope, input, exprs.iter())?;
+    Ok(builder(input).list(element, exprs))
+}
+
+// The key and value types are the joins of the types of the keys and values
+fn check_map(scope: &Scope, input: &ast0::ExprRef, entries: &[L<ast0::Entry>]) -> Result<L<Expr>> {
+    let mut errors = Errors::default();
+    let mut checked = Vec::with_capacity(entries.len());
+    for e in entries {
+        if let Some(entry) = errors.add_result(merge2(scope, &e.it.key, &e.it.value)) {
+            checked.push(entry);
+        }
+    }
+    errors.to_unit_result()?;
+    let (key, value) = Error::merge(
+        join_all(scope, input, checked.iter().map(|(k, _)| k)),
+        join_all(scope, input, checked.iter().map(|(_, v)| v)),
+    )?;
+    Ok(builder(input).map(key, value, checked))
+}
+
+// Empty collections with no expected type are rejected, as there are no types to join
+fn join_all<'a>(
+    scope: &Scope,
+    input: &ast0::ExprRef,
+    mut exprs: impl Iterator<Item = &'a L<Expr>>,
+) -> Result<Type> {
+    let Some(first) = exprs.next() else {
+        return input.loc.err(Error::EmptyCollection);
+    };
+    let mut result = first.get_type();
+    for expr in exprs {
+        match scope.join(&result, &expr.get_type()) {
+            Some(joined) => result = joined,
+            None => return expr.type_mismatch(result),
+        }
+    }
+    Ok(result)
+}
+
+// The type arguments of generic records are inferred from the field values
+fn infer_record(scope: &Scope, tipo: Type, values: &[(Symbol, L<Expr>)]) -> Type {
+    let Some(fields) = tipo.generic().and_then(|(_, t)| scope.record_fields(t)) else {
+        return tipo;
+    };
+    let (expected, actual): (Vec<Type>, Vec<Type>) = values
+        .iter()
+        .filter_map(|(symbol, expr)| {
+            let (_, t) = fields.iter().find(|(s, _)| s == symbol)?;
+            Some((t.clone(), expr.get_type()))
+        })
+        .unzip();
+    scope.infer(&tipo, &expected, &actual)
+}
+
+fn check_fields(scope: &Scope, input: &[L<ast0::Field>]) -> Result<Vec<(Symbol, L<Expr>)>> {
+    let mut errors = Errors::default();
+    let mut fields = Vec::with_capacity(input.len());
+    for f in input {
+        if let Some(expr) = errors.add_result(check(scope, &f.it.expr)) {
+            fields.push((f.it.symbol.clone(), expr));
+        }
+    }
+    errors.to_result(fields)
+}
+
+// Any value can be interpolated
+fn check_interpolation(
+    scope: &Scope,
+    input: &ast0::ExprRef,
+    parts: &[ast0::StringPart],
+) -> Result<L<Expr>> {
+    let mut errors = Errors::default();
+    let mut checked = Vec::with_capacity(parts.len());
+    for part in parts {
+        match part {
+            ast0::StringPart::Text(text) => checked.push(builder(input).val_string(text.clone())),
+            ast0::StringPart::Expr(e) => {
+                if let Some(expr) = errors.add_result(check(scope, e)) {
+                    checked.push(expr);
+                }
+            }
+        }
+    }
+    errors.to_result(builder(input).interpolation(checked))
+}
+
+// Calling a generic function instantiates it with the type arguments inferred from the
+// arguments. Calls can only perform the effects allowed in the scope: none in the initializers
+// of top-level lets, the declared ones in functions and any in the top-level expression.
+fn check_call(
+    scope: &Scope,
+    input: &ast0::ExprRef,
+    func: L<Expr>,
+    args: Vec<L<Expr>>,
+) -> Result<L<Expr>> {
+    let mut tipo = func.get_type();
+    if let Some((_, generic)) = tipo.generic()
+        && let Some(function) = generic.as_function()
+    {
+        let types: Vec<Type> = args.iter().map(|a| a.get_type()).collect();
+        tipo = scope.infer(&tipo, &function.params, &types);
+    }
+    if let Some(function) = tipo.as_function()
+        && let Some(effect) = function
+            .effects
+            .iter()
+            .find(|e| !scope.effects.contains(*e))
+    {
+        return error(input, Error::EffectNotAllowed(effect));
+    }
+    builder(input).call(func, tipo, args)
+}
+
+// Calling a type builds a value of the tuple type. The type arguments of generic types are
+// inferred from the arguments.
+fn check_constructor(
+    scope: &Scope,
+    input: &ast0::ExprRef,
+    symbol: &Q<TSymbol>,
+    args: &[ast0::ExprRef],
+) -> Result<L<Expr>> {
+    let (mut tipo, args) =
+        Error::merge(resolve_type(scope, input, symbol), check_all(scope, args))?;
+    if let Some(types) = tipo.generic().and_then(|(_, t)| scope.tuple_types(t)) {
+        let actual: Vec<Type> = args.iter().map(|a| a.get_type()).collect();
+        tipo = scope.infer(&tipo, &types, &actual);
+    }
+    match scope.tuple_types(&tipo) {
+        Some(types) => builder(input).tuple(tipo, types, args),
+        None => error(input, Error::TupleExpected(tipo)),
+    }
+}
+
+fn check_all(scope: &Scope, input: &[ast0::ExprRef]) -> Result<Vec<L<Expr>>> {
+    let mut errors = Errors::default();
+    let mut exprs = Vec::with_capacity(input.len());
+    for e in input {
+        if let Some(expr) = errors.add_result(check(scope, e)) {
+            exprs.push(expr);
+        }
+    }
+    errors.to_result(exprs)
+}
+
+pub(super) fn resolve_type_expr(scope: &Scope, input: &ast0::TypeExprRef) -> Result<Type> {
+    match &input.it {
+        ast0::TypeExpr::Type(q) => {
+            let tipo = scope.resolve_type(&input.loc, q)?;
+            match tipo.generic() {
+                Some((params, _)) => input
+                    .loc
+                    .err(Error::TypeArgumentCountMismatch(params.len(), 0)),
+                None => Ok(tipo),
+            }
+        }
+        ast0::TypeExpr::Tuple(types) => {
+            let mut errors = Errors::default();
+            let mut checked = Vec::with_capacity(types.len());
+            for t in types {
+                if let Some(tipo) = errors.add_result(resolve_type_expr(scope, t)) {
+                    checked.push(tipo);
+                }
+            }
+            errors.to_result(Type::type_tuple(checked))
+        }
+        ast0::TypeExpr::Function(params, result, effects) => {
+            let mut errors = Errors::default();
+            let mut types = Vec::with_capacity(params.len());
+            for p in params {
+                if let Some(tipo) = errors.add_result(resolve_type_expr(scope, p)) {
+                    types.push(tipo);
+                }
+            }
+            let result = errors.add_result(resolve_type_expr(scope, result));
+            let effects = errors.add_result(resolve_effects(&input.loc, effects));
+            errors.to_unit_result()?;
+            Ok(Type::type_function_with_effects(
+                types,
+                result.unwrap(),
+                effects.unwrap(),
+            ))
+        }
+        ast0::TypeExpr::Parameterized(q, args) => {
+            let mut errors = Errors::default();
+            let mut types = Vec::with_capacity(args.len());
+            for a in args {
+                if let Some(tipo) = errors.add_result(resolve_type_expr(scope, a)) {
+                    types.push(tipo);
+                }
+            }
+            errors.to_unit_result()?;
+            // Besides the builtin collections, only generic types take type arguments
+            let expected = match &q.symbol {
+                s if q.segments.is_empty() && *s == symbol::LIST => 1,
+                s if q.segments.is_empty() && *s == symbol::MAP => 2,
+                _ => {
+                    let tipo = scope.resolve_type(&input.loc, q)?;
+                    return tipo.instantiate(&types).map_or_else(
+                        || {
+                            let expected = tipo.generic().map_or(0, |(params, _)| params.len());
+                            input
+                                .loc
+                                .err(Error::TypeArgumentCountMismatch(expected, types.len()))
+                        },
+                        Ok,
+                    );
+                }
+            };
+            if expected != types.len() {
+                return input
+                    .loc
+                    .err(Error::TypeArgumentCountMismatch(expected, types.len()));
+            }
+            let mut types = types.into_iter();
+            let first = types.next().unwrap();
+            Ok(match types.next() {
+                Some(second) => Type::type_map(first, second),
+                None => Type::type_list(first),
+            })
+        }
+    }
+}
+
+pub(super) fn resolve_effects(loc: &Loc, symbols: &[TSymbol]) -> Result<Effects> {
+    let mut errors = Errors::default();
+    let mut effects = Vec::with_capacity(symbols.len());
+    for symbol in symbols {
+        match Effect::parse(symbol) {
+            Some(effect) => effects.push(effect),
+            None => errors.add(loc.wrap(Error::UnknownEffect(symbol.clone()))),
+        }
+    }
+    errors.to_lazy_result(|| effects.into_iter().collect())
+}
+
+// Parameters are set in a child scope, so the body can use the locals of the enclosing scopes.
+// Type parameters are visible in the types of the parameters and in the body, which can only
+// perform the declared effects.
+fn check_lambda(scope: &Scope, input: &ast0::ExprRef, lambda: &ast0::Lambda) -> Result<L<Expr>> {
+    let mut child_scope = scope.child();
+    let mut errors = Errors::default();
+    if let Some(effects) = errors.add_result(resolve_effects(&input.loc, &lambda.effects)) {
+        child_scope.effects = effects;
+    }
+    errors.add_result(child_scope.add_type_params(&input.loc, &lambda.type_params));
+    let mut params = Vec::with_capacity(lambda.params.len());
+    for p in &lambda.params {
+        let symbol = p.it.symbol.clone();
+        if let Some(tipo) = errors.add_result(resolve_type_expr(&child_scope, &p.it.tipo)) {
+            let added = child_scope
+                .add_current(&p.loc, symbol.clone())
+                .and_then(|_| child_scope.set(&p.loc, symbol.clone(), tipo.clone()));
+            if errors.add_result(added).is_some() {
+                params.push((symbol, tipo));
+            }
+        }
+    }
+    let result = match &lambda.result {
+        Some(t) => errors.add_result(resolve_type_expr(&child_scope, t)),
+        None => None,
+    };
+    errors.to_unit_result()?;
+    let body = match &result {
+        Some(tipo) => check_expected(&child_scope, &lambda.body, tipo)?,
+        None => check(&child_scope, &lambda.body)?,
+    };
+    builder(input).lambda(
+        lambda.type_params.clone(),
+        params,
+        result,
+        child_scope.effects,
+        body,
+    )
+}
+
+// Each arm is checked in a child scope with the bindings of its pattern. The type is the join
+// of the types of the arms. Patterns are checked against the declared type of the value, so
+// the other variants of its union are unreachable rather than mismatched, while the arms
+// needed are found with the actual type.
+fn check_match(scope: &Scope, input: &ast0::ExprRef, m: &ast0::Match) -> Result<L<Expr>> {
+    let expr = check(scope, &m.expr)?;
+    let tipo = expr.get_type();
+    let declared = scope.declared(&tipo);
+    let mut errors = Errors::default();
+    let mut patterns = Vec::with_capacity(m.arms.len());
+    let mut arms = Vec::with_capacity(m.arms.len());
+    for arm in &m.arms {
+        let mut child_scope = scope.child();
+        let checked = pattern::check(&mut child_scope, &arm.it.pattern, &declared)
+            .and_then(|pattern| Ok((pattern, check(&child_scope, &arm.it.expr)?)));
+        if let Some((pattern, expr)) = errors.add_result(checked) {
+            patterns.push(arm.loc.wrap(pattern.clone()));
+            arms.push(Arm { pattern, expr });
+        }
+    }
+    errors.to_unit_result()?;
+    pattern::check_usefulness(scope, &input.loc, &tipo, &patterns)?;
+    // There is at least one arm, as matches without arms are not exhaustive
+    let mut result = arms[0].expr.get_type();
+    for arm in &arms[1..] {
+        match scope.join(&result, &arm.expr.get_type()) {
+            Some(joined) => result = joined,
+            None => return arm.expr.type_mismatch(result),
+        }
+    }
+    Ok(builder(input).pattern_match(expr, arms, result))
+}
+
 fn resolve_type(scope: &Scope, input: &ast0::ExprRef, symbol: &Q<TSymbol>) -> Result<Type> {
     scope.resolve_type(&input.loc, symbol)
 }
@@ -48,35 +481,67 @@ fn resolve_symbol(scope: &Scope, input: &ast0::ExprRef, symbol: &Q<Symbol>) -> R
 }
 
 fn check_binary(
-    scope: &Scope,
+    _scope: &Scope,
     input: &ast0::ExprRef,
     op: BinaryOp,
     expr1: L<Expr>,
     expr2: L<Expr>,
 ) -> Result<L<Expr>> {
+    // Decimal arithmetic if any of the operands is a decimal, and the same for strings
+    let decimal = expr1.get_type().is_decimal() || expr2.get_type().is_decimal();
+    let string = expr1.get_type().is_string() || expr2.get_type().is_string();
     match op {
+        BinaryOp::Add if string => builder(input).str_concat(expr1, expr2),
+        BinaryOp::LT if string => builder(input).str_lt(expr1, expr2),
+        BinaryOp::LE if string => builder(input).str_le(expr1, expr2),
+        BinaryOp::GT if string => builder(input).str_gt(expr1, expr2),
+        BinaryOp::GE if string => builder(input).str_ge(expr1, expr2),
+        BinaryOp::Add if decimal => builder(input).dec_add(expr1, expr2),
+        BinaryOp::Sub if decimal => builder(input).dec_sub(expr1, expr2),
+        BinaryOp::Mul if decimal => builder(input).dec_mul(expr1, expr2),
+        // The scale and rounding mode must be explicit
+        BinaryOp::Div if decimal => error(input, Error::ImplicitDecimalDivision),
         BinaryOp::Add => builder(input).int_add(expr1, expr2),
         BinaryOp::Sub => builder(input).int_sub(expr1, expr2),
         BinaryOp::Mul => builder(input).int_mul(expr1, expr2),
         BinaryOp::Div => builder(input).int_div(expr1, expr2),
+        BinaryOp::Rem => builder(input).int_rem(expr1, expr2),
+        BinaryOp::Pow => builder(input).int_pow(expr1, expr2),
         BinaryOp::And => builder(input).log_and(expr1, expr2),
         BinaryOp::Or => builder(input).log_or(expr1, expr2),
-        _ => error(input, Error::InvalidType),
+        BinaryOp::Eq => builder(input).eq(expr1, expr2),
+        BinaryOp::NEq => builder(input).neq(expr1, expr2),
+        BinaryOp::LT => builder(input).int_lt(expr1, expr2),
+        BinaryOp::LE => builder(input).int_le(expr1, expr2),
+        BinaryOp::GT => builder(input).int_gt(expr1, expr2),
+        BinaryOp::GE => builder(input).int_ge(expr1, expr2),
     }
 }
 
 fn check_block(scope: &Scope, input: &ast0::ExprRef, block: &ast0::Block) -> Result<L<Expr>> {
+    check_block_expected(scope, input, block, None)
+}
+
+fn check_block_expected(
+    scope: &Scope,
+    input: &ast0::ExprRef,
+    block: &ast0::Block,
+    expected: Option<&Type>,
+) -> Result<L<Expr>> {
     let mut child_scope = scope.child();
     let assignments = check_assignments(&mut child_scope, &block.assignments)?;
     let mut expr: Option<L<Expr>> = None;
     for e in &block.exprs {
         if expr.is_none() {
-            expr = Some(check(&child_scope, &e)?)
+            expr = Some(match expected {
+                Some(tipo) => check_expected(&child_scope, e, tipo)?,
+                None => check(&child_scope, e)?,
+            })
         } else {
             return error(input, Error::OnlyOneExpressionAllowed);
         }
     }
-    builder(&input).block(assignments, expr)
+    builder(input).block(assignments, expr)
 }
 
 fn check_assignments(
@@ -89,10 +554,10 @@ fn check_assignments(
         let mut errors = Errors::default();
         let progress = assignments.len();
         for a in ast {
-            if scope.eval_pending(&a.it.symbol) {
-                errors
-                    .add_result(check_assignment(scope, a))
-                    .map(|a| assignments.push(a));
+            if scope.eval_pending(&a.it.symbol)
+                && let Some(a) = errors.add_result(check_assignment(scope, a))
+            {
+                assignments.push(a)
             }
         }
         match errors.to_unit_result() {
@@ -0,0 +1,263 @@
+// Patterns of match expressions. Non-exhaustive matches and unreachable arms are found by
+// checking the usefulness of the patterns, as described in "Warnings for pattern matching"
+// by Luc Maranget.
+
+use crate::ast0::{self, Q};
+use crate::context::Type;
+use crate::error::{Error, Errors, L, Loc, Result};
+use crate::symbol::{Symbol, TSymbol};
+use crate::tst::Pattern;
+
+use super::{Scope, Value};
+
+// Checks the pattern against the type of the matched value, adding the bindings to the scope
+pub(super) fn check(scope: &mut Scope, input: &ast0::PatternRef, tipo: &Type) -> Result<Pattern> {
+    match &input.it {
+        ast0::Pattern::Wildcard => Ok(Pattern::Any),
+        ast0::Pattern::Symbol(symbol) => {
+            scope.add_current(&input.loc, symbol.clone())?;
+            scope.set(&input.loc, symbol.clone(), tipo.clone())?;
+            Ok(Pattern::Binding(symbol.clone()))
+        }
+        ast0::Pattern::LitInteger(value) => {
+            let value = Value::v_integer(&input.loc, Type::type_integer(), value.clone())?;
+            check_accepts(input, tipo, &value.get_type())?;
+            Ok(Pattern::Value(value))
+        }
+        ast0::Pattern::TSymbol(q) => {
+            let t = scope.resolve_type(&input.loc, q)?;
+            check_accepts(input, tipo, &t)?;
+            Value::v_singleton(&input.loc, t).map(Pattern::Value)
+        }
+        ast0::Pattern::Tuple(patterns) => match scope.tuple_types(tipo) {
+            Some(types) if tipo.is_anonymous() => {
+                check_all(scope, input, patterns, &types).map(Pattern::Tuple)
+            }
+            _ => input.loc.err(Error::TupleExpected(tipo.clone())),
+        },
+        ast0::Pattern::Constructor(q, patterns) => {
+            let t = resolve_instance(scope, input, q, tipo)?;
+            match scope.tuple_types(&t) {
+                Some(types) => check_all(scope, input, patterns, &types)
+                    .map(|patterns| Pattern::Constructor(t, patterns)),
+                None => input.loc.err(Error::TupleExpected(t)),
+            }
+        }
+        ast0::Pattern::Record(q, fields) => {
+            let t = resolve_instance(scope, input, q, tipo)?;
+            match scope.record_fields(&t) {
+                Some(types) => check_fields(scope, &t, fields, &types)
+                    .map(|patterns| Pattern::Constructor(t, patterns)),
+                None => input.loc.err(Error::RecordExpected(t)),
+            }
+        }
+    }
+}
+
+// Generic types are instantiated with the type arguments of the matched type
+fn resolve_instance(
+    scope: &Scope,
+    input: &ast0::PatternRef,
+    q: &Q<TSymbol>,
+    tipo: &Type,
+) -> Result<Type> {
+    let t = scope.resolve_type(&input.loc, q)?;
+    if t.is_generic() {
+        match scope.instance_of(&t, tipo) {
+            Some(instance) => Ok(instance),
+            None => input.loc.err(Error::type_mismatch(tipo.clone(), t)),
+        }
+    } else {
+        check_accepts(input, tipo, &t)?;
+        Ok(t)
+    }
+}
+
+fn check_accepts(input: &ast0::PatternRef, expected: &Type, actual: &Type) -> Result<()> {
+    if expected.accepts(actual) {
+        Ok(())
+    } else {
+        input
+            .loc
+            .err(Error::type_mismatch(expected.clone(), actual.clone()))
+    }
+}
+
+fn check_all(
+    scope: &mut Scope,
+    input: &ast0::PatternRef,
+    patterns: &[ast0::PatternRef],
+    types: &[Type],
+) -> Result<Vec<Pattern>> {
+    if types.len() != patterns.len() {
+        return input
+            .loc
+            .err(Error::ArgumentCountMismatch(types.len(), patterns.len()));
+    }
+    let mut errors = Errors::default();
+    let mut checked = Vec::with_capacity(patterns.len());
+    for (p, t) in patterns.iter().zip(types) {
+        if let Some(pattern) = errors.add_result(check(scope, p, t)) {
+            checked.push(pattern);
+        }
+    }
+    errors.to_result(checked)
+}
+
+// Missing fields match any value
+fn check_fields(
+    scope: &mut Scope,
+    tipo: &Type,
+    fields: &[L<ast0::FieldPattern>],
+    types: &[(Symbol, Type)],
+) -> Result<Vec<Pattern>> {
+    let mut errors = Errors::default();
+    let mut checked: Vec<Option<Pattern>> = vec![None; types.len()];
+    for f in fields {
+        let symbol = &f.it.symbol;
+        match types.iter().position(|(s, _)| s == symbol) {
+            None => errors.add(f.error(Error::UnknownField(tipo.clone(), symbol.clone()))),
+            Some(index) if checked[index].is_some() => {
+                errors.add(f.error(Error::DuplicateField(symbol.clone())))
+            }
+            Some(index) => {
+                checked[index] = errors.add_result(check(scope, &f.it.pattern, &types[index].1));
+            }
+        }
+    }
+    let patterns = checked
+        .into_iter()
+        .map(|p| p.unwrap_or(Pattern::Any))
+        .collect();
+    errors.to_result(patterns)
+}
+
+// Reports the arms that can't be reached because of the previous ones, and whether there are
+// values not matched by any arm
+pub(super) fn check_usefulness(
+    scope: &Scope,
+    loc: &Loc,
+    tipo: &Type,
+    patterns: &[L<Pattern>],
+) -> Result<()> {
+    let types = [tipo.clone()];
+    let mut errors = Errors::default();
+    let mut matrix: Vec<Vec<Pattern>> = Vec::with_capacity(patterns.len());
+    for p in patterns {
+        let row = vec![p.it.clone()];
+        if !is_useful(scope, &types, &matrix, &row) {
+            errors.add(p.error(Error::UnreachableArm));
+        }
+        matrix.push(row);
+    }
+    if is_useful(scope, &types, &matrix, &[Pattern::Any]) {
+        errors.add(loc.wrap(Error::NonExhaustiveMatch));
+    }
+    errors.to_unit_result()
+}
+
+// Constructors of the values matched by patterns. Bindings and wildcards have none.
+#[derive(Clone, PartialEq)]
+enum Ctor {
+    Value(Value),
+    Tuple,
+    Type(Type),
+}
+
+fn head(pattern: &Pattern) -> Option<(Ctor, &[Pattern])> {
+    match pattern {
+        Pattern::Any | Pattern::Binding(_) => None,
+        Pattern::Value(v) => Some((Ctor::Value(v.clone()), &[])),
+        Pattern::Tuple(patterns) => Some((Ctor::Tuple, patterns)),
+        Pattern::Constructor(t, patterns) => Some((Ctor::Type(t.clone()), patterns)),
+    }
+}
+
+// Returns all the constructors of the values of a type, if there is a finite number of them
+fn signature(scope: &Scope, tipo: &Type) -> Option<Vec<Ctor>> {
+    let types = scope.variants(tipo).unwrap_or_else(|| vec![tipo.clone()]);
+    types
+        .into_iter()
+        .map(|t| {
+            if t.is_singleton() {
+                Value::v_singleton(&Loc::None, t).ok().map(Ctor::Value)
+            } else if t.is_tuple() && t.is_anonymous() {
+                Some(Ctor::Tuple)
+            } else if t.is_tuple() || t.is_record() {
+                Some(Ctor::Type(t))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+// Types of the elements of the values built by the constructor
+fn ctor_types(scope: &Scope, ctor: &Ctor, tipo: &Type) -> Vec<Type> {
+    match ctor {
+        Ctor::Value(_) => Vec::default(),
+        Ctor::Tuple => scope.tuple_types(tipo).unwrap_or_default(),
+        Ctor::Type(t) => scope
+            .tuple_types(t)
+            .or_else(|| {
+                scope
+                    .record_fields(t)
+                    .map(|fields| fields.into_iter().map(|(_, t)| t).collect())
+            })
+            .unwrap_or_default(),
+    }
+}
+
+// Returns the rest of the row, preceded by the elements of the first pattern if it matches the
+// constructor, or None if it doesn't
+fn specialize(row: &[Pattern], ctor: &Ctor, arity: usize) -> Option<Vec<Pattern>> {
+    let (first, rest) = row.split_first().unwrap();
+    let mut patterns = match head(first) {
+        None => vec![Pattern::Any; arity],
+        Some((c, elements)) if c == *ctor => elements.to_vec(),
+        Some(_) => return None,
+    };
+    patterns.extend_from_slice(rest);
+    Some(patterns)
+}
+
+// Returns whether there are values matched by the row and by none of the rows of the matrix.
+// Every row has a pattern for each of the types.
+fn is_useful(scope: &Scope, types: &[Type], matrix: &[Vec<Pattern>], row: &[Pattern]) -> bool {
+    let Some((tipo, rest)) = types.split_first() else {
+        return matrix.is_empty();
+    };
+    let specialized = |ctor: &Ctor| {
+        let mut types = ctor_types(scope, ctor, tipo);
+        let arity = types.len();
+        types.extend_from_slice(rest);
+        let matrix: Vec<Vec<Pattern>> = matrix
+            .iter()
+            .filter_map(|r| specialize(r, ctor, arity))
+            .collect();
+        specialize(row, ctor, arity).is_some_and(|row| is_useful(scope, &types, &matrix, &row))
+    };
+    match head(&row[0]) {
+        Some((ctor, _)) => specialized(&ctor),
+        None => {
+            let heads: Vec<Ctor> = matrix
+                .iter()
+                .filter_map(|r| head(&r[0]).map(|(c, _)| c))
+                .collect();
+            match signature(scope, tipo) {
+                Some(ctors) if ctors.iter().all(|c| heads.contains(c)) => {
+                    ctors.iter().any(specialized)
+                }
+                // Only the rows matching any value can match the missing constructors
+                _ => {
+                    let matrix: Vec<Vec<Pattern>> = matrix
+                        .iter()
+                        .filter(|r| head(&r[0]).is_none())
+                        .map(|r| r[1..].to_vec())
+                        .collect();
+                    is_useful(scope, rest, &matrix, &row[1..])
+                }
+            }
+        }
+    }
+}
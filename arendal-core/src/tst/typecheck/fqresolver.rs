@@ -1,9 +1,9 @@
 use std::collections::HashMap;
 
 use crate::{
-    ast0::Q,
+    ast0::{Q, Segment},
     error::{Error, Errors, Loc, Result},
-    symbol::{FQ, FQPath, FQSym, FQType, Pkg, Symbol, TSymbol},
+    symbol::{FQ, FQPath, FQSym, FQType, Path, Pkg, Symbol, TSymbol},
     visibility::Visibility,
 };
 
@@ -32,10 +32,6 @@ impl FQResolvers {
     pub(super) fn for_path(&self, path: &FQPath) -> &FQResolver {
         self.resolvers.get(path).unwrap()
     }
-
-    pub(super) fn for_symbol<T>(&self, symbol: &FQ<T>) -> &FQResolver {
-        self.resolvers.get(&symbol.path()).unwrap()
-    }
 }
 
 #[derive(Debug)]
@@ -53,14 +49,42 @@ impl FQResolver {
         if q.segments.is_empty() {
             candidates.push(b(&self.path, q.symbol.clone()));
             candidates.push(b(&Pkg::Std.empty(), q.symbol.clone()));
-        } else {
-            todo!();
+        } else if let Some(path) = self.get_path(&q.segments) {
+            candidates.push(b(&path, q.symbol.clone()));
         }
         candidates
     }
 
+    // Paths starting with a dependency alias refer to that package, the rest to the current one
+    fn get_path(&self, segments: &[Segment]) -> Option<FQPath> {
+        let mut symbols = Vec::with_capacity(segments.len());
+        for segment in segments {
+            match segment {
+                Segment::Symbol(s) => symbols.push(s.clone()),
+                Segment::Type(_) => return None, // Nested symbols are not supported yet
+            }
+        }
+        match self.input.dependencies.get(&symbols[0]) {
+            Some(id) => Some(Pkg::External(id.clone()).path(Path::new(symbols.split_off(1)))),
+            None => Some(self.path.pkg.path(Path::new(symbols))),
+        }
+    }
+
+    // Types after a type segment are enclosed in it, e.g. union variants
     fn get_type_candidates(&self, q: &Q<TSymbol>) -> Vec<FQType> {
-        self.get_candidates(|p, s| p.fq_type(s), q)
+        match q.segments.split_last() {
+            Some((Segment::Type(parent), segments)) => {
+                let parent = Q {
+                    segments: segments.to_vec(),
+                    symbol: parent.clone(),
+                };
+                self.get_type_candidates(&parent)
+                    .iter()
+                    .map(|p| FQ::enclosed(p, q.symbol.clone()))
+                    .collect()
+            }
+            _ => self.get_candidates(|p, s| p.fq_type(s), q),
+        }
     }
 
     fn get_symbol_candidates(&self, q: &Q<Symbol>) -> Vec<FQSym> {
@@ -71,9 +95,18 @@ impl FQResolver {
         self.path.can_see(visibility, path)
     }
 
+    // Variants of new types have the visibility of the enclosing type
     fn get_type_visibility(&self, f: &FQType) -> Option<Visibility> {
         self.input.types.get(f).map_or_else(
-            || self.input.new_types.get(f).map(|t| Visibility::Exported), // TODO
+            || match f.parent() {
+                Some(parent) => self
+                    .input
+                    .new_types
+                    .get(parent)
+                    .filter(|t| t.it.it.dfn.has_variant(f.symbol()))
+                    .map(|t| t.it.visibility),
+                None => self.input.new_types.get(f).map(|t| t.it.visibility),
+            },
             |t| Some(t.visibility),
         )
     }
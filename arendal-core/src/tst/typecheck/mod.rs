@@ -1,14 +1,17 @@
+mod deps;
 mod expr;
 mod fqresolver;
 mod types;
 
 use std::sync::Arc;
 
-use im::HashMap;
+use im::{HashMap, HashSet};
 
 use crate::ast0::{self, ExprRef, Q};
-use crate::context::{Context, Type};
+use crate::context::{Context, Type, TypeDfnMap};
+use crate::effect::Effects;
 use crate::error::{Error, Errors, L, Loc, Result};
+use crate::id::Id;
 use crate::symbol::{FQPath, FQSym, FQType, Pkg, Symbol, TSymbol};
 
 use crate::env0::{Env, Symbols};
@@ -20,8 +23,8 @@ use super::{Builder, Expr, Global, Local, Package, TLAssignment, Value};
 pub(super) fn check(env: &Env, ast: &ast0::Package) -> Result<Package> {
     let input = Input::new(env, ast)?;
     let fqresolvers = fqresolver::get(&input)?;
-    let types = types::check(&input, &fqresolvers)?;
-    Checker::new(input, fqresolvers, types).check()
+    let (types, new_types) = types::check(&input, &fqresolvers)?;
+    Checker::new(input, fqresolvers, types, new_types).check()
 }
 
 type TCandidates = HashMap<FQType, ast0::NewTypeRef>;
@@ -38,6 +41,7 @@ struct ECandidate {
 struct Input {
     types: Context,
     symbols: Symbols,
+    dependencies: HashMap<Symbol, Id>,
     pkg: Pkg,
     paths: Vec<FQPath>,
     new_types: TCandidates,
@@ -52,6 +56,7 @@ impl Input {
         let mut input = Self {
             types: env.types.clone(),
             symbols: env.symbols.clone(),
+            dependencies: env.dependencies.clone(),
             pkg: ast.pkg.clone(),
             paths: Vec::default(),
             new_types: Default::default(),
@@ -98,30 +103,39 @@ struct Checker {
     input: InputRef,
     fqresolvers: FQResolvers,
     types: Context,
+    new_types: TypeDfnMap,
     symbols: Symbols,
     assignments: Vec<L<TLAssignment>>,
     expr: Option<L<Expr>>,
 }
 
 impl Checker {
-    fn new(input: InputRef, fqresolvers: FQResolvers, types: Context) -> Self {
+    fn new(
+        input: InputRef,
+        fqresolvers: FQResolvers,
+        types: Context,
+        new_types: TypeDfnMap,
+    ) -> Self {
         let symbols = input.symbols.clone();
         Self {
             input,
             fqresolvers,
             types,
+            new_types,
             symbols,
             assignments: Vec::default(),
             expr: None,
         }
     }
 
-    fn new_scope<'a>(&'a self, path: &'a FQPath) -> Scope {
+    fn new_scope<'a>(&'a self, path: &'a FQPath) -> Scope<'a> {
         Scope {
             checker: self,
             path,
             all: Default::default(),
             current: Default::default(),
+            type_params: Default::default(),
+            effects: Effects::NONE,
         }
     }
 
@@ -156,49 +170,124 @@ impl Checker {
         Ok(Package {
             pkg: self.input.pkg.clone(),
             types: self.types,
+            new_types: self.new_types,
             symbols: self.symbols,
             assignments: self.assignments,
             expr: self.expr,
         })
     }
 
+    // Assignments are checked in dependency order. Components depending on failed ones are
+    // skipped, as they would only report missing dependencies.
     fn check_assignments(&mut self) -> Result<()> {
-        let assignments = self.input.assignments.clone();
-        loop {
-            let mut errors = Errors::default();
-            let progress = self.assignments.len();
-            for (fq, a) in &assignments {
-                if !self.symbols.contains(fq) {
-                    errors.add_result(self.check_assignment(fq, a));
-                }
-            }
-            match errors.to_unit_result() {
-                Ok(_) => return Ok(()),
-                Err(e) => {
-                    let missing = e.missing_symbol_deps();
-                    if self.assignments.len() == progress || missing.is_empty() {
-                        return Err(e);
-                    }
-                }
+        let graph = deps::Graph::new(&self.input, &self.fqresolvers);
+        let mut errors = Errors::default();
+        let mut failed: Vec<FQSym> = Vec::default();
+        for component in graph.components() {
+            let result = if graph.depends_on(&component, &failed) {
+                Ok(())
+            } else if graph.is_cyclic(&component) {
+                self.check_recursive(&graph, &component)
+            } else {
+                self.check_assignment(&component[0])
+            };
+            if errors.add_result(result).is_none() || graph.depends_on(&component, &failed) {
+                failed.extend(component);
             }
         }
+        errors.to_unit_result()
     }
 
-    fn check_assignment(&mut self, fq: &FQSym, a: &ast0::GAssignmentRef) -> Result<()> {
+    fn assignment(&self, fq: &FQSym) -> ast0::GAssignmentRef {
+        self.input.assignments.get(fq).unwrap().clone()
+    }
+
+    fn check_assignment(&mut self, fq: &FQSym) -> Result<()> {
+        let a = self.assignment(fq);
         let expr = expr::check(&self.new_scope(&fq.path()), &a.it.it.expr)?;
         self.symbols
             .set(&a.loc, fq.clone(), a.it.visibility, expr.get_type())?;
+        self.add_assignment(fq, &a, expr);
+        Ok(())
+    }
+
+    fn add_assignment(&mut self, fq: &FQSym, a: &ast0::GAssignmentRef, expr: L<Expr>) {
         self.assignments.push(a.loc.wrap(TLAssignment {
+            visibility: a.it.visibility,
             symbol: fq.clone(),
             expr,
         }));
-        Ok(())
+    }
+
+    // Cycles are only allowed between functions, whose types are set from their signatures
+    // before checking their bodies.
+    fn check_recursive(&mut self, graph: &deps::Graph, component: &[FQSym]) -> Result<()> {
+        let mut lambdas = Vec::with_capacity(component.len());
+        for fq in component {
+            let a = self.assignment(fq);
+            match &a.it.it.expr.it {
+                ast0::Expr::Lambda(l) => lambdas.push((fq, a.clone(), l.clone())),
+                _ => {
+                    return a
+                        .loc
+                        .err(Error::CyclicDependency(graph.cycle(component, fq)));
+                }
+            }
+        }
+        let mut errors = Errors::default();
+        for (fq, a, lambda) in &lambdas {
+            let tipo = match &lambda.result {
+                Some(result) => self.function_type(fq, lambda, result),
+                None => a.loc.err(Error::ResultTypeExpected((*fq).clone())),
+            };
+            if let Some(tipo) = errors.add_result(tipo) {
+                errors.add_result(
+                    self.symbols
+                        .set(&a.loc, (*fq).clone(), a.it.visibility, tipo),
+                );
+            }
+        }
+        errors.to_unit_result()?;
+        let mut errors = Errors::default();
+        for (fq, a, _) in lambdas {
+            let expr = errors.add_result(expr::check(&self.new_scope(&fq.path()), &a.it.it.expr));
+            if let Some(expr) = expr {
+                self.add_assignment(fq, &a, expr);
+            }
+        }
+        errors.to_unit_result()
+    }
+
+    fn function_type(
+        &self,
+        fq: &FQSym,
+        lambda: &ast0::Lambda,
+        result: &ast0::TypeExprRef,
+    ) -> Result<Type> {
+        let path = fq.path();
+        let mut scope = self.new_scope(&path);
+        let mut errors = Errors::default();
+        errors.add_result(scope.add_type_params(&result.loc, &lambda.type_params));
+        let mut types = Vec::with_capacity(lambda.params.len());
+        for p in &lambda.params {
+            if let Some(tipo) = errors.add_result(expr::resolve_type_expr(&scope, &p.it.tipo)) {
+                types.push(tipo);
+            }
+        }
+        let effects = errors.add_result(expr::resolve_effects(&result.loc, &lambda.effects));
+        let result = errors.add_result(expr::resolve_type_expr(&scope, result));
+        errors.to_unit_result()?;
+        let function = Type::type_function_with_effects(types, result.unwrap(), effects.unwrap());
+        Ok(Type::type_generic(lambda.type_params.clone(), function))
     }
 
     fn check_expressions(&mut self) -> Result<()> {
         for e in &self.input.exprs {
             if self.expr.is_none() {
-                self.expr = Some(expr::check(&self.new_scope(&e.path), &e.expr)?);
+                // The top-level expression can perform any effect
+                let mut scope = self.new_scope(&e.path);
+                scope.effects = Effects::all();
+                self.expr = Some(expr::check(&scope, &e.expr)?);
             } else {
                 return e.expr.loc.err(Error::OnlyOneExpressionAllowed);
             }
@@ -217,13 +306,47 @@ struct Scope<'a> {
     path: &'a FQPath,
     all: HashMap<Symbol, Type>,
     current: HashMap<Symbol, bool>,
+    type_params: HashSet<TSymbol>, // Of the enclosing generic functions
+    effects: Effects,              // Allowed in calls, none by default
 }
 
 impl<'a> Scope<'a> {
+    // Type parameters shadow the types with the same name
     fn resolve_type(&self, loc: &Loc, symbol: &Q<TSymbol>) -> Result<Type> {
+        if symbol.segments.is_empty() && self.type_params.contains(&symbol.symbol) {
+            return Ok(Type::type_var(symbol.symbol.clone()));
+        }
         self.checker.resolve_type(loc, self.path, symbol)
     }
 
+    fn infer(&self, tipo: &Type, expected: &[Type], actual: &[Type]) -> Type {
+        self.checker.types.infer(tipo, expected, actual)
+    }
+
+    fn instance_of(&self, generic: &Type, expected: &Type) -> Option<Type> {
+        self.checker.types.instance_of(generic, expected)
+    }
+
+    fn tuple_types(&self, tipo: &Type) -> Option<Vec<Type>> {
+        self.checker.types.tuple_types(tipo)
+    }
+
+    fn join(&self, t1: &Type, t2: &Type) -> Option<Type> {
+        self.checker.types.join(t1, t2)
+    }
+
+    fn record_fields(&self, tipo: &Type) -> Option<Vec<(Symbol, Type)>> {
+        self.checker.types.record_fields(tipo)
+    }
+
+    fn declared(&self, tipo: &Type) -> Type {
+        self.checker.types.declared(tipo)
+    }
+
+    fn variants(&self, tipo: &Type) -> Option<Vec<Type>> {
+        self.checker.types.variants(tipo)
+    }
+
     fn resolve_symbol(&self, loc: &Loc, symbol: &Q<Symbol>) -> Result<Resolved> {
         if symbol.segments.is_empty() {
             if let Some(false) = self.current.get(&symbol.symbol) {
@@ -245,7 +368,21 @@ impl<'a> Scope<'a> {
             path: self.path,
             all: self.all.clone(),
             current: Default::default(),
+            type_params: self.type_params.clone(),
+            effects: self.effects,
+        }
+    }
+
+    fn add_type_params(&mut self, loc: &Loc, params: &[TSymbol]) -> Result<()> {
+        let mut errors = Errors::default();
+        let mut added = HashSet::<TSymbol>::new();
+        for param in params {
+            if added.insert(param.clone()).is_some() {
+                errors.add(loc.wrap(Error::DuplicateLocalType(param.clone())));
+            }
         }
+        self.type_params.extend(added);
+        errors.to_unit_result()
     }
 
     fn add_current(&mut self, loc: &Loc, symbol: Symbol) -> Result<()> {
@@ -1,7 +1,13 @@
 use super::{Package, Type};
+use crate::ast0::Q;
+use crate::context::{Context, TypeDfn, TypeDfnMap, TypeDfnRef};
+use crate::effect::Effect;
 use crate::env0::Env;
-use crate::error::{Error, Result};
-use crate::symbol::Pkg;
+use crate::error::{Error, Loc, Result};
+use crate::id::Id;
+use crate::store::{self, Literal, Name, SymbolEntry, TypeEntry, TypeRef};
+use crate::symbol::{FQ, FQSym, FQType, Path, Pkg, Symbol, TSymbol};
+use crate::visibility::Visibility;
 
 fn check_module(input: &str) -> Result<Package> {
     let parsed = crate::ast0::parser::parse(Pkg::Local, input)?;
@@ -9,14 +15,37 @@ fn check_module(input: &str) -> Result<Package> {
     super::check(&env, &parsed)
 }
 
-fn ok(input: &str) {
-    check_module(input).unwrap();
+fn check_modules(env: &Env, inputs: &[(&str, &str)]) -> Result<Package> {
+    let inputs: Vec<(Path, &str)> = inputs.iter().map(|(p, i)| (path(p), *i)).collect();
+    let parsed = crate::ast0::parser::parse_modules(Pkg::Local, &inputs)?;
+    super::check(env, &parsed)
 }
 
-fn error(input: &str) {
-    if let Ok(_) = check_module(input) {
-        panic!("Expected Error")
-    }
+fn path(path: &str) -> Path {
+    Path::new(
+        path.split("::")
+            .filter(|s| !s.is_empty())
+            .map(|s| Symbol::new(&Loc::None, s.into()).unwrap())
+            .collect(),
+    )
+}
+
+fn symbol(name: &str) -> Symbol {
+    Symbol::new(&Loc::None, name.into()).unwrap()
+}
+
+fn fq_sym(pkg: &Pkg, p: &str, name: &str) -> FQSym {
+    pkg.path(path(p))
+        .fq_sym(Symbol::new(&Loc::None, name.into()).unwrap())
+}
+
+fn fq_type(pkg: &Pkg, p: &str, name: &str) -> FQType {
+    pkg.path(path(p))
+        .fq_type(TSymbol::new(&Loc::None, name.into()).unwrap())
+}
+
+fn ok(input: &str) {
+    check_module(input).unwrap();
 }
 
 fn expect_error(input: &str, error: &Error) {
@@ -78,9 +107,28 @@ fn mismatch2() {
     mismatch("1 && True", Type::type_boolean(), Type::type_integer())
 }
 
+#[test]
+fn comparisons() {
+    let boolean = Type::type_boolean;
+    ok_expression("1 < 2", boolean());
+    ok_expression("1 + 1 >= 2", boolean());
+    ok_expression("1 == 2", boolean());
+    ok_expression("(1 < 2) != False", boolean());
+    ok_expression("True == True", Type::type_true());
+    ok_expression("True == False", Type::type_false());
+    ok_expression("True != False", Type::type_true());
+    ok_expression("None != None", Type::type_false());
+    mismatch("1 == True", Type::type_integer(), Type::type_true());
+    mismatch("1 == 1.0", Type::type_integer(), Type::type_decimal());
+    mismatch("1 < True", Type::type_integer(), Type::type_true());
+    mismatch("1.0 > 2.0", Type::type_integer(), Type::type_decimal());
+}
+
 #[test]
 fn conditionals() {
     ok_int("if True then 1 else 2");
+    ok_expression("if True then True else False", Type::type_boolean());
+    ok_expression("if True then False else False", Type::type_false());
     mismatch(
         "if 0 then 1 else 2",
         Type::type_boolean(),
@@ -102,19 +150,793 @@ fn assignments() {
     ok_int("let a = b + c\nlet b = c\nlet c = 3\nc - 1");
 }
 
+#[test]
+fn annotations() {
+    ok_int("let x: Integer = 1\nx");
+    ok_expression("let b: Boolean = True\nb", Type::type_boolean());
+    ok_expression("(True : Boolean)", Type::type_boolean());
+    ok_expression(
+        "{ let l: List[Boolean] = [True]\nl }",
+        Type::type_list(Type::type_boolean()),
+    );
+    mismatch(
+        "let x: Integer = True",
+        Type::type_integer(),
+        Type::type_true(),
+    );
+    mismatch("(1 > 2 : True)", Type::type_true(), Type::type_boolean());
+}
+
 #[test]
 fn seq() {
     ok_int("True then False then 3")
 }
 
+#[test]
+fn effects() {
+    let io = || Effect::IO.into();
+    let print_type =
+        || Type::type_function_with_effects(vec![Type::type_string()], Type::type_none(), io());
+    ok_expression("print(\"a\") then 1", Type::type_integer());
+    ok_expression("print", print_type());
+    ok_expression(
+        "fn(s: String) with IO = print(s)",
+        Type::type_function_with_effects(vec![Type::type_string()], Type::type_none(), io()),
+    );
+    ok("fn log(s: String): None with IO = print(s)\nlog(\"a\")");
+    ok("let p: (String) -> None with IO = print");
+    // Pure functions can be used where effectful ones are expected
+    ok(
+        "fn apply(f: (String) -> None with IO): None with IO = f(\"a\")\napply(fn(s: String) = None)",
+    );
+    expect_error("let x = print(\"a\")", &Error::EffectNotAllowed(Effect::IO));
+    expect_error(
+        "fn(s: String) = print(s)",
+        &Error::EffectNotAllowed(Effect::IO),
+    );
+    expect_error(
+        "fn f(): None with IO = { let g = fn() = print(\"a\")\ng() }",
+        &Error::EffectNotAllowed(Effect::IO),
+    );
+    mismatch(
+        "let p: (String) -> None = print",
+        Type::type_function(vec![Type::type_string()], Type::type_none()),
+        print_type(),
+    );
+    expect_error(
+        "fn() with Network = 1",
+        &Error::UnknownEffect(TSymbol::new(&Loc::None, "Network".into()).unwrap()),
+    );
+}
+
 #[test]
 fn blocks() {
     ok_int("{ let x = 1\n x+2 }");
     ok_int("{ let x = y+2\nlet y = 3\n x+2 }")
 }
 
+#[test]
+fn functions() {
+    let int = Type::type_integer;
+    let int_to_int = || Type::type_function(vec![int()], int());
+    ok_expression("fn(x: Integer) = x + 1", int_to_int());
+    ok_expression("fn(x: Integer, b: Boolean): Integer = x", {
+        Type::type_function(vec![int(), Type::type_boolean()], int())
+    });
+    ok_expression(
+        "fn(f: (Integer) -> Integer) = f(1)",
+        Type::type_function(vec![int_to_int()], int()),
+    );
+    ok_expression("fn(b: Boolean): Boolean = True", {
+        Type::type_function(vec![Type::type_boolean()], Type::type_boolean())
+    });
+    ok_int("fn f(x: Integer): Integer = x\nf(1)");
+    ok_int("fn f(x: Integer): Integer = g(x)\nfn g(x: Integer): Integer = x\nf(1)");
+    ok_expression("fn f(b: Boolean): Integer = 1\nf(True)", int());
+    mismatch("fn(x: Integer): Boolean = x", Type::type_boolean(), int());
+    mismatch(
+        "fn f(x: Integer): Integer = x\nf(True)",
+        int(),
+        Type::type_true(),
+    );
+    expect_error("1(2)", &Error::FunctionExpected(int()));
+    expect_error(
+        "fn f(x: Integer): Integer = x\nf()",
+        &Error::ArgumentCountMismatch(1, 0),
+    );
+    expect_error(
+        "fn(x: Integer, x: Integer) = x",
+        &Error::DuplicateLocalSymbol(symbol("x")),
+    );
+}
+
+// Long chains of dependencies don't overflow the stack when sorting the assignments
+#[test]
+fn long_dependency_chains() {
+    let n = 20_000;
+    let input: Vec<String> = (1..n)
+        .map(|i| format!("let x{i} = x{}", i - 1))
+        .chain(["let x0 = 0".to_string()])
+        .collect();
+    ok(&input.join("\n"));
+    expect_error(
+        &format!("{}\nlet x0 = x{}", input[..n - 1].join("\n"), n - 1),
+        &Error::CyclicDependency(
+            [0].into_iter()
+                .chain((0..n).rev())
+                .map(|i| fq_sym(&Pkg::Local, "", &format!("x{i}")))
+                .collect(),
+        ),
+    );
+}
+
+#[test]
+fn recursion() {
+    let fq = |name| fq_sym(&Pkg::Local, "", name);
+    ok_int("fn f(n: Integer): Integer = if n > 0 then n * f(n - 1) else 1\nf(3)");
+    ok(
+        "fn even(n: Integer): Boolean = if n == 0 then True else odd(n - 1)\nfn odd(n: Integer): Boolean = if n == 0 then False else even(n - 1)",
+    );
+    ok("let x = f(1)\nfn f(n: Integer): Integer = if n > 0 then f(n - 1) else 0");
+    // Locals shadow top-level symbols
+    ok("let x = fn(x: Integer) = x + 1");
+    expect_error(
+        "let x = y + 1\nlet y = x",
+        &Error::CyclicDependency(vec![fq("x"), fq("y"), fq("x")]),
+    );
+    expect_error(
+        "let a = b\nlet b = c\nlet c = a",
+        &Error::CyclicDependency(vec![fq("a"), fq("b"), fq("c"), fq("a")]),
+    );
+    expect_error(
+        "let x = x",
+        &Error::CyclicDependency(vec![fq("x"), fq("x")]),
+    );
+    expect_error(
+        "let x = f(1)\nfn f(n: Integer): Integer = x",
+        &Error::CyclicDependency(vec![fq("x"), fq("f"), fq("x")]),
+    );
+    expect_error(
+        "let f = fn(n: Integer) = f(n)",
+        &Error::ResultTypeExpected(fq("f")),
+    );
+    mismatch(
+        "fn f(n: Integer): Integer = g(n)\nfn g(n: Integer): Boolean = f(n) == 1\nf(1)",
+        Type::type_integer(),
+        Type::type_boolean(),
+    );
+}
+
 #[test]
 fn only_one_expr() {
     expect_error("1\n2", &Error::OnlyOneExpressionAllowed);
     expect_error("{ 1\n2 }", &Error::OnlyOneExpressionAllowed)
 }
+
+fn visibility_error(env: &Env, inputs: &[(&str, &str)], error: Error) {
+    match check_modules(env, inputs) {
+        Ok(_) => panic!("Expected Error: {:?}", error),
+        Err(e) => assert!(
+            e.contains(&error),
+            "Expected Error: {error:?} - Actual Error: {e:?}"
+        ),
+    }
+}
+
+#[test]
+fn visibility_across_modules() {
+    let env = Env::default();
+    let a = [
+        (
+            "a",
+            "let x = 1
+pkg let y = x
+pub let z = y",
+        ),
+        (
+            "a",
+            "type Red
+pkg type Green
+pub type Blue
+let r = Red",
+        ),
+    ];
+    check_modules(&env, &[a[0], ("", "a::y + a::z")]).unwrap();
+    check_modules(&env, &[a[0], ("b", "pkg let w = a::y")]).unwrap();
+    check_modules(&env, &[a[1], ("", "a::Green then a::Blue")]).unwrap();
+    visibility_error(
+        &env,
+        &[a[0], ("", "a::x")],
+        Error::SymbolNotVisible(fq_sym(&Pkg::Local, "a", "x")),
+    );
+    visibility_error(
+        &env,
+        &[a[0], ("b::c", "let w = a::x")],
+        Error::SymbolNotVisible(fq_sym(&Pkg::Local, "a", "x")),
+    );
+    visibility_error(
+        &env,
+        &[a[1], ("", "a::Red")],
+        Error::TypeNotVisible(fq_type(&Pkg::Local, "a", "Red")),
+    );
+}
+
+#[test]
+fn visibility_across_packages() {
+    let name = |n: &str| Name::new(Vec::default(), n.to_string());
+    let entry = |visibility, n: &str| SymbolEntry {
+        visibility,
+        name: name(n),
+        tipo: TypeRef::Std("Integer".to_string()),
+        value: Literal::Integer(1.into()),
+    };
+    let package = store::Package {
+        types: vec![
+            TypeEntry {
+                visibility: Visibility::Exported,
+                name: name("Blue"),
+                dfn: store::TypeDfn::Singleton,
+            },
+            TypeEntry {
+                visibility: Visibility::Package,
+                name: name("Green"),
+                dfn: store::TypeDfn::Singleton,
+            },
+        ],
+        symbols: vec![
+            entry(Visibility::Exported, "x"),
+            entry(Visibility::Package, "y"),
+            entry(Visibility::Module, "z"),
+        ],
+    };
+    let id = Id::digest(b"lib");
+    let lib = Pkg::External(id.clone());
+    let mut env = Env::default();
+    env.add_dependency("lib", id, &package).unwrap();
+    check_modules(&env, &[("", "lib::x then lib::Blue")]).unwrap();
+    for hidden in ["y", "z"] {
+        visibility_error(
+            &env,
+            &[("a", &format!("pub let w = lib::{hidden}"))],
+            Error::SymbolNotVisible(fq_sym(&lib, "", hidden)),
+        );
+    }
+    visibility_error(
+        &env,
+        &[("", "lib::Green")],
+        Error::TypeNotVisible(fq_type(&lib, "", "Green")),
+    );
+}
+
+#[test]
+fn tuples() {
+    let int = Type::type_integer;
+    let pair = || Type::type_tuple(vec![int(), Type::type_true()]);
+    ok_expression("(1, True)", pair());
+    ok_expression("(1, True).1", Type::type_true());
+    ok_expression("(1, (2, 3)).1.0", int());
+    ok_expression(
+        "fn f(p: (Integer, Boolean)): Integer = p.0\nf((1, True))",
+        int(),
+    );
+    ok_expression("fn(p: (Integer,)) = p", {
+        let single = Type::type_tuple(vec![int()]);
+        Type::type_function(vec![single.clone()], single)
+    });
+    assert_eq!(pair().to_string(), "(std::Integer, std::True)");
+    assert_eq!(Type::type_tuple(vec![int()]).to_string(), "(std::Integer,)");
+    ok_int("type Point(Integer, Integer)\nPoint(1, 2).1");
+    ok_int(
+        "type Point(Integer, Integer)\ntype Line(Point, Point)\nLine(Point(1, 2), Point(3, 4)).1.0",
+    );
+    mismatch(
+        "type Point(Integer, Integer)\nPoint(1, True)",
+        int(),
+        Type::type_true(),
+    );
+    expect_error(
+        "type Point(Integer, Integer)\nPoint(1)",
+        &Error::ArgumentCountMismatch(2, 1),
+    );
+    expect_error("1.0.0", &Error::TupleExpected(Type::type_decimal()));
+    expect_error(
+        "(1, 2).2",
+        &Error::TupleIndexOutOfBounds(Type::type_tuple(vec![int(), int()]), 2),
+    );
+    expect_error("True(1)", &Error::TupleExpected(Type::type_true()));
+    ok_int("type Pair((Integer, Integer), Boolean)\nPair((1, 2), True).0.1");
+}
+
+#[test]
+fn records() {
+    let int = Type::type_integer;
+    let person = "type Person { age: Integer, adult: Boolean }\n";
+    let ok_person = |input: &str, t: Type| ok_expression(&format!("{person}{input}"), t);
+    let person_error = |input: &str, e: Error| expect_error(&format!("{person}{input}"), &e);
+    let person_type = || {
+        check_module(person)
+            .unwrap()
+            .types
+            .get(&fq_type(&Pkg::Local, "", "Person"))
+            .unwrap()
+            .it
+            .clone()
+    };
+    ok_person("Person { age: 1, adult: False }.age", int());
+    ok_person("Person { adult: True, age: 1 }.adult", Type::type_boolean());
+    ok_person(
+        "Person { age: 1, adult: False } with { age: 2 }",
+        person_type(),
+    );
+    ok_person(
+        "Person { age: 1, adult: False } == Person { age: 1, adult: False }",
+        Type::type_boolean(),
+    );
+    ok_int(
+        "type Line { from: Point, to: Point }\ntype Point(Integer, Integer)\nLine { from: Point(1, 2), to: Point(3, 4) }.to.0",
+    );
+    person_error(
+        "Person { age: True, adult: True }",
+        Error::type_mismatch(int(), Type::type_true()),
+    );
+    person_error(
+        "Person { age: 1 }",
+        Error::MissingField(person_type(), symbol("adult")),
+    );
+    person_error(
+        "Person { age: 1, adult: True, name: 2 }",
+        Error::UnknownField(person_type(), symbol("name")),
+    );
+    person_error(
+        "Person { age: 1, age: 2, adult: True }",
+        Error::DuplicateField(symbol("age")),
+    );
+    person_error(
+        "Person { age: 1, adult: True }.name",
+        Error::UnknownField(person_type(), symbol("name")),
+    );
+    person_error(
+        "Person { age: 1, adult: True } with { age: False }",
+        Error::type_mismatch(int(), Type::type_false()),
+    );
+    expect_error("1.age", &Error::RecordExpected(int()));
+    expect_error("1 with { age: 2 }", &Error::RecordExpected(int()));
+    expect_error("True { age: 2 }", &Error::RecordExpected(Type::type_true()));
+    expect_error(
+        "type Person { age: Integer, age: Integer }",
+        &Error::DuplicateField(symbol("age")),
+    );
+    let env = Env::default();
+    let a = ("a", "type Secret\npkg type Person { age: Integer }");
+    check_modules(&env, &[a, ("", "a::Person { age: 1 }.age")]).unwrap();
+    visibility_error(
+        &env,
+        &[a, ("b", "pub type Hidden { secret: a::Secret }")],
+        Error::TypeNotVisible(fq_type(&Pkg::Local, "a", "Secret")),
+    );
+}
+
+#[test]
+fn unions() {
+    let shape = "type Shape = Circle(Integer) | Square(Integer) | Empty\n";
+    let ok_shape = |input: &str, t: Type| ok_expression(&format!("{shape}{input}"), t);
+    let shape_error = |input: &str, e: Error| expect_error(&format!("{shape}{input}"), &e);
+    let types = || check_module(shape).unwrap().types;
+    let shape_type = || {
+        types()
+            .get(&fq_type(&Pkg::Local, "", "Shape"))
+            .unwrap()
+            .it
+            .clone()
+    };
+    let variant = |name: &str| {
+        let fq = FQ::enclosed(
+            &fq_type(&Pkg::Local, "", "Shape"),
+            TSymbol::new(&Loc::None, name.into()).unwrap(),
+        );
+        types().get(&fq).unwrap().it.clone()
+    };
+    ok_shape("Shape::Circle(1)", variant("Circle"));
+    ok_shape("Shape::Empty", variant("Empty"));
+    ok_shape("Shape::Square(2).0", Type::type_integer());
+    ok_shape(
+        "fn area(s: Shape): Shape = s\narea(Shape::Circle(1))",
+        shape_type(),
+    );
+    ok_shape(
+        "if True then Shape::Empty else Shape::Circle(1)",
+        shape_type(),
+    );
+    ok_shape("Shape::Circle(1) == Shape::Empty", Type::type_boolean());
+    ok_expression("if True then False else True", Type::type_boolean());
+    ok_expression(
+        "fn f(b: Boolean): Boolean = b\nf(True)",
+        Type::type_boolean(),
+    );
+    shape_error(
+        "fn area(s: Shape): Shape = s\narea(1)",
+        Error::type_mismatch(shape_type(), Type::type_integer()),
+    );
+    shape_error(
+        "fn f(s: Shape::Circle): Shape::Circle = s\nf(Shape::Empty)",
+        Error::type_mismatch(variant("Circle"), variant("Empty")),
+    );
+    shape_error(
+        "Shape::Circle(True)",
+        Error::type_mismatch(Type::type_integer(), Type::type_true()),
+    );
+    shape_error(
+        "if True then Shape::Empty else 1",
+        Error::type_mismatch(variant("Empty"), Type::type_integer()),
+    );
+    expect_error(
+        "type Shape = Empty | Empty",
+        &Error::DuplicateType(FQ::enclosed(
+            &fq_type(&Pkg::Local, "", "Shape"),
+            TSymbol::new(&Loc::None, "Empty".into()).unwrap(),
+        )),
+    );
+    let env = Env::default();
+    let a = ("a", "type Secret\npkg type Shape = Circle(Integer) | Empty");
+    check_modules(&env, &[a, ("", "a::Shape::Circle(1).0")]).unwrap();
+    visibility_error(
+        &env,
+        &[a, ("b", "pub type Hidden = Hide(a::Secret)")],
+        Error::TypeNotVisible(fq_type(&Pkg::Local, "a", "Secret")),
+    );
+}
+
+#[test]
+fn matches() {
+    let shape = "type Shape = Circle(Integer) | Square(Integer) | Rect { w: Integer, h: Integer } | Empty\n";
+    let ok_shape = |input: &str, t: Type| ok_expression(&format!("{shape}{input}"), t);
+    let shape_error = |input: &str, e: Error| expect_error(&format!("{shape}{input}"), &e);
+    let fn_area = |arms: &str| {
+        format!("fn area(s: Shape): Integer = match s {{ {arms} }}\narea(Shape::Empty)")
+    };
+    ok_int("match 1 { 0 -> 1, n -> n }");
+    ok_int("fn f(b: Boolean): Integer = match b { True -> 1, False -> 2 }\nf(True)");
+    ok_int(
+        "fn f(t: (Integer, Boolean)): Integer = match t { (0, _) -> 0, (n, True) -> n, (_, False) -> 2 }\nf((1, True))",
+    );
+    ok_shape(
+        &fn_area(
+            "Shape::Circle(r) -> r * r, Shape::Square(l) -> l * l, Shape::Rect { w: w, h: h } -> w * h, Shape::Empty -> 0",
+        ),
+        Type::type_integer(),
+    );
+    ok_shape(
+        &fn_area("Shape::Circle(0) -> 0, Shape::Rect { h: 0 } -> 0, Shape::Empty -> 0, _ -> 1"),
+        Type::type_integer(),
+    );
+    // The type is the join of the types of the arms
+    ok_expression(
+        "let x = 1\nmatch x { 0 -> True, _ -> False }",
+        Type::type_boolean(),
+    );
+    // Bindings shadow the enclosing locals, and are only visible in their arm
+    ok_int("let n = True\nmatch 1 { n -> n + 1 }");
+    let non_exhaustive = Error::NonExhaustiveMatch;
+    expect_error("match 1 { 0 -> 1, 1 -> 2 }", &non_exhaustive);
+    expect_error(
+        "fn f(b: Boolean): Integer = match b { True -> 1 }",
+        &non_exhaustive,
+    );
+    expect_error(
+        "fn f(t: (Boolean, Boolean)): Integer = match t { (True, _) -> 1, (_, True) -> 2 }",
+        &non_exhaustive,
+    );
+    shape_error(
+        &fn_area("Shape::Circle(r) -> r, Shape::Square(l) -> l, Shape::Empty -> 0"),
+        non_exhaustive.clone(),
+    );
+    shape_error(
+        &fn_area(
+            "Shape::Circle(1) -> 1, Shape::Square(_) -> 1, Shape::Rect { } -> 1, Shape::Empty -> 0",
+        ),
+        non_exhaustive,
+    );
+    expect_error("match 1 { _ -> 1, 0 -> 2 }", &Error::UnreachableArm);
+    expect_error("match 1 { 0 -> 1, 0 -> 2, _ -> 3 }", &Error::UnreachableArm);
+    expect_error(
+        "fn f(b: Boolean): Integer = match b { True -> 1, False -> 2, _ -> 3 }",
+        &Error::UnreachableArm,
+    );
+    // Values are matched as their declared type
+    ok_int("match True { True -> 1, False -> 2 }");
+    expect_error(
+        "match True { True -> 1, False -> 2, _ -> 3 }",
+        &Error::UnreachableArm,
+    );
+    ok_int("match True { True -> 1 }");
+    ok_shape(
+        "match Shape::Empty { Shape::Empty -> 0, Shape::Circle(r) -> r }",
+        Type::type_integer(),
+    );
+    shape_error(
+        "match Shape::Empty { Shape::Circle(r) -> r, Shape::Empty -> 0, _ -> 1 }",
+        Error::UnreachableArm,
+    );
+    shape_error(
+        &fn_area("Shape::Circle(_) -> 1, _ -> 2, Shape::Empty -> 0"),
+        Error::UnreachableArm,
+    );
+    expect_error(
+        "match 1 { True -> 1, _ -> 2 }",
+        &Error::type_mismatch(Type::type_integer(), Type::type_true()),
+    );
+    expect_error(
+        "match 1 { 0 -> 1, _ -> True }",
+        &Error::type_mismatch(Type::type_integer(), Type::type_true()),
+    );
+    expect_error(
+        "match 1 { (a, b) -> a }",
+        &Error::TupleExpected(Type::type_integer()),
+    );
+    expect_error(
+        "match (1, 2) { (a, b, c) -> a }",
+        &Error::ArgumentCountMismatch(2, 3),
+    );
+    expect_error(
+        "match (1, 2) { (a, a) -> a }",
+        &Error::DuplicateLocalSymbol(symbol("a")),
+    );
+    expect_error(
+        "match 1 { 0 -> n, n -> n }",
+        &Error::UnableToResolveSymbol(Q {
+            segments: Vec::default(),
+            symbol: symbol("n"),
+        }),
+    );
+    shape_error(
+        &fn_area("Shape::Rect { d: 0 } -> 0, _ -> 1"),
+        Error::UnknownField(
+            check_module(shape)
+                .unwrap()
+                .types
+                .get(&FQ::enclosed(
+                    &fq_type(&Pkg::Local, "", "Shape"),
+                    TSymbol::new(&Loc::None, "Rect".into()).unwrap(),
+                ))
+                .unwrap()
+                .it
+                .clone(),
+            symbol("d"),
+        ),
+    );
+}
+
+#[test]
+fn visibility_in_type_definitions() {
+    let dfn = |visibility: Visibility, refs: Vec<FQType>| {
+        let refs = refs
+            .into_iter()
+            .map(|r| Loc::None.wrap(TypeDfnRef::Symbol(r)))
+            .collect();
+        Loc::None.wrap(visibility.wrap(TypeDfn::Tuple(refs)))
+    };
+    let single = |visibility: Visibility| Loc::None.wrap(visibility.wrap(TypeDfn::Singleton));
+    let red = fq_type(&Pkg::Local, "a", "Red");
+    let green = fq_type(&Pkg::Local, "a", "Green");
+    let types = |pair: FQType| {
+        let mut types = TypeDfnMap::default();
+        types.insert(red.clone(), single(Visibility::Module));
+        types.insert(green.clone(), single(Visibility::Package));
+        types.insert(
+            pair,
+            dfn(Visibility::Exported, vec![red.clone(), green.clone()]),
+        );
+        types
+    };
+    let context = Context::default();
+    assert!(
+        context
+            .add_types(&types(fq_type(&Pkg::Local, "a", "Pair")))
+            .is_ok()
+    );
+    let e = context
+        .add_types(&types(fq_type(&Pkg::Local, "b", "Pair")))
+        .unwrap_err();
+    assert!(e.contains(&Error::TypeNotVisible(red.clone())), "{e:?}");
+    assert!(!e.contains(&Error::TypeNotVisible(green.clone())), "{e:?}");
+}
+
+#[test]
+fn strings() {
+    let string = Type::type_string;
+    ok_expression(r#""abc""#, string());
+    ok_expression(r#""a" + "b""#, string());
+    ok_expression(r#""a".length + 1"#, Type::type_integer());
+    ok_expression(r#""a" < "b""#, Type::type_boolean());
+    ok_expression(r#""a" == "b""#, Type::type_boolean());
+    ok_expression("let x = 1\n\"x = {x}, {(x, True)}\"", string());
+    mismatch(r#""a" + 1"#, string(), Type::type_integer());
+    mismatch(r#"1 + "a""#, string(), Type::type_integer());
+    mismatch(r#""a" - "b""#, Type::type_integer(), string());
+    mismatch(r#""a" == 1"#, string(), Type::type_integer());
+    expect_error(r#""a".size"#, &Error::RecordExpected(string()));
+    expect_error(
+        r#""{y}""#,
+        &Error::UnableToResolveSymbol(Q {
+            segments: vec![],
+            symbol: symbol("y"),
+        }),
+    );
+}
+
+#[test]
+fn collections() {
+    let int = Type::type_integer;
+    let list = Type::type_list;
+    ok_expression("[1, 2]", list(int()));
+    ok_expression("[True, False]", list(Type::type_boolean()));
+    ok_expression("[[1], [2, 3]]", list(list(int())));
+    ok_expression(
+        r#"{"a": 1, "b": 2}"#,
+        Type::type_map(Type::type_string(), int()),
+    );
+    ok_expression("[1, 2][0] + 1", int());
+    ok_expression(r#"{"a": True}["a"]"#, Type::type_true());
+    ok_expression("[1].length + {1: 2}.length", int());
+    ok_expression("[1] == [1, 2]", Type::type_boolean());
+    ok_expression(
+        "let f = fn(l: List[Boolean], m: Map[Integer, List[Integer]]) = l[m[0][0]]\nf([True], {0: [0]})",
+        Type::type_boolean(),
+    );
+    mismatch("[1, True]", int(), Type::type_true());
+    mismatch(r#"{1: 2, "a": 3}"#, int(), Type::type_string());
+    mismatch("[1][True]", int(), Type::type_true());
+    mismatch(r#"{"a": 1}[1]"#, Type::type_string(), int());
+    mismatch("[1] == [True]", list(int()), list(Type::type_true()));
+    mismatch(
+        "let f = fn(l: List[Integer]) = l\nf([True])",
+        list(int()),
+        list(Type::type_true()),
+    );
+    expect_error("[]", &Error::EmptyCollection);
+    expect_error("{:}", &Error::EmptyCollection);
+    expect_error("[[]]", &Error::EmptyCollection);
+    expect_error("1[0]", &Error::CollectionExpected(int()));
+    expect_error("[1].size", &Error::RecordExpected(list(int())));
+    expect_error(
+        "let f = fn(l: List[Integer, Integer]) = l",
+        &Error::TypeArgumentCountMismatch(1, 2),
+    );
+    expect_error(
+        "let f = fn(l: Integer[Integer]) = l",
+        &Error::TypeArgumentCountMismatch(0, 1),
+    );
+}
+
+// Empty collections get their types from the expected type: annotations, ascriptions, results
+// of functions and parameters of non-generic functions
+#[test]
+fn empty_collections() {
+    let int = Type::type_integer;
+    let list = Type::type_list;
+    let map = |k, v| Type::type_map(k, v);
+    ok_expression("([] : List[Integer])", list(int()));
+    ok_expression("let e: List[Integer] = []\ne", list(int()));
+    ok_expression("let e: List[Integer] = []\ne.length", int());
+    ok_expression(
+        "({:} : Map[String, Integer])",
+        map(Type::type_string(), int()),
+    );
+    ok_expression(
+        "let m: Map[String, List[Integer]] = {:}\nm",
+        map(Type::type_string(), list(int())),
+    );
+    ok_expression("([[], [1]] : List[List[Integer]])", list(list(int())));
+    ok_expression(
+        "([{:}] : List[Map[Integer, Boolean]])",
+        list(map(int(), Type::type_boolean())),
+    );
+    ok_expression("(if 1 > 0 then [] else [1] : List[Integer])", list(int()));
+    ok_expression("({ let x = 1\n[] } : List[Integer])", list(int()));
+    ok_expression("fn empty(): List[Integer] = []\nempty()", list(int()));
+    ok_expression(
+        "fn count(l: List[Integer], m: Map[String, Integer]): Integer = l.length + m.length\ncount([], {:})",
+        int(),
+    );
+    expect_error("([] : Integer)", &Error::EmptyCollection);
+    expect_error(
+        "fn first[T](l: List[T]): T = l[0]\nfirst([])",
+        &Error::EmptyCollection,
+    );
+}
+
+// Collections, tuples and functions can be used in type definitions, including the type
+// being defined
+#[test]
+fn collections_in_types() {
+    let int = Type::type_integer;
+    let list = Type::type_list;
+    let bag = "type Bag { items: List[Integer], counts: Map[String, Integer] }\n";
+    ok_expression(
+        &format!("{bag}Bag {{ items: [1], counts: {{\"a\": 2}} }}.items"),
+        list(int()),
+    );
+    mismatch(
+        &format!("{bag}Bag {{ items: [True], counts: {{\"a\": 2}} }}"),
+        list(int()),
+        list(Type::type_true()),
+    );
+    ok_int(
+        "type Tree = Leaf(Integer) | Node(List[Tree])\nlet t: Tree = Tree::Node([Tree::Leaf(1), Tree::Node([Tree::Leaf(2)])])\nmatch t { Tree::Node(l) -> l.length, Tree::Leaf(n) -> n }",
+    );
+    ok_int(
+        "type Op(Integer, (Integer, Integer) -> Integer)\nlet o = Op(1, fn(a: Integer, b: Integer) = a + b)\no.1(o.0, 2)",
+    );
+    ok_int(
+        "type Pairs[T] = Many(List[(T, T)]) | One\nmatch Pairs::Many([(1, 2)]) { Pairs::Many(l) -> l[0].1 }",
+    );
+    expect_error(
+        "type Bag { items: List[Integer, Integer] }",
+        &Error::TypeArgumentCountMismatch(1, 2),
+    );
+    expect_error(
+        "type Bag { items: Map[Integer] }",
+        &Error::TypeArgumentCountMismatch(2, 1),
+    );
+    expect_error(
+        "type Task { run: () -> Integer with Magic }",
+        &Error::UnknownEffect(TSymbol::new(&Loc::None, "Magic".into()).unwrap()),
+    );
+}
+
+#[test]
+fn generics() {
+    let int = Type::type_integer;
+    let pair = "type Pair[A, B](A, B)\n";
+    let type_of = |input: &str| {
+        let input = format!("{pair}{input}");
+        check_module(&input).unwrap().expr.unwrap().get_type()
+    };
+    let with_pair = |input: &str| format!("{pair}{input}");
+    ok_expression(&with_pair("Pair(1, True).1"), Type::type_true());
+    ok_expression("fn id[T](x: T): T = x\nid(1)", int());
+    ok_expression("(fn[T](x: T) = [x])(1)", Type::type_list(int()));
+    ok_expression(
+        &with_pair(
+            "fn swap[A, B](p: Pair[A, B]): Pair[B, A] = Pair(p.1, p.0)\nswap(Pair(1, True)).0",
+        ),
+        Type::type_true(),
+    );
+    ok_expression(
+        "type Option[T] = Some(T) | None\nif True then Option::Some(1) else Option::None",
+        type_of(
+            "type Option[T] = Some(T) | None\nfn f(o: Option[Integer]): Option[Integer] = o\nf(Option::Some(1))",
+        ),
+    );
+    mismatch(
+        "fn f[T](x: T, y: T): T = y\nf(1, \"a\")",
+        int(),
+        Type::type_string(),
+    );
+    mismatch(
+        &with_pair("fn f(p: Pair[Integer, Integer]): Integer = p.0\nf(Pair(1, True))"),
+        type_of("Pair(1, 1)"),
+        type_of("Pair(1, True)"),
+    );
+    expect_error(
+        &with_pair("let f = fn(p: Pair) = p"),
+        &Error::TypeArgumentCountMismatch(2, 0),
+    );
+    expect_error(
+        &with_pair("let f = fn(p: Pair[Integer]) = p"),
+        &Error::TypeArgumentCountMismatch(2, 1),
+    );
+    expect_error(
+        &with_pair("type Wrapper(Pair[Integer])"),
+        &Error::TypeArgumentCountMismatch(2, 1),
+    );
+    expect_error("type Empty[T]", &Error::TypeArgumentCountMismatch(0, 1));
+    expect_error(
+        "type Pair[A, A](A, A)",
+        &Error::DuplicateLocalType(TSymbol::new(&Loc::None, "A".into()).unwrap()),
+    );
+    expect_error(
+        "let f = fn[T, T](x: T) = x",
+        &Error::DuplicateLocalType(TSymbol::new(&Loc::None, "T".into()).unwrap()),
+    );
+}
@@ -1,18 +1,21 @@
 use crate::{
     ast0,
-    context::{Context, TypeDfn, TypeDfnMap},
-    error::{Errors, Result},
+    context::{Context, TypeDfn, TypeDfnMap, TypeDfnRef},
+    error::{Error, Errors, L, Result},
+    symbol::{self, FQ, FQType, Symbol, TSymbol},
 };
 
-use super::{FQResolvers, Input};
+use super::Input;
+use super::expr::resolve_effects;
+use super::fqresolver::{FQResolver, FQResolvers};
 
-pub(super) fn check(input: &Input, fqresolvers: &FQResolvers) -> Result<Context> {
-    let errors = Errors::default();
+// Returns the new context and the definitions of the new types
+pub(super) fn check(input: &Input, fqresolvers: &FQResolvers) -> Result<(Context, TypeDfnMap)> {
+    let mut errors = Errors::default();
     let mut dfns = TypeDfnMap::default();
     for (symbol, new_type) in &input.new_types {
-        let maybe = match new_type.it.it.dfn {
-            ast0::TypeDfn::Singleton => Some(TypeDfn::Singleton),
-        };
+        let fqresolver = fqresolvers.for_path(&symbol.path());
+        let maybe = check_new_type(&mut errors, fqresolver, symbol, new_type);
         if let Some(checked) = maybe {
             dfns.insert(
                 symbol.clone(),
@@ -20,5 +23,162 @@ pub(super) fn check(input: &Input, fqresolvers: &FQResolvers) -> Result<Context>
             );
         }
     }
-    input.types.add_types(&errors.to_result(dfns)?)
+    let dfns = errors.to_result(dfns)?;
+    Ok((input.types.add_types(&dfns)?, dfns))
+}
+
+// Generic definitions are wrapped with their parameters
+fn check_new_type(
+    errors: &mut Errors,
+    fqresolver: &FQResolver,
+    symbol: &FQType,
+    new_type: &ast0::NewTypeRef,
+) -> Option<TypeDfn> {
+    let params = &new_type.it.it.params;
+    let mut valid = true;
+    for (i, param) in params.iter().enumerate() {
+        if params[..i].contains(param) {
+            errors.add(new_type.error(Error::DuplicateLocalType(param.clone())));
+            valid = false;
+        }
+    }
+    if !params.is_empty() && new_type.it.it.dfn == ast0::TypeDfn::Singleton {
+        errors.add(new_type.error(Error::TypeArgumentCountMismatch(0, params.len())));
+        valid = false;
+    }
+    let dfn = check_dfn(errors, fqresolver, params, symbol, &new_type.it.it.dfn)?;
+    if !valid {
+        None
+    } else if params.is_empty() {
+        Some(dfn)
+    } else {
+        Some(TypeDfn::Generic(params.clone(), Box::new(dfn)))
+    }
+}
+
+// Returns None if there are errors, which are added to the provided ones
+fn check_dfn(
+    errors: &mut Errors,
+    fqresolver: &FQResolver,
+    params: &[TSymbol],
+    symbol: &FQType,
+    dfn: &ast0::TypeDfn,
+) -> Option<TypeDfn> {
+    match dfn {
+        ast0::TypeDfn::Singleton => Some(TypeDfn::Singleton),
+        ast0::TypeDfn::Tuple(types) => errors
+            .add_result(check_refs(fqresolver, params, types))
+            .map(TypeDfn::Tuple),
+        ast0::TypeDfn::Record(fields) => {
+            let mut refs: Vec<(Symbol, L<TypeDfnRef>)> = Vec::with_capacity(fields.len());
+            let mut valid = true;
+            for f in fields {
+                let field = &f.it.symbol;
+                if refs.iter().any(|(s, _)| s == field) {
+                    errors.add(f.error(Error::DuplicateField(field.clone())));
+                    valid = false;
+                } else {
+                    match errors.add_result(check_ref(fqresolver, params, &f.it.tipo)) {
+                        Some(r) => refs.push((field.clone(), r)),
+                        None => valid = false,
+                    }
+                }
+            }
+            valid.then_some(TypeDfn::Record(refs))
+        }
+        ast0::TypeDfn::Union(variants) => {
+            let mut checked: Vec<(TSymbol, TypeDfn)> = Vec::with_capacity(variants.len());
+            let mut valid = true;
+            for v in variants {
+                let variant = &v.it.symbol;
+                let fq = FQ::enclosed(symbol, variant.clone());
+                if checked.iter().any(|(s, _)| s == variant) {
+                    errors.add(v.error(Error::DuplicateType(fq)));
+                    valid = false;
+                } else {
+                    match check_dfn(errors, fqresolver, params, &fq, &v.it.dfn) {
+                        Some(dfn) => checked.push((variant.clone(), dfn)),
+                        None => valid = false,
+                    }
+                }
+            }
+            valid.then_some(TypeDfn::Union(checked))
+        }
+    }
+}
+
+// Besides named types and the parameters of the type being defined, definitions can use
+// instances of generic types and of the builtin collections, tuples and functions
+fn check_ref(
+    fqresolver: &FQResolver,
+    params: &[TSymbol],
+    t: &ast0::TypeExprRef,
+) -> Result<L<TypeDfnRef>> {
+    match &t.it {
+        ast0::TypeExpr::Type(q) if q.segments.is_empty() && params.contains(&q.symbol) => {
+            Ok(t.loc.wrap(TypeDfnRef::Var(q.symbol.clone())))
+        }
+        ast0::TypeExpr::Type(q) => fqresolver
+            .resolve_fq_type(&t.loc, q)
+            .map(|fq| t.loc.wrap(TypeDfnRef::Symbol(fq))),
+        ast0::TypeExpr::Parameterized(q, args) if q.segments.is_empty() && is_builtin(q) => {
+            let expected = if q.symbol == symbol::LIST { 1 } else { 2 };
+            if args.len() != expected {
+                return t
+                    .loc
+                    .err(Error::TypeArgumentCountMismatch(expected, args.len()));
+            }
+            let mut args = check_refs(fqresolver, params, args)?
+                .into_iter()
+                .map(Box::new);
+            let first = args.next().unwrap();
+            Ok(t.loc.wrap(match args.next() {
+                Some(second) => TypeDfnRef::Map(first, second),
+                None => TypeDfnRef::List(first),
+            }))
+        }
+        ast0::TypeExpr::Parameterized(q, args) => {
+            let mut errors = Errors::default();
+            let fq = errors.add_result(fqresolver.resolve_fq_type(&t.loc, q));
+            let args = errors.add_result(check_refs(fqresolver, params, args));
+            errors.to_lazy_result(|| t.loc.wrap(TypeDfnRef::Apply(fq.unwrap(), args.unwrap())))
+        }
+        ast0::TypeExpr::Tuple(types) => {
+            let types = check_refs(fqresolver, params, types)?;
+            Ok(t.loc.wrap(TypeDfnRef::Tuple(types)))
+        }
+        ast0::TypeExpr::Function(types, result, effects) => {
+            let mut errors = Errors::default();
+            let types = errors.add_result(check_refs(fqresolver, params, types));
+            let result = errors.add_result(check_ref(fqresolver, params, result));
+            let effects = errors.add_result(resolve_effects(&t.loc, effects));
+            errors.to_lazy_result(|| {
+                let result = Box::new(result.unwrap());
+                t.loc.wrap(TypeDfnRef::Function(
+                    types.unwrap(),
+                    result,
+                    effects.unwrap(),
+                ))
+            })
+        }
+    }
+}
+
+fn is_builtin(q: &ast0::Q<TSymbol>) -> bool {
+    q.symbol == symbol::LIST || q.symbol == symbol::MAP
+}
+
+fn check_refs(
+    fqresolver: &FQResolver,
+    params: &[TSymbol],
+    types: &[ast0::TypeExprRef],
+) -> Result<Vec<L<TypeDfnRef>>> {
+    let mut errors = Errors::default();
+    let mut refs = Vec::with_capacity(types.len());
+    for t in types {
+        if let Some(r) = errors.add_result(check_ref(fqresolver, params, t)) {
+            refs.push(r);
+        }
+    }
+    errors.to_result(refs)
 }
@@ -1,14 +1,128 @@
 use std::fmt::{self, Debug};
 
 use ast::position::{EqNoPosition, Position};
-use ast::symbol::{FQPath, Symbol, TSymbol};
+use ast::symbol::FQSym;
 
 use crate::resolved;
 use crate::types::{TypeExpr, Value};
 
+pub(crate) type ERef = Box<Expression>;
+
+pub(crate) type Seq = ast::common::Seq<ERef>;
+pub(crate) type Conditional = ast::common::Conditional<ERef>;
+
+#[derive(Debug)]
+pub(crate) struct TwoInts {
+    pub(crate) expr1: ERef,
+    pub(crate) expr2: ERef,
+}
+
+impl EqNoPosition for TwoInts {
+    fn eq_nopos(&self, other: &Self) -> bool {
+        self.expr1.eq_nopos(&other.expr1) && self.expr2.eq_nopos(&other.expr2)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct TwoDecs {
+    pub(crate) expr1: ERef,
+    pub(crate) expr2: ERef,
+}
+
+impl EqNoPosition for TwoDecs {
+    fn eq_nopos(&self, other: &Self) -> bool {
+        self.expr1.eq_nopos(&other.expr1) && self.expr2.eq_nopos(&other.expr2)
+    }
+}
+
+// Arguments of the div builtin
+#[derive(Debug)]
+pub(crate) struct DecDiv {
+    pub(crate) expr1: ERef,
+    pub(crate) expr2: ERef,
+    pub(crate) scale: ERef,
+    pub(crate) rounding: ERef,
+}
+
+impl EqNoPosition for DecDiv {
+    fn eq_nopos(&self, other: &Self) -> bool {
+        self.expr1.eq_nopos(&other.expr1)
+            && self.expr2.eq_nopos(&other.expr2)
+            && self.scale.eq_nopos(&other.scale)
+            && self.rounding.eq_nopos(&other.rounding)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct TwoBools {
+    pub(crate) expr1: ERef,
+    pub(crate) expr2: ERef,
+}
+
+impl EqNoPosition for TwoBools {
+    fn eq_nopos(&self, other: &Self) -> bool {
+        self.expr1.eq_nopos(&other.expr1) && self.expr2.eq_nopos(&other.expr2)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct TwoStrs {
+    pub(crate) expr1: ERef,
+    pub(crate) expr2: ERef,
+}
+
+impl EqNoPosition for TwoStrs {
+    fn eq_nopos(&self, other: &Self) -> bool {
+        self.expr1.eq_nopos(&other.expr1) && self.expr2.eq_nopos(&other.expr2)
+    }
+}
+
+// Operands of an equality, of the same type or both booleans
+#[derive(Debug)]
+pub(crate) struct TwoValues {
+    pub(crate) expr1: ERef,
+    pub(crate) expr2: ERef,
+}
+
+impl EqNoPosition for TwoValues {
+    fn eq_nopos(&self, other: &Self) -> bool {
+        self.expr1.eq_nopos(&other.expr1) && self.expr2.eq_nopos(&other.expr2)
+    }
+}
+
 #[derive(Debug)]
 pub(crate) enum Expr {
-    Value(Value), // TODO
+    Value(Value),
+    Global(FQSym),
+    Seq(Seq),
+    Conditional(Conditional),
+    IntNeg(ERef),
+    DecNeg(ERef),
+    LogicalNot(ERef),
+    IntAdd(TwoInts),
+    IntSub(TwoInts),
+    IntMul(TwoInts),
+    IntDiv(TwoInts),
+    IntRem(TwoInts),
+    IntPow(TwoInts),
+    DecAdd(TwoDecs),
+    DecSub(TwoDecs),
+    DecMul(TwoDecs),
+    DecDiv(DecDiv),
+    LogicalAnd(TwoBools),
+    LogicalOr(TwoBools),
+    Eq(TwoValues),
+    NEq(TwoValues),
+    IntLT(TwoInts),
+    IntLE(TwoInts),
+    IntGT(TwoInts),
+    IntGE(TwoInts),
+    StrConcat(TwoStrs),
+    StrLT(TwoStrs),
+    StrLE(TwoStrs),
+    StrGT(TwoStrs),
+    StrGE(TwoStrs),
+    Interpolation(Vec<Expression>), // Parts of any type
 }
 
 impl Expr {
@@ -31,15 +145,38 @@ impl Expr {
 
 impl EqNoPosition for Expr {
     fn eq_nopos(&self, other: &Self) -> bool {
-        match self {
-            Expr::Value(n1) => {
-                if let Expr::Value(n2) = other {
-                    n1 == n2
-                } else {
-                    false
-                }
-            }
-            _ => panic!("TODO!"),
+        match (self, other) {
+            (Expr::Value(v1), Expr::Value(v2)) => v1 == v2,
+            (Expr::Global(s1), Expr::Global(s2)) => s1 == s2,
+            (Expr::Seq(s1), Expr::Seq(s2)) => s1.eq_nopos(s2),
+            (Expr::Conditional(c1), Expr::Conditional(c2)) => c1.eq_nopos(c2),
+            (Expr::IntNeg(e1), Expr::IntNeg(e2))
+            | (Expr::DecNeg(e1), Expr::DecNeg(e2))
+            | (Expr::LogicalNot(e1), Expr::LogicalNot(e2)) => e1.eq_nopos(e2),
+            (Expr::IntAdd(t1), Expr::IntAdd(t2))
+            | (Expr::IntSub(t1), Expr::IntSub(t2))
+            | (Expr::IntMul(t1), Expr::IntMul(t2))
+            | (Expr::IntDiv(t1), Expr::IntDiv(t2))
+            | (Expr::IntRem(t1), Expr::IntRem(t2))
+            | (Expr::IntPow(t1), Expr::IntPow(t2))
+            | (Expr::IntLT(t1), Expr::IntLT(t2))
+            | (Expr::IntLE(t1), Expr::IntLE(t2))
+            | (Expr::IntGT(t1), Expr::IntGT(t2))
+            | (Expr::IntGE(t1), Expr::IntGE(t2)) => t1.eq_nopos(t2),
+            (Expr::DecAdd(t1), Expr::DecAdd(t2))
+            | (Expr::DecSub(t1), Expr::DecSub(t2))
+            | (Expr::DecMul(t1), Expr::DecMul(t2)) => t1.eq_nopos(t2),
+            (Expr::DecDiv(d1), Expr::DecDiv(d2)) => d1.eq_nopos(d2),
+            (Expr::LogicalAnd(t1), Expr::LogicalAnd(t2))
+            | (Expr::LogicalOr(t1), Expr::LogicalOr(t2)) => t1.eq_nopos(t2),
+            (Expr::Eq(t1), Expr::Eq(t2)) | (Expr::NEq(t1), Expr::NEq(t2)) => t1.eq_nopos(t2),
+            (Expr::StrConcat(t1), Expr::StrConcat(t2))
+            | (Expr::StrLT(t1), Expr::StrLT(t2))
+            | (Expr::StrLE(t1), Expr::StrLE(t2))
+            | (Expr::StrGT(t1), Expr::StrGT(t2))
+            | (Expr::StrGE(t1), Expr::StrGE(t2)) => t1.eq_nopos(t2),
+            (Expr::Interpolation(p1), Expr::Interpolation(p2)) => p1.eq_nopos(p2),
+            _ => false,
         }
     }
 }
@@ -62,8 +199,15 @@ impl fmt::Debug for Expression {
     }
 }
 
+#[derive(Debug)]
+pub(crate) struct Assignment {
+    pub(crate) position: Position,
+    pub(crate) symbol: FQSym,
+    pub(crate) expr: Expression,
+}
+
 #[derive(Debug)]
 pub(crate) struct TypeChecked {
-    pub(crate) path: FQPath,
+    pub(crate) assignments: Vec<Assignment>,
     pub(crate) expression: Option<Expression>,
 }
@@ -1,48 +1,539 @@
+use std::collections::{HashMap, HashSet};
+
 use ast::{
-    problem::Output,
-    symbol::{FQSym, FQType},
+    common::{BinaryOp, UnaryOp},
+    problem::{ErrorType, Output},
+    symbol::FQSym,
 };
 
 use crate::{
-    resolved,
-    typechecked::{Expr, Expression, TypeChecked},
-    types::{Type, Value},
+    GlobalScope,
+    resolved::{self, Builtin},
+    typechecked::{
+        Assignment, Conditional, DecDiv, Expr, Expression, Seq, TwoBools, TwoDecs, TwoInts,
+        TwoStrs, TwoValues, TypeChecked,
+    },
+    types::{Type, TypeExpr, Value},
 };
 
-use crate::types::TypeExpr;
-
-pub(super) fn typecheck(tree: resolved::Resolved) -> Output<TypeChecked> {
-    let mut type_checked = TypeChecked {
-        path: tree.path,
-        expression: None,
+pub(super) fn typecheck(global: &GlobalScope, tree: resolved::Resolved) -> Output<TypeChecked> {
+    let mut checker = Checker {
+        global,
+        checked: HashMap::default(),
     };
-    match tree.expression {
-        Some(e) => typecheck_expression(&e).map(|te| {
-            type_checked.expression = Some(te);
-            type_checked
+    checker
+        .typecheck_assignments(tree.assignments)
+        .merge_to_tuple(match &tree.expression {
+            Some(e) => checker.typecheck_expression(e).map(Some),
+            None => Output::ok(None),
+        })
+        .map(|(assignments, expression)| TypeChecked {
+            assignments,
+            expression,
+        })
+}
+
+struct Checker<'a> {
+    global: &'a GlobalScope,
+    checked: HashMap<FQSym, TypeExpr>, // Types of the symbols checked from the current input
+}
+
+impl Checker<'_> {
+    // Type checks the assignments in dependency order.
+    fn typecheck_assignments(
+        &mut self,
+        assignments: Vec<resolved::Assignment>,
+    ) -> Output<Vec<Assignment>> {
+        let mut output = Output::empty();
+        let mut pending: HashMap<FQSym, (resolved::Assignment, HashSet<FQSym>)> = assignments
+            .into_iter()
+            .map(|a| {
+                let mut dependencies = HashSet::default();
+                a.expr.add_symbols(&mut dependencies);
+                (a.symbol.clone(), (a, dependencies))
+            })
+            .collect();
+        let mut failed: HashSet<FQSym> = HashSet::default();
+        while !pending.is_empty() {
+            let ready: Vec<FQSym> = pending
+                .iter()
+                .filter(|(_, (_, deps))| deps.iter().all(|d| !pending.contains_key(d)))
+                .map(|(symbol, _)| symbol.clone())
+                .collect();
+            if ready.is_empty() {
+                for (symbol, (a, _)) in pending.drain() {
+                    output.add_error(Error::CyclicDependency(symbol).at(a.position));
+                }
+                break;
+            }
+            for symbol in ready {
+                let (a, deps) = pending.remove(&symbol).unwrap();
+                // Errors have already been reported for the failed dependencies
+                if deps.iter().any(|d| failed.contains(d)) {
+                    failed.insert(symbol);
+                    continue;
+                }
+                let checked = self.typecheck_expression(&a.expr);
+                if checked.has_errors() {
+                    failed.insert(symbol);
+                    output.merge_problems(checked);
+                } else if let Some(expr) = output.merge_problems(checked) {
+                    self.checked.insert(symbol.clone(), expr.type_expr.clone());
+                    output.add_value(Assignment {
+                        position: a.position,
+                        symbol,
+                        expr,
+                    });
+                }
+            }
+        }
+        output
+    }
+
+    fn symbol_type(&self, symbol: &FQSym) -> Option<TypeExpr> {
+        self.checked.get(symbol).cloned().or_else(|| {
+            self.global
+                .symbols
+                .get(symbol)
+                .map(|dfn| dfn.type_expr().clone())
+        })
+    }
+
+    // Annotated expressions take the annotated type, which must accept the checked one
+    fn typecheck_expression(&self, expression: &resolved::Expression) -> Output<Expression> {
+        let checked = self.typecheck_expr(expression);
+        match &expression.annotation {
+            None => checked,
+            Some(symbol) => checked.and_then(|mut checked| match self.global.types.get(symbol) {
+                Some(dfn) => {
+                    let tipo = dfn.type_expr().get_type();
+                    if tipo.accepts(checked.type_expr.get_type()) {
+                        checked.type_expr = TypeExpr::Type(tipo.clone());
+                        Output::ok(checked)
+                    } else {
+                        mismatch(&checked, tipo.clone())
+                    }
+                }
+                None => error(expression, Error::UnknownType(symbol.clone())),
+            }),
+        }
+    }
+
+    fn typecheck_expr(&self, expression: &resolved::Expression) -> Output<Expression> {
+        match &expression.expr {
+            resolved::Expr::LitInteger(num) => Output::ok(
+                Expr::Value(Value::Integer(num.clone()))
+                    .wrap_from(expression, TypeExpr::Type(Type::Integer)),
+            ),
+            resolved::Expr::LitDecimal(num) => Output::ok(
+                Expr::Value(Value::Decimal(num.clone()))
+                    .wrap_from(expression, TypeExpr::Type(Type::Decimal)),
+            ),
+            resolved::Expr::LitString(s) => Output::ok(
+                Expr::Value(Value::String(s.clone()))
+                    .wrap_from(expression, TypeExpr::Type(Type::String)),
+            ),
+            resolved::Expr::Interpolation(parts) => {
+                let mut output = Output::empty();
+                for part in parts {
+                    output.add_output(self.typecheck_expression(part));
+                }
+                output.map(|parts| {
+                    Expr::Interpolation(parts).wrap_from(expression, TypeExpr::Type(Type::String))
+                })
+            }
+            resolved::Expr::Symbol(symbol) => match self.symbol_type(symbol) {
+                Some(type_expr) => {
+                    Output::ok(Expr::Global(symbol.clone()).wrap_from(expression, type_expr))
+                }
+                None => error(expression, Error::UnknownSymbol(symbol.clone())),
+            },
+            resolved::Expr::Type(symbol) => {
+                let tipo = self
+                    .global
+                    .types
+                    .get(symbol)
+                    .map(|dfn| dfn.type_expr().get_type().clone());
+                match tipo {
+                    Some(tipo) => match tipo.singleton() {
+                        Some(value) => Output::ok(
+                            Expr::Value(value).wrap_from(expression, TypeExpr::Type(tipo)),
+                        ),
+                        None => error(expression, Error::SingletonExpected(tipo)),
+                    },
+                    None => error(expression, Error::UnknownType(symbol.clone())),
+                }
+            }
+            resolved::Expr::Unary(u) => self
+                .typecheck_expression(&u.expr)
+                .and_then(|e| typecheck_unary(expression, u.op, e)),
+            resolved::Expr::Binary(b) => {
+                // We check both operands first to collect as many problems as possible.
+                self.typecheck_expression(&b.expr1)
+                    .merge_to_tuple(self.typecheck_expression(&b.expr2))
+                    .and_then(|(e1, e2)| self.typecheck_binary(expression, b.op, e1, e2))
+            }
+            resolved::Expr::Seq(s) => self
+                .typecheck_expression(&s.expr)
+                .merge_to_tuple(self.typecheck_expression(&s.then))
+                .map(|(expr, then)| {
+                    let type_expr = then.type_expr.clone();
+                    Expr::Seq(Seq {
+                        expr: expr.into(),
+                        then: then.into(),
+                    })
+                    .wrap_from(expression, type_expr)
+                }),
+            resolved::Expr::Conditional(c) => self
+                .typecheck_expression(&c.expr)
+                .merge_to_tuple(self.typecheck_expression(&c.then))
+                .merge_to_tuple(self.typecheck_expression(&c.otherwise))
+                .and_then(|((expr, then), otherwise)| {
+                    typecheck_conditional(expression, expr, then, otherwise)
+                }),
+            resolved::Expr::Builtin(builtin, args) => {
+                let mut output = Output::empty();
+                for arg in args {
+                    output.add_output(self.typecheck_expression(arg));
+                }
+                output.and_then(|args| typecheck_builtin(expression, *builtin, args))
+            }
+        }
+    }
+
+    fn typecheck_binary(
+        &self,
+        expression: &resolved::Expression,
+        op: BinaryOp,
+        expr1: Expression,
+        expr2: Expression,
+    ) -> Output<Expression> {
+        // Decimal arithmetic if any of the operands is a decimal
+        let decimal =
+            expr1.type_expr.get_type().is_decimal() || expr2.type_expr.get_type().is_decimal();
+        let string =
+            expr1.type_expr.get_type().is_string() || expr2.type_expr.get_type().is_string();
+        match op {
+            BinaryOp::Add if string => two_strs(expression, expr1, expr2, Expr::StrConcat),
+            BinaryOp::LT if string => str_comparison(expression, expr1, expr2, Expr::StrLT),
+            BinaryOp::LE if string => str_comparison(expression, expr1, expr2, Expr::StrLE),
+            BinaryOp::GT if string => str_comparison(expression, expr1, expr2, Expr::StrGT),
+            BinaryOp::GE if string => str_comparison(expression, expr1, expr2, Expr::StrGE),
+            BinaryOp::Add if decimal => two_decs(expression, expr1, expr2, Expr::DecAdd),
+            BinaryOp::Sub if decimal => two_decs(expression, expr1, expr2, Expr::DecSub),
+            BinaryOp::Mul if decimal => two_decs(expression, expr1, expr2, Expr::DecMul),
+            // The scale and rounding mode must be explicit, using div
+            BinaryOp::Div if decimal => error(expression, Error::ImplicitDecimalDivision),
+            BinaryOp::Add => two_ints(expression, expr1, expr2, Expr::IntAdd),
+            BinaryOp::Sub => two_ints(expression, expr1, expr2, Expr::IntSub),
+            BinaryOp::Mul => two_ints(expression, expr1, expr2, Expr::IntMul),
+            BinaryOp::Div => two_ints(expression, expr1, expr2, Expr::IntDiv),
+            BinaryOp::Rem => two_ints(expression, expr1, expr2, Expr::IntRem),
+            BinaryOp::Pow => two_ints(expression, expr1, expr2, Expr::IntPow),
+            BinaryOp::And => two_bools(expression, expr1, expr2, Expr::LogicalAnd),
+            BinaryOp::Or => two_bools(expression, expr1, expr2, Expr::LogicalOr),
+            BinaryOp::Eq => two_values(expression, expr1, expr2, true, Expr::Eq),
+            BinaryOp::NEq => two_values(expression, expr1, expr2, false, Expr::NEq),
+            BinaryOp::LT => int_comparison(expression, expr1, expr2, Expr::IntLT),
+            BinaryOp::LE => int_comparison(expression, expr1, expr2, Expr::IntLE),
+            BinaryOp::GT => int_comparison(expression, expr1, expr2, Expr::IntGT),
+            BinaryOp::GE => int_comparison(expression, expr1, expr2, Expr::IntGE),
+        }
+    }
+}
+
+fn typecheck_unary(
+    expression: &resolved::Expression,
+    op: UnaryOp,
+    expr: Expression,
+) -> Output<Expression> {
+    let tipo = expr.type_expr.get_type().clone();
+    match op {
+        UnaryOp::Minus if tipo.is_decimal() => {
+            Output::ok(Expr::DecNeg(expr.into()).wrap_from(expression, TypeExpr::Type(tipo)))
+        }
+        UnaryOp::Minus => expect_integer(&expr)
+            .map(|_| Expr::IntNeg(expr.into()).wrap_from(expression, TypeExpr::Type(tipo))),
+        UnaryOp::Not => expect_boolean(&expr).map(|_| {
+            let tipo = match tipo {
+                Type::True => Type::False,
+                Type::False => Type::True,
+                _ => Type::Boolean,
+            };
+            Expr::LogicalNot(expr.into()).wrap_from(expression, TypeExpr::Type(tipo))
         }),
-        _ => Output::ok(type_checked),
-    }
-}
-
-fn typecheck_expression(expression: &resolved::Expression) -> Output<Expression> {
-    match &expression.expr {
-        resolved::Expr::LitInteger(num) => Output::ok(
-            Expr::Value(Value::Integer(num.clone()))
-                .wrap_from(expression, TypeExpr::Type(Type::Integer)),
-        ),
-        resolved::Expr::Binary(b) => {
-            let _option1 = typecheck_expression(&b.expr1);
-            let _option2 = typecheck_expression(&b.expr2);
-            // We extract from the option later to collect as many problems as possible.
-            // let expr1 = option1?;
-            // let expr2 = option2?;
-            panic!("TODO")
+    }
+}
+
+fn typecheck_builtin(
+    expression: &resolved::Expression,
+    builtin: Builtin,
+    args: Vec<Expression>,
+) -> Output<Expression> {
+    let actual = args.len();
+    match builtin {
+        Builtin::Div => {
+            let Ok([expr1, expr2, scale, rounding]) = <[Expression; 4]>::try_from(args) else {
+                return error(
+                    expression,
+                    Error::ArgumentCount {
+                        expected: 4,
+                        actual,
+                    },
+                );
+            };
+            expect_decimal(&expr1)
+                .merge_to_tuple(expect_decimal(&expr2))
+                .merge_to_tuple(expect_integer(&scale))
+                .merge_to_tuple(expect_rounding(&rounding))
+                .map(|_| {
+                    Expr::DecDiv(DecDiv {
+                        expr1: expr1.into(),
+                        expr2: expr2.into(),
+                        scale: scale.into(),
+                        rounding: rounding.into(),
+                    })
+                    .wrap_from(expression, TypeExpr::Type(Type::Decimal))
+                })
         }
-        _ => panic!("TODO"),
     }
 }
 
-pub(crate) enum TypeError {
-    TypeError,
+fn two_ints<F>(
+    expression: &resolved::Expression,
+    expr1: Expression,
+    expr2: Expression,
+    f: F,
+) -> Output<Expression>
+where
+    F: FnOnce(TwoInts) -> Expr,
+{
+    expect_integer(&expr1)
+        .merge_to_tuple(expect_integer(&expr2))
+        .map(|_| {
+            f(TwoInts {
+                expr1: expr1.into(),
+                expr2: expr2.into(),
+            })
+            .wrap_from(expression, TypeExpr::Type(Type::Integer))
+        })
+}
+
+fn int_comparison<F>(
+    expression: &resolved::Expression,
+    expr1: Expression,
+    expr2: Expression,
+    f: F,
+) -> Output<Expression>
+where
+    F: FnOnce(TwoInts) -> Expr,
+{
+    expect_integer(&expr1)
+        .merge_to_tuple(expect_integer(&expr2))
+        .map(|_| {
+            f(TwoInts {
+                expr1: expr1.into(),
+                expr2: expr2.into(),
+            })
+            .wrap_from(expression, TypeExpr::Type(Type::Boolean))
+        })
+}
+
+// Operands must have the same type, unless both are booleans or rounding modes. If both
+// types are singletons the result is known at compile time, and so it has a singleton type.
+fn two_values<F>(
+    expression: &resolved::Expression,
+    expr1: Expression,
+    expr2: Expression,
+    equal: bool,
+    f: F,
+) -> Output<Expression>
+where
+    F: FnOnce(TwoValues) -> Expr,
+{
+    let type1 = expr1.type_expr.get_type().clone();
+    let type2 = expr2.type_expr.get_type().clone();
+    if type1.common(&type2).is_none() {
+        return mismatch(&expr2, type1);
+    }
+    let tipo = match (type1.singleton(), type2.singleton()) {
+        (Some(v1), Some(v2)) => Type::boolean((v1 == v2) == equal),
+        _ => Type::Boolean,
+    };
+    Output::ok(
+        f(TwoValues {
+            expr1: expr1.into(),
+            expr2: expr2.into(),
+        })
+        .wrap_from(expression, TypeExpr::Type(tipo)),
+    )
+}
+
+fn two_decs<F>(
+    expression: &resolved::Expression,
+    expr1: Expression,
+    expr2: Expression,
+    f: F,
+) -> Output<Expression>
+where
+    F: FnOnce(TwoDecs) -> Expr,
+{
+    expect_decimal(&expr1)
+        .merge_to_tuple(expect_decimal(&expr2))
+        .map(|_| {
+            f(TwoDecs {
+                expr1: expr1.into(),
+                expr2: expr2.into(),
+            })
+            .wrap_from(expression, TypeExpr::Type(Type::Decimal))
+        })
 }
+
+fn two_strs<F>(
+    expression: &resolved::Expression,
+    expr1: Expression,
+    expr2: Expression,
+    f: F,
+) -> Output<Expression>
+where
+    F: FnOnce(TwoStrs) -> Expr,
+{
+    expect_string(&expr1)
+        .merge_to_tuple(expect_string(&expr2))
+        .map(|_| {
+            f(TwoStrs {
+                expr1: expr1.into(),
+                expr2: expr2.into(),
+            })
+            .wrap_from(expression, TypeExpr::Type(Type::String))
+        })
+}
+
+fn str_comparison<F>(
+    expression: &resolved::Expression,
+    expr1: Expression,
+    expr2: Expression,
+    f: F,
+) -> Output<Expression>
+where
+    F: FnOnce(TwoStrs) -> Expr,
+{
+    expect_string(&expr1)
+        .merge_to_tuple(expect_string(&expr2))
+        .map(|_| {
+            f(TwoStrs {
+                expr1: expr1.into(),
+                expr2: expr2.into(),
+            })
+            .wrap_from(expression, TypeExpr::Type(Type::Boolean))
+        })
+}
+
+fn two_bools<F>(
+    expression: &resolved::Expression,
+    expr1: Expression,
+    expr2: Expression,
+    f: F,
+) -> Output<Expression>
+where
+    F: FnOnce(TwoBools) -> Expr,
+{
+    expect_boolean(&expr1)
+        .merge_to_tuple(expect_boolean(&expr2))
+        .map(|_| {
+            f(TwoBools {
+                expr1: expr1.into(),
+                expr2: expr2.into(),
+            })
+            .wrap_from(expression, TypeExpr::Type(Type::Boolean))
+        })
+}
+
+fn typecheck_conditional(
+    expression: &resolved::Expression,
+    expr: Expression,
+    then: Expression,
+    otherwise: Expression,
+) -> Output<Expression> {
+    let then_type = then.type_expr.get_type();
+    let otherwise_type = otherwise.type_expr.get_type();
+    let tipo = match then_type.common(otherwise_type) {
+        Some(tipo) => Output::ok(tipo),
+        None => mismatch(&otherwise, then_type.clone()),
+    };
+    expect_boolean(&expr).merge_to_tuple(tipo).map(|(_, tipo)| {
+        Expr::Conditional(Conditional {
+            expr: expr.into(),
+            then: then.into(),
+            otherwise: otherwise.into(),
+        })
+        .wrap_from(expression, TypeExpr::Type(tipo))
+    })
+}
+
+fn expect_integer(expr: &Expression) -> Output<()> {
+    if expr.type_expr.get_type().is_integer() {
+        Output::ok(())
+    } else {
+        mismatch(expr, Type::Integer)
+    }
+}
+
+fn expect_decimal(expr: &Expression) -> Output<()> {
+    if expr.type_expr.get_type().is_decimal() {
+        Output::ok(())
+    } else {
+        mismatch(expr, Type::Decimal)
+    }
+}
+
+fn expect_string(expr: &Expression) -> Output<()> {
+    if expr.type_expr.get_type().is_string() {
+        Output::ok(())
+    } else {
+        mismatch(expr, Type::String)
+    }
+}
+
+fn expect_boolean(expr: &Expression) -> Output<()> {
+    if expr.type_expr.get_type().is_boolean() {
+        Output::ok(())
+    } else {
+        mismatch(expr, Type::Boolean)
+    }
+}
+
+fn expect_rounding(expr: &Expression) -> Output<()> {
+    if expr.type_expr.get_type().is_rounding() {
+        Output::ok(())
+    } else {
+        mismatch(expr, Type::Rounding)
+    }
+}
+
+fn mismatch<T>(expr: &Expression, expected: Type) -> Output<T> {
+    Error::TypeMismatch {
+        expected,
+        actual: expr.type_expr.get_type().clone(),
+    }
+    .at(expr.position.clone())
+    .into()
+}
+
+// Creates and returns an error
+fn error<T>(expression: &resolved::Expression, error: Error) -> Output<T> {
+    error.at(expression.position.clone()).into()
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Error {
+    UnknownSymbol(FQSym),
+    UnknownType(ast::symbol::FQType),
+    CyclicDependency(FQSym),
+    SingletonExpected(Type),
+    TypeMismatch { expected: Type, actual: Type },
+    ImplicitDecimalDivision,
+    ArgumentCount { expected: usize, actual: usize },
+}
+
+impl ErrorType for Error {}
@@ -1,38 +1,205 @@
 use std::collections::HashMap;
+use std::fmt;
 
-use ast::{position::Position, symbol::FQType};
-use num::Integer;
+use ast::symbol::{FQPath, FQType, TSymbol};
+use num::{Decimal, Integer, RoundingMode};
+
+use crate::ArcStr;
+use crate::context::{ROUNDING_MODES, quote};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum Type {
-    Unit,
     True,
     False,
+    Boolean,
     Integer, // Temporary
+    Decimal,
+    String,
+    Rounding,
+    RoundingMode(RoundingMode), // Variant of Rounding
+    Singleton(FQType),          // Defined by a package
 }
 
-#[derive(Debug, PartialEq, Eq)]
+impl Type {
+    // Singleton boolean type of the provided value
+    pub(crate) fn boolean(value: bool) -> Self {
+        if value { Type::True } else { Type::False }
+    }
+
+    pub(crate) fn is_boolean(&self) -> bool {
+        matches!(self, Type::True | Type::False | Type::Boolean)
+    }
+
+    pub(crate) fn is_integer(&self) -> bool {
+        *self == Type::Integer
+    }
+
+    pub(crate) fn is_decimal(&self) -> bool {
+        *self == Type::Decimal
+    }
+
+    pub(crate) fn is_string(&self) -> bool {
+        *self == Type::String
+    }
+
+    pub(crate) fn is_rounding(&self) -> bool {
+        matches!(self, Type::Rounding | Type::RoundingMode(_))
+    }
+
+    // Returns whether a value of the provided type can be used where this type is expected.
+    // True and False are subtypes of Boolean, and the rounding modes of Rounding.
+    pub(crate) fn accepts(&self, other: &Type) -> bool {
+        self == other
+            || (*self == Type::Boolean && other.is_boolean())
+            || (*self == Type::Rounding && other.is_rounding())
+    }
+
+    // Returns the narrowest type that accepts both types, if any
+    pub(crate) fn common(&self, other: &Type) -> Option<Type> {
+        if self == other {
+            Some(self.clone())
+        } else if self.is_boolean() && other.is_boolean() {
+            Some(Type::Boolean)
+        } else if self.is_rounding() && other.is_rounding() {
+            Some(Type::Rounding)
+        } else {
+            None
+        }
+    }
+
+    // Returns the only value of the type, if it is a singleton
+    pub(crate) fn singleton(&self) -> Option<Value> {
+        match self {
+            Type::True => Some(Value::True),
+            Type::False => Some(Value::False),
+            Type::RoundingMode(mode) => Some(Value::Rounding(*mode)),
+            Type::Singleton(symbol) => Some(Value::Singleton(symbol.clone())),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::True => f.write_str("True"),
+            Type::False => f.write_str("False"),
+            Type::Boolean => f.write_str("Boolean"),
+            Type::Integer => f.write_str("Integer"),
+            Type::Decimal => f.write_str("Decimal"),
+            Type::String => f.write_str("String"),
+            Type::Rounding => f.write_str("Rounding"),
+            Type::RoundingMode(mode) => write!(f, "Rounding::{}", mode_name(*mode)),
+            Type::Singleton(symbol) => symbol.fmt(f),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum TypeExpr {
     Type(Type),
 }
 
+impl TypeExpr {
+    pub(crate) fn get_type(&self) -> &Type {
+        match self {
+            TypeExpr::Type(t) => t,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct TypeDfn {
-    position: Position,
     type_expr: TypeExpr,
 }
 
+impl TypeDfn {
+    pub(crate) fn type_expr(&self) -> &TypeExpr {
+        &self.type_expr
+    }
+}
+
 #[derive(Debug, Default)]
 pub(crate) struct Types {
     values: HashMap<FQType, TypeDfn>,
 }
 
+impl Types {
+    // Types available in every environment
+    pub(crate) fn prelude() -> Self {
+        let mut types = Types::default();
+        let std = FQPath::std_empty();
+        for tipo in [
+            Type::True,
+            Type::False,
+            Type::Boolean,
+            Type::Integer,
+            Type::Decimal,
+            Type::String,
+        ] {
+            let symbol = TSymbol::new(&tipo.to_string()).unwrap();
+            types.add(std.fq_type(symbol), tipo);
+        }
+        let rounding = std.fq_type(TSymbol::new("Rounding").unwrap());
+        types.add(rounding.clone(), Type::Rounding);
+        for (name, mode) in ROUNDING_MODES {
+            let symbol = TSymbol::new(name).unwrap();
+            types.add(rounding.nested(symbol), Type::RoundingMode(mode));
+        }
+        types
+    }
+
+    pub(crate) fn contains(&self, symbol: &FQType) -> bool {
+        self.values.contains_key(symbol)
+    }
+
+    pub(crate) fn get(&self, symbol: &FQType) -> Option<&TypeDfn> {
+        self.values.get(symbol)
+    }
+
+    pub(crate) fn add(&mut self, symbol: FQType, tipo: Type) {
+        self.values.insert(
+            symbol,
+            TypeDfn {
+                type_expr: TypeExpr::Type(tipo),
+            },
+        );
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Value {
-    Unit,
     True,
     False,
     Integer(Integer),
+    Decimal(Decimal),
+    String(ArcStr),
+    Rounding(RoundingMode),
+    Singleton(FQType), // The only value of a singleton type
+}
+
+impl Value {
+    pub(crate) fn boolean(value: bool) -> Self {
+        if value { Value::True } else { Value::False }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::True => f.write_str("True"),
+            Value::False => f.write_str("False"),
+            Value::Integer(n) => n.fmt(f),
+            Value::Decimal(n) => n.fmt(f),
+            Value::String(s) => f.write_str(&quote(s)),
+            Value::Rounding(mode) => write!(f, "Rounding::{}", mode_name(*mode)),
+            Value::Singleton(symbol) => symbol.fmt(f),
+        }
+    }
+}
+
+fn mode_name(mode: RoundingMode) -> &'static str {
+    ROUNDING_MODES.iter().find(|(_, m)| *m == mode).unwrap().0
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -40,3 +207,19 @@ pub struct TypedValue {
     value: Value,
     value_type: Type,
 }
+
+impl TypedValue {
+    pub(crate) fn new(value: Value, value_type: Type) -> Self {
+        TypedValue { value, value_type }
+    }
+
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+}
+
+impl fmt::Display for TypedValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} : {}", self.value, self.value_type)
+    }
+}
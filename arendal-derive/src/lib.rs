@@ -0,0 +1,203 @@
+// Derives the conversions between Rust types and Arendal values. Structs map to named tuples, or
+// to singletons if they have no fields, and enums to unions with a variant per Rust variant.
+// Fields can be of any Typed type, including vectors, maps and tuples, which map to the List,
+// Map and tuple types that programs use in their definitions.
+// The Arendal type is the one with the same name in the root module of the local package. The
+// module and the name can be changed with #[arendal(path = "geo", name = "Point")], and the
+// path of the core crate with #[arendal(crate = "core")].
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Fields, LitStr, parse_macro_input};
+
+#[proc_macro_derive(Typed, attributes(arendal))]
+pub fn derive_typed(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+struct Attrs {
+    krate: syn::Path,
+    path: String,
+    name: String,
+}
+
+fn attrs(input: &DeriveInput) -> syn::Result<Attrs> {
+    let mut attrs = Attrs {
+        krate: syn::parse_quote!(::arendal_core),
+        path: String::default(),
+        name: input.ident.to_string(),
+    };
+    for attr in &input.attrs {
+        if !attr.path().is_ident("arendal") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            let value: LitStr = meta.value()?.parse()?;
+            if meta.path.is_ident("path") {
+                attrs.path = value.value();
+            } else if meta.path.is_ident("name") {
+                attrs.name = value.value();
+            } else if meta.path.is_ident("crate") {
+                attrs.krate = value.parse()?;
+            } else {
+                return Err(meta.error("expected `path`, `name` or `crate`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(attrs)
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            input.generics.span(),
+            "generic types are not supported",
+        ));
+    }
+    let attrs = attrs(input)?;
+    let krate = &attrs.krate;
+    let (path, name) = (&attrs.path, &attrs.name);
+    let ident = &input.ident;
+    let (tipo, from, try_from) = match &input.data {
+        Data::Struct(s) => {
+            let tipo = tuple_type(krate, quote!(symbol), &s.fields);
+            let (pattern, values) = destructure(quote!(#ident), &s.fields);
+            let build = construct(krate, quote!(#ident), &s.fields);
+            (
+                tipo.clone(),
+                quote! {
+                    let #pattern = value;
+                    #krate::context::Value::v_named(#tipo, vec![#(#values),*])
+                },
+                quote! { Ok(#build) },
+            )
+        }
+        Data::Enum(e) if e.variants.is_empty() => {
+            return Err(syn::Error::new(
+                input.span(),
+                "enums without variants are not supported",
+            ));
+        }
+        Data::Enum(e) => {
+            let mut variants = Vec::with_capacity(e.variants.len());
+            let mut from_arms = Vec::with_capacity(e.variants.len());
+            let mut try_from_arms = Vec::with_capacity(e.variants.len());
+            for v in &e.variants {
+                let v_ident = &v.ident;
+                let v_name = v_ident.to_string();
+                let symbol = quote!(#krate::context::variant_type(&symbol, #v_name));
+                let tipo = tuple_type(krate, symbol, &v.fields);
+                let (pattern, values) = destructure(quote!(#ident::#v_ident), &v.fields);
+                let build = construct(krate, quote!(#ident::#v_ident), &v.fields);
+                from_arms.push(quote! {
+                    #pattern => #krate::context::Value::v_named(#tipo, vec![#(#values),*]),
+                });
+                try_from_arms.push(quote! { #v_name => Ok(#build), });
+                variants.push(tipo);
+            }
+            (
+                quote! {
+                    #krate::context::Type::type_union(symbol.clone(), vec![#(#variants),*])
+                },
+                quote! {
+                    match value {
+                        #(#from_arms)*
+                    }
+                },
+                quote! {
+                    match variant.to_string().as_str() {
+                        #(#try_from_arms)*
+                        _ => unreachable!("The variant is checked against the union type"),
+                    }
+                },
+            )
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new(
+                input.span(),
+                "only structs and enums are supported",
+            ));
+        }
+    };
+    Ok(quote! {
+        impl #krate::context::Typed for #ident {
+            fn tipo() -> #krate::context::Type {
+                let symbol = #krate::context::local_type(#path, #name);
+                #tipo
+            }
+        }
+
+        impl ::std::convert::From<#ident> for #krate::context::Value {
+            fn from(value: #ident) -> Self {
+                let symbol = #krate::context::local_type(#path, #name);
+                #from
+            }
+        }
+
+        impl ::std::convert::TryFrom<#krate::context::Value> for #ident {
+            type Error = #krate::error::Error;
+
+            #[allow(unused_mut, unused_variables)]
+            fn try_from(value: #krate::context::Value) -> ::std::result::Result<Self, Self::Error> {
+                let tipo = <Self as #krate::context::Typed>::tipo();
+                let (variant, values) = value.into_elements(&tipo)?;
+                let mut values = values.into_iter();
+                #try_from
+            }
+        }
+    })
+}
+
+// Returns the named tuple type with the types of the fields, in declaration order
+fn tuple_type(krate: &syn::Path, symbol: TokenStream2, fields: &Fields) -> TokenStream2 {
+    let types = fields.iter().map(|f| {
+        let ty = &f.ty;
+        quote!(<#ty as #krate::context::Typed>::tipo())
+    });
+    quote!(#krate::context::Type::type_named_tuple(#symbol, vec![#(#types),*]))
+}
+
+// Returns the pattern binding the fields to f0, f1... and their conversions to values
+fn destructure(ctor: TokenStream2, fields: &Fields) -> (TokenStream2, Vec<TokenStream2>) {
+    let bindings: Vec<_> = (0..fields.len()).map(|i| format_ident!("f{}", i)).collect();
+    let values = bindings.iter().map(|b| quote!(#b.into())).collect();
+    let pattern = match fields {
+        Fields::Named(named) => {
+            let names = named.named.iter().map(|f| &f.ident);
+            quote!(#ctor { #(#names: #bindings),* })
+        }
+        Fields::Unnamed(_) => quote!(#ctor(#(#bindings),*)),
+        Fields::Unit => ctor,
+    };
+    (pattern, values)
+}
+
+// Returns the construction of the Rust value from the elements, already checked to be as many
+// as the fields
+fn construct(krate: &syn::Path, ctor: TokenStream2, fields: &Fields) -> TokenStream2 {
+    let values: Vec<_> = fields
+        .iter()
+        .map(|f| {
+            let ty = &f.ty;
+            quote! {
+                <#ty as ::std::convert::TryFrom<#krate::context::Value>>::try_from(
+                    values.next().unwrap(),
+                )?
+            }
+        })
+        .collect();
+    match fields {
+        Fields::Named(named) => {
+            let names = named.named.iter().map(|f| &f.ident);
+            quote!(#ctor { #(#names: #values),* })
+        }
+        Fields::Unnamed(_) => quote!(#ctor(#(#values),*)),
+        Fields::Unit => ctor,
+    }
+}
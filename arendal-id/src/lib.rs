@@ -1,9 +1,14 @@
+mod sha3;
+
 use std::{
+    collections::BTreeSet,
     fmt::{self, Write},
+    str::FromStr,
     sync::Arc,
 };
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+// We use SHA3-256 hashes as ids when using content-adressing
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct RawId {
     bytes: [u8; 32],
 }
@@ -28,15 +33,36 @@ impl fmt::Debug for RawId {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Id {
     id: Arc<RawId>,
 }
 
 impl Id {
+    // Returns the id of the provided content
+    pub fn digest(content: &[u8]) -> Self {
+        Self::from_bytes(sha3::sha3_256(content))
+    }
+
+    fn from_bytes(bytes: [u8; 32]) -> Self {
+        Id {
+            id: Arc::new(RawId { bytes }),
+        }
+    }
+
     pub fn as_string(&self) -> String {
         self.id.as_string()
     }
+
+    // The prefix is case insensitive
+    pub fn has_prefix(&self, prefix: &str) -> bool {
+        prefix.len() <= 64
+            && self
+                .as_string()
+                .bytes()
+                .zip(prefix.bytes())
+                .all(|(c, p)| c == p.to_ascii_lowercase())
+    }
 }
 
 impl fmt::Display for Id {
@@ -50,3 +76,102 @@ impl fmt::Debug for Id {
         self.id.fmt(f)
     }
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseIdError;
+
+impl fmt::Display for ParseIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid id, 64 hexadecimal digits expected")
+    }
+}
+
+impl std::error::Error for ParseIdError {}
+
+impl FromStr for Id {
+    type Err = ParseIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let decoded = data_encoding::HEXLOWER_PERMISSIVE
+            .decode(s.as_bytes())
+            .map_err(|_| ParseIdError)?;
+        let bytes = decoded.try_into().map_err(|_| ParseIdError)?;
+        Ok(Self::from_bytes(bytes))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrefixError {
+    Invalid,
+    NotFound,
+    Ambiguous(Vec<Id>),
+}
+
+impl fmt::Display for PrefixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid => f.write_str("invalid id prefix"),
+            Self::NotFound => f.write_str("no id found with the provided prefix"),
+            Self::Ambiguous(ids) => write!(f, "ambiguous id prefix matching {} ids", ids.len()),
+        }
+    }
+}
+
+impl std::error::Error for PrefixError {}
+
+// Set of ids allowing lookups by a short prefix
+#[derive(Debug, Clone, Default)]
+pub struct Ids {
+    ids: BTreeSet<Id>,
+}
+
+impl Ids {
+    pub fn insert(&mut self, id: Id) -> bool {
+        self.ids.insert(id)
+    }
+
+    pub fn contains(&self, id: &Id) -> bool {
+        self.ids.contains(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    // Returns the only id starting with the provided hexadecimal prefix
+    pub fn find(&self, prefix: &str) -> Result<&Id, PrefixError> {
+        if prefix.is_empty() || prefix.len() > 64 || !prefix.bytes().all(|b| b.is_ascii_hexdigit())
+        {
+            return Err(PrefixError::Invalid);
+        }
+        // Ids are sorted by their bytes, so the candidates start at the prefix padded with zeros
+        let padded = format!("{:0<64}", prefix);
+        let start: Id = padded.parse().map_err(|_| PrefixError::Invalid)?;
+        let mut found = self
+            .ids
+            .range(start..)
+            .take_while(|id| id.has_prefix(prefix));
+        match (found.next(), found.next()) {
+            (None, _) => Err(PrefixError::NotFound),
+            (Some(id), None) => Ok(id),
+            (Some(first), Some(second)) => {
+                let mut ambiguous = vec![first.clone(), second.clone()];
+                ambiguous.extend(found.cloned());
+                Err(PrefixError::Ambiguous(ambiguous))
+            }
+        }
+    }
+}
+
+impl Extend<Id> for Ids {
+    fn extend<T: IntoIterator<Item = Id>>(&mut self, iter: T) {
+        self.ids.extend(iter)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,42 @@
+use super::{Sha3, sha3_256};
+
+fn hex(bytes: [u8; 32]) -> String {
+    data_encoding::HEXLOWER.encode(&bytes)
+}
+
+#[test]
+fn test_vectors() {
+    assert_eq!(
+        hex(sha3_256(b"")),
+        "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a"
+    );
+    assert_eq!(
+        hex(sha3_256(b"abc")),
+        "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532"
+    );
+    assert_eq!(
+        hex(sha3_256(
+            b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+        )),
+        "41c0dba2a9d6240849100376a8235e2c82e1b9998a999e21db32dd97496d3376"
+    );
+    assert_eq!(
+        hex(sha3_256(&[0xa3; 200])),
+        "79f38adec5c20307a98ef76e8324afbfd46cfd81b22e3973c65fa1bd9de31787"
+    );
+    assert_eq!(
+        hex(sha3_256(&[b'a'; 1_000_000])),
+        "5c8875ae474a3634ba4fd55ec85bffd661f32aca75c6d699d0cdcb6c115891c1"
+    );
+}
+
+#[test]
+fn incremental() {
+    let data: Vec<u8> = (0..1000u32).map(|i| (i * 7 % 251) as u8).collect();
+    for split in [0, 1, 135, 136, 137, 500, 1000] {
+        let mut hasher = Sha3::default();
+        hasher.update(&data[..split]);
+        hasher.update(&data[split..]);
+        assert_eq!(hasher.finalize(), sha3_256(&data), "split at {split}");
+    }
+}
@@ -0,0 +1,46 @@
+use super::{Id, Ids, PrefixError};
+
+fn id(s: &str) -> Id {
+    s.parse().unwrap()
+}
+
+#[test]
+fn digest_and_parse() {
+    let digest = Id::digest(b"abc");
+    let hex = "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532";
+    assert_eq!(digest.as_string(), hex);
+    assert_eq!(digest, id(hex));
+    assert_eq!(digest, id(&hex.to_uppercase()));
+    assert_eq!(format!("{:?}", digest), format!("Id({hex})"));
+    for invalid in [
+        "",
+        "3a98",
+        &hex[1..],
+        &format!("{hex}00"),
+        &hex.replace('a', "x"),
+    ] {
+        assert!(invalid.parse::<Id>().is_err(), "{invalid}");
+    }
+}
+
+#[test]
+fn prefix_lookup() {
+    let mut ids = Ids::default();
+    let a = id("3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532");
+    let b = id("3a9f000000000000000000000000000000000000000000000000000000000000");
+    let c = id("a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a");
+    for i in [&a, &b, &c] {
+        assert!(ids.insert(i.clone()));
+    }
+    assert!(!ids.insert(a.clone()));
+    assert_eq!(ids.len(), 3);
+    assert_eq!(ids.find("3a98"), Ok(&a));
+    assert_eq!(ids.find("3A9F"), Ok(&b));
+    assert_eq!(ids.find("a"), Ok(&c));
+    assert_eq!(ids.find(&c.as_string()), Ok(&c));
+    assert_eq!(ids.find("3a9"), Err(PrefixError::Ambiguous(vec![a, b])));
+    assert_eq!(ids.find("3a90"), Err(PrefixError::NotFound));
+    assert_eq!(ids.find("ff"), Err(PrefixError::NotFound));
+    assert_eq!(ids.find(""), Err(PrefixError::Invalid));
+    assert_eq!(ids.find("3g"), Err(PrefixError::Invalid));
+}
@@ -1,4 +0,0 @@
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
-pub struct Decimal {
-    value: f64, // Temporary, this is not a decimal
-}
@@ -0,0 +1,353 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops;
+use std::str::FromStr;
+
+use crate::Integer;
+
+// Largest scale accepted by the parser and produced by the checked operations and the
+// division
+const MAX_SCALE: u32 = 1 << 16;
+
+// Exact base-10 decimal: unscaled * 10^-scale.
+// The scale is kept as written (1.50 has scale 2) but it is ignored by
+// equality, ordering and hashing.
+#[derive(Clone)]
+pub struct Decimal {
+    unscaled: Integer,
+    scale: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundingMode {
+    Up,       // Away from zero
+    Down,     // Towards zero
+    Ceiling,  // Towards positive infinity
+    Floor,    // Towards negative infinity
+    HalfUp,   // To nearest, ties away from zero
+    HalfDown, // To nearest, ties towards zero
+    HalfEven, // To nearest, ties to the even neighbour
+}
+
+impl Decimal {
+    pub fn new(unscaled: Integer, scale: u32) -> Self {
+        Decimal { unscaled, scale }
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.unscaled.is_zero()
+    }
+
+    // Returns the same value with the provided scale, rounding if needed
+    pub fn with_scale(&self, scale: u32, rounding: RoundingMode) -> Self {
+        match scale.cmp(&self.scale) {
+            Ordering::Equal => self.clone(),
+            Ordering::Greater => Decimal {
+                unscaled: self.unscaled.clone() * Integer::pow10(scale - self.scale),
+                scale,
+            },
+            Ordering::Less => Decimal {
+                unscaled: div_round(
+                    self.unscaled.clone(),
+                    Integer::pow10(self.scale - scale),
+                    rounding,
+                ),
+                scale,
+            },
+        }
+    }
+
+    // Divides with the provided result scale and rounding mode.
+    // Returns None if the divisor is zero or the result would be too large, as the checked
+    // operations.
+    pub fn div(&self, other: &Decimal, scale: u32, rounding: RoundingMode) -> Option<Self> {
+        if other.is_zero() || scale > MAX_SCALE {
+            return None;
+        }
+        // self / other = (u1 * 10^(scale + s2 - s1)) / u2 * 10^-scale
+        let exp = scale as i64 + other.scale as i64 - self.scale as i64;
+        let (dividend, divisor) = if exp >= 0 {
+            (
+                self.unscaled.clone().checked_mul(pow10(exp)?)?,
+                other.unscaled.clone(),
+            )
+        } else {
+            (
+                self.unscaled.clone(),
+                other.unscaled.clone().checked_mul(pow10(-exp)?)?,
+            )
+        };
+        Some(Decimal {
+            unscaled: div_round(dividend, divisor, rounding),
+            scale,
+        })
+    }
+
+    // Checked operations return None instead of panicking if the result would be larger than
+    // the checked integer operations allow or its scale would be larger than MAX_SCALE.
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let (v1, v2, scale) = self.checked_aligned(&other)?;
+        Some(Decimal::new(v1.checked_add(v2)?, scale))
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        let (v1, v2, scale) = self.checked_aligned(&other)?;
+        Some(Decimal::new(v1.checked_sub(v2)?, scale))
+    }
+
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        let scale = self.scale.checked_add(other.scale)?;
+        if scale > MAX_SCALE {
+            return None;
+        }
+        Some(Decimal::new(
+            self.unscaled.checked_mul(other.unscaled)?,
+            scale,
+        ))
+    }
+
+    // Like aligned, without building values larger than the checked operations allow
+    fn checked_aligned(&self, other: &Decimal) -> Option<(Integer, Integer, u32)> {
+        let scale = self.scale.max(other.scale);
+        if scale > MAX_SCALE {
+            return None;
+        }
+        let align = |d: &Decimal| {
+            let exp = (scale - d.scale) as i64;
+            d.unscaled.clone().checked_mul(pow10(exp)?)
+        };
+        Some((align(self)?, align(other)?, scale))
+    }
+
+    // Position of the most significant digit relative to the decimal point, 1 for 1.5
+    // and -1 for 0.05. The value must not be zero.
+    fn exponent(&self) -> i64 {
+        let digits = self.unscaled.clone().abs().to_string().len();
+        digits as i64 - self.scale as i64
+    }
+
+    fn signum(&self) -> i8 {
+        match (self.is_zero(), self.unscaled.is_negative()) {
+            (true, _) => 0,
+            (false, true) => -1,
+            (false, false) => 1,
+        }
+    }
+
+    // Equal value with the smallest possible scale
+    fn normalized(&self) -> Self {
+        let mut unscaled = self.unscaled.clone();
+        let mut scale = self.scale;
+        let ten = Integer::from(10);
+        while scale > 0 && !unscaled.is_zero() {
+            let (q, r) = unscaled.clone().div_rem(ten.clone());
+            if !r.is_zero() {
+                break;
+            }
+            unscaled = q;
+            scale -= 1;
+        }
+        if unscaled.is_zero() {
+            scale = 0;
+        }
+        Decimal { unscaled, scale }
+    }
+}
+
+// Returns 10 raised to the provided exponent, if it is not larger than MAX_SCALE
+fn pow10(exp: i64) -> Option<Integer> {
+    (exp <= MAX_SCALE as i64).then(|| Integer::pow10(exp as u32))
+}
+
+// Divides rounding the quotient with the provided mode. The divisor must not be zero.
+fn div_round(dividend: Integer, divisor: Integer, rounding: RoundingMode) -> Integer {
+    let negative = dividend.is_negative() != divisor.is_negative();
+    let (q, r) = dividend.div_rem(divisor.clone());
+    if r.is_zero() {
+        return q;
+    }
+    // Compare twice the remainder with the divisor to find where the exact value lies
+    let half = (r.abs() * Integer::from(2)).cmp(&divisor.abs());
+    let away = match rounding {
+        RoundingMode::Up => true,
+        RoundingMode::Down => false,
+        RoundingMode::Ceiling => !negative,
+        RoundingMode::Floor => negative,
+        RoundingMode::HalfUp => half != Ordering::Less,
+        RoundingMode::HalfDown => half == Ordering::Greater,
+        RoundingMode::HalfEven => match half {
+            Ordering::Less => false,
+            Ordering::Greater => true,
+            Ordering::Equal => !q.clone().div_rem(Integer::from(2)).1.is_zero(),
+        },
+    };
+    match (away, negative) {
+        (false, _) => q,
+        (true, false) => q + Integer::from(1),
+        (true, true) => q - Integer::from(1),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDecimalError;
+
+impl fmt::Display for ParseDecimalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid decimal literal")
+    }
+}
+
+impl std::error::Error for ParseDecimalError {}
+
+impl FromStr for Decimal {
+    type Err = ParseDecimalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (integer, fraction) = match s.split_once('.') {
+            Some((_, "")) => return Err(ParseDecimalError),
+            Some((i, f)) => (i, f),
+            None => (s, ""),
+        };
+        let digits = integer.trim_start_matches(['+', '-']);
+        if digits.is_empty()
+            || fraction.len() > MAX_SCALE as usize
+            || !fraction.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(ParseDecimalError);
+        }
+        let unscaled: Integer = format!("{integer}{fraction}")
+            .parse()
+            .map_err(|_| ParseDecimalError)?;
+        Ok(Decimal {
+            unscaled,
+            scale: fraction.len() as u32,
+        })
+    }
+}
+
+impl From<Integer> for Decimal {
+    fn from(value: Integer) -> Self {
+        Decimal::new(value, 0)
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(value: i64) -> Self {
+        Integer::from(value).into()
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.unscaled.clone().abs().to_string();
+        let scale = self.scale as usize;
+        let formatted = if scale == 0 {
+            digits
+        } else if digits.len() > scale {
+            let (integer, fraction) = digits.split_at(digits.len() - scale);
+            format!("{integer}.{fraction}")
+        } else {
+            format!("0.{}{digits}", "0".repeat(scale - digits.len()))
+        };
+        f.pad_integral(!self.unscaled.is_negative(), "", &formatted)
+    }
+}
+
+impl fmt::Debug for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let normalized = self.normalized();
+        normalized.unscaled.hash(state);
+        normalized.scale.hash(state);
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let sign = self.signum();
+        if sign != other.signum() || sign == 0 {
+            return sign.cmp(&other.signum());
+        }
+        // Values with the same sign are ordered by their most significant digit first, so the
+        // scales are only aligned when the unscaled values have about the same size.
+        let magnitude = match self.exponent().cmp(&other.exponent()) {
+            Ordering::Equal => {
+                let scale = self.scale.max(other.scale);
+                let v1 = self.with_scale(scale, RoundingMode::Down).unscaled.abs();
+                let v2 = other.with_scale(scale, RoundingMode::Down).unscaled.abs();
+                v1.cmp(&v2)
+            }
+            ordering => ordering,
+        };
+        if sign < 0 {
+            magnitude.reverse()
+        } else {
+            magnitude
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// The operators panic if the result would be larger than the checked operations allow.
+
+impl ops::Add for Decimal {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        self.checked_add(other)
+            .expect("attempt to add decimals with overflow")
+    }
+}
+
+impl ops::Sub for Decimal {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self.checked_sub(other)
+            .expect("attempt to subtract decimals with overflow")
+    }
+}
+
+impl ops::Mul for Decimal {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        self.checked_mul(other)
+            .expect("attempt to multiply decimals with overflow")
+    }
+}
+
+impl ops::Neg for Decimal {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Decimal::new(-self.unscaled, self.scale)
+    }
+}
+
+#[cfg(test)]
+mod tests;
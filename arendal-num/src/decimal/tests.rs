@@ -0,0 +1,153 @@
+use std::collections::HashSet;
+
+use super::{Decimal, RoundingMode};
+use crate::Integer;
+
+fn dec(s: &str) -> Decimal {
+    s.parse().unwrap()
+}
+
+fn check_div(a: &str, b: &str, scale: u32, rounding: RoundingMode, expected: &str) {
+    let result = dec(a).div(&dec(b), scale, rounding).unwrap();
+    assert_eq!(result.to_string(), expected, "{a} / {b} ({rounding:?})");
+}
+
+#[test]
+fn parse_and_display() {
+    for s in [
+        "0",
+        "1.25",
+        "-1.25",
+        "0.001",
+        "-0.5",
+        "1.50",
+        "123456789012345678901234567890.123456789",
+    ] {
+        assert_eq!(dec(s).to_string(), s);
+    }
+    assert_eq!(dec("+1.5").to_string(), "1.5");
+    assert_eq!(dec("1.50").scale(), 2);
+    assert_eq!(format!("{:>7}", dec("-1.5")), "   -1.5");
+    for s in ["", ".", "1.", ".5", "1.2.3", "1,5", "-", "1.-5", "a.b"] {
+        assert!(s.parse::<Decimal>().is_err(), "{s}");
+    }
+}
+
+#[test]
+fn equality_ignores_scale() {
+    assert_eq!(dec("1.5"), dec("1.50"));
+    assert_eq!(dec("0"), dec("0.000"));
+    assert_eq!(dec("2"), Decimal::from(2));
+    assert!(dec("1.05") < dec("1.5"));
+    assert!(dec("-1.5") < dec("-1.05"));
+    let set: HashSet<Decimal> = [dec("1.5"), dec("1.50"), dec("1.500")].into();
+    assert_eq!(set.len(), 1);
+}
+
+#[test]
+fn arithmetic() {
+    assert_eq!((dec("1.25") + dec("0.75")).to_string(), "2.00");
+    assert_eq!((dec("0.1") + dec("0.2")).to_string(), "0.3");
+    assert_eq!((dec("1") - dec("0.01")).to_string(), "0.99");
+    assert_eq!((dec("0.01") - dec("1")).to_string(), "-0.99");
+    assert_eq!((dec("1.5") * dec("-1.5")).to_string(), "-2.25");
+    assert_eq!((dec("0.1") * dec("0.1")).to_string(), "0.01");
+    assert_eq!((-dec("2.5")).to_string(), "-2.5");
+    assert_eq!(
+        (dec("99999999999999999999.99") * dec("100.01")).to_string(),
+        "10000999999999999999998.9999"
+    );
+}
+
+#[test]
+fn division() {
+    use RoundingMode::*;
+    check_div("1", "3", 4, HalfUp, "0.3333");
+    check_div("2", "3", 4, HalfUp, "0.6667");
+    check_div("2", "3", 4, Down, "0.6666");
+    check_div("10", "4", 0, HalfEven, "2");
+    check_div("1.000", "0.5", 1, Down, "2.0");
+    check_div("1", "8", 3, Down, "0.125");
+    check_div("100", "0.25", 0, Down, "400");
+    assert!(dec("1").div(&dec("0.00"), 2, HalfUp).is_none());
+}
+
+#[test]
+fn rounding_modes() {
+    use RoundingMode::*;
+    // Each row: value, then the expected result of rounding to no decimals with
+    // Up, Down, Ceiling, Floor, HalfUp, HalfDown and HalfEven.
+    let table = [
+        ("5.5", ["6", "5", "6", "5", "6", "5", "6"]),
+        ("2.5", ["3", "2", "3", "2", "3", "2", "2"]),
+        ("1.6", ["2", "1", "2", "1", "2", "2", "2"]),
+        ("1.1", ["2", "1", "2", "1", "1", "1", "1"]),
+        ("1.0", ["1", "1", "1", "1", "1", "1", "1"]),
+        ("-1.0", ["-1", "-1", "-1", "-1", "-1", "-1", "-1"]),
+        ("-1.1", ["-2", "-1", "-1", "-2", "-1", "-1", "-1"]),
+        ("-1.6", ["-2", "-1", "-1", "-2", "-2", "-2", "-2"]),
+        ("-2.5", ["-3", "-2", "-2", "-3", "-3", "-2", "-2"]),
+        ("-5.5", ["-6", "-5", "-5", "-6", "-6", "-5", "-6"]),
+    ];
+    let modes = [Up, Down, Ceiling, Floor, HalfUp, HalfDown, HalfEven];
+    for (value, expected) in table {
+        for (mode, expected) in modes.iter().zip(expected) {
+            assert_eq!(
+                dec(value).with_scale(0, *mode).to_string(),
+                expected,
+                "{value} {mode:?}"
+            );
+            check_div(value, "1", 0, *mode, expected);
+        }
+    }
+    assert_eq!(dec("1.5").with_scale(3, Down).to_string(), "1.500");
+}
+
+#[test]
+fn checked_arithmetic() {
+    assert_eq!(dec("1.25").checked_add(dec("0.75")), Some(dec("2.00")));
+    assert_eq!(dec("1").checked_sub(dec("0.01")), Some(dec("0.99")));
+    assert_eq!(dec("1.5").checked_mul(dec("-1.5")), Some(dec("-2.25")));
+    // Values are bounded like integers
+    let big = Decimal::new(Integer::pow10(160_000), 0);
+    assert!(big.clone().checked_add(big.clone()).is_some());
+    assert!(big.clone().checked_mul(big.clone()).is_none());
+    assert!(dec("1").div(&big, 0, RoundingMode::Down).is_some());
+    // So are scales
+    let small = Decimal::new(1.into(), 40_000);
+    assert!(small.clone().checked_add(dec("1")).is_some());
+    assert!(small.clone().checked_mul(small.clone()).is_none());
+    assert!(
+        dec("1")
+            .div(&dec("3"), 1 << 17, RoundingMode::Down)
+            .is_none()
+    );
+}
+
+#[test]
+fn bounded_scales() {
+    let max = format!("0.{}1", "0".repeat(65_535));
+    assert_eq!(dec(&max).scale(), 1 << 16);
+    let over = format!("0.{}1", "0".repeat(65_536));
+    assert!(over.parse::<Decimal>().is_err());
+    // Comparing does not build 10^scale
+    let tiny = Decimal::new(1.into(), u32::MAX);
+    let huge = Decimal::new(Integer::pow10(1_000), 0);
+    assert!(tiny < huge);
+    assert!(-huge.clone() < -tiny.clone());
+    assert!(tiny > Decimal::from(0));
+    assert!(tiny < Decimal::new(1.into(), u32::MAX - 1));
+    assert_eq!(
+        Decimal::new(10.into(), u32::MAX),
+        Decimal::new(1.into(), u32::MAX - 1)
+    );
+    assert_ne!(dec("1.5"), dec("-1.5"));
+    assert!(dec("9.99") < dec("10"));
+    assert!(dec("-10") < dec("-9.99"));
+}
+
+#[test]
+#[should_panic(expected = "attempt to add decimals with overflow")]
+fn unchecked_operators_are_bounded() {
+    let _ = Decimal::new(1.into(), u32::MAX) + dec("1");
+}
@@ -0,0 +1,632 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops;
+use std::str::FromStr;
+
+// Base of the limbs of big magnitudes
+const BASE: u64 = 1 << 32;
+// Largest power of ten that fits in a limb, used for parsing and formatting
+const DEC_BASE: u32 = 1_000_000_000;
+const DEC_DIGITS: usize = 9;
+// Largest magnitude produced by the checked operations, in bits (about 315k decimal digits)
+const MAX_BITS: u64 = 1 << 20;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Integer {
+    repr: Repr,
+}
+
+// Values that fit in an i64 are always represented as Small, so the derived
+// equality and hashing are correct.
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Repr {
+    Small(i64),
+    // Little-endian limbs without trailing zeros
+    Big { negative: bool, magnitude: Vec<u32> },
+}
+
+impl Integer {
+    pub fn is_zero(&self) -> bool {
+        self.repr == Repr::Small(0)
+    }
+
+    pub fn is_negative(&self) -> bool {
+        match &self.repr {
+            Repr::Small(value) => *value < 0,
+            Repr::Big { negative, .. } => *negative,
+        }
+    }
+
+    // Returns the value as an index, if it is not negative and small enough
+    pub fn to_usize(&self) -> Option<usize> {
+        match &self.repr {
+            Repr::Small(value) => usize::try_from(*value).ok(),
+            Repr::Big { .. } => None,
+        }
+    }
+
+    // Returns the value if it fits in an i64
+    pub fn to_i64(&self) -> Option<i64> {
+        match &self.repr {
+            Repr::Small(value) => Some(*value),
+            Repr::Big { .. } => None,
+        }
+    }
+
+    // Not a From implementation, as a second one would break the inference of literals
+    pub fn from_u64(value: u64) -> Self {
+        Self::from_parts(false, vec![value as u32, (value >> 32) as u32])
+    }
+
+    // Returns the value if it fits in a u64
+    pub fn to_u64(&self) -> Option<u64> {
+        match &self.repr {
+            Repr::Small(value) => u64::try_from(*value).ok(),
+            Repr::Big {
+                negative: false,
+                magnitude,
+            } if magnitude.len() <= 2 => Some(
+                magnitude
+                    .iter()
+                    .rev()
+                    .fold(0u64, |acc, limb| (acc << 32) | *limb as u64),
+            ),
+            Repr::Big { .. } => None,
+        }
+    }
+
+    pub fn abs(self) -> Self {
+        if self.is_negative() { -self } else { self }
+    }
+
+    // Truncated division returning the quotient and the remainder, which has the
+    // sign of the dividend. Panics if the divisor is zero.
+    pub fn div_rem(self, other: Self) -> (Self, Self) {
+        if let (Repr::Small(v1), Repr::Small(v2)) = (&self.repr, &other.repr)
+            && let (Some(q), Some(r)) = (v1.checked_div(*v2), v1.checked_rem(*v2))
+        {
+            return (q.into(), r.into());
+        }
+        if other.is_zero() {
+            panic!("attempt to divide by zero");
+        }
+        let (n1, m1) = self.to_parts();
+        let (n2, m2) = other.to_parts();
+        let (q, r) = div_rem_mag(&m1, &m2);
+        (Self::from_parts(n1 != n2, q), Self::from_parts(n1, r))
+    }
+
+    // Checked operations return None instead of panicking if the divisor is zero
+    // or the result would be larger than MAX_BITS.
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        (self + other).bounded()
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        (self - other).bounded()
+    }
+
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        if self.bits() + other.bits() > MAX_BITS + 1 {
+            return None;
+        }
+        (self * other).bounded()
+    }
+
+    pub fn checked_div(self, other: Self) -> Option<Self> {
+        if other.is_zero() {
+            None
+        } else {
+            Some(self / other)
+        }
+    }
+
+    pub fn checked_rem(self, other: Self) -> Option<Self> {
+        if other.is_zero() {
+            None
+        } else {
+            Some(self % other)
+        }
+    }
+
+    // Also returns None for negative exponents.
+    pub fn checked_pow(self, exp: Self) -> Option<Self> {
+        if exp.is_negative() {
+            return None;
+        }
+        if self.abs_is_one_or_zero() {
+            // The result is zero, one or minus one for any exponent
+            let odd = exp.clone() % 2.into() != 0.into();
+            return Some(match self.repr {
+                Repr::Small(-1) if !odd => 1.into(),
+                _ if exp.is_zero() => 1.into(),
+                _ => self,
+            });
+        }
+        // The result needs at least (bits - 1) * exp bits
+        let exp = match exp.repr {
+            Repr::Small(exp) if (self.bits() - 1).saturating_mul(exp as u64) <= MAX_BITS => exp,
+            _ => return None,
+        };
+        let mut result: Self = 1.into();
+        let mut base = self;
+        let mut remaining = exp;
+        while remaining > 0 {
+            if remaining & 1 == 1 {
+                result = result.checked_mul(base.clone())?;
+            }
+            remaining >>= 1;
+            if remaining > 0 {
+                base = base.clone().checked_mul(base)?;
+            }
+        }
+        Some(result)
+    }
+
+    fn abs_is_one_or_zero(&self) -> bool {
+        matches!(self.repr, Repr::Small(-1..=1))
+    }
+
+    // Number of significant bits of the magnitude
+    fn bits(&self) -> u64 {
+        let (_, magnitude) = self.to_parts();
+        match magnitude.last() {
+            Some(last) => (magnitude.len() as u64 - 1) * 32 + (32 - last.leading_zeros()) as u64,
+            None => 0,
+        }
+    }
+
+    fn bounded(self) -> Option<Self> {
+        if self.bits() > MAX_BITS {
+            None
+        } else {
+            Some(self)
+        }
+    }
+
+    // Returns 10 raised to the provided exponent
+    pub(crate) fn pow10(exp: u32) -> Self {
+        let mut magnitude = vec![1u32];
+        let mut remaining = exp;
+        while remaining > 0 {
+            let n = remaining.min(DEC_DIGITS as u32);
+            mul_add_small(&mut magnitude, 10u32.pow(n), 0);
+            remaining -= n;
+        }
+        Self::from_parts(false, magnitude)
+    }
+
+    fn from_parts(negative: bool, mut magnitude: Vec<u32>) -> Self {
+        trim(&mut magnitude);
+        if magnitude.len() <= 2 {
+            let value = magnitude
+                .iter()
+                .rev()
+                .fold(0u64, |acc, limb| (acc << 32) | *limb as u64);
+            if !negative && value <= i64::MAX as u64 {
+                return (value as i64).into();
+            }
+            if negative && value <= i64::MIN.unsigned_abs() {
+                return (value as i64).wrapping_neg().into();
+            }
+        }
+        Integer {
+            repr: Repr::Big {
+                negative,
+                magnitude,
+            },
+        }
+    }
+
+    fn to_parts(&self) -> (bool, Vec<u32>) {
+        match &self.repr {
+            Repr::Small(value) => {
+                let abs = value.unsigned_abs();
+                let mut magnitude = vec![abs as u32, (abs >> 32) as u32];
+                trim(&mut magnitude);
+                (*value < 0, magnitude)
+            }
+            Repr::Big {
+                negative,
+                magnitude,
+            } => (*negative, magnitude.clone()),
+        }
+    }
+
+    fn add_parts(n1: bool, m1: &[u32], n2: bool, m2: &[u32]) -> Self {
+        if n1 == n2 {
+            Self::from_parts(n1, add_mag(m1, m2))
+        } else {
+            match cmp_mag(m1, m2) {
+                Ordering::Less => Self::from_parts(n2, sub_mag(m2, m1)),
+                _ => Self::from_parts(n1, sub_mag(m1, m2)),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseIntegerError;
+
+impl fmt::Display for ParseIntegerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid integer literal")
+    }
+}
+
+impl std::error::Error for ParseIntegerError {}
+
+impl FromStr for Integer {
+    type Err = ParseIntegerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(value) = s.parse::<i64>() {
+            return Ok(value.into());
+        }
+        let (negative, digits) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseIntegerError);
+        }
+        let mut magnitude = Vec::new();
+        // The first chunk is the shortest one so the rest are full
+        let first = match digits.len() % DEC_DIGITS {
+            0 => DEC_DIGITS,
+            n => n,
+        };
+        let mut start = 0;
+        let mut end = first;
+        while start < digits.len() {
+            let chunk: u32 = digits[start..end].parse().unwrap();
+            let multiplier = 10u32.pow((end - start) as u32);
+            mul_add_small(&mut magnitude, multiplier, chunk);
+            start = end;
+            end += DEC_DIGITS;
+        }
+        Ok(Self::from_parts(negative, magnitude))
+    }
+}
+
+impl From<i64> for Integer {
+    fn from(value: i64) -> Self {
+        Integer {
+            repr: Repr::Small(value),
+        }
+    }
+}
+
+impl fmt::Display for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.repr {
+            Repr::Small(value) => value.fmt(f),
+            Repr::Big {
+                negative,
+                magnitude,
+            } => {
+                let mut chunks = Vec::new();
+                let mut remaining = magnitude.clone();
+                while !remaining.is_empty() {
+                    let (q, r) = div_rem_small(&remaining, DEC_BASE);
+                    chunks.push(r);
+                    remaining = q;
+                }
+                let mut digits = String::with_capacity(chunks.len() * DEC_DIGITS);
+                let mut iter = chunks.iter().rev();
+                if let Some(first) = iter.next() {
+                    digits.push_str(&first.to_string());
+                }
+                for chunk in iter {
+                    digits.push_str(&format!("{:09}", chunk));
+                }
+                f.pad_integral(!negative, "", &digits)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Ord for Integer {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (&self.repr, &other.repr) {
+            (Repr::Small(v1), Repr::Small(v2)) => v1.cmp(v2),
+            // Big values are always out of the range of small ones
+            (Repr::Small(_), Repr::Big { negative, .. }) => {
+                if *negative {
+                    Ordering::Greater
+                } else {
+                    Ordering::Less
+                }
+            }
+            (Repr::Big { negative, .. }, Repr::Small(_)) => {
+                if *negative {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                }
+            }
+            (
+                Repr::Big {
+                    negative: n1,
+                    magnitude: m1,
+                },
+                Repr::Big {
+                    negative: n2,
+                    magnitude: m2,
+                },
+            ) => match (n1, n2) {
+                (false, true) => Ordering::Greater,
+                (true, false) => Ordering::Less,
+                (false, false) => cmp_mag(m1, m2),
+                (true, true) => cmp_mag(m2, m1),
+            },
+        }
+    }
+}
+
+impl PartialOrd for Integer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl ops::Add for Integer {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        if let (Repr::Small(v1), Repr::Small(v2)) = (&self.repr, &other.repr)
+            && let Some(value) = v1.checked_add(*v2)
+        {
+            return value.into();
+        }
+        let (n1, m1) = self.to_parts();
+        let (n2, m2) = other.to_parts();
+        Self::add_parts(n1, &m1, n2, &m2)
+    }
+}
+
+impl ops::Sub for Integer {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        if let (Repr::Small(v1), Repr::Small(v2)) = (&self.repr, &other.repr)
+            && let Some(value) = v1.checked_sub(*v2)
+        {
+            return value.into();
+        }
+        let (n1, m1) = self.to_parts();
+        let (n2, m2) = other.to_parts();
+        Self::add_parts(n1, &m1, !n2, &m2)
+    }
+}
+
+impl ops::Mul for Integer {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        if let (Repr::Small(v1), Repr::Small(v2)) = (&self.repr, &other.repr)
+            && let Some(value) = v1.checked_mul(*v2)
+        {
+            return value.into();
+        }
+        let (n1, m1) = self.to_parts();
+        let (n2, m2) = other.to_parts();
+        Self::from_parts(n1 != n2, mul_mag(&m1, &m2))
+    }
+}
+
+// Truncated division, panics if the divisor is zero.
+impl ops::Div for Integer {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        if let (Repr::Small(v1), Repr::Small(v2)) = (&self.repr, &other.repr)
+            && let Some(value) = v1.checked_div(*v2)
+        {
+            return value.into();
+        }
+        self.div_rem(other).0
+    }
+}
+
+// Remainder of the truncated division, panics if the divisor is zero.
+impl ops::Rem for Integer {
+    type Output = Self;
+
+    fn rem(self, other: Self) -> Self {
+        if let (Repr::Small(v1), Repr::Small(v2)) = (&self.repr, &other.repr)
+            && let Some(value) = v1.checked_rem(*v2)
+        {
+            return value.into();
+        }
+        self.div_rem(other).1
+    }
+}
+
+impl ops::Neg for Integer {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        if let Repr::Small(value) = &self.repr
+            && let Some(value) = value.checked_neg()
+        {
+            return value.into();
+        }
+        let (negative, magnitude) = self.to_parts();
+        Self::from_parts(!negative, magnitude)
+    }
+}
+
+fn trim(magnitude: &mut Vec<u32>) {
+    while magnitude.last() == Some(&0) {
+        magnitude.pop();
+    }
+}
+
+fn cmp_mag(m1: &[u32], m2: &[u32]) -> Ordering {
+    m1.len()
+        .cmp(&m2.len())
+        .then_with(|| m1.iter().rev().cmp(m2.iter().rev()))
+}
+
+fn add_mag(m1: &[u32], m2: &[u32]) -> Vec<u32> {
+    let (long, short) = if m1.len() >= m2.len() {
+        (m1, m2)
+    } else {
+        (m2, m1)
+    };
+    let mut result = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0u64;
+    for (i, limb) in long.iter().enumerate() {
+        let sum = *limb as u64 + *short.get(i).unwrap_or(&0) as u64 + carry;
+        result.push(sum as u32);
+        carry = sum >> 32;
+    }
+    if carry > 0 {
+        result.push(carry as u32);
+    }
+    result
+}
+
+// Requires m1 >= m2
+fn sub_mag(m1: &[u32], m2: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(m1.len());
+    let mut borrow = 0i64;
+    for (i, limb) in m1.iter().enumerate() {
+        let mut diff = *limb as i64 - *m2.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = if diff < 0 {
+            diff += BASE as i64;
+            1
+        } else {
+            0
+        };
+        result.push(diff as u32);
+    }
+    trim(&mut result);
+    result
+}
+
+fn mul_mag(m1: &[u32], m2: &[u32]) -> Vec<u32> {
+    let mut result = vec![0u32; m1.len() + m2.len()];
+    for (i, l1) in m1.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, l2) in m2.iter().enumerate() {
+            let t = *l1 as u64 * *l2 as u64 + result[i + j] as u64 + carry;
+            result[i + j] = t as u32;
+            carry = t >> 32;
+        }
+        result[i + m2.len()] = carry as u32;
+    }
+    trim(&mut result);
+    result
+}
+
+// magnitude = magnitude * multiplier + addend
+fn mul_add_small(magnitude: &mut Vec<u32>, multiplier: u32, addend: u32) {
+    let mut carry = addend as u64;
+    for limb in magnitude.iter_mut() {
+        let t = *limb as u64 * multiplier as u64 + carry;
+        *limb = t as u32;
+        carry = t >> 32;
+    }
+    if carry > 0 {
+        magnitude.push(carry as u32);
+    }
+}
+
+fn div_rem_small(magnitude: &[u32], divisor: u32) -> (Vec<u32>, u32) {
+    let mut quotient = vec![0u32; magnitude.len()];
+    let mut rem = 0u64;
+    for (i, limb) in magnitude.iter().enumerate().rev() {
+        let t = (rem << 32) | *limb as u64;
+        quotient[i] = (t / divisor as u64) as u32;
+        rem = t % divisor as u64;
+    }
+    trim(&mut quotient);
+    (quotient, rem as u32)
+}
+
+// Long division (Knuth, TAOCP vol. 2, 4.3.1, algorithm D). Requires a non-zero divisor.
+fn div_rem_mag(u: &[u32], v: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if cmp_mag(u, v) == Ordering::Less {
+        return (Vec::new(), u.to_vec());
+    }
+    if v.len() == 1 {
+        let (q, r) = div_rem_small(u, v[0]);
+        let mut r = vec![r];
+        trim(&mut r);
+        return (q, r);
+    }
+    let n = v.len();
+    let m = u.len() - n;
+    // Normalize so that the top limb of the divisor has its highest bit set
+    let shift = v[n - 1].leading_zeros();
+    let vn = shl(v, shift);
+    let mut un = shl(u, shift);
+    un.push(((u[u.len() - 1] as u64) >> (32 - shift)) as u32);
+    let mut q = vec![0u32; m + 1];
+    for j in (0..=m).rev() {
+        let num = ((un[j + n] as u64) << 32) | un[j + n - 1] as u64;
+        let mut qhat = num / vn[n - 1] as u64;
+        let mut rhat = num % vn[n - 1] as u64;
+        while qhat >= BASE || qhat * vn[n - 2] as u64 > ((rhat << 32) | un[j + n - 2] as u64) {
+            qhat -= 1;
+            rhat += vn[n - 1] as u64;
+            if rhat >= BASE {
+                break;
+            }
+        }
+        // Multiply and subtract
+        let mut k = 0i64;
+        for i in 0..n {
+            let p = qhat * vn[i] as u64;
+            let t = un[i + j] as i64 - k - (p & 0xFFFF_FFFF) as i64;
+            un[i + j] = t as u32;
+            k = (p >> 32) as i64 - (t >> 32);
+        }
+        let t = un[j + n] as i64 - k;
+        un[j + n] = t as u32;
+        q[j] = qhat as u32;
+        // Add back if we subtracted too much
+        if t < 0 {
+            q[j] = q[j].wrapping_sub(1);
+            let mut carry = 0u64;
+            for i in 0..n {
+                let t = un[i + j] as u64 + vn[i] as u64 + carry;
+                un[i + j] = t as u32;
+                carry = t >> 32;
+            }
+            un[j + n] = un[j + n].wrapping_add(carry as u32);
+        }
+    }
+    trim(&mut q);
+    let mut r: Vec<u32> = (0..n)
+        .map(|i| ((((un[i + 1] as u64) << 32) | un[i] as u64) >> shift) as u32)
+        .collect();
+    trim(&mut r);
+    (q, r)
+}
+
+// Shifts left by less than a limb, dropping any overflow
+fn shl(magnitude: &[u32], shift: u32) -> Vec<u32> {
+    let mut carry = 0u32;
+    magnitude
+        .iter()
+        .map(|limb| {
+            let t = ((*limb as u64) << shift) | carry as u64;
+            carry = (t >> 32) as u32;
+            t as u32
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests;
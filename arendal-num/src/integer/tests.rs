@@ -0,0 +1,358 @@
+use std::cmp::Ordering;
+
+use super::{Integer, MAX_BITS};
+
+fn int(s: &str) -> Integer {
+    s.parse().unwrap()
+}
+
+// Deterministic pseudo-random values covering small, boundary and i128-only ranges.
+fn samples() -> Vec<i128> {
+    let mut values: Vec<i128> = vec![
+        0,
+        1,
+        -1,
+        2,
+        10,
+        1_000_000_000,
+        u32::MAX as i128,
+        u32::MAX as i128 + 1,
+        i64::MAX as i128,
+        i64::MAX as i128 + 1,
+        i64::MIN as i128,
+        i64::MIN as i128 - 1,
+        u64::MAX as i128,
+        u64::MAX as i128 + 1,
+        -(u64::MAX as i128),
+    ];
+    let mut seed: u64 = 0x2545_F491_4F6C_DD1D;
+    for _ in 0..40 {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        let wide = ((seed as i128) << 32) ^ (seed >> 7) as i128;
+        let value = wide >> (seed % 96);
+        values.push(value);
+        values.push(-value * (seed as i128 % 1000));
+    }
+    values
+}
+
+fn fits(value: Option<i128>) -> Option<i128> {
+    value.filter(|v| v.unsigned_abs() < (1u128 << 126))
+}
+
+#[test]
+fn parse_and_display() {
+    for s in [
+        "0",
+        "-1",
+        "9223372036854775807",
+        "9223372036854775808",
+        "-9223372036854775808",
+        "-9223372036854775809",
+        "18446744073709551616",
+        "123456789012345678901234567890123456789",
+        "-100000000000000000000000000000000000000000000",
+    ] {
+        assert_eq!(int(s).to_string(), s);
+    }
+    assert_eq!(int("+12").to_string(), "12");
+    assert_eq!(int("-0").to_string(), "0");
+    assert_eq!(int("0000000000000000000000000042").to_string(), "42");
+    assert_eq!(
+        format!("{:>25}", int("-99999999999999999999")),
+        "    -99999999999999999999"
+    );
+    for s in ["", "-", "+", "1a", "--1", "1 2", "99999999999999999999x"] {
+        assert!(s.parse::<Integer>().is_err(), "{s}");
+    }
+}
+
+#[test]
+fn small_and_big_are_equal() {
+    let big = int("18446744073709551616");
+    assert_eq!(big.clone() - int("18446744073709551615"), 1.into());
+    assert_eq!(big.clone() - big, 0.into());
+    assert!((int("9223372036854775808") - int("9223372036854775808")).is_zero());
+}
+
+#[test]
+fn to_usize() {
+    assert_eq!(int("0").to_usize(), Some(0));
+    assert_eq!(int("42").to_usize(), Some(42));
+    assert_eq!(int("-1").to_usize(), None);
+    assert_eq!(int("18446744073709551616").to_usize(), None);
+}
+
+#[test]
+fn to_i64() {
+    assert_eq!(int("-42").to_i64(), Some(-42));
+    assert_eq!(int("9223372036854775807").to_i64(), Some(i64::MAX));
+    assert_eq!(int("-9223372036854775808").to_i64(), Some(i64::MIN));
+    assert_eq!(int("9223372036854775808").to_i64(), None);
+}
+
+#[test]
+fn u64() {
+    assert_eq!(Integer::from_u64(u64::MAX), int("18446744073709551615"));
+    assert_eq!(Integer::from_u64(42), 42.into());
+    assert_eq!(int("18446744073709551615").to_u64(), Some(u64::MAX));
+    assert_eq!(int("9223372036854775808").to_u64(), Some(1 << 63));
+    assert_eq!(int("42").to_u64(), Some(42));
+    assert_eq!(int("-1").to_u64(), None);
+    assert_eq!(int("18446744073709551616").to_u64(), None);
+    assert_eq!(int("-18446744073709551615").to_u64(), None);
+}
+
+#[test]
+fn against_i128() {
+    let values = samples();
+    for a in &values {
+        for b in &values {
+            let (ia, ib) = (int(&a.to_string()), int(&b.to_string()));
+            assert_eq!(ia.cmp(&ib), a.cmp(b), "{a} cmp {b}");
+            assert_eq!(ia == ib, a == b, "{a} == {b}");
+            if let Some(r) = fits(a.checked_add(*b)) {
+                assert_eq!(
+                    (ia.clone() + ib.clone()).to_string(),
+                    r.to_string(),
+                    "{a} + {b}"
+                );
+            }
+            if let Some(r) = fits(a.checked_sub(*b)) {
+                assert_eq!(
+                    (ia.clone() - ib.clone()).to_string(),
+                    r.to_string(),
+                    "{a} - {b}"
+                );
+            }
+            if let Some(r) = fits(a.checked_mul(*b)) {
+                assert_eq!(
+                    (ia.clone() * ib.clone()).to_string(),
+                    r.to_string(),
+                    "{a} * {b}"
+                );
+            }
+            if let Some(r) = a.checked_div(*b) {
+                assert_eq!((ia / ib).to_string(), r.to_string(), "{a} / {b}");
+            }
+        }
+    }
+}
+
+// Reference arithmetic on decimal strings of non-negative numbers
+
+fn digits(s: &str) -> Vec<u32> {
+    s.bytes().rev().map(|b| (b - b'0') as u32).collect()
+}
+
+fn undigits(mut d: Vec<u32>) -> String {
+    while d.len() > 1 && d.last() == Some(&0) {
+        d.pop();
+    }
+    d.iter()
+        .rev()
+        .map(|d| char::from(b'0' + *d as u8))
+        .collect()
+}
+
+fn str_add(a: &str, b: &str) -> String {
+    let (a, b) = (digits(a), digits(b));
+    let mut result = Vec::new();
+    let mut carry = 0;
+    for i in 0..a.len().max(b.len()) {
+        let sum = a.get(i).unwrap_or(&0) + b.get(i).unwrap_or(&0) + carry;
+        result.push(sum % 10);
+        carry = sum / 10;
+    }
+    result.push(carry);
+    undigits(result)
+}
+
+fn str_mul(a: &str, b: &str) -> String {
+    let (a, b) = (digits(a), digits(b));
+    let mut result = vec![0u32; a.len() + b.len()];
+    for (i, x) in a.iter().enumerate() {
+        let mut carry = 0;
+        for (j, y) in b.iter().enumerate() {
+            let t = result[i + j] + x * y + carry;
+            result[i + j] = t % 10;
+            carry = t / 10;
+        }
+        result[i + b.len()] += carry;
+    }
+    undigits(result)
+}
+
+fn str_cmp(a: &str, b: &str) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+fn big_samples() -> Vec<String> {
+    let mut values = vec![
+        "1".to_string(),
+        "4294967296".to_string(),
+        "18446744073709551615".to_string(),
+        "340282366920938463463374607431768211456".to_string(),
+        "99999999999999999999999999999999999999999999999999".to_string(),
+    ];
+    let mut seed: u64 = 0x9E37_79B9_7F4A_7C15;
+    for len in [5, 12, 19, 20, 27, 40, 64, 91] {
+        let mut s = String::new();
+        for i in 0..len {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let d = (seed >> 33) % 10;
+            s.push(char::from(b'0' + if i == 0 { d.max(1) } else { d } as u8));
+        }
+        values.push(s);
+    }
+    values
+}
+
+fn signed(negative: bool, s: &str) -> String {
+    if negative {
+        format!("-{s}")
+    } else {
+        s.to_string()
+    }
+}
+
+#[test]
+fn against_strings() {
+    let values = big_samples();
+    for a in &values {
+        for b in &values {
+            for (na, nb) in [(false, false), (false, true), (true, false), (true, true)] {
+                let (sa, sb) = (signed(na, a), signed(nb, b));
+                let (ia, ib) = (int(&sa), int(&sb));
+                let product = signed(na != nb, &str_mul(a, b));
+                assert_eq!(
+                    (ia.clone() * ib.clone()).to_string(),
+                    product,
+                    "{sa} * {sb}"
+                );
+                if na == nb {
+                    let sum = signed(na, &str_add(a, b));
+                    assert_eq!((ia.clone() + ib.clone()).to_string(), sum, "{sa} + {sb}");
+                    assert_eq!((int(&sum) - ib.clone()).to_string(), sa, "{sum} - {sb}");
+                }
+                let expected = match (na, nb) {
+                    (false, true) => Ordering::Greater,
+                    (true, false) => Ordering::Less,
+                    (false, false) => str_cmp(a, b),
+                    (true, true) => str_cmp(b, a),
+                };
+                assert_eq!(ia.cmp(&ib), expected, "{sa} cmp {sb}");
+            }
+        }
+    }
+}
+
+#[test]
+fn division() {
+    let values = big_samples();
+    for q in &values {
+        for d in &values {
+            for r in &values {
+                // a = q * d + r with r < d, so a / d must be q
+                if str_cmp(r, d) != Ordering::Less {
+                    continue;
+                }
+                let a = str_add(&str_mul(q, d), r);
+                assert_eq!((int(&a) / int(d)).to_string(), *q, "{a} / {d}");
+                assert_eq!(
+                    (int(&format!("-{a}")) / int(d)).to_string(),
+                    format!("-{q}"),
+                    "-{a} / {d}"
+                );
+            }
+            let a = str_mul(q, d);
+            assert_eq!((int(&a) / int(q)).to_string(), *d, "{a} / {q}");
+        }
+    }
+}
+
+#[test]
+fn div_rem_and_neg() {
+    let values = samples();
+    for a in &values {
+        assert_eq!((-int(&a.to_string())).to_string(), (-a).to_string(), "-{a}");
+        assert_eq!(int(&a.to_string()).abs().to_string(), a.abs().to_string());
+        for b in &values {
+            if let (Some(q), Some(r)) = (a.checked_div(*b), a.checked_rem(*b)) {
+                let (iq, ir) = int(&a.to_string()).div_rem(int(&b.to_string()));
+                assert_eq!(iq.to_string(), q.to_string(), "{a} / {b}");
+                assert_eq!(ir.to_string(), r.to_string(), "{a} % {b}");
+            }
+        }
+    }
+    assert_eq!(-int("-9223372036854775808"), int("9223372036854775808"));
+    assert_eq!(Integer::pow10(0), 1.into());
+    assert_eq!(Integer::pow10(20).to_string(), "100000000000000000000");
+}
+
+#[test]
+fn checked() {
+    let values = samples();
+    for a in &values {
+        for b in &values {
+            let (ia, ib) = (int(&a.to_string()), int(&b.to_string()));
+            assert_eq!(
+                ia.clone().checked_rem(ib.clone()).map(|r| r.to_string()),
+                a.checked_rem(*b).map(|r| r.to_string()),
+                "{a} % {b}"
+            );
+            assert_eq!(ia.clone().checked_div(ib.clone()).is_none(), *b == 0);
+            assert_eq!(
+                ia.checked_add(ib),
+                Some(int(&a.to_string()) + int(&b.to_string()))
+            );
+        }
+    }
+    for (base, exp) in [
+        (2i128, 0u32),
+        (2, 10),
+        (-3, 5),
+        (-3, 4),
+        (10, 30),
+        (7, 40),
+        (0, 0),
+        (0, 7),
+    ] {
+        assert_eq!(
+            int(&base.to_string()).checked_pow((exp as i64).into()),
+            Some(int(&base.pow(exp).to_string())),
+            "{base} ** {exp}"
+        );
+    }
+    assert_eq!(
+        int("2").checked_pow(int("1000")).unwrap().to_string(),
+        str_pow2(1000)
+    );
+    assert_eq!(
+        int("1").checked_pow(int("100000000000000000000")),
+        Some(1.into())
+    );
+    assert_eq!(
+        int("-1").checked_pow(int("100000000000000000001")),
+        Some((-1).into())
+    );
+    assert_eq!(int("2").checked_pow((-1).into()), None);
+    assert_eq!(int("2").checked_pow(int("100000000")), None);
+    assert_eq!(int("10").checked_pow(int("1000000")), None);
+    // The largest allowed value is 2 ** MAX_BITS - 1
+    let mut magnitude = vec![0u32; (MAX_BITS / 32) as usize - 1];
+    magnitude.push(1 << 31);
+    let big = Integer::from_parts(false, magnitude);
+    assert_eq!(big.clone().checked_add(big.clone()), None);
+    assert_eq!(big.clone().checked_mul(2.into()), None);
+    assert_eq!(big.clone().checked_sub(-big.clone()), None);
+    assert_eq!(big.clone().checked_add(1.into()), Some(big + 1.into()));
+}
+
+fn str_pow2(exp: usize) -> String {
+    (0..exp).fold("1".to_string(), |acc, _| str_add(&acc, &acc))
+}
@@ -1,5 +1,5 @@
 mod decimal;
 mod integer;
 
-pub use decimal::Decimal;
-pub use integer::Integer;
+pub use decimal::{Decimal, ParseDecimalError, RoundingMode};
+pub use integer::{Integer, ParseIntegerError};
@@ -2,12 +2,13 @@ mod tokenizer;
 
 use std::fmt;
 
+use arcstr::ArcStr;
 use ast::input::StringInput;
 use ast::keyword::Keyword;
 use ast::position::{EqNoPosition, Position};
 use ast::problem::{ErrorType, Output};
 use ast::symbol::{Symbol, TSymbol};
-use num::Integer;
+use num::{Decimal, Integer};
 use tokenizer::{Token, TokenKind, Tokens, tokenize};
 
 pub(super) fn lex(input: StringInput) -> Output<Lexemes> {
@@ -90,6 +91,10 @@ impl Lexemes {
     pub(super) fn len(&self) -> usize {
         self.lexemes.len()
     }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.lexemes.is_empty()
+    }
 }
 
 impl fmt::Debug for Lexemes {
@@ -109,7 +114,9 @@ pub(super) enum LexemeData {
     Plus,
     Minus,
     Star,
+    DoubleStar,
     Slash,
+    Percent,
     Dot,
     Greater,
     GreaterOrEq,
@@ -123,12 +130,17 @@ pub(super) enum LexemeData {
     LogicalOr,
     TypeAnnSeparator,
     PathSeparator,
+    Comma,
+    Arrow,
     Integer(Integer),
+    Decimal(Decimal),
     Level(Level),
     Underscore,
     Symbol(Symbol),
     TSymbol(TSymbol),
     Keyword(Keyword),
+    Quote,
+    Text(ArcStr),
 }
 
 struct Lexer<'me> {
@@ -145,12 +157,14 @@ enum Error {
     InvalidWord,
     NoOpenEnclosure,
     InvalidOpenEnclosure,
+    NoCloseEnclosure,
+    InvalidEscape,
 }
 
 impl ErrorType for Error {}
 
 impl<'me> Lexer<'me> {
-    fn new(tokens: &Tokens) -> Lexer {
+    fn new(tokens: &Tokens) -> Lexer<'_> {
         Lexer {
             separator: Separator::Start,
             tokens,
@@ -191,7 +205,9 @@ impl<'me> Lexer<'me> {
                 TokenKind::Plus => self.add_lexeme(LexemeData::Plus, 1),
                 TokenKind::Minus => self.add_lexeme(LexemeData::Minus, 1),
                 TokenKind::Star => self.add_lexeme(LexemeData::Star, 1),
+                TokenKind::DoubleStar => self.add_lexeme(LexemeData::DoubleStar, 1),
                 TokenKind::Slash => self.add_lexeme(LexemeData::Slash, 1),
+                TokenKind::Percent => self.add_lexeme(LexemeData::Percent, 1),
                 TokenKind::Dot => self.add_lexeme(LexemeData::Dot, 1),
                 TokenKind::Greater => self.add_lexeme(LexemeData::Greater, 1),
                 TokenKind::GreaterOrEq => self.add_lexeme(LexemeData::GreaterOrEq, 1),
@@ -206,10 +222,18 @@ impl<'me> Lexer<'me> {
                 TokenKind::Underscore => self.add_lexeme(LexemeData::Underscore, 1),
                 TokenKind::Colon => self.add_lexeme(LexemeData::TypeAnnSeparator, 1),
                 TokenKind::DoubleColon => self.add_lexeme(LexemeData::PathSeparator, 1),
+                TokenKind::Comma => self.add_lexeme(LexemeData::Comma, 1),
+                TokenKind::Arrow => self.add_lexeme(LexemeData::Arrow, 1),
                 TokenKind::Open(enclosure) => self.add_level(enclosure),
                 TokenKind::Close(enclosure) => return self.close(t, enclosure),
                 TokenKind::Digits => self.add_digits(t),
+                TokenKind::Decimal => self.add_decimal(t),
                 TokenKind::Word => self.add_word(t),
+                TokenKind::Quote => self.add_lexeme(LexemeData::Quote, 1),
+                TokenKind::Text => {
+                    self.add_lexeme(LexemeData::Text(t.range.substr().as_str().into()), 1)
+                }
+                TokenKind::Escape => self.add_escape(t),
                 _ => panic!("Unexpected token"),
             }
         }
@@ -257,9 +281,14 @@ impl<'me> Lexer<'me> {
         // - end_index = 1
         // - Tokens to consume = 1 + (end_index - start_index) + 1
         let ntokens = 2 + end_index - start_index;
-        self.output.merge_problems(result).map(|lexemes| {
-            self.add_lexeme(LexemeData::Level(Level { enclosure, lexemes }), ntokens)
-        });
+        if let Some(lexemes) = self.output.merge_problems(result) {
+            if end_index < self.tokens.len() {
+                self.add_lexeme(LexemeData::Level(Level { enclosure, lexemes }), ntokens)
+            } else {
+                let token = self.peek().unwrap().clone();
+                self.add_error(&token, Error::NoCloseEnclosure, ntokens - 1)
+            }
+        }
         /*
         match self.problems.add_result(result) {
             Some(lexemes) => {
@@ -297,6 +326,27 @@ impl<'me> Lexer<'me> {
         );
     }
 
+    fn add_decimal(&mut self, decimal: Token) {
+        self.add_lexeme(
+            LexemeData::Decimal(decimal.range.substr().parse().unwrap()),
+            1,
+        );
+    }
+
+    fn add_escape(&mut self, token: Token) {
+        let text = match token.range.substr().as_str() {
+            "\\n" => "\n",
+            "\\r" => "\r",
+            "\\t" => "\t",
+            "\\\\" => "\\",
+            "\\\"" => "\"",
+            "\\{" => "{",
+            "\\}" => "}",
+            _ => return self.add_error(&token, Error::InvalidEscape, 1),
+        };
+        self.add_lexeme(LexemeData::Text(ArcStr::from(text)), 1);
+    }
+
     fn add_word(&mut self, token: Token) {
         let word = token.range.substr();
         if let Some(k) = Keyword::parse(word.as_str()) {
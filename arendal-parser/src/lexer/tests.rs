@@ -1,5 +1,3 @@
-use std::rc::Rc;
-
 use super::{
     Enclosure, Keyword, Level, Lexeme, LexemeData, Lexemes, Output, Position, Separator, Symbol,
     TSymbol,
@@ -105,10 +103,7 @@ impl TestCase {
 
     fn err(self) {
         match self.parent {
-            None => match self.lex().to_result() {
-                Ok(_) => panic!(),
-                Err(_) => (),
-            },
+            None => assert!(self.lex().to_result().is_err()),
             _ => self.close().err(),
         }
     }
@@ -140,6 +135,18 @@ fn digits3() {
         .ok_without_pos();
 }
 
+#[test]
+fn decimal() {
+    test("1.25 + 3")
+        .token(
+            Separator::Start,
+            LexemeData::Decimal("1.25".parse().unwrap()),
+        )
+        .token(Separator::Whitespace, LexemeData::Plus)
+        .integer(Separator::Whitespace, 3)
+        .ok_without_pos();
+}
+
 #[test]
 fn add1() {
     test("1234+456")
@@ -252,3 +259,28 @@ fn path() {
         .tsymbol(Separator::Nothing, "C")
         .ok_without_pos();
 }
+
+#[test]
+fn string() {
+    test(r#""a\tb{x}" "\{\}""#)
+        .token(Separator::Start, LexemeData::Quote)
+        .token(Separator::Nothing, LexemeData::Text("a".into()))
+        .token(Separator::Nothing, LexemeData::Text("\t".into()))
+        .token(Separator::Nothing, LexemeData::Text("b".into()))
+        .level(Separator::Nothing, Enclosure::Curly)
+        .symbol(Separator::Nothing, "x")
+        .close()
+        .token(Separator::Nothing, LexemeData::Quote)
+        .token(Separator::Whitespace, LexemeData::Quote)
+        .token(Separator::Nothing, LexemeData::Text("{".into()))
+        .token(Separator::Nothing, LexemeData::Text("}".into()))
+        .token(Separator::Nothing, LexemeData::Quote)
+        .ok_without_pos();
+}
+
+#[test]
+fn string_err() {
+    test(r#""a\qb""#).err();
+    test(r#""a{x"#).err();
+    test("(1").err();
+}
@@ -34,6 +34,10 @@ impl Tokens {
     pub(super) fn get(&self, index: usize) -> Option<&Token> {
         self.tokens.get(index)
     }
+
+    pub(super) fn len(&self) -> usize {
+        self.tokens.len()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -44,7 +48,9 @@ pub(super) enum TokenKind {
     Plus,
     Minus,
     Star,
+    DoubleStar,
     Slash,
+    Percent,
     Dot,
     Greater,
     GreaterOrEq,
@@ -60,29 +66,24 @@ pub(super) enum TokenKind {
     LogicalOr,
     Colon,
     DoubleColon,
+    Comma,
+    Arrow,
     Open(Enclosure),
     Close(Enclosure),
     Underscore,
     Digits,
+    Decimal,
     Word,
+    Quote,
+    Text,
+    Escape,
     Other,
 }
 
-impl Token {
-    pub fn is_whitespace(&self) -> bool {
-        matches!(
-            self.kind,
-            TokenKind::Spaces | TokenKind::Tabs | TokenKind::NewLine
-        )
-    }
-
-    fn chars(&self) -> usize {
-        self.range.substr().chars().count()
-    }
-
-    fn bytes(&self) -> usize {
-        self.range.substr().len()
-    }
+// Whether the tokenizer is inside a string literal or an interpolated expression
+enum Nesting {
+    Text,
+    Interpolation(usize), // Number of open curly braces inside the expression
 }
 
 struct Tokenizer {
@@ -90,6 +91,7 @@ struct Tokenizer {
     index: usize,
     range: StrRange,
     tokens: Vec<Token>,
+    nesting: Vec<Nesting>,
 }
 
 impl Tokenizer {
@@ -100,6 +102,7 @@ impl Tokenizer {
             index: 0,
             range: StrRange::new(input),
             tokens: Vec::default(),
+            nesting: Vec::default(),
         }
     }
 
@@ -133,7 +136,9 @@ impl Tokenizer {
     fn tokenize(mut self) -> Tokens {
         while let Some(c) = self.peek() {
             self.advance(c);
-            if !self.add_known_first_char(c) && !self.add_digits(c) && !self.add_word(c) {
+            if let Some(Nesting::Text) = self.nesting.last() {
+                self.add_text(c);
+            } else if !self.add_known_first_char(c) && !self.add_digits(c) && !self.add_word(c) {
                 self.add_token(TokenKind::Other);
             }
         }
@@ -155,9 +160,10 @@ impl Tokenizer {
                 self.add_token(TokenKind::Tabs)
             }
             '+' => self.add_token(TokenKind::Plus),
-            '-' => self.add_token(TokenKind::Minus),
-            '*' => self.add_token(TokenKind::Star),
+            '-' => self.add_token_if_next_or_else('>', TokenKind::Arrow, TokenKind::Minus),
+            '*' => self.add_token_if_next_or_else('*', TokenKind::DoubleStar, TokenKind::Star),
             '/' => self.add_token(TokenKind::Slash),
+            '%' => self.add_token(TokenKind::Percent),
             '.' => self.add_token(TokenKind::Dot),
             '>' => self.add_token_if_next_or_else('=', TokenKind::GreaterOrEq, TokenKind::Greater),
             '<' => self.add_token_if_next_or_else('=', TokenKind::LessOrEq, TokenKind::Less),
@@ -167,20 +173,79 @@ impl Tokenizer {
             '|' => self.add_token_if_next_or_else('|', TokenKind::LogicalOr, TokenKind::Pipe),
             '(' => self.add_token(TokenKind::Open(Enclosure::Parens)),
             ')' => self.add_token(TokenKind::Close(Enclosure::Parens)),
-            '{' => self.add_token(TokenKind::Open(Enclosure::Curly)),
-            '}' => self.add_token(TokenKind::Close(Enclosure::Curly)),
+            '{' => self.add_open_curly(),
+            '}' => self.add_close_curly(),
+            '"' => {
+                self.nesting.push(Nesting::Text);
+                self.add_token(TokenKind::Quote)
+            }
             '[' => self.add_token(TokenKind::Open(Enclosure::Square)),
             ']' => self.add_token(TokenKind::Close(Enclosure::Square)),
             '_' => self.add_token(TokenKind::Underscore),
             ':' => self.add_token_if_next_or_else(':', TokenKind::DoubleColon, TokenKind::Colon),
+            ',' => self.add_token(TokenKind::Comma),
             _ => false,
         }
     }
 
+    // Adds a token inside a string literal, starting with the provided char
+    fn add_text(&mut self, c: char) -> bool {
+        match c {
+            '"' => {
+                self.nesting.pop();
+                self.add_token(TokenKind::Quote)
+            }
+            '\\' => {
+                if let Some(n) = self.peek() {
+                    self.advance(n);
+                }
+                self.add_token(TokenKind::Escape)
+            }
+            '{' => {
+                self.nesting.push(Nesting::Interpolation(0));
+                self.add_token(TokenKind::Open(Enclosure::Curly))
+            }
+            _ => {
+                self.advance_while(|n| n != '"' && n != '\\' && n != '{');
+                self.add_token(TokenKind::Text)
+            }
+        }
+    }
+
+    fn add_open_curly(&mut self) -> bool {
+        if let Some(Nesting::Interpolation(n)) = self.nesting.last_mut() {
+            *n += 1;
+        }
+        self.add_token(TokenKind::Open(Enclosure::Curly))
+    }
+
+    // A closing curly brace that matches an interpolation goes back to the string literal
+    fn add_close_curly(&mut self) -> bool {
+        match self.nesting.last_mut() {
+            Some(Nesting::Interpolation(0)) => {
+                self.nesting.pop();
+            }
+            Some(Nesting::Interpolation(n)) => *n -= 1,
+            _ => (),
+        }
+        self.add_token(TokenKind::Close(Enclosure::Curly))
+    }
+
     fn add_digits(&mut self, c: char) -> bool {
         if c.is_ascii_digit() {
             self.advance_while(|n| n.is_ascii_digit());
-            self.add_token(TokenKind::Digits)
+            // A dot followed by a digit makes it a decimal literal
+            let fraction = self
+                .chars
+                .get(self.index + 1)
+                .is_some_and(|n| n.is_ascii_digit());
+            if self.peek() == Some('.') && fraction {
+                self.advance('.');
+                self.advance_while(|n| n.is_ascii_digit());
+                self.add_token(TokenKind::Decimal)
+            } else {
+                self.add_token(TokenKind::Digits)
+            }
         } else {
             false
         }
@@ -208,11 +273,9 @@ impl Tokenizer {
 
     // Adds a token spanning two chars if the next one is the provided one
     fn add_token_if_next(&mut self, c: char, token: TokenKind) -> bool {
-        if let Some(next) = self.peek() {
-            if next == c {
-                self.advance(c);
-                return self.add_token(token);
-            }
+        if self.peek() == Some(c) {
+            self.advance(c);
+            return self.add_token(token);
         }
         false
     }
@@ -7,7 +7,7 @@ struct TestCase {
 }
 
 fn test(input: &str) -> TestCase {
-    let input = StringInput::from_str(input);
+    let input = StringInput::from(input);
     let tokenizer = Tokenizer::new(input.clone());
     TestCase { input, tokenizer }
 }
@@ -119,6 +119,24 @@ fn bang() {
         .ok();
 }
 
+#[test]
+fn arrow_and_comma() {
+    test("->-,")
+        .token("->", TokenKind::Arrow)
+        .token("-", TokenKind::Minus)
+        .token(",", TokenKind::Comma)
+        .ok();
+}
+
+#[test]
+fn rem_and_pow() {
+    test("%***")
+        .token("%", TokenKind::Percent)
+        .token("**", TokenKind::DoubleStar)
+        .token("*", TokenKind::Star)
+        .ok();
+}
+
 #[test]
 fn digits1() {
     test("1234").digits("1234").ok();
@@ -129,6 +147,61 @@ fn digits2() {
     test("12 34").digits("12").spaces(1).digits("34").ok();
 }
 
+#[test]
+fn decimal() {
+    test("1.25").token("1.25", TokenKind::Decimal).ok();
+    test("1.").digits("1").token(".", TokenKind::Dot).ok();
+    test("1.a")
+        .digits("1")
+        .token(".", TokenKind::Dot)
+        .word("a")
+        .ok();
+    test("1.2.3")
+        .token("1.2", TokenKind::Decimal)
+        .token(".", TokenKind::Dot)
+        .digits("3")
+        .ok();
+}
+
+#[test]
+fn string() {
+    test(r#""a b\"c" """#)
+        .token("\"", TokenKind::Quote)
+        .token("a b", TokenKind::Text)
+        .token("\\\"", TokenKind::Escape)
+        .token("c", TokenKind::Text)
+        .token("\"", TokenKind::Quote)
+        .spaces(1)
+        .token("\"", TokenKind::Quote)
+        .token("\"", TokenKind::Quote)
+        .ok();
+}
+
+#[test]
+fn interpolation() {
+    test(r#""x={ {1} }""#)
+        .token("\"", TokenKind::Quote)
+        .token("x=", TokenKind::Text)
+        .token("{", TokenKind::Open(Enclosure::Curly))
+        .spaces(1)
+        .token("{", TokenKind::Open(Enclosure::Curly))
+        .digits("1")
+        .token("}", TokenKind::Close(Enclosure::Curly))
+        .spaces(1)
+        .token("}", TokenKind::Close(Enclosure::Curly))
+        .token("\"", TokenKind::Quote)
+        .ok();
+    test(r#""{"a"}""#)
+        .token("\"", TokenKind::Quote)
+        .token("{", TokenKind::Open(Enclosure::Curly))
+        .token("\"", TokenKind::Quote)
+        .token("a", TokenKind::Text)
+        .token("\"", TokenKind::Quote)
+        .token("}", TokenKind::Close(Enclosure::Curly))
+        .token("\"", TokenKind::Quote)
+        .ok();
+}
+
 #[test]
 fn word1() {
     test("abc").word("abc").ok();
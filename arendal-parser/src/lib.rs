@@ -1,26 +1,31 @@
 mod lexer;
 
 use ast::{
-    AST, Binary, Expr, Expression, Q, Statement, TypeExpr,
-    common::BinaryOp,
+    AST, Assignment, Binary, Call, Conditional, Expr, Expression, Lambda, Param, Q, Seq, Statement,
+    StringPart, TypeExpr, Unary,
+    common::{BinaryOp, UnaryOp},
     input::StringInput,
-    problem::{ErrorType, Output, Result},
+    keyword::Keyword,
+    position::Position,
+    problem::{ErrorType, Output},
+    symbol::{Symbol, TSymbol},
 };
-use lexer::{Lexeme, LexemeData, Lexemes, Separator};
+use lexer::{Enclosure, Level, Lexeme, LexemeData, Lexemes, Separator};
 
 pub fn parse(input: &str) -> Output<AST> {
-    parse_statements(input).and_then(|stmts| stmts_to_ast(stmts))
+    parse_statements(input).and_then(stmts_to_ast)
 }
 
 fn stmts_to_ast(stmts: Vec<Statement>) -> Output<AST> {
     let mut output: Output<AST> = Output::new();
-    let mut ast = AST { expression: None };
+    let mut ast = AST::default();
     for s in stmts {
         match s {
             Statement::Expression(e) => match ast.expression {
                 None => ast.expression = Some(e),
                 _ => output.add_error(Error::OnlyOneExpressionAllowed.at(e.position)),
             },
+            Statement::Assignment(a) => ast.assignments.push(a),
         }
     }
     output.replace(ast);
@@ -28,7 +33,7 @@ fn stmts_to_ast(stmts: Vec<Statement>) -> Output<AST> {
 }
 
 pub fn parse_statements(input: &str) -> Output<Vec<Statement>> {
-    let input = StringInput::from_str(input);
+    let input = StringInput::from(input);
     lexer::lex(input).and_then(|lexemes| parse_lexemes(&lexemes))
 }
 
@@ -47,40 +52,287 @@ fn parse_lexemes(lexemes: &Lexemes) -> Output<Vec<Statement>> {
 // I.e., either end of the input or a newline separator
 fn is_eos(index: usize, lexemes: &Lexemes) -> bool {
     match lexemes.get(index) {
-        Some(lexeme) => match lexeme.separator {
-            Separator::Start | Separator::Nothing => false,
-            _ => true,
-        },
+        Some(lexeme) => !matches!(lexeme.separator, Separator::Start | Separator::Nothing),
         None => true,
     }
 }
 
-/*
+// Returns whether the current lexeme is the provided keyword
+fn is_keyword(index: usize, lexemes: &Lexemes, keyword: Keyword) -> bool {
+    matches!(lexemes.get(index), Some(l) if l.data == LexemeData::Keyword(keyword))
+}
 
-fn advance(&mut self) {
-    self.index += 1;
+// Returns whether the current lexeme starts a function definition, i.e., `fn` followed by a symbol
+fn is_fn_definition(index: usize, lexemes: &Lexemes) -> bool {
+    is_keyword(index, lexemes, Keyword::Fn)
+        && matches!(lexemes.get(index + 1), Some(l) if matches!(l.data, LexemeData::Symbol(_)))
 }
 
-fn get_and_advance<'a>(&mut self, lexemes: &'a Lexemes) -> Option<&'a Lexeme> {
-    let current = self.index;
-    self.advance();
-    lexemes.get(current)
+// Returns the lexemes enclosed in parentheses by the current lexeme, if any
+fn parens(index: usize, lexemes: &Lexemes) -> Option<&Lexemes> {
+    match lexemes.get(index) {
+        Some(Lexeme {
+            data:
+                LexemeData::Level(Level {
+                    enclosure: Enclosure::Parens,
+                    lexemes,
+                }),
+            ..
+        }) => Some(lexemes),
+        _ => None,
+    }
 }
 
-*/
+// Returns the position of the current lexeme, or of the last one if we have reached the end.
+fn position(index: usize, lexemes: &Lexemes) -> Position {
+    match lexemes
+        .get(index)
+        .or_else(|| lexemes.get(lexemes.len().wrapping_sub(1)))
+    {
+        Some(lexeme) => lexeme.position.clone(),
+        None => Position::NoPosition,
+    }
+}
+
+// Consumes the current lexeme if it is the expected one or returns the provided error.
+fn expect(index: &mut usize, lexemes: &Lexemes, expected: LexemeData, error: Error) -> Output<()> {
+    match lexemes.get(*index) {
+        Some(lexeme) if lexeme.data == expected => {
+            *index += 1;
+            Output::ok(())
+        }
+        _ => error.at(position(*index, lexemes)).into(),
+    }
+}
 
 fn rule_statement(index: &mut usize, lexemes: &Lexemes) -> Output<Statement> {
-    rule_expression(index, lexemes).and_then(|e| {
+    let statement = if is_keyword(*index, lexemes, Keyword::Let) {
+        *index += 1;
+        rule_assignment(index, lexemes).map(Statement::Assignment)
+    } else if is_fn_definition(*index, lexemes) {
+        rule_fn_definition(index, lexemes).map(Statement::Assignment)
+    } else {
+        rule_expression(index, lexemes).map(Statement::Expression)
+    };
+    statement.and_then(|s| {
         if !is_eos(*index, lexemes) {
-            Error::EndOfStatementExpected.to_output(lexemes.get(*index).unwrap())
+            Error::EndOfStatementExpected.at_lexeme(lexemes.get(*index).unwrap())
         } else {
-            Output::ok(Statement::Expression(e))
+            Output::ok(s)
+        }
+    })
+}
+
+fn rule_assignment(index: &mut usize, lexemes: &Lexemes) -> Output<Assignment> {
+    let lvalue = match lexemes.get(*index) {
+        Some(Lexeme {
+            position,
+            data: LexemeData::Symbol(symbol),
+            ..
+        }) => {
+            *index += 1;
+            Output::ok((position.clone(), symbol.clone()))
         }
+        _ => Error::SymbolExpected.at(position(*index, lexemes)).into(),
+    };
+    // The type annotation of the symbol is the annotation of the expression
+    lvalue
+        .and_then(|lvalue| rule_type_ann(index, lexemes).map(|type_expr| (lvalue, type_expr)))
+        .and_then(|lvalue| {
+            expect(
+                index,
+                lexemes,
+                LexemeData::Assignment,
+                Error::AssignmentExpected,
+            )
+            .map(|_| lvalue)
+        })
+        .and_then(|((position, symbol), type_expr)| {
+            rule_expression(index, lexemes)
+                .and_then(|expr| annotate(expr, type_expr))
+                .map(|expr| Assignment {
+                    position,
+                    symbol,
+                    expr,
+                })
+        })
+}
+
+// A function definition is an assignment of a lambda with a mandatory result type.
+fn rule_fn_definition(index: &mut usize, lexemes: &Lexemes) -> Output<Assignment> {
+    let position = position(*index + 1, lexemes);
+    let symbol = match lexemes.get(*index + 1) {
+        Some(Lexeme {
+            data: LexemeData::Symbol(symbol),
+            ..
+        }) => symbol.clone(),
+        _ => unreachable!("checked by is_fn_definition"),
+    };
+    let lambda_position = position.clone();
+    *index += 2;
+    rule_lambda_rest(index, lexemes, true).map(|lambda| Assignment {
+        position,
+        symbol,
+        expr: Expr::Lambda(lambda).to_expression(lambda_position, None),
     })
 }
 
+fn annotate(mut expression: Expression, type_expr: Option<TypeExpr>) -> EResult {
+    match type_expr {
+        None => Output::ok(expression),
+        Some(_) if expression.type_expr.is_some() => Error::DuplicateTypeAnnotation
+            .at(expression.position)
+            .into(),
+        _ => {
+            expression.type_expr = type_expr;
+            Output::ok(expression)
+        }
+    }
+}
+
 fn rule_expression(index: &mut usize, lexemes: &Lexemes) -> EResult {
-    rule_logterm(index, lexemes)
+    let mut left = rule_expr(index, lexemes);
+    while is_keyword(*index, lexemes, Keyword::Then) {
+        let position = position(*index, lexemes);
+        *index += 1;
+        left = left
+            .merge_to_tuple(rule_expr(index, lexemes))
+            .map(|(expr, then)| {
+                Expr::Seq(Seq {
+                    expr: expr.into(),
+                    then: then.into(),
+                })
+                .to_expression(position, None)
+            });
+    }
+    left
+}
+
+fn rule_expr(index: &mut usize, lexemes: &Lexemes) -> EResult {
+    if is_keyword(*index, lexemes, Keyword::If) {
+        rule_conditional(index, lexemes)
+    } else if is_keyword(*index, lexemes, Keyword::Fn) {
+        let position = position(*index, lexemes);
+        *index += 1;
+        rule_lambda_rest(index, lexemes, false)
+            .map(|lambda| Expr::Lambda(lambda).to_expression(position, None))
+    } else {
+        rule_logterm(index, lexemes)
+    }
+}
+
+fn rule_conditional(index: &mut usize, lexemes: &Lexemes) -> EResult {
+    let position = position(*index, lexemes);
+    *index += 1;
+    rule_expr(index, lexemes)
+        .and_then(|expr| rule_keyword_expr(index, lexemes, Keyword::Then).map(|then| (expr, then)))
+        .and_then(|(expr, then)| {
+            rule_keyword_expr(index, lexemes, Keyword::Else).map(|otherwise| {
+                Expr::Conditional(Conditional {
+                    expr: expr.into(),
+                    then: then.into(),
+                    otherwise: otherwise.into(),
+                })
+                .to_expression(position, None)
+            })
+        })
+}
+
+// Parses the parameters, the result type and the body of a function
+fn rule_lambda_rest(index: &mut usize, lexemes: &Lexemes, result_required: bool) -> Output<Lambda> {
+    let params = match parens(*index, lexemes) {
+        Some(enclosed) => {
+            *index += 1;
+            rule_list(enclosed, rule_param)
+        }
+        None => {
+            return Error::ParametersExpected
+                .at(position(*index, lexemes))
+                .into();
+        }
+    };
+    let result = rule_type_ann(index, lexemes).and_then(|result| match result {
+        None if result_required => Error::TypeAnnotationExpected
+            .at(position(*index, lexemes))
+            .into(),
+        result => Output::ok(result),
+    });
+    params.merge_to_tuple(result).and_then(|(params, result)| {
+        expect(
+            index,
+            lexemes,
+            LexemeData::Assignment,
+            Error::AssignmentExpected,
+        )
+        .and_then(|_| rule_expr(index, lexemes))
+        .map(|body| Lambda {
+            params,
+            result,
+            body: body.into(),
+        })
+    })
+}
+
+fn rule_param(index: &mut usize, lexemes: &Lexemes) -> Output<Param> {
+    match lexemes.get(*index) {
+        Some(Lexeme {
+            position,
+            data: LexemeData::Symbol(symbol),
+            ..
+        }) => {
+            *index += 1;
+            rule_type_ann(index, lexemes).and_then(|type_expr| match type_expr {
+                Some(type_expr) => Output::ok(Param {
+                    position: position.clone(),
+                    symbol: symbol.clone(),
+                    type_expr,
+                }),
+                None => Error::TypeAnnotationExpected
+                    .at(self::position(*index, lexemes))
+                    .into(),
+            })
+        }
+        _ => Error::ParameterExpected
+            .at(position(*index, lexemes))
+            .into(),
+    }
+}
+
+// Parses a comma-separated list of items using all the provided lexemes. A trailing comma is allowed.
+fn rule_list<T, F>(lexemes: &Lexemes, rule: F) -> Output<Vec<T>>
+where
+    F: Fn(&mut usize, &Lexemes) -> Output<T>,
+{
+    let mut output = Output::empty();
+    let mut index: usize = 0;
+    while index < lexemes.len() {
+        let item = rule(&mut index, lexemes);
+        let failed = item.has_errors();
+        output.add_output(item);
+        if failed {
+            break;
+        }
+        if let Some(lexeme) = lexemes.get(index) {
+            if lexeme.data == LexemeData::Comma {
+                index += 1;
+            } else {
+                output.add_error(Error::CommaExpected.at(lexeme.position.clone()));
+                break;
+            }
+        }
+    }
+    output
+}
+
+// Parses an expr preceded by the provided keyword
+fn rule_keyword_expr(index: &mut usize, lexemes: &Lexemes, keyword: Keyword) -> EResult {
+    expect(
+        index,
+        lexemes,
+        LexemeData::Keyword(keyword),
+        Error::KeywordExpected(keyword),
+    )
+    .and_then(|_| rule_expr(index, lexemes))
 }
 
 fn binary_rule<O, F>(index: &mut usize, lexemes: &Lexemes, op: O, rule: F) -> EResult
@@ -184,43 +436,228 @@ fn rule_factor(index: &mut usize, lexemes: &Lexemes) -> EResult {
         |k| match k {
             LexemeData::Star => Some(BinaryOp::Mul),
             LexemeData::Slash => Some(BinaryOp::Div),
+            LexemeData::Percent => Some(BinaryOp::Rem),
             _ => None,
         },
-        rule_primary,
+        rule_unary,
     )
 }
 
+// Unary operators bind tighter than any binary operator but exponentiation, so -2 ** 2 is -4.
+fn rule_unary(index: &mut usize, lexemes: &Lexemes) -> EResult {
+    let op = match lexemes.get(*index) {
+        Some(lexeme) => match lexeme.data {
+            LexemeData::Minus => Some((UnaryOp::Minus, lexeme.position.clone())),
+            LexemeData::Bang => Some((UnaryOp::Not, lexeme.position.clone())),
+            _ => None,
+        },
+        None => None,
+    };
+    match op {
+        Some((op, position)) => {
+            *index += 1;
+            rule_unary(index, lexemes).map(|expr| {
+                Expr::Unary(Unary {
+                    op,
+                    expr: expr.into(),
+                })
+                .to_expression(position, None)
+            })
+        }
+        None => rule_power(index, lexemes),
+    }
+}
+
+// Exponentiation is right-associative.
+fn rule_power(index: &mut usize, lexemes: &Lexemes) -> EResult {
+    let base = rule_primary(index, lexemes);
+    match lexemes.get(*index) {
+        Some(lexeme) if lexeme.data == LexemeData::DoubleStar => {
+            let position = lexeme.position.clone();
+            *index += 1;
+            base.merge_to_tuple(rule_unary(index, lexemes))
+                .and_then(|(expr1, expr2)| {
+                    Output::ok(
+                        Expr::Binary(Binary {
+                            op: BinaryOp::Pow,
+                            expr1: expr1.into(),
+                            expr2: expr2.into(),
+                        })
+                        .to_expression(position, None),
+                    )
+                })
+        }
+        _ => base,
+    }
+}
+
 fn rule_primary(index: &mut usize, lexemes: &Lexemes) -> EResult {
-    let current = lexemes.get(*index);
+    let current = match lexemes.get(*index) {
+        Some(lexeme) => lexeme,
+        None => {
+            return Error::ExpressionExpected
+                .at(position(*index, lexemes))
+                .into();
+        }
+    };
+    let position = current.position.clone();
+    match &current.data {
+        LexemeData::Integer(n) => {
+            *index += 1;
+            Output::ok(Expr::LitInteger(n.clone()).to_expression(position, None))
+        }
+        LexemeData::Decimal(d) => {
+            *index += 1;
+            Output::ok(Expr::LitDecimal(d.clone()).to_expression(position, None))
+        }
+        LexemeData::Symbol(_) | LexemeData::TSymbol(_) => {
+            rule_q(index, lexemes).map(|expr| expr.to_expression(position, None))
+        }
+        LexemeData::Quote => {
+            rule_string(index, lexemes).map(|expr| expr.to_expression(position, None))
+        }
+        LexemeData::Level(Level {
+            enclosure: Enclosure::Parens,
+            lexemes: enclosed,
+        }) => {
+            *index += 1;
+            rule_enclosed(current, enclosed)
+        }
+        _ => {
+            *index += 1;
+            Error::ExpressionExpected.at_lexeme(current)
+        }
+    }
+    .and_then(|expression| rule_calls(index, lexemes, expression))
+    .and_then(|expression| {
+        rule_type_ann(index, lexemes).and_then(|type_expr| annotate(expression, type_expr))
+    })
+}
+
+// Parses a string literal, which is interpolated if it contains any enclosed expression.
+// Adjacent text parts are merged.
+fn rule_string(index: &mut usize, lexemes: &Lexemes) -> Output<Expr> {
+    let start = lexemes.get(*index).unwrap();
     *index += 1;
-    if let Some(lexeme) = current {
+    let mut parts: Output<Vec<StringPart>> = Output::empty();
+    let mut text = String::new();
+    while let Some(lexeme) = lexemes.get(*index) {
+        *index += 1;
         match &lexeme.data {
-            LexemeData::Integer(n) => {
-                Output::ok((lexeme.position.clone(), Expr::LitInteger(n.clone())))
+            LexemeData::Text(t) => text.push_str(t),
+            LexemeData::Level(Level {
+                enclosure: Enclosure::Curly,
+                lexemes: enclosed,
+            }) => {
+                if !text.is_empty() {
+                    parts.add_value(StringPart::Text(std::mem::take(&mut text).into()));
+                }
+                parts.add_output(
+                    rule_enclosed(lexeme, enclosed).map(|e| StringPart::Expr(e.into())),
+                );
+            }
+            LexemeData::Quote => {
+                return parts.map(|mut parts| {
+                    if parts.is_empty() {
+                        Expr::LitString(text.into())
+                    } else {
+                        if !text.is_empty() {
+                            parts.push(StringPart::Text(text.into()));
+                        }
+                        Expr::Interpolation(parts)
+                    }
+                });
             }
-            _ => panic!("TODO: error"),
+            _ => return Error::UnterminatedString.at_lexeme(start),
         }
-    } else {
-        panic!("TODO: error")
     }
-    .and_then(|(position, expr)| {
-        rule_type_ann(index, lexemes).map(|type_expr| expr.to_expression(position, type_expr))
+    Error::UnterminatedString.at_lexeme(start)
+}
+
+// Parses an expression enclosed in parentheses, which must use all the enclosed lexemes.
+fn rule_enclosed(level: &Lexeme, lexemes: &Lexemes) -> EResult {
+    if lexemes.is_empty() {
+        return Error::ExpressionExpected.at_lexeme(level);
+    }
+    let mut index: usize = 0;
+    rule_expression(&mut index, lexemes).and_then(|e| match lexemes.get(index) {
+        Some(lexeme) => Error::EndOfEnclosureExpected.at_lexeme(lexeme),
+        None => Output::ok(e),
     })
 }
 
+// Parses any argument lists following an expression without separation, e.g., f(1)(2).
+fn rule_calls(index: &mut usize, lexemes: &Lexemes, func: Expression) -> EResult {
+    let mut expression = Output::ok(func);
+    while let Some(enclosed) = parens(*index, lexemes) {
+        let lexeme = lexemes.get(*index).unwrap();
+        if lexeme.separator != Separator::Nothing {
+            break;
+        }
+        let position = lexeme.position.clone();
+        *index += 1;
+        expression = expression
+            .merge_to_tuple(rule_list(enclosed, rule_expression))
+            .map(|(func, args)| {
+                Expr::Call(Call {
+                    func: func.into(),
+                    args,
+                })
+                .to_expression(position, None)
+            });
+    }
+    expression
+}
+
+// Parses a qualified symbol or type symbol, e.g., a::b::C.
+// Module segments must come before type segments.
+fn rule_q(index: &mut usize, lexemes: &Lexemes) -> Output<Expr> {
+    let mut path: Vec<Symbol> = Vec::default();
+    let mut types: Vec<TSymbol> = Vec::default();
+    while let Some(lexeme) = lexemes.get(*index) {
+        *index += 1;
+        let more = matches!(
+            lexemes.get(*index),
+            Some(l) if l.data == LexemeData::PathSeparator && l.separator == Separator::Nothing
+        );
+        match &lexeme.data {
+            LexemeData::Symbol(symbol) if !more => {
+                return Output::ok(Expr::Symbol(Q {
+                    path,
+                    types,
+                    symbol: symbol.clone(),
+                }));
+            }
+            LexemeData::TSymbol(symbol) if !more => {
+                return Output::ok(Expr::Type(Q {
+                    path,
+                    types,
+                    symbol: symbol.clone(),
+                }));
+            }
+            LexemeData::Symbol(symbol) if types.is_empty() => path.push(symbol.clone()),
+            LexemeData::TSymbol(symbol) => types.push(symbol.clone()),
+            _ => return Error::InvalidQualifiedName.at_lexeme(lexeme),
+        }
+        // Skip the path separator, the next segment can't be separated from it.
+        *index += 1;
+        if !matches!(lexemes.get(*index), Some(l) if l.separator == Separator::Nothing) {
+            break;
+        }
+    }
+    Error::InvalidQualifiedName
+        .at(position(*index, lexemes))
+        .into()
+}
+
 fn rule_type_ann(index: &mut usize, lexemes: &Lexemes) -> Output<Option<TypeExpr>> {
     if let Some(seplex) = lexemes.get(*index) {
         if LexemeData::TypeAnnSeparator == seplex.data {
             *index += 1;
-            if let Some(lexeme) = lexemes.get(*index) {
-                if let LexemeData::TSymbol(s) = &lexeme.data {
-                    *index += 1;
-                    Output::ok(Some(TypeExpr::Type(Q::of(s.clone()))))
-                } else {
-                    Error::TypeAnnotationExpected.to_output(&lexeme)
-                }
+            if lexemes.get(*index).is_some() {
+                rule_type(index, lexemes).map(Some)
             } else {
-                Error::TypeAnnotationExpected.to_output(&seplex)
+                Error::TypeAnnotationExpected.at_lexeme(seplex)
             }
         } else {
             Output::ok(None)
@@ -230,16 +667,52 @@ fn rule_type_ann(index: &mut usize, lexemes: &Lexemes) -> Output<Option<TypeExpr
     }
 }
 
+// Parses a type symbol or a function type, e.g., (Integer, Boolean) -> Integer
+fn rule_type(index: &mut usize, lexemes: &Lexemes) -> Output<TypeExpr> {
+    if let Some(enclosed) = parens(*index, lexemes) {
+        *index += 1;
+        rule_list(enclosed, rule_type).and_then(|params| {
+            expect(index, lexemes, LexemeData::Arrow, Error::ArrowExpected)
+                .and_then(|_| rule_type(index, lexemes))
+                .map(|result| TypeExpr::Function(params, result.into()))
+        })
+    } else {
+        match lexemes.get(*index) {
+            Some(Lexeme {
+                data: LexemeData::TSymbol(s),
+                ..
+            }) => {
+                *index += 1;
+                Output::ok(TypeExpr::Type(Q::of(s.clone())))
+            }
+            _ => Error::TypeAnnotationExpected
+                .at(position(*index, lexemes))
+                .into(),
+        }
+    }
+}
+
 #[derive(Debug)]
-enum Error {
+pub enum Error {
     EndOfStatementExpected,
+    EndOfEnclosureExpected,
+    ExpressionExpected,
+    SymbolExpected,
+    AssignmentExpected,
+    KeywordExpected(Keyword),
+    InvalidQualifiedName,
     TypeAnnotationExpected,
+    DuplicateTypeAnnotation,
+    ParametersExpected,
+    ParameterExpected,
+    CommaExpected,
+    ArrowExpected,
     OnlyOneExpressionAllowed,
-    UnexpectedStatement,
+    UnterminatedString,
 }
 
 impl Error {
-    fn to_output<T>(self, lexeme: &Lexeme) -> Output<T> {
+    fn at_lexeme<T>(self, lexeme: &Lexeme) -> Output<T> {
         self.at(lexeme.position.clone()).into()
     }
 }
@@ -1,6 +1,9 @@
 use ast::{
-    Binary, Expr, Expression, Q, Statement, TypeExpr, common::BinaryOp, position::EqNoPosition,
-    symbol::TSymbol,
+    Assignment, Binary, Call, Conditional, Expr, Expression, Lambda, Param, Q, Seq, Statement,
+    StringPart, TypeExpr, Unary,
+    common::{BinaryOp, UnaryOp},
+    position::{EqNoPosition, Position},
+    symbol::{Symbol, TSymbol},
 };
 
 fn check_statements(input: &str, expected: Vec<Statement>) {
@@ -16,14 +19,50 @@ fn check_expression(input: &str, expected: Expression) {
     check_statement(input, Statement::Expression(expected));
 }
 
+fn check_error(input: &str) {
+    assert!(super::parse(input).to_result().is_err());
+}
+
 fn e(expr: Expr) -> Expression {
-    expr.to_expression(ast::position::Position::NoPosition, None)
+    expr.to_expression(Position::NoPosition, None)
+}
+
+fn sym(name: &str) -> Symbol {
+    Symbol::new(name).unwrap()
+}
+
+fn tsym(name: &str) -> TSymbol {
+    TSymbol::new(name).unwrap()
+}
+
+fn e_symbol(path: Vec<Symbol>, types: Vec<TSymbol>, symbol: &str) -> Expression {
+    e(Expr::Symbol(Q {
+        path,
+        types,
+        symbol: sym(symbol),
+    }))
+}
+
+fn e_type(path: Vec<Symbol>, types: Vec<TSymbol>, symbol: &str) -> Expression {
+    e(Expr::Type(Q {
+        path,
+        types,
+        symbol: tsym(symbol),
+    }))
 }
 
 fn e_i64(value: i64) -> Expression {
     e(Expr::LitInteger(value.into()))
 }
 
+fn e_decimal(value: &str) -> Expression {
+    e(Expr::LitDecimal(value.parse().unwrap()))
+}
+
+fn e_string(value: &str) -> Expression {
+    e(Expr::LitString(value.into()))
+}
+
 fn e_binary(expr1: Expression, op: BinaryOp, expr2: Expression) -> Expression {
     e(Expr::Binary(Binary {
         op,
@@ -32,6 +71,17 @@ fn e_binary(expr1: Expression, op: BinaryOp, expr2: Expression) -> Expression {
     }))
 }
 
+fn e_unary(op: UnaryOp, expr: Expression) -> Expression {
+    e(Expr::Unary(Unary {
+        op,
+        expr: expr.into(),
+    }))
+}
+
+fn e_neg(expr: Expression) -> Expression {
+    e_unary(UnaryOp::Minus, expr)
+}
+
 fn e_add(expr1: Expression, expr2: Expression) -> Expression {
     e_binary(expr1, BinaryOp::Add, expr2)
 }
@@ -40,8 +90,58 @@ fn e_add_i64(v1: i64, v2: i64) -> Expression {
     e_add(e_i64(v1), e_i64(v2))
 }
 
+fn e_seq(expr: Expression, then: Expression) -> Expression {
+    e(Expr::Seq(Seq {
+        expr: expr.into(),
+        then: then.into(),
+    }))
+}
+
+fn e_conditional(expr: Expression, then: Expression, otherwise: Expression) -> Expression {
+    e(Expr::Conditional(Conditional {
+        expr: expr.into(),
+        then: then.into(),
+        otherwise: otherwise.into(),
+    }))
+}
+
+fn s_let(symbol: &str, expr: Expression) -> Statement {
+    Statement::Assignment(Assignment {
+        position: Position::NoPosition,
+        symbol: sym(symbol),
+        expr,
+    })
+}
+
+fn t_int() -> TypeExpr {
+    TypeExpr::Type(Q::of(tsym("Integer")))
+}
+
+fn param(symbol: &str, type_expr: TypeExpr) -> Param {
+    Param {
+        position: Position::NoPosition,
+        symbol: sym(symbol),
+        type_expr,
+    }
+}
+
+fn e_lambda(params: Vec<Param>, result: Option<TypeExpr>, body: Expression) -> Expression {
+    e(Expr::Lambda(Lambda {
+        params,
+        result,
+        body: body.into(),
+    }))
+}
+
+fn e_call(func: Expression, args: Vec<Expression>) -> Expression {
+    e(Expr::Call(Call {
+        func: func.into(),
+        args,
+    }))
+}
+
 fn tai(mut e: Expression) -> Expression {
-    e.type_expr = Some(TypeExpr::Type(Q::of(TSymbol::new("Integer").unwrap())));
+    e.type_expr = Some(TypeExpr::Type(Q::of(tsym("Integer"))));
     e
 }
 
@@ -52,6 +152,12 @@ fn int_literal_expr() {
     check_expression("\t \n 1234  \n\t", e_i64(1234));
 }
 
+#[test]
+fn decimal_literal_expr() {
+    check_expression("1.25", e_decimal("1.25"));
+    check_expression("1.50 + 2", e_add(e_decimal("1.50"), e_i64(2)));
+}
+
 #[test]
 fn ann_int_literal_expr() {
     check_expression("1234 : Integer", tai(e_i64(1234)));
@@ -63,3 +169,228 @@ fn ann_int_literal_expr() {
 fn int_add() {
     check_expression("1 +2 ", e_add_i64(1, 2));
 }
+
+#[test]
+fn int_mul_precedence() {
+    check_expression(
+        "1 + 2 * 3",
+        e_add(e_i64(1), e_binary(e_i64(2), BinaryOp::Mul, e_i64(3))),
+    );
+}
+
+#[test]
+fn rem_and_pow() {
+    check_expression(
+        "1 + 2 % 3",
+        e_add(e_i64(1), e_binary(e_i64(2), BinaryOp::Rem, e_i64(3))),
+    );
+    // Exponentiation binds tighter than multiplication and is right-associative
+    check_expression(
+        "2 * 3 ** 2 ** 4",
+        e_binary(
+            e_i64(2),
+            BinaryOp::Mul,
+            e_binary(
+                e_i64(3),
+                BinaryOp::Pow,
+                e_binary(e_i64(2), BinaryOp::Pow, e_i64(4)),
+            ),
+        ),
+    );
+    check_error("2 ** ");
+}
+
+#[test]
+fn unary() {
+    check_expression("-1", e_neg(e_i64(1)));
+    check_expression("1 - -2", e_binary(e_i64(1), BinaryOp::Sub, e_neg(e_i64(2))));
+    check_expression("--1", e_neg(e_neg(e_i64(1))));
+    check_expression(
+        "!True && False",
+        e_binary(
+            e_unary(UnaryOp::Not, e_type(vec![], vec![], "True")),
+            BinaryOp::And,
+            e_type(vec![], vec![], "False"),
+        ),
+    );
+    check_expression(
+        "-2 ** 2",
+        e_neg(e_binary(e_i64(2), BinaryOp::Pow, e_i64(2))),
+    );
+    check_expression(
+        "2 ** -1",
+        e_binary(e_i64(2), BinaryOp::Pow, e_neg(e_i64(1))),
+    );
+    check_expression("-1 * 2", e_binary(e_neg(e_i64(1)), BinaryOp::Mul, e_i64(2)));
+    check_error("-");
+    check_error("1 * !");
+}
+
+#[test]
+fn parens() {
+    check_expression(
+        "(1 + 2) * 3",
+        e_binary(e_add_i64(1, 2), BinaryOp::Mul, e_i64(3)),
+    );
+    check_expression("((1))", e_i64(1));
+    check_expression("(1 + 2) : Integer", tai(e_add_i64(1, 2)));
+    // Annotations apply to the primary before them
+    check_expression("(1 + 2 : Integer)", e_add(e_i64(1), tai(e_i64(2))));
+    check_expression("1 + 2 : Integer", e_add(e_i64(1), tai(e_i64(2))));
+    check_expression("(1 : Integer) + 2", e_add(tai(e_i64(1)), e_i64(2)));
+    check_error("()");
+    check_error("(1 2)");
+    check_error("(1 : Integer) : Integer");
+}
+
+#[test]
+fn symbols() {
+    check_expression("x", e_symbol(vec![], vec![], "x"));
+    check_expression("a::b::x", e_symbol(vec![sym("a"), sym("b")], vec![], "x"));
+    check_expression("a::B::x", e_symbol(vec![sym("a")], vec![tsym("B")], "x"));
+    check_expression("x + 1", e_add(e_symbol(vec![], vec![], "x"), e_i64(1)));
+    check_error("A::b::x");
+    check_error("a::");
+    check_error("a:: b");
+}
+
+#[test]
+fn types() {
+    check_expression("True", e_type(vec![], vec![], "True"));
+    check_expression("a::B::C", e_type(vec![sym("a")], vec![tsym("B")], "C"));
+}
+
+#[test]
+fn logical() {
+    check_expression(
+        "True || False && True",
+        e_binary(
+            e_type(vec![], vec![], "True"),
+            BinaryOp::Or,
+            e_binary(
+                e_type(vec![], vec![], "False"),
+                BinaryOp::And,
+                e_type(vec![], vec![], "True"),
+            ),
+        ),
+    );
+}
+
+#[test]
+fn seq() {
+    check_expression("1 then 2", e_seq(e_i64(1), e_i64(2)));
+    check_expression(
+        "1 then 2 then 3",
+        e_seq(e_seq(e_i64(1), e_i64(2)), e_i64(3)),
+    );
+}
+
+#[test]
+fn conditional() {
+    check_expression(
+        "if True then 1 else 2",
+        e_conditional(e_type(vec![], vec![], "True"), e_i64(1), e_i64(2)),
+    );
+    check_error("if True then 1");
+    check_error("if True 1 else 2");
+}
+
+#[test]
+fn assignment() {
+    check_statement("let x = 1", s_let("x", e_i64(1)));
+    check_statements(
+        "let x = 1\nlet y = x + 2",
+        vec![
+            s_let("x", e_i64(1)),
+            s_let("y", e_add(e_symbol(vec![], vec![], "x"), e_i64(2))),
+        ],
+    );
+    check_statement("let x: Integer = 1 + 2", s_let("x", tai(e_add_i64(1, 2))));
+    check_error("let X = 1");
+    check_error("let x 1");
+    check_error("let x: = 1");
+    check_error("let x: Integer = 1 : Integer");
+    check_statement(
+        "let x: Integer = 1 + 2 : Integer",
+        s_let("x", tai(e_add(e_i64(1), tai(e_i64(2))))),
+    );
+}
+
+#[test]
+fn functions() {
+    let x = || e_symbol(vec![], vec![], "x");
+    let f = || e_symbol(vec![], vec![], "f");
+    let int_to_int = || TypeExpr::Function(vec![t_int()], t_int().into());
+    check_expression(
+        "fn(x: Integer) = x",
+        e_lambda(vec![param("x", t_int())], None, x()),
+    );
+    check_expression(
+        "fn(x: Integer, f: (Integer) -> Integer,): Integer = f(x)",
+        e_lambda(
+            vec![param("x", t_int()), param("f", int_to_int())],
+            Some(t_int()),
+            e_call(f(), vec![x()]),
+        ),
+    );
+    check_expression("fn() = 1", e_lambda(vec![], None, e_i64(1)));
+    check_expression(
+        "f(1, x)(2) + 3",
+        e_add(
+            e_call(e_call(f(), vec![e_i64(1), x()]), vec![e_i64(2)]),
+            e_i64(3),
+        ),
+    );
+    check_expression("f(): Integer", tai(e_call(f(), vec![])));
+    check_statement(
+        "fn f(): (Integer) -> Integer = x",
+        s_let("f", e_lambda(vec![], Some(int_to_int()), x())),
+    );
+    check_error("f (1)");
+    check_error("f(1 2)");
+    check_error("fn(x) = x");
+    check_error("fn(x: 1) = x");
+    check_error("fn f() = 1");
+    check_error("fn(x: Integer) x");
+    check_error("fn(x: (Integer)) = x");
+    check_error("fn x = 1");
+}
+
+#[test]
+fn ast() {
+    let ast = super::parse("let x = 1\nx + 2").to_result().unwrap().value;
+    assert_eq!(ast.assignments.len(), 1);
+    ast.expression
+        .unwrap()
+        .assert_eq_nopos(&e_add(e_symbol(vec![], vec![], "x"), e_i64(2)));
+    check_error("1\n2");
+}
+
+#[test]
+fn missing_operand() {
+    check_error("1 +");
+    check_error("*");
+}
+
+#[test]
+fn strings() {
+    check_expression(r#""""#, e_string(""));
+    check_expression(r#""a\"b\{" + "c""#, e_add(e_string("a\"b{"), e_string("c")));
+    check_expression(
+        r#""x = {x + 1}!""#,
+        e(Expr::Interpolation(vec![
+            StringPart::Text("x = ".into()),
+            StringPart::Expr(e_add(e_symbol(vec![], vec![], "x"), e_i64(1)).into()),
+            StringPart::Text("!".into()),
+        ])),
+    );
+    check_expression(
+        r#""{"{1}"}""#,
+        e(Expr::Interpolation(vec![StringPart::Expr(
+            e(Expr::Interpolation(vec![StringPart::Expr(e_i64(1).into())])).into(),
+        )])),
+    );
+    check_error(r#""abc"#);
+    check_error(r#""{}""#);
+    check_error(r#""{1 2}""#);
+}
@@ -6,17 +6,17 @@ use rustyline::Editor;
 use rustyline::error::ReadlineError;
 
 fn main() -> rustyline::Result<()> {
-    let mut repl = REPL::new();
+    let mut repl = Repl::new();
     repl.run()
 }
 
-struct REPL {
+struct Repl {
     env: Env,
 }
 
-impl REPL {
+impl Repl {
     fn new() -> Self {
-        REPL {
+        Repl {
             env: Env::default(),
         }
     }
@@ -4,7 +4,7 @@ use core::{
 };
 
 fn eval_ok(input: &str, result: Value) {
-    match super::REPL::new().eval(input) {
+    match super::Repl::new().eval(input) {
         Ok(v) => {
             assert_eq!(v, result);
         }